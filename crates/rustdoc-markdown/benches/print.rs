@@ -0,0 +1,127 @@
+//! Benchmarks rendering a generated fixture crate through [`Printer::print`].
+//!
+//! Run with `cargo bench --bench print`. Besides the timings, the number of heap
+//! allocations of one `print()` call is reported, as rendering item docs used to
+//! clone the whole printer state.
+
+use cargo_manifest::Manifest;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rustdoc_markdown::{run_rustdoc, Printer, RustdocOptions};
+use rustdoc_types::Crate;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts the allocations made through the system allocator.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// The number of types (each with a trait impl and an inherent impl) in the fixture.
+const TYPES: usize = 50;
+
+/// Returns the `lib.rs` of the fixture: documented structs with documented inherent
+/// and trait impls, and a trait with documented associated items.
+fn fixture_source() -> String {
+    let mut source = String::from(
+        "/// A shape.
+        pub trait Shape {
+            /// The unit of the area.
+            type Unit;
+            /// The number of sides.
+            const SIDES: u32;
+            /// Returns the area.
+            ///
+            /// # Panics
+            ///
+            /// Never.
+            fn area(&self) -> f64;
+        }
+        ",
+    );
+    for i in 0..TYPES {
+        write!(
+            source,
+            "/// Shape number {i}.
+            pub struct Shape{i} {{
+                /// The side.
+                pub side: f64,
+            }}
+            /// Inherent methods of shape {i}.
+            impl Shape{i} {{
+                /// Creates the shape.
+                pub fn new(side: f64) -> Self {{ Self {{ side }} }}
+                /// Doubles the side.
+                pub fn double(&mut self) {{ self.side *= 2.0; }}
+            }}
+            /// Shape {i} is a shape.
+            impl Shape for Shape{i} {{
+                type Unit = f64;
+                const SIDES: u32 = {i};
+                fn area(&self) -> f64 {{ self.side * self.side }}
+            }}
+            "
+        )
+        .unwrap();
+    }
+    source
+}
+
+/// Writes the fixture crate to a temporary directory and runs rustdoc on it.
+fn document_fixture() -> (Manifest, Crate) {
+    let dir = tempfile::tempdir().unwrap();
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(
+        &manifest_path,
+        "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), fixture_source()).unwrap();
+    let manifest = Manifest::from_path(&manifest_path).unwrap();
+    let krate = run_rustdoc(dir.path(), "fixture", &RustdocOptions::default(), true).unwrap();
+    (manifest, krate)
+}
+
+fn print(c: &mut Criterion) {
+    let (manifest, krate) = document_fixture();
+    for (name, template_mode) in [("print", false), ("print template_mode", true)] {
+        let render = || {
+            let printer = Printer::new(&manifest, &krate);
+            let printer = if template_mode {
+                printer.template_mode()
+            } else {
+                printer
+            };
+            printer.print().unwrap()
+        };
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        render();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        eprintln!("{}: {} allocations per call", name, allocations);
+        c.bench_function(name, |b| b.iter(render));
+    }
+}
+
+criterion_group!(benches, print);
+criterion_main!(benches);
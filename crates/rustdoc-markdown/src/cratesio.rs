@@ -5,9 +5,74 @@ use semver::{Version, VersionReq};
 use serde::Deserialize;
 use std::io::Cursor; // Use IoWrite alias and IMPORT Cursor
 use std::path::{Path as FilePath, PathBuf}; // Corrected use statement
+use std::time::Duration;
 use tar::Archive;
 use tracing::{debug, info, warn};
 
+/// Delay before the first retry of a failed request; it doubles with each attempt.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Upper bound for the delay between retries, including a server's `Retry-After`.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Sends a GET request to `url`, retrying up to `max_retries` times on rate limiting
+/// (429), server errors (5xx), timeouts and connection failures.
+///
+/// Retries wait for the server's `Retry-After` (in seconds) if present, or else back off
+/// exponentially from [`INITIAL_RETRY_DELAY`] with some jitter.
+async fn get_with_retry(
+    client: &reqwest::Client,
+    url: &str,
+    max_retries: u32,
+) -> Result<reqwest::Response> {
+    let mut attempt = 0;
+    loop {
+        let retry_after = match client.get(url).send().await {
+            Ok(response)
+                if attempt < max_retries
+                    && (response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+                        || response.status().is_server_error()) =>
+            {
+                warn!("Request to {} failed with {}", url, response.status());
+                response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs)
+            }
+            Ok(response) => return Ok(response.error_for_status()?),
+            Err(e) if attempt < max_retries && (e.is_timeout() || e.is_connect()) => {
+                warn!("Request to {} failed: {}", url, e);
+                None
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let delay = retry_after
+            .unwrap_or_else(|| backoff_delay(attempt))
+            .min(MAX_RETRY_DELAY);
+        attempt += 1;
+        info!(
+            "Retrying in {:.1}s (attempt {} of {})...",
+            delay.as_secs_f64(),
+            attempt,
+            max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Returns the exponential backoff delay before retry number `attempt + 1`, with up
+/// to 50% of random jitter so that concurrent clients don't retry in lockstep.
+fn backoff_delay(attempt: u32) -> Duration {
+    let delay = INITIAL_RETRY_DELAY.saturating_mul(1 << attempt.min(16));
+    let jitter_range = delay.as_millis() as u64 / 2 + 1;
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |now| u64::from(now.subsec_nanos()));
+    delay + Duration::from_millis(nanos % jitter_range)
+}
+
 #[derive(Deserialize, Debug)]
 struct CratesApiResponse {
     versions: Vec<CrateVersion>,
@@ -42,6 +107,7 @@ pub struct CrateVersion {
 ///   If "*", the latest suitable version is selected.
 /// * `include_prerelease`: If `true`, pre-release versions (e.g., "1.0.0-alpha") are considered.
 ///   Otherwise, they are ignored unless explicitly matched by `version_req_str`.
+/// * `max_retries`: How often to retry the request on rate limiting or transient errors.
 ///
 /// # Returns
 ///
@@ -52,13 +118,14 @@ pub async fn find_best_version(
     crate_name: &str,
     version_req_str: &str,
     include_prerelease: bool,
+    max_retries: u32,
 ) -> Result<CrateVersion> {
     info!(
         "Fetching versions for crate '{}' from crates.io...",
         crate_name
     );
     let url = format!("https://crates.io/api/v1/crates/{}", crate_name);
-    let response = client.get(&url).send().await?.error_for_status()?;
+    let response = get_with_retry(client, &url, max_retries).await?;
     let mut api_data: CratesApiResponse = response
         .json()
         .await
@@ -152,6 +219,7 @@ pub async fn find_best_version(
 /// * `krate`: The [`CrateVersion`] specifying the crate and version to download.
 /// * `build_path`: The base directory where the crate source should be unpacked.
 ///   The crate will be unpacked into a subdirectory like `{build_path}/{crate_name}-{version}`.
+/// * `max_retries`: How often to retry the download on rate limiting or transient errors.
 ///
 /// # Returns
 ///
@@ -161,6 +229,7 @@ pub async fn download_and_unpack_crate(
     client: &reqwest::Client,
     krate: &CrateVersion,
     build_path: &FilePath, // Renamed from output_path
+    max_retries: u32,
 ) -> Result<PathBuf> {
     let crate_dir_name = format!("{}-{}", krate.crate_name, krate.num);
     let target_dir = build_path.join(crate_dir_name); // Use build_path
@@ -178,7 +247,7 @@ pub async fn download_and_unpack_crate(
         "https://crates.io/api/v1/crates/{}/{}/download",
         krate.crate_name, krate.num
     );
-    let response = client.get(&url).send().await?.error_for_status()?;
+    let response = get_with_retry(client, &url, max_retries).await?;
 
     let content = response.bytes().await?;
     let reader = Cursor::new(content); // Cursor is now in scope
@@ -218,3 +287,49 @@ pub async fn download_and_unpack_crate(
     info!("Unpacked to: {}", target_dir.display());
     Ok(target_dir)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves one response per connection from `statuses`, returning the server URL.
+    async fn serve(statuses: &'static [u16]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0; 1024];
+                let _ = socket.read(&mut request).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nRetry-After: 0\r\nContent-Length: 2\r\n\
+                     Connection: close\r\n\r\nok",
+                    status
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn get_with_retry_retries_rate_limited_requests() {
+        let url = serve(&[429, 429, 200]).await;
+        let response = get_with_retry(&reqwest::Client::new(), &url, 3)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        assert_eq!(response.text().await.unwrap(), "ok");
+    }
+
+    #[tokio::test]
+    async fn get_with_retry_gives_up_after_max_retries() {
+        let url = serve(&[503, 503]).await;
+        let error = get_with_retry(&reqwest::Client::new(), &url, 1)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("503"), "{}", error);
+    }
+}
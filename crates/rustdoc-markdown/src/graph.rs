@@ -1,15 +1,16 @@
 use anyhow::{anyhow, Result};
 use rustdoc_types::{
-    Crate, GenericArg, GenericArgs, GenericBound, GenericParamDef, Generics, Id, ItemEnum, Path,
-    Term, Type, WherePredicate,
+    Crate, GenericArg, GenericArgs, GenericBound, GenericParamDef, Generics, Id, Item, ItemEnum,
+    ItemKind, Path, StructKind, Term, Type, VariantKind, WherePredicate,
 };
-use std::collections::{HashMap, HashSet, VecDeque}; // Use HashMap instead of BTreeMap where needed
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque}; // Use HashMap instead of BTreeMap where needed
 use std::fmt::{Display, Formatter}; // Use FmtWrite alias
 use std::hash::Hash;
 use std::io::Write as IoWrite; // Use IoWrite alias and IMPORT Cursor
 use tracing::{debug, info, warn};
 
-use crate::get_type_id;
+use crate::{get_type_id, infer_item_kind};
 
 // --- ID Graph Structures ---
 
@@ -111,6 +112,18 @@ impl IdGraph {
         self.adjacency.get(source_id)
     }
 
+    /// Returns the children of `source_id`, keeping only those in `node_filter` if given.
+    fn get_children_within<'g>(
+        &'g self,
+        source_id: &Id,
+        node_filter: Option<&'g HashSet<Id>>,
+    ) -> impl Iterator<Item = &'g (Id, EdgeLabel)> + 'g {
+        self.get_children(source_id)
+            .into_iter()
+            .flatten()
+            .filter(move |(child_id, _)| node_filter.is_none_or(|nodes| nodes.contains(child_id)))
+    }
+
     /// Finds all nodes that have no incoming edges *from within the graph*.
     #[doc(hidden)]
     pub fn find_roots(&self) -> HashSet<Id> {
@@ -134,26 +147,22 @@ impl IdGraph {
             .collect()
     }
 
-    /// Filters the graph to keep only edges that are part of a path leading to the target_leaf_id.
-    /// Returns a new `IdGraph` containing only the filtered edges.
+    /// Returns the nodes that are part of a path leading to `target_leaf_id` (including
+    /// the target itself), or an empty set if no edge leads to it.
+    ///
+    /// Unlike [`filter_to_leaf`](IdGraph::filter_to_leaf), no edges are copied, so this
+    /// can be passed as the node filter of the dump functions to stream the filtered
+    /// graph directly.
     #[doc(hidden)]
-    pub fn filter_to_leaf(&self, target_leaf_id: Id) -> IdGraph {
-        let mut filtered_graph = IdGraph::new();
+    pub fn nodes_reaching(&self, target_leaf_id: Id) -> HashSet<Id> {
         let mut reachable_nodes = HashSet::new(); // Nodes that can reach the target
         let mut queue = VecDeque::new();
 
         // Start BFS from the target node using the reverse adjacency list
-        // Check existence in reverse_adjacency OR adjacency (node might exist but have no incoming edges)
-        if self.reverse_adjacency.contains_key(&target_leaf_id)
-            || self.adjacency.contains_key(&target_leaf_id)
-        {
+        if self.reverse_adjacency.contains_key(&target_leaf_id) {
             reachable_nodes.insert(target_leaf_id);
             queue.push_back(target_leaf_id);
-        } else {
-            // Target ID doesn't exist in the original graph's node set
-            return filtered_graph; // Return empty graph
         }
-
         while let Some(current_id) = queue.pop_front() {
             if let Some(parents) = self.reverse_adjacency.get(&current_id) {
                 for (parent_id, _) in parents {
@@ -163,6 +172,32 @@ impl IdGraph {
                 }
             }
         }
+        reachable_nodes
+    }
+
+    /// Like [`find_roots`](IdGraph::find_roots), but for the subgraph induced by `nodes`
+    /// (e.g. from [`nodes_reaching`](IdGraph::nodes_reaching)).
+    #[doc(hidden)]
+    pub fn find_roots_within(&self, nodes: &HashSet<Id>) -> HashSet<Id> {
+        nodes
+            .iter()
+            .filter(|id| {
+                !self
+                    .reverse_adjacency
+                    .get(id)
+                    .is_some_and(|parents| parents.iter().any(|(p, _)| nodes.contains(p)))
+            })
+            .filter(|id| self.get_children_within(id, Some(nodes)).next().is_some())
+            .cloned()
+            .collect()
+    }
+
+    /// Filters the graph to keep only edges that are part of a path leading to the target_leaf_id.
+    /// Returns a new `IdGraph` containing only the filtered edges.
+    #[doc(hidden)]
+    pub fn filter_to_leaf(&self, target_leaf_id: Id) -> IdGraph {
+        let mut filtered_graph = IdGraph::new();
+        let reachable_nodes = self.nodes_reaching(target_leaf_id);
 
         // Now, add edges from the original graph *only if both* source and target are in reachable_nodes
         for edge in &self.edges {
@@ -185,6 +220,98 @@ impl IdGraph {
 
         filtered_graph // Return the newly constructed filtered graph
     }
+
+    /// Ranks the nodes so that every node ranks higher than the nodes it uses, with
+    /// leaves at rank 0. Edges from a type or trait to its impl blocks and intra-doc
+    /// links are ignored, as they don't make the source depend on the target.
+    ///
+    /// The nodes of a cycle (a strongly connected component) share a rank.
+    pub(crate) fn dependency_ranks(&self, krate: &Crate) -> HashMap<Id, usize> {
+        let successors = |id: &Id| -> Vec<Id> {
+            self.get_children(id)
+                .into_iter()
+                .flatten()
+                .filter(|(target, label)| {
+                    *label != EdgeLabel::IntraDocLink
+                        && !matches!(
+                            krate.index.get(target).map(|item| &item.inner),
+                            Some(ItemEnum::Impl(_))
+                        )
+                })
+                .map(|(target, _)| *target)
+                .collect()
+        };
+
+        let mut nodes: Vec<Id> = self.adjacency.keys().cloned().collect();
+        nodes.sort_by_key(|id| id.0);
+
+        // Iterative Tarjan: components are completed after all components they reach,
+        // so the ranks of their successors are known by then.
+        let mut index_of: HashMap<Id, usize> = HashMap::new();
+        let mut low_link: HashMap<Id, usize> = HashMap::new();
+        let mut stack: Vec<Id> = Vec::new();
+        let mut on_stack: HashSet<Id> = HashSet::new();
+        let mut ranks: HashMap<Id, usize> = HashMap::new();
+        for root in nodes {
+            if index_of.contains_key(&root) {
+                continue;
+            }
+            // (node, its successors, position of the next successor to visit)
+            let mut call_stack: Vec<(Id, Vec<Id>, usize)> = Vec::new();
+            let mut to_visit = Some(root);
+            loop {
+                if let Some(id) = to_visit.take() {
+                    let index = index_of.len();
+                    index_of.insert(id, index);
+                    low_link.insert(id, index);
+                    stack.push(id);
+                    on_stack.insert(id);
+                    call_stack.push((id, successors(&id), 0));
+                }
+                let Some((node, node_successors, position)) = call_stack.last_mut() else {
+                    break;
+                };
+                let node = *node;
+                if let Some(&next) = node_successors.get(*position) {
+                    *position += 1;
+                    match index_of.get(&next) {
+                        None => to_visit = Some(next),
+                        Some(&next_index) if on_stack.contains(&next) => {
+                            let low = low_link[&node].min(next_index);
+                            low_link.insert(node, low);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some((parent, _, _)) = call_stack.last() {
+                    let low = low_link[parent].min(low_link[&node]);
+                    low_link.insert(*parent, low);
+                }
+                if low_link[&node] == index_of[&node] {
+                    let mut component = HashSet::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack.remove(&member);
+                        component.insert(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    let rank = component
+                        .iter()
+                        .flat_map(&successors)
+                        .filter(|successor| !component.contains(successor))
+                        .filter_map(|successor| ranks.get(&successor).map(|rank| rank + 1))
+                        .max()
+                        .unwrap_or(0);
+                    ranks.extend(component.into_iter().map(|member| (member, rank)));
+                }
+            }
+        }
+        ranks
+    }
 }
 
 // --- End ID Graph Structures ---
@@ -816,24 +943,21 @@ fn path_matches(item_path: &[String], filter_path: &[String]) -> bool {
 
 /// Selects items based on path filters and recursively includes their dependencies.
 /// Builds the graph for *all* items in the crate, regardless of filtering.
+///
+/// `user_paths` are prefix filters, while `allow_list` entries must match an
+/// item's canonical path exactly.
 #[doc(hidden)]
 pub fn select_items(
     krate: &Crate,
     user_paths: &[String],
+    allow_list: &[String],
     resolved_modules: &HashMap<Id, ResolvedModule>,
 ) -> Result<(HashSet<Id>, IdGraph)> {
     let mut selected_ids: HashSet<Id> = HashSet::new();
-    let mut graph = IdGraph::new(); // Instantiate the graph
-
-    // --- Build the full graph first ---
-    info!("Building full dependency graph...");
-    for id in krate.index.keys() {
-        build_graph_for_item(*id, krate, &mut graph);
-    }
-    info!("Built full graph with {} edges.", graph.edges.len());
+    let graph = build_full_graph(krate);
 
     // --- Now select items based on filters ---
-    if user_paths.is_empty() {
+    if user_paths.is_empty() && allow_list.is_empty() {
         info!("No path filters specified, selecting all items.");
         selected_ids.extend(krate.index.keys().cloned());
         return Ok((selected_ids, graph));
@@ -854,7 +978,13 @@ pub fn select_items(
         .map(|p| normalize_path(p, crate_name, &normalized_crate_name))
         .collect();
 
+    let normalized_allow_list: Vec<Vec<String>> = allow_list
+        .iter()
+        .map(|p| normalize_path(p, crate_name, &normalized_crate_name))
+        .collect();
+
     info!("Normalized path filters: {:?}", normalized_filters);
+    info!("Normalized allow-list: {:?}", normalized_allow_list);
 
     // Initial selection based on paths matching items in resolved modules
     // Iterate through resolved modules instead of krate.paths directly
@@ -882,6 +1012,14 @@ pub fn select_items(
                             // No break here, an item might be reachable via multiple modules/paths
                         }
                     }
+
+                    if normalized_allow_list.contains(&qualified_item_path) {
+                        debug!(
+                            "Allow-list matched item {:?} ({:?}) via module {:?}",
+                            qualified_item_path, item_id, resolved_mod.id
+                        );
+                        selected_ids.insert(*item_id);
+                    }
                 }
             }
         }
@@ -889,18 +1027,140 @@ pub fn select_items(
 
     if selected_ids.is_empty() {
         warn!(
-            "No items matched the provided path filters: {:?}",
-            user_paths
+            "No items matched the provided path filters: {:?} or allow-list: {:?}",
+            user_paths, allow_list
         );
         // Still return the full graph even if selection is empty
         return Ok((selected_ids, graph));
     }
 
     info!(
-        "Initially selected {} items based on path filters and resolved modules.",
+        "Initially selected {} items based on path filters, allow-list and resolved modules.",
         selected_ids.len()
     );
 
+    include_dependencies(&mut selected_ids, &graph, krate);
+    Ok((selected_ids, graph))
+}
+
+/// Removes the items whose canonical path matches one of the `exclude_paths`
+/// prefixes from `selected_ids`, normalizing the prefixes like the path filters of
+/// [`select_items`], along with the items they own (fields, variants, impls and
+/// associated items, which have no path of their own). Meant to run after
+/// selection, so exclusion wins over inclusion.
+#[doc(hidden)]
+pub fn exclude_items(krate: &Crate, selected_ids: &mut HashSet<Id>, exclude_paths: &[String]) {
+    if exclude_paths.is_empty() {
+        return;
+    }
+    let Some(crate_name) = krate
+        .index
+        .get(&krate.root)
+        .and_then(|root| root.name.as_ref())
+    else {
+        return;
+    };
+    let normalized_crate_name = crate_name.replace('-', "_");
+    let normalized_excludes: Vec<Vec<String>> = exclude_paths
+        .iter()
+        .map(|p| normalize_path(p, crate_name, &normalized_crate_name))
+        .collect();
+    info!("Normalized exclude filters: {:?}", normalized_excludes);
+
+    let before = selected_ids.len();
+    let mut excluded_ids = Vec::new();
+    selected_ids.retain(|id| {
+        let Some(item_summary) = krate.paths.get(id) else {
+            return true;
+        };
+        if item_summary.crate_id != 0 {
+            return true;
+        }
+        let mut qualified_item_path = item_summary.path.clone();
+        if !qualified_item_path.is_empty() && qualified_item_path[0] != normalized_crate_name {
+            qualified_item_path.insert(0, normalized_crate_name.clone());
+        }
+        let excluded = normalized_excludes
+            .iter()
+            .any(|filter| path_matches(&qualified_item_path, filter));
+        if excluded {
+            debug!(
+                "Exclude filter matched item {:?} ({:?})",
+                qualified_item_path, id
+            );
+            excluded_ids.push(*id);
+        }
+        !excluded
+    });
+    while let Some(id) = excluded_ids.pop() {
+        let Some(item) = krate.index.get(&id) else {
+            continue;
+        };
+        for owned_id in owned_items(item) {
+            if selected_ids.remove(&owned_id) {
+                excluded_ids.push(owned_id);
+            }
+        }
+    }
+    info!(
+        "Excluded {} items matching {:?}.",
+        before - selected_ids.len(),
+        exclude_paths
+    );
+}
+
+/// Returns the ids of the items `item` owns: the fields of a struct, union or variant,
+/// the variants of an enum, the items of a trait or impl, and the impls of a type.
+fn owned_items(item: &Item) -> Vec<Id> {
+    match &item.inner {
+        ItemEnum::Struct(s) => {
+            let fields = match &s.kind {
+                StructKind::Unit => vec![],
+                StructKind::Tuple(fields) => fields.iter().flatten().copied().collect(),
+                StructKind::Plain { fields, .. } => fields.clone(),
+            };
+            fields.into_iter().chain(s.impls.iter().copied()).collect()
+        }
+        ItemEnum::Union(u) => u.fields.iter().chain(&u.impls).copied().collect(),
+        ItemEnum::Enum(e) => e.variants.iter().chain(&e.impls).copied().collect(),
+        ItemEnum::Variant(v) => match &v.kind {
+            VariantKind::Plain => vec![],
+            VariantKind::Tuple(fields) => fields.iter().flatten().copied().collect(),
+            VariantKind::Struct { fields, .. } => fields.clone(),
+        },
+        ItemEnum::Trait(t) => t.items.clone(),
+        ItemEnum::Impl(i) => i.items.clone(),
+        _ => vec![],
+    }
+}
+
+/// Selects the items `ids` and recursively includes their dependencies.
+/// Builds the graph for *all* items in the crate, like [`select_items`].
+#[doc(hidden)]
+pub fn select_items_by_id(krate: &Crate, ids: &[Id]) -> (HashSet<Id>, IdGraph) {
+    let graph = build_full_graph(krate);
+    let mut selected_ids: HashSet<Id> = ids
+        .iter()
+        .filter(|id| krate.index.contains_key(id))
+        .cloned()
+        .collect();
+    include_dependencies(&mut selected_ids, &graph, krate);
+    (selected_ids, graph)
+}
+
+/// Builds the dependency graph for all items in the crate.
+fn build_full_graph(krate: &Crate) -> IdGraph {
+    let mut graph = IdGraph::new(); // Instantiate the graph
+    info!("Building full dependency graph...");
+    for id in krate.index.keys() {
+        build_graph_for_item(*id, krate, &mut graph);
+    }
+    info!("Built full graph with {} edges.", graph.edges.len());
+    graph
+}
+
+/// Adds the dependencies of `selected_ids` to it, following the graph's edges recursively.
+fn include_dependencies(selected_ids: &mut HashSet<Id>, graph: &IdGraph, krate: &Crate) {
     // --- Iterative dependency selection (using the pre-built graph) ---
     let mut queue: VecDeque<Id> = selected_ids.iter().cloned().collect();
     let mut visited_for_selection = HashSet::new(); // Keep track of visited nodes during selection traversal
@@ -926,8 +1186,6 @@ pub fn select_items(
         "Selected {} items after including dependencies.",
         selected_ids.len()
     );
-
-    Ok((selected_ids, graph))
 }
 
 /// Finds dependencies for a single item AND adds corresponding edges to the graph.
@@ -1326,7 +1584,7 @@ fn get_item_info_string(id: &Id, krate: &Crate) -> String {
     let kind_str = krate
         .index
         .get(id)
-        .map(|item| format!("{:?}", crate::Printer::infer_item_kind(item))) // Reuse infer_item_kind
+        .map(|item| format!("{:?}", infer_item_kind(item)))
         .or_else(|| {
             krate
                 .paths
@@ -1351,11 +1609,12 @@ fn dump_node(
     visited: &mut HashSet<Id>,        // Use mutable reference to shared visited set
     path_to_target: &mut HashSet<Id>, // Tracks current path to target leaf
     indent: usize,
-    depth: usize,                     // Current recursion depth
-    max_depth: Option<usize>,         // Maximum allowed depth
-    prefix: &str,                     // Prefix like "├── " or "└── "
-    parent_label: Option<&EdgeLabel>, // Label connecting this node to its parent
-    is_root_call: bool,               // Flag to know if this is the initial call for a root
+    depth: usize,                      // Current recursion depth
+    max_depth: Option<usize>,          // Maximum allowed depth
+    prefix: &str,                      // Prefix like "├── " or "└── "
+    parent_label: Option<&EdgeLabel>,  // Label connecting this node to its parent
+    is_root_call: bool,                // Flag to know if this is the initial call for a root
+    node_filter: Option<&HashSet<Id>>, // Only follow edges to these nodes, if given
 ) -> Result<()> {
     // Track current node in the path being explored towards the target
     let inserted_in_path = path_to_target.insert(node_id);
@@ -1402,12 +1661,13 @@ fn dump_node(
     if let Some(max) = max_depth {
         if depth >= max {
             // If we've reached max depth and there are children, indicate truncation
-            if is_newly_visited && graph.get_children(&node_id).is_some_and(|c| !c.is_empty()) {
+            let num_children = graph.get_children_within(&node_id, node_filter).count();
+            if is_newly_visited && num_children > 0 {
                 writeln!(
                     writer,
                     "{}{} [... children truncated due to max depth ...]",
                     " ".repeat(indent + 4), // Indent the truncation message
-                    if graph.get_children(&node_id).unwrap().len() == 1 {
+                    if num_children == 1 {
                         "└──"
                     } else {
                         "├──"
@@ -1426,37 +1686,38 @@ fn dump_node(
     // (If !is_newly_visited, we've already explored its children from a previous encounter)
     if is_newly_visited {
         // Get children from the potentially filtered graph and sort them
-        if let Some(children) = graph.get_children(&node_id) {
-            let mut sorted_children = children.to_vec(); // Clone to sort
-
-            // Sort by target Id primarily, then label for stability
-            sorted_children.sort_by_key(|(target_id, label)| (target_id.0, format!("{}", label)));
-
-            let num_children = sorted_children.len();
-            for (i, (child_id, child_label)) in sorted_children.iter().enumerate() {
-                let new_prefix = if i == num_children - 1 {
-                    "└── "
-                } else {
-                    "├── "
-                };
-                let child_indent = indent + 4; // Indent children further
+        let mut sorted_children: Vec<_> = graph
+            .get_children_within(&node_id, node_filter)
+            .cloned()
+            .collect();
+        // Sort by target Id primarily, then label for stability
+        sorted_children.sort_by_key(|(target_id, label)| (target_id.0, format!("{}", label)));
+
+        let num_children = sorted_children.len();
+        for (i, (child_id, child_label)) in sorted_children.iter().enumerate() {
+            let new_prefix = if i == num_children - 1 {
+                "└── "
+            } else {
+                "├── "
+            };
+            let child_indent = indent + 4; // Indent children further
 
-                // Recurse with the same mutable visited set and path_to_target set
-                dump_node(
-                    *child_id,
-                    graph, // Pass the same graph down
-                    krate,
-                    writer,
-                    visited,        // Pass mutable reference down
-                    path_to_target, // Pass mutable reference down
-                    child_indent,
-                    depth + 1, // Increment depth for child
-                    max_depth, // Pass max_depth down
-                    new_prefix,
-                    Some(child_label),
-                    false, // Not a root call anymore
-                )?;
-            }
+            // Recurse with the same mutable visited set and path_to_target set
+            dump_node(
+                *child_id,
+                graph, // Pass the same graph down
+                krate,
+                writer,
+                visited,        // Pass mutable reference down
+                path_to_target, // Pass mutable reference down
+                child_indent,
+                depth + 1, // Increment depth for child
+                max_depth, // Pass max_depth down
+                new_prefix,
+                Some(child_label),
+                false, // Not a root call anymore
+                node_filter,
+            )?;
         }
     }
 
@@ -1469,6 +1730,10 @@ fn dump_node(
 }
 
 /// Dumps a subset of the dependency graph to a writer.
+///
+/// With a `node_filter` (see [`IdGraph::nodes_reaching`]), only edges between the
+/// given nodes are followed, which dumps the same tree as the graph returned by
+/// [`IdGraph::filter_to_leaf`] without building it.
 #[doc(hidden)]
 pub fn dump_graph_subset(
     graph: &IdGraph, // Use the potentially filtered graph
@@ -1477,6 +1742,7 @@ pub fn dump_graph_subset(
     writer: &mut dyn IoWrite, // Changed to dyn Write
     dump_description: &str,
     max_depth: Option<usize>, // Add max_depth parameter
+    node_filter: Option<&HashSet<Id>>,
 ) -> Result<()> {
     // Use a single visited set for the entire dump process across all roots
     let mut visited = HashSet::new();
@@ -1485,10 +1751,19 @@ pub fn dump_graph_subset(
     // Sort roots by Id for consistent output
     sorted_roots.sort_by_key(|id| id.0);
 
-    if sorted_roots.is_empty() && !graph.edges.is_empty() {
+    let has_edges = match node_filter {
+        Some(nodes) => !nodes.is_empty(),
+        None => !graph.edges.is_empty(),
+    };
+    if sorted_roots.is_empty() && has_edges {
         writeln!(writer, "Warning: Graph has edges but no {} roots found (potentially due to filtering or cycles). Dumping all nodes alphabetically:", dump_description)?;
         // Fallback: dump all nodes if no roots found
-        let mut all_nodes: Vec<_> = graph.adjacency.keys().cloned().collect();
+        let mut all_nodes: Vec<_> = graph
+            .adjacency
+            .keys()
+            .filter(|id| node_filter.is_none_or(|nodes| nodes.contains(id)))
+            .cloned()
+            .collect();
         all_nodes.sort_by_key(|id| id.0);
         for node_id in all_nodes {
             // Check if already visited globally
@@ -1508,6 +1783,7 @@ pub fn dump_graph_subset(
                     "",        // No prefix for top-level nodes in fallback
                     None,
                     true, // It's a root call in this fallback context
+                    node_filter,
                 )?;
             }
         }
@@ -1531,6 +1807,7 @@ pub fn dump_graph_subset(
                     "",        // No prefix for root nodes
                     None,
                     true, // It's a root call
+                    node_filter,
                 )?;
             }
         }
@@ -1539,3 +1816,403 @@ pub fn dump_graph_subset(
 }
 
 // --- End Graph Dumping Logic ---
+
+/// A node of a [`GraphJson`] dump.
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeJson {
+    /// Canonical path of the item, if it has one (impls and fields don't).
+    pub path: Option<String>,
+    /// Name of the item, if it has one.
+    pub name: Option<String>,
+    /// Kind of the item, if known.
+    pub kind: Option<ItemKind>,
+}
+
+/// An edge of a [`GraphJson`] dump.
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct EdgeJson {
+    pub source: u32,
+    pub target: u32,
+    /// The [`EdgeLabel`] name, e.g. `ImplFor`.
+    pub label: String,
+}
+
+/// The JSON representation of a graph dump written by [`dump_graph_json`].
+#[doc(hidden)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphJson {
+    pub roots: Vec<u32>,
+    /// Nodes of all edges (and the roots), keyed by item id.
+    pub nodes: BTreeMap<u32, NodeJson>,
+    pub edges: Vec<EdgeJson>,
+}
+
+/// Collects the part of the graph reachable from `root_ids` (within `max_depth` edges).
+fn collect_graph_subset(
+    graph: &IdGraph,
+    krate: &Crate,
+    root_ids: &HashSet<Id>,
+    max_depth: Option<usize>,
+    node_filter: Option<&HashSet<Id>>,
+) -> GraphJson {
+    let mut roots: Vec<Id> = root_ids.iter().cloned().collect();
+    roots.sort_by_key(|id| id.0);
+
+    // Breadth-first, so each node is expanded at its smallest depth
+    let mut edges: HashSet<EdgeJson> = HashSet::new();
+    let mut depths: HashMap<Id, usize> = roots.iter().map(|id| (*id, 0)).collect();
+    let mut queue: VecDeque<Id> = roots.iter().cloned().collect();
+    while let Some(id) = queue.pop_front() {
+        let depth = depths[&id];
+        if max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+        for (child_id, label) in graph.get_children_within(&id, node_filter) {
+            edges.insert(EdgeJson {
+                source: id.0,
+                target: child_id.0,
+                label: label.to_string(),
+            });
+            if !depths.contains_key(child_id) {
+                depths.insert(*child_id, depth + 1);
+                queue.push_back(*child_id);
+            }
+        }
+    }
+    let mut edges: Vec<EdgeJson> = edges.into_iter().collect();
+    edges.sort_by(|a, b| (a.source, a.target, &a.label).cmp(&(b.source, b.target, &b.label)));
+
+    let nodes = depths
+        .keys()
+        .map(|id| {
+            let summary = krate.paths.get(id);
+            let item = krate.index.get(id);
+            let node = NodeJson {
+                path: summary.map(|summary| summary.path.join("::")),
+                name: item.and_then(|item| item.name.clone()),
+                kind: item
+                    .map(infer_item_kind)
+                    .or_else(|| summary.map(|summary| summary.kind)),
+            };
+            (id.0, node)
+        })
+        .collect();
+
+    GraphJson {
+        roots: roots.iter().map(|id| id.0).collect(),
+        nodes,
+        edges,
+    }
+}
+
+/// Dumps the part of the graph reachable from `root_ids` (within `max_depth` edges)
+/// as [`GraphJson`].
+#[doc(hidden)]
+pub fn dump_graph_json(
+    graph: &IdGraph,
+    krate: &Crate,
+    root_ids: &HashSet<Id>,
+    writer: &mut dyn IoWrite,
+    max_depth: Option<usize>,
+    node_filter: Option<&HashSet<Id>>,
+) -> Result<()> {
+    let dump = collect_graph_subset(graph, krate, root_ids, max_depth, node_filter);
+    serde_json::to_writer_pretty(&mut *writer, &dump)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Escapes a string for use inside a double-quoted Graphviz DOT identifier.
+fn escape_dot(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Dumps the part of the graph reachable from `root_ids` (within `max_depth` edges)
+/// as a Graphviz DOT digraph. Nodes are labeled with their canonical path (or name)
+/// and kind, edges with their [`EdgeLabel`]; roots are drawn with a bold outline.
+#[doc(hidden)]
+pub fn dump_graph_dot(
+    graph: &IdGraph,
+    krate: &Crate,
+    root_ids: &HashSet<Id>,
+    writer: &mut dyn IoWrite,
+    max_depth: Option<usize>,
+    node_filter: Option<&HashSet<Id>>,
+) -> Result<()> {
+    let dump = collect_graph_subset(graph, krate, root_ids, max_depth, node_filter);
+    let roots: HashSet<u32> = dump.roots.iter().cloned().collect();
+
+    writeln!(writer, "digraph items {{")?;
+    writeln!(writer, "    node [shape=box];")?;
+    for (id, node) in &dump.nodes {
+        let name = node
+            .path
+            .clone()
+            .or_else(|| node.name.clone())
+            .unwrap_or_else(|| format!("Id({})", id));
+        let label = match node.kind {
+            Some(kind) => format!("{}\n{:?}", name, kind),
+            None => name,
+        };
+        write!(writer, "    {} [label=\"{}\"", id, escape_dot(&label))?;
+        if roots.contains(id) {
+            write!(writer, ", style=bold")?;
+        }
+        writeln!(writer, "];")?;
+    }
+    for edge in &dump.edges {
+        writeln!(
+            writer,
+            "    {} -> {} [label=\"{}\"];",
+            edge.source,
+            edge.target,
+            escape_dot(&edge.label)
+        )?;
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/// Returns a Mermaid node ID for the node `id` named `name`: the name with every
+/// character that isn't ASCII alphanumeric replaced by `_`, suffixed with the ID so
+/// that distinct nodes with the same name stay distinct, e.g. `my_crate__Foo_12`.
+fn mermaid_node_id(name: &str, id: u32) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", sanitized, id)
+}
+
+/// Escapes a string for use inside a double-quoted Mermaid label.
+fn escape_mermaid(s: &str) -> String {
+    s.replace('"', "#quot;").replace('\n', "<br/>")
+}
+
+/// Dumps the part of the graph reachable from `root_ids` (within `max_depth` edges)
+/// as a Mermaid `graph TD` flowchart. Nodes are labeled with their canonical path (or
+/// name) and kind, edges with their [`EdgeLabel`]; roots get the `root` class.
+#[doc(hidden)]
+pub fn dump_graph_mermaid(
+    graph: &IdGraph,
+    krate: &Crate,
+    root_ids: &HashSet<Id>,
+    writer: &mut dyn IoWrite,
+    max_depth: Option<usize>,
+    node_filter: Option<&HashSet<Id>>,
+) -> Result<()> {
+    let dump = collect_graph_subset(graph, krate, root_ids, max_depth, node_filter);
+    let roots: HashSet<u32> = dump.roots.iter().cloned().collect();
+
+    writeln!(writer, "graph TD")?;
+    writeln!(writer, "    classDef root stroke-width:3px;")?;
+    let mut node_ids: HashMap<u32, String> = HashMap::new();
+    for (id, node) in &dump.nodes {
+        let name = node
+            .path
+            .clone()
+            .or_else(|| node.name.clone())
+            .unwrap_or_else(|| format!("Id({})", id));
+        let label = match node.kind {
+            Some(kind) => format!("{}\n{:?}", name, kind),
+            None => name.clone(),
+        };
+        let node_id = mermaid_node_id(&name, *id);
+        write!(writer, "    {}[\"{}\"]", node_id, escape_mermaid(&label))?;
+        if roots.contains(id) {
+            write!(writer, ":::root")?;
+        }
+        writeln!(writer)?;
+        node_ids.insert(*id, node_id);
+    }
+    for edge in &dump.edges {
+        let (Some(source), Some(target)) = (node_ids.get(&edge.source), node_ids.get(&edge.target))
+        else {
+            continue;
+        };
+        writeln!(
+            writer,
+            "    {} -->|\"{}\"| {}",
+            source,
+            escape_mermaid(&edge.label),
+            target
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A trait, a struct implementing it and a function using both.
+    const SHAPES: &str = "/// A trait.
+    pub trait Shape {}
+    /// A struct.
+    pub struct Square;
+    impl Shape for Square {}
+    /// A function.
+    pub fn area(_s: &dyn Shape) -> Square {
+        Square
+    }";
+
+    /// Runs rustdoc on a library crate with `source` as its `lib.rs`.
+    fn document(source: &str) -> Crate {
+        let dir = tempfile::tempdir().unwrap();
+        crate::document_in(dir.path(), "fixture", source, false).1
+    }
+
+    #[test]
+    fn graph_json_round_trips_the_edge_set() {
+        let krate = document(SHAPES);
+        let resolved_modules = build_resolved_module_index(&krate);
+        let (_, graph) = select_items(&krate, &[], &[], &resolved_modules).unwrap();
+        let mut buffer = Vec::new();
+        let roots = HashSet::from([krate.root]);
+        dump_graph_json(&graph, &krate, &roots, &mut buffer, None, None).unwrap();
+        let dump: GraphJson = serde_json::from_slice(&buffer).unwrap();
+
+        // Every edge reachable from the root, in the dump's representation
+        let mut expected = HashSet::new();
+        let mut seen = HashSet::from([krate.root]);
+        let mut queue = vec![krate.root];
+        while let Some(id) = queue.pop() {
+            for (child, label) in graph.adjacency.get(&id).into_iter().flatten() {
+                expected.insert((id.0, child.0, label.to_string()));
+                if seen.insert(*child) {
+                    queue.push(*child);
+                }
+            }
+        }
+        let edges: HashSet<(u32, u32, String)> = dump
+            .edges
+            .iter()
+            .map(|edge| (edge.source, edge.target, edge.label.clone()))
+            .collect();
+        assert!(!edges.is_empty());
+        assert_eq!(edges, expected);
+        assert_eq!(dump.edges.len(), edges.len());
+        assert_eq!(dump.roots, vec![krate.root.0]);
+        for (source, target, _) in &edges {
+            assert!(dump.nodes.contains_key(source) && dump.nodes.contains_key(target));
+        }
+    }
+
+    #[test]
+    fn graph_dot_counts_match_the_json_dump() {
+        let krate = document(SHAPES);
+        let resolved_modules = build_resolved_module_index(&krate);
+        let (_, graph) = select_items(&krate, &[], &[], &resolved_modules).unwrap();
+        let roots = HashSet::from([krate.root]);
+        let mut json = Vec::new();
+        dump_graph_json(&graph, &krate, &roots, &mut json, None, None).unwrap();
+        let dump: GraphJson = serde_json::from_slice(&json).unwrap();
+        let mut dot = Vec::new();
+        dump_graph_dot(&graph, &krate, &roots, &mut dot, None, None).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.starts_with("digraph items {\n"), "{}", dot);
+        assert!(dot.ends_with("}\n"), "{}", dot);
+        let body: Vec<&str> = dot
+            .lines()
+            .filter_map(|line| line.strip_prefix("    "))
+            .collect();
+        let is_id = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        let nodes = body
+            .iter()
+            .filter(|line| line.split_once(" [label=").is_some_and(|(id, _)| is_id(id)))
+            .count();
+        let edges = body
+            .iter()
+            .filter(|line| {
+                line.split_once(" [label=")
+                    .and_then(|(head, _)| head.split_once(" -> "))
+                    .is_some_and(|(source, target)| is_id(source) && is_id(target))
+            })
+            .count();
+        assert!(edges > 0, "{}", dot);
+        assert_eq!(nodes, dump.nodes.len(), "{}", dot);
+        assert_eq!(edges, dump.edges.len(), "{}", dot);
+    }
+
+    #[test]
+    fn streamed_leaf_dump_matches_the_filtered_graph() {
+        let krate = document(&format!(
+            "{}\n/// Unrelated.\npub fn other(_x: u8) {{}}",
+            SHAPES
+        ));
+        let resolved_modules = build_resolved_module_index(&krate);
+        let (_, graph) = select_items(&krate, &[], &[], &resolved_modules).unwrap();
+        let leaf = krate
+            .index
+            .values()
+            .find(|item| item.name.as_deref() == Some("Square"))
+            .unwrap()
+            .id;
+
+        let nodes = graph.nodes_reaching(leaf);
+        let mut streamed = Vec::new();
+        dump_graph_subset(
+            &graph,
+            &krate,
+            &graph.find_roots_within(&nodes),
+            &mut streamed,
+            "full",
+            None,
+            Some(&nodes),
+        )
+        .unwrap();
+
+        let filtered = graph.filter_to_leaf(leaf);
+        let mut materialized = Vec::new();
+        dump_graph_subset(
+            &filtered,
+            &krate,
+            &filtered.find_roots(),
+            &mut materialized,
+            "full",
+            None,
+            None,
+        )
+        .unwrap();
+
+        let streamed = String::from_utf8(streamed).unwrap();
+        assert!(streamed.contains("Square"), "{}", streamed);
+        assert!(!streamed.contains("other"), "{}", streamed);
+        assert_eq!(streamed, String::from_utf8(materialized).unwrap());
+    }
+
+    #[test]
+    fn graph_mermaid_has_one_line_per_reachable_edge() {
+        let krate = document(SHAPES);
+        let resolved_modules = build_resolved_module_index(&krate);
+        let (_, graph) = select_items(&krate, &[], &[], &resolved_modules).unwrap();
+        let roots = HashSet::from([krate.root]);
+        let mut json = Vec::new();
+        dump_graph_json(&graph, &krate, &roots, &mut json, None, None).unwrap();
+        let dump: GraphJson = serde_json::from_slice(&json).unwrap();
+        let mut mermaid = Vec::new();
+        dump_graph_mermaid(&graph, &krate, &roots, &mut mermaid, None, None).unwrap();
+        let mermaid = String::from_utf8(mermaid).unwrap();
+
+        assert!(mermaid.starts_with("graph TD\n"), "{}", mermaid);
+        let edges = mermaid
+            .lines()
+            .filter(|line| line.contains(" -->|"))
+            .count();
+        assert!(edges > 0, "{}", mermaid);
+        assert_eq!(edges, dump.edges.len(), "{}", mermaid);
+    }
+}
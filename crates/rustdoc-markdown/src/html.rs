@@ -0,0 +1,168 @@
+//! Conversion of the generated Markdown into a single self-contained HTML document.
+
+use crate::{parse_numbered_header, section_anchor};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser as CmarkParser, Tag, TagEnd};
+use std::fmt::Write as FmtWrite;
+
+/// Stylesheet embedded by [`render_standalone_html`] unless a custom one is given.
+pub const DEFAULT_CSS: &str = r#"
+body { margin: 0; font: 16px/1.6 -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; color: #1f2328; }
+nav { position: fixed; top: 0; bottom: 0; left: 0; width: 18rem; overflow-y: auto; padding: 1rem; box-sizing: border-box; background: #f6f8fa; border-right: 1px solid #d0d7de; font-size: 14px; }
+nav ul { list-style: none; margin: 0; padding-left: 1rem; }
+nav > ul { padding-left: 0; }
+nav a { color: #0969da; text-decoration: none; }
+nav a:hover { text-decoration: underline; }
+main { margin-left: 18rem; padding: 1rem 2rem; max-width: 60rem; }
+h1, h2, h3, h4, h5, h6 { line-height: 1.25; margin: 1.5em 0 0.5em; }
+h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 0.3em; }
+code { font: 85% ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; background: #eff1f3; padding: 0.2em 0.4em; border-radius: 6px; }
+pre { background: #f6f8fa; padding: 1rem; overflow-x: auto; border-radius: 6px; }
+pre code { background: none; padding: 0; font-size: 85%; }
+blockquote { margin: 0; padding: 0 1em; color: #59636e; border-left: 0.25em solid #d0d7de; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #d0d7de; padding: 6px 13px; }
+"#;
+
+/// Renders the Markdown produced by [`Printer::print`](crate::Printer::print) as a
+/// complete HTML document with an embedded stylesheet and a `<nav>` sidebar linking
+/// its H2 and H3 sections.
+///
+/// Numbered section headers get the same `section-...` ids as the links of
+/// [`Printer::toc`](crate::Printer::toc). `css` replaces [`DEFAULT_CSS`].
+pub fn render_standalone_html(markdown: &str, title: &str, css: Option<&str>) -> String {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_HEADING_ATTRIBUTES;
+    let mut events: Vec<Event> = CmarkParser::new_ext(markdown, options).collect();
+
+    // Collect the text of each heading, then give numbered headings a section id
+    let mut nav_entries: Vec<(usize, String, String)> = Vec::new(); // (level, id, text)
+    let mut open_heading: Option<(usize, String)> = None; // (event index, text)
+    for idx in 0..events.len() {
+        match &events[idx] {
+            Event::Start(Tag::Heading { .. }) => open_heading = Some((idx, String::new())),
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, heading_text)) = &mut open_heading {
+                    heading_text.push_str(text);
+                }
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let Some((start, text)) = open_heading.take() else {
+                    continue;
+                };
+                let level = *level as usize;
+                let Some(id) = parse_numbered_header(&format!("{} {}", "#".repeat(level), text))
+                    .map(|(_, number, _)| section_anchor(number))
+                else {
+                    continue;
+                };
+                if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[start] {
+                    *heading_id = Some(CowStr::from(id.clone()));
+                }
+                if matches!(level, 2 | 3) {
+                    nav_entries.push((level, id, text));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut body = String::with_capacity(markdown.len() * 2);
+    html::push_html(&mut body, events.into_iter());
+
+    // H3 entries are nested in the list item of the preceding H2 entry
+    let mut nav = String::from("<ul>\n");
+    let mut open_item = false;
+    let mut open_sublist = false;
+    for (level, id, text) in &nav_entries {
+        let link = format!("<a href=\"#{}\">{}</a>", id, escape_html(text));
+        if *level == 3 && open_item {
+            if !open_sublist {
+                nav.push_str("\n<ul>\n");
+                open_sublist = true;
+            }
+            writeln!(nav, "<li>{}</li>", link).unwrap();
+            continue;
+        }
+        if open_sublist {
+            nav.push_str("</ul>\n");
+            open_sublist = false;
+        }
+        if open_item {
+            nav.push_str("</li>\n");
+        }
+        write!(nav, "<li>{}", link).unwrap();
+        open_item = true;
+    }
+    if open_sublist {
+        nav.push_str("</ul>\n");
+    }
+    if open_item {
+        nav.push_str("</li>\n");
+    }
+    nav.push_str("</ul>\n");
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<nav>\n{}</nav>\n\
+         <main>\n{}</main>\n</body>\n</html>\n",
+        escape_html(title),
+        css.unwrap_or(DEFAULT_CSS),
+        nav,
+        body
+    )
+}
+
+/// Escapes the characters with a special meaning in HTML text and attributes.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standalone_html_is_a_complete_document() {
+        let markdown = "# fixture API (0.1.0)\n\n## 1: Module: `fixture`\n\n\
+                        ### 1.1: Structs\n\n#### 1.1.1: `struct fixture::A <B>`\n\nText.\n";
+        let html = render_standalone_html(markdown, "fixture & co", None);
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n"));
+        assert!(html.ends_with("</main>\n</body>\n</html>\n"), "{}", html);
+        assert!(html.contains("<title>fixture &amp; co</title>"), "{}", html);
+        assert!(
+            html.contains(&format!("<style>{}</style>", DEFAULT_CSS)),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<h2 id=\"section-1\">1: Module: <code>fixture</code></h2>"),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains(
+                "<h4 id=\"section-1-1-1\">1.1.1: <code>struct fixture::A &lt;B&gt;</code></h4>"
+            ),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("<a href=\"#section-1-1\">1.1: Structs</a>"),
+            "{}",
+            html
+        );
+    }
+}
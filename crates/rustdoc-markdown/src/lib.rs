@@ -32,6 +32,7 @@
 //!
 //! - [`Printer`]: The core struct responsible for orchestrating the documentation
 //!   generation process. It uses a builder pattern for configuration.
+//! - [`render_crate`]: Renders an already parsed crate with common [`RenderOptions`].
 //! - [`run_rustdoc`]: A utility function to execute `rustdoc` and parse its JSON output.
 //! - [`CrateExtraReader`]: Reads supplementary information like READMEs and examples.
 //!
@@ -40,7 +41,7 @@
 //! ```no_run
 //! use anyhow::Result;
 //! use cargo_manifest::Manifest;
-//! use rustdoc_markdown::{Printer, CrateExtraReader, run_rustdoc, cratesio};
+//! use rustdoc_markdown::{Printer, CrateExtraReader, RustdocOptions, run_rustdoc, cratesio};
 //! use std::path::Path;
 //! use reqwest::Client; // Add this line
 //!
@@ -60,6 +61,7 @@
 //!         crate_name,
 //!         version_req,
 //!         false, // include_prerelease
+//!         3,     // max_retries
 //!     )
 //!     .await?;
 //!
@@ -68,6 +70,7 @@
 //!         &client,
 //!         &target_version,
 //!         build_dir,
+//!         3, // max_retries
 //!     )
 //!     .await?;
 //!
@@ -79,10 +82,8 @@
 //!     let krate_data = run_rustdoc(
 //!         &crate_dir,
 //!         &target_version.crate_name,
-//!         None,    // features
-//!         false,   // no_default_features
-//!         None,    // target
-//!         true,    // allow_rustup (ensure nightly is available)
+//!         &RustdocOptions::default(), // Public items, default features
+//!         true,                       // allow_rustup (ensure nightly is available)
 //!     )?;
 //!
 //!     // 6. Read extra crate information (README, examples)
@@ -113,21 +114,26 @@ use cargo_manifest::{FeatureSet, Manifest as CargoManifest, StringOrBool}; // Re
 use graph::{Edge, IdGraph, ResolvedModule};
 use rustdoc_json::Builder;
 use rustdoc_types::{
-    Abi, Constant, Crate, Discriminant, Enum, Function, GenericArg, GenericArgs, GenericBound,
-    GenericParamDef, Generics, Id, Impl, Item, ItemEnum, ItemKind, Path, PolyTrait, Primitive,
-    Struct, StructKind, Term, Trait, Type, Union, Variant, VariantKind, WherePredicate,
+    Abi, Constant, Crate, Deprecation, Discriminant, Enum, Function, GenericArg, GenericArgs,
+    GenericBound, GenericParamDef, Generics, Id, Impl, Item, ItemEnum, ItemKind, Path, PolyTrait,
+    Primitive, Span, Struct, StructKind, Term, Trait, Type, Union, Variant, VariantKind,
+    WherePredicate,
 };
-use std::collections::{HashMap, HashSet}; // Use HashMap instead of BTreeMap where needed
+use std::borrow::Cow;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{HashMap, HashSet, VecDeque}; // Use HashMap instead of BTreeMap where needed
 use std::fmt::Write as FmtWrite; // Use FmtWrite alias
 use std::hash::{Hash, Hasher};
-use std::path::Path as FilePath; // Corrected use statement
+use std::path::{Path as FilePath, PathBuf}; // Corrected use statement
 use tracing::{debug, info, trace, warn};
 // Add fs import for CrateExtraReader
 use std::fs;
 use std::io::BufReader; // Added for reading JSON file
 
 // Import pulldown-cmark related items
-use pulldown_cmark::{Event, Parser as CmarkParser, Tag, TagEnd}; // Import Tag, TagEnd
+use pulldown_cmark::{
+    BrokenLink, CowStr, Event, LinkType, Options, Parser as CmarkParser, Tag, TagEnd,
+}; // Import Tag, TagEnd
 use pulldown_cmark_to_cmark::cmark;
 
 /// The specific nightly Rust toolchain version required by this crate.
@@ -138,6 +144,10 @@ pub mod cratesio;
 #[doc(hidden)]
 pub mod graph;
 
+pub mod html;
+
+pub use rustdoc_json::PackageTarget;
+
 // --- CrateExtra Structures ---
 
 /// Holds extra crate information like README and examples.
@@ -146,9 +156,9 @@ pub mod graph;
 /// included in the generated Markdown documentation.
 #[derive(Debug, Clone, Default)]
 pub struct CrateExtra {
-    /// The content of the crate's main `README.md` or `README` file.
+    /// The content of the crate's main README file (e.g. `README.md` or `Readme.markdown`).
     pub readme_content: Option<String>,
-    /// The content of the `README.md` or `README` file within the `examples/` directory.
+    /// The content of the README file within the `examples/` directory.
     pub examples_readme_content: Option<String>,
     /// A list of (filename, content) tuples for Rust files found in the `examples/` directory.
     pub examples: Vec<(String, String)>, // Vec of (filename, content)
@@ -161,6 +171,7 @@ pub struct CrateExtra {
 pub struct CrateExtraReader {
     read_readme: bool,
     read_examples: bool,
+    readme_file: Option<PathBuf>,
 }
 
 impl CrateExtraReader {
@@ -170,15 +181,25 @@ impl CrateExtraReader {
         Self {
             read_readme: true,
             read_examples: true,
+            readme_file: None,
         }
     }
 
-    /// Disables reading of the crate's main README file (`README.md` or `README`).
+    /// Disables reading of the crate's main README file (e.g. `README.md`).
     pub fn no_readme(mut self) -> Self {
         self.read_readme = false;
         self
     }
 
+    /// Reads the README from `path` instead of discovering it in the package directory.
+    ///
+    /// This takes precedence over [`no_readme()`](CrateExtraReader::no_readme) and the
+    /// `readme` field of `Cargo.toml`.
+    pub fn readme_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.readme_file = Some(path.into());
+        self
+    }
+
     /// Disables reading of example files from the `examples/` directory and its README.
     pub fn no_examples(mut self) -> Self {
         self.read_examples = false;
@@ -195,10 +216,19 @@ impl CrateExtraReader {
     /// # Returns
     ///
     /// A `Result` containing the [`CrateExtra`] data, or an error if reading fails.
+    /// A missing or unreadable [`readme_file`](CrateExtraReader::readme_file) is an
+    /// error, while problems with the discovered README are only logged.
     pub fn read(&self, manifest: &CargoManifest, package_dir: &FilePath) -> Result<CrateExtra> {
         let mut extra = CrateExtra::default();
 
-        if self.read_readme {
+        if let Some(path) = &self.readme_file {
+            if !path.is_file() {
+                bail!("README file not found: {}", path.display());
+            }
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read README file {}", path.display()))?;
+            extra.readme_content = Some(content);
+        } else if self.read_readme {
             let readme_path_from_manifest = manifest
                 .package
                 .as_ref()
@@ -210,14 +240,8 @@ impl CrateExtraReader {
                     Some(package_dir.join(readme_filename))
                 }
                 Some(StringOrBool::Bool(true)) | None => {
-                    // Default to README.md if true or not specified
-                    let default_readme_md = package_dir.join("README.md");
-                    if default_readme_md.exists() {
-                        Some(default_readme_md)
-                    } else {
-                        // Fallback to README if README.md doesn't exist
-                        Some(package_dir.join("README"))
-                    }
+                    // Default to a README file in the package root if true or not specified
+                    find_readme(package_dir)
                 }
                 Some(StringOrBool::Bool(false)) => {
                     info!("README explicitly disabled in Cargo.toml.");
@@ -246,13 +270,7 @@ impl CrateExtraReader {
         if self.read_examples {
             let examples_dir = package_dir.join("examples");
             if examples_dir.is_dir() {
-                let ex_readme_md_path = examples_dir.join("README.md");
-                let ex_readme_path = examples_dir.join("README");
-                if let Some(path) = ex_readme_md_path
-                    .exists()
-                    .then_some(ex_readme_md_path)
-                    .or_else(|| ex_readme_path.exists().then_some(ex_readme_path))
-                {
+                if let Some(path) = find_readme(&examples_dir) {
                     if let Ok(content) = fs::read_to_string(path) {
                         extra.examples_readme_content = Some(content);
                     }
@@ -281,6 +299,35 @@ impl CrateExtraReader {
     }
 }
 
+/// Finds the README file in `dir`, matching the `readme` file stem case-insensitively.
+///
+/// Markdown files (`.md`, then `.markdown`) are preferred over a plain `README`, which
+/// is preferred over `.txt` and any other extension.
+fn find_readme(dir: &FilePath) -> Option<PathBuf> {
+    let extension_rank = |path: &FilePath| match path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .as_deref()
+    {
+        Some("md") => 0,
+        Some("markdown") => 1,
+        None => 2,
+        Some("txt") => 3,
+        Some(_) => 4,
+    };
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_stem()
+                    .is_some_and(|stem| stem.eq_ignore_ascii_case("readme"))
+        })
+        .min_by_key(|path| (extension_rank(path), path.file_name().map(|n| n.to_owned())))
+}
+
 // --- Manifest Data ---
 
 #[derive(Debug, Clone, Default)]
@@ -289,6 +336,7 @@ struct CrateManifestData {
     homepage: Option<String>,
     repository: Option<String>,
     categories: Vec<String>,
+    keywords: Vec<String>,
     license: Option<String>,
     rust_version: Option<String>,
     edition: Option<String>,
@@ -316,6 +364,11 @@ impl CrateManifestData {
                 .and_then(|c| c.as_ref().as_local())
                 .cloned()
                 .unwrap_or_default(),
+            keywords: package_data
+                .and_then(|p| p.keywords.as_ref())
+                .and_then(|k| k.as_ref().as_local())
+                .cloned()
+                .unwrap_or_default(),
             license: package_data
                 .and_then(|p| p.license.as_ref())
                 .and_then(|l| l.as_ref().as_local())
@@ -333,6 +386,120 @@ impl CrateManifestData {
     }
 }
 
+/// Ensures the pinned nightly toolchain ([`NIGHTLY_RUST_VERSION`]) is installed,
+/// installing it with `rustup` if necessary.
+///
+/// Call this before any slow or network work (such as downloading a crate) so a
+/// missing toolchain is reported up front.
+///
+/// # Errors
+///
+/// Returns an error suggesting the manual `rustup toolchain install` command if
+/// `rustup` is unavailable or the installation fails.
+pub fn install_toolchain() -> Result<()> {
+    install_named_toolchain(NIGHTLY_RUST_VERSION)
+}
+
+/// Installs `toolchain` with `rustup` if necessary, see [`install_toolchain`].
+fn install_named_toolchain(toolchain: &str) -> Result<()> {
+    rustup_toolchain::install(toolchain).with_context(|| {
+        format!(
+            "Failed to install the required toolchain `{0}`. \
+             Make sure `rustup` is on PATH, or install it manually with \
+             `rustup toolchain install {0}`",
+            toolchain
+        )
+    })
+}
+
+/// The flags [`run_rustdoc`] and [`build_rustdoc_json`] build the rustdoc JSON with.
+///
+/// The default documents the public items of the library with its default
+/// features, for the host target.
+#[derive(Debug, Clone, Default)]
+pub struct RustdocOptions {
+    /// An optional space-separated string of features to enable.
+    pub features: Option<String>,
+    /// If `true`, every feature of the crate is activated.
+    pub all_features: bool,
+    /// If `true`, the `default` feature will not be activated.
+    pub no_default_features: bool,
+    /// An optional target triple to build documentation for.
+    pub target: Option<String>,
+    /// The library, binary or example of the package to document.
+    pub package_target: PackageTarget,
+    /// If `true`, private items are documented as well.
+    pub document_private_items: bool,
+    /// If `true`, `#[doc(hidden)]` items are kept in the JSON (see
+    /// [`Printer::include_hidden`]).
+    pub document_hidden_items: bool,
+}
+
+/// Returns the path under `crate_dir/target/doc` where [`run_rustdoc`] caches the
+/// rustdoc JSON of `crate_name` built with `options`.
+///
+/// The file name carries a hash of the (sorted) feature list, the other
+/// [`RustdocOptions`] and [`NIGHTLY_RUST_VERSION`], e.g.
+/// `my_crate-0f3a9c1d2b4e5f60.json`, so builds with different flags never share a
+/// cached file.
+pub fn rustdoc_json_cache_path(
+    crate_dir: &FilePath,
+    crate_name: &str,
+    options: &RustdocOptions,
+) -> PathBuf {
+    let mut feature_list: Vec<&str> = options
+        .features
+        .as_deref()
+        .unwrap_or("")
+        .split_whitespace()
+        .collect();
+    feature_list.sort_unstable();
+    feature_list.dedup();
+    let key = format!(
+        "{}\0{}\0{}\0{}\0{}\0{:?}\0{}\0{}",
+        NIGHTLY_RUST_VERSION,
+        feature_list.join(" "),
+        options.all_features,
+        options.no_default_features,
+        options.target.as_deref().unwrap_or(""),
+        options.package_target,
+        options.document_private_items,
+        options.document_hidden_items
+    );
+    // FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+    let hash = key.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    crate_dir.join("target/doc").join(format!(
+        "{}-{:016x}.json",
+        crate_name.replace('-', "_"),
+        hash
+    ))
+}
+
+/// Returns the most recent modification time of the files in `dir`, skipping
+/// `target` and hidden directories.
+fn newest_source_mtime(dir: &FilePath) -> Option<std::time::SystemTime> {
+    let mut newest = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let mtime = if metadata.is_dir() {
+            let name = entry.file_name();
+            if name == "target" || name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            newest_source_mtime(&path)
+        } else {
+            metadata.modified().ok()
+        };
+        newest = newest.max(mtime);
+    }
+    newest
+}
+
 /// Runs `rustdoc` for a given crate and parses the resulting JSON output.
 ///
 /// This function uses the `rustdoc-json` crate to invoke `rustdoc` with the
@@ -343,12 +510,14 @@ impl CrateManifestData {
 ///
 /// * `crate_dir`: Path to the root directory of the crate.
 /// * `crate_name`: The name of the crate (as it appears in `Cargo.toml`).
-/// * `features`: An optional space-separated string of features to enable.
-/// * `no_default_features`: If `true`, the `default` feature will not be activated.
-/// * `target`: An optional target triple to build documentation for.
+/// * `options`: The features, target and other flags to build with.
 /// * `allow_rustup`: If `true`, the function will attempt to install the required
-///   nightly toolchain using `rustup`. If `false` and the toolchain is not
-///   present, it may fail.
+///   nightly toolchain using [`install_toolchain`]. If `false` and the toolchain is
+///   not present, it may fail.
+///
+/// The JSON is cached at [`rustdoc_json_cache_path`] and reused as long as no file
+/// of the crate changed since, so runs with different features or targets don't
+/// pick up each other's output.
 ///
 /// # Returns
 ///
@@ -357,11 +526,37 @@ impl CrateManifestData {
 pub fn run_rustdoc(
     crate_dir: &FilePath,
     crate_name: &str,
-    features: Option<&str>,
-    no_default_features: bool,
-    target: Option<&str>,
+    options: &RustdocOptions,
+    allow_rustup: bool,
+) -> Result<Crate> {
+    let json_path = build_rustdoc_json(crate_dir, crate_name, options, allow_rustup)?;
+    read_rustdoc_json(&json_path, crate_name)
+}
+
+/// Like [`run_rustdoc`], recording the `rustdoc build` and `json parse` phases in
+/// `timings`.
+pub fn run_rustdoc_timed(
+    crate_dir: &FilePath,
+    crate_name: &str,
+    options: &RustdocOptions,
     allow_rustup: bool,
+    timings: &mut PhaseTimings,
 ) -> Result<Crate> {
+    let json_path = timings.time("rustdoc build", || {
+        build_rustdoc_json(crate_dir, crate_name, options, allow_rustup)
+    })?;
+    timings.time("json parse", || read_rustdoc_json(&json_path, crate_name))
+}
+
+/// The build half of [`run_rustdoc`]: runs `rustdoc` with the same arguments (or
+/// reuses a fresh cached build) and returns the path of the JSON file without
+/// parsing it. Pair it with [`read_rustdoc_json`] to time the two steps separately.
+pub fn build_rustdoc_json(
+    crate_dir: &FilePath,
+    crate_name: &str,
+    options: &RustdocOptions,
+    allow_rustup: bool,
+) -> Result<PathBuf> {
     let manifest_path = crate_dir.join("Cargo.toml");
     if !manifest_path.exists() {
         bail!(
@@ -371,25 +566,58 @@ pub fn run_rustdoc(
     }
 
     if allow_rustup {
-        // Install the required nightly toolchain
-        rustup_toolchain::install(NIGHTLY_RUST_VERSION).unwrap();
+        install_toolchain()?;
+    }
+
+    let cache_path = rustdoc_json_cache_path(crate_dir, crate_name, options);
+    let cache_is_fresh = fs::metadata(&cache_path)
+        .and_then(|metadata| metadata.modified())
+        .is_ok_and(|cached| newest_source_mtime(crate_dir).is_none_or(|newest| newest <= cached));
+    if cache_is_fresh {
+        info!("Reusing cached rustdoc JSON: {}", cache_path.display());
+        return Ok(cache_path);
     }
 
     info!("Generating rustdoc JSON using rustdoc-json crate...");
 
-    let crate_name_underscore = crate_name.replace('-', "_");
-    let json_output_path = crate_dir
-        .join("target/doc")
-        .join(format!("{}.json", crate_name_underscore));
+    // Binaries and examples are documented under their own crate name
+    let target_crate_name = match &options.package_target {
+        PackageTarget::Bin(name)
+        | PackageTarget::Example(name)
+        | PackageTarget::Test(name)
+        | PackageTarget::Bench(name) => name,
+        _ => crate_name, // The enum is non-exhaustive, `Lib` is the default
+    };
+    // Cargo puts the docs of an explicit target under `target/<triple>/doc`
+    let json_output_path = match &options.target {
+        Some(target_str) => crate_dir.join("target").join(target_str).join("doc"),
+        None => crate_dir.join("target/doc"),
+    }
+    .join(format!("{}.json", target_crate_name.replace('-', "_")));
 
     let mut builder = Builder::default()
         .manifest_path(manifest_path)
         .toolchain(NIGHTLY_RUST_VERSION) // Specify the nightly toolchain
         .target_dir(crate_dir.join("target")) // Set the output directory
-        .package(crate_name); // Specify the package
+        .package(crate_name) // Specify the package
+        .package_target(options.package_target.clone());
+
+    if options.document_private_items {
+        info!("Documenting private items.");
+        builder = builder.document_private_items(true);
+    }
+
+    if options.document_hidden_items {
+        // The builder has no option for this unstable flag, but cargo passes
+        // RUSTDOCFLAGS to rustdoc along with the builder's `-Z unstable-options`
+        info!("Documenting #[doc(hidden)] items.");
+        let mut rustdoc_flags = std::env::var("RUSTDOCFLAGS").unwrap_or_default();
+        rustdoc_flags.push_str(" --document-hidden-items");
+        builder = builder.env("RUSTDOCFLAGS", rustdoc_flags.trim_start());
+    }
 
     // Apply feature flags
-    if let Some(features_str) = features {
+    if let Some(features_str) = &options.features {
         let feature_list: Vec<String> = features_str.split_whitespace().map(String::from).collect();
         if !feature_list.is_empty() {
             info!("Enabling features: {:?}", feature_list);
@@ -397,21 +625,35 @@ pub fn run_rustdoc(
         }
     }
 
-    if no_default_features {
+    if options.all_features {
+        info!("Enabling all features.");
+        builder = builder.all_features(true);
+    }
+
+    if options.no_default_features {
         info!("Disabling default features.");
         builder = builder.no_default_features(true);
     }
 
     // Apply target
-    if let Some(target_str) = target {
+    if let Some(target_str) = &options.target {
         info!("Setting target: {}", target_str);
-        builder = builder.target(target_str.to_string());
+        builder = builder.target(target_str.clone());
     }
 
     // Generate the JSON file
     match builder.build() {
         Ok(s) => {
             info!("Generated rustdoc JSON at: {}", s.display());
+            // With a target, nothing else is written to `target/doc`
+            if let Some(cache_dir) = cache_path.parent() {
+                fs::create_dir_all(cache_dir).with_context(|| {
+                    format!("Failed to create cache directory {}", cache_dir.display())
+                })?;
+            }
+            fs::copy(&s, &cache_path).with_context(|| {
+                format!("Failed to cache rustdoc JSON at {}", cache_path.display())
+            })?;
         }
         Err(e) => {
             // Attempt to read stderr if possible (rustdoc-json might not expose it easily)
@@ -433,12 +675,17 @@ pub fn run_rustdoc(
         }
     }
 
-    info!("Parsing rustdoc JSON: {}", json_output_path.display());
-    let file = fs::File::open(&json_output_path)
-        .with_context(|| format!("Failed to open JSON file: {}", json_output_path.display()))?;
+    Ok(cache_path)
+}
+
+/// Parses the rustdoc JSON file of `crate_name` at `json_path`.
+pub fn read_rustdoc_json(json_path: &FilePath, crate_name: &str) -> Result<Crate> {
+    info!("Parsing rustdoc JSON: {}", json_path.display());
+    let file = fs::File::open(json_path)
+        .with_context(|| format!("Failed to open JSON file: {}", json_path.display()))?;
     let reader = BufReader::new(file);
     let krate_data: Crate = serde_json::from_reader(reader)
-        .with_context(|| format!("Failed to parse JSON file: {}", json_output_path.display()))?;
+        .with_context(|| format!("Failed to parse JSON file: {}", json_path.display()))?;
     info!(
         "Loaded rustdoc JSON for {} v{}",
         crate_name,
@@ -447,6 +694,48 @@ pub fn run_rustdoc(
     Ok(krate_data)
 }
 
+/// Lists the files that changed between `git_ref` and the working tree of the git
+/// repository containing `dir`, including untracked files.
+///
+/// The returned paths are absolute, suitable for [`Printer::changed_files`].
+///
+/// # Errors
+///
+/// Returns an error if `dir` is not inside a (non-bare) git repository, `git_ref`
+/// cannot be resolved to a tree, or the diff fails.
+pub fn changed_files_since(dir: &FilePath, git_ref: &str) -> Result<Vec<PathBuf>> {
+    let repo = git2::Repository::discover(dir)
+        .with_context(|| format!("Failed to find a git repository at {}", dir.display()))?;
+    let workdir = repo
+        .workdir()
+        .context("Cannot diff a bare git repository")?
+        .to_path_buf();
+    let tree = repo
+        .revparse_single(git_ref)
+        .and_then(|object| object.peel_to_tree())
+        .with_context(|| format!("Failed to resolve git ref `{}`", git_ref))?;
+    let mut options = git2::DiffOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let diff = repo
+        .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut options))
+        .with_context(|| format!("Failed to diff working tree against `{}`", git_ref))?;
+
+    let mut files = Vec::new();
+    for delta in diff.deltas() {
+        for path in [delta.old_file().path(), delta.new_file().path()]
+            .into_iter()
+            .flatten()
+        {
+            let path = workdir.join(path);
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    info!("{} files changed since `{}`", files.len(), git_ref);
+    Ok(files)
+}
+
 /// Gets the `Id` associated with a type, if it's a path-based type.
 pub(crate) fn get_type_id(ty: &Type) -> Option<Id> {
     match ty {
@@ -467,15 +756,82 @@ pub(crate) fn get_type_id(ty: &Type) -> Option<Id> {
     }
 }
 
+/// Infers the `ItemKind` of an item from its contents, e.g. for items missing from
+/// the crate's paths map.
+pub(crate) fn infer_item_kind(item: &Item) -> ItemKind {
+    match item.inner {
+        ItemEnum::Module(_) => ItemKind::Module,
+        ItemEnum::ExternCrate { .. } => ItemKind::ExternCrate,
+        ItemEnum::Use { .. } => ItemKind::Use, // Keep Use kind for completeness
+        ItemEnum::Union(_) => ItemKind::Union,
+        ItemEnum::Struct(_) => ItemKind::Struct,
+        ItemEnum::StructField(_) => ItemKind::StructField,
+        ItemEnum::Enum(_) => ItemKind::Enum,
+        ItemEnum::Variant(_) => ItemKind::Variant,
+        ItemEnum::Function(_) => ItemKind::Function,
+        ItemEnum::Trait(_) => ItemKind::Trait,
+        ItemEnum::TraitAlias(_) => ItemKind::TraitAlias,
+        ItemEnum::Impl { .. } => ItemKind::Impl,
+        ItemEnum::TypeAlias(_) => ItemKind::TypeAlias,
+        ItemEnum::Constant { .. } => ItemKind::Constant, // Use struct pattern
+        ItemEnum::Static(_) => ItemKind::Static,
+        ItemEnum::ExternType => ItemKind::ExternType, // Renamed
+        ItemEnum::Macro(_) => ItemKind::Macro,
+        ItemEnum::ProcMacro(ref pm) => match pm.kind {
+            rustdoc_types::MacroKind::Bang => ItemKind::Macro, // Treat bang proc macro as Macro kind
+            rustdoc_types::MacroKind::Attr => ItemKind::ProcAttribute,
+            rustdoc_types::MacroKind::Derive => ItemKind::ProcDerive,
+        },
+        ItemEnum::Primitive(_) => ItemKind::Primitive,
+        ItemEnum::AssocConst { .. } => ItemKind::AssocConst,
+        ItemEnum::AssocType { .. } => ItemKind::AssocType,
+    }
+}
+
 // --- Formatting Helpers ---
 
-/// Formats a list of attributes, filtering out derive attributes.
+/// Performance and diagnostics attributes that don't change how an item is used.
+/// They are shown in a note (see [`format_metadata_attributes_note`]) rather than in
+/// declarations.
+const METADATA_ATTRIBUTES: &[&str] = &["#[track_caller]", "#[cold]", "#[inline"];
+
+/// Returns `true` if the attribute is one of [`METADATA_ATTRIBUTES`], e.g.
+/// `#[inline(always)]`.
+fn is_metadata_attribute(attr: &str) -> bool {
+    METADATA_ATTRIBUTES.iter().any(|prefix| {
+        attr.strip_prefix(prefix)
+            .is_some_and(|rest| prefix.ends_with(']') || rest.starts_with(['(', ']']))
+    })
+}
+
+/// Returns `true` if the attribute belongs in a declaration, i.e. it is not a derive
+/// (listed with the trait implementations), a `cfg` (see [`format_cfg_note`]) or a
+/// metadata attribute (see [`format_metadata_attributes_note`]).
+fn is_declaration_attribute(attr: &str) -> bool {
+    !attr.starts_with("#[derive(") && !attr.starts_with("#[cfg(") && !is_metadata_attribute(attr)
+}
+
+/// Formats the [`METADATA_ATTRIBUTES`] of an item as a note, e.g.
+/// `_Attributes: `#[track_caller]`_`.
+fn format_metadata_attributes_note(attrs: &[String]) -> Option<String> {
+    let metadata: Vec<String> = attrs
+        .iter()
+        .filter(|attr| is_metadata_attribute(attr))
+        .map(|attr| format!("`{}`", attr))
+        .collect();
+    if metadata.is_empty() {
+        return None;
+    }
+    Some(format!("_Attributes: {}_", metadata.join(", ")))
+}
+
+/// Formats a list of attributes, keeping only [declaration attributes](is_declaration_attribute).
 /// Each attribute is on a new line.
 /// Returns a string like `#[attr1]\n#[attr2]\n` (with a trailing newline if not empty).
 fn format_attributes(attrs: &[String]) -> String {
     let filtered_attrs: Vec<String> = attrs
         .iter()
-        .filter(|attr| !attr.starts_with("#[derive("))
+        .filter(|attr| is_declaration_attribute(attr))
         .cloned()
         .collect();
 
@@ -489,12 +845,13 @@ fn format_attributes(attrs: &[String]) -> String {
     }
 }
 
-/// Formats a list of attributes, filtering out derive attributes, for inline display.
+/// Formats a list of attributes, keeping only [declaration attributes](is_declaration_attribute),
+/// for inline display.
 /// Returns a string like `#[attr1] #[attr2] ` (with a trailing space if not empty).
 fn format_attributes_inline(attrs: &[String]) -> String {
     let filtered_attrs: Vec<String> = attrs
         .iter()
-        .filter(|attr| !attr.starts_with("#[derive("))
+        .filter(|attr| is_declaration_attribute(attr))
         .cloned()
         .collect();
 
@@ -505,17 +862,287 @@ fn format_attributes_inline(attrs: &[String]) -> String {
     }
 }
 
+/// Removes ANSI escape sequences (e.g. `\x1b[31m`) and other control characters,
+/// except tabs and newlines, from doc text so printing the Markdown to a terminal
+/// can't be hijacked by a crate's docs.
+fn strip_control_chars(text: &str) -> Cow<'_, str> {
+    let is_stripped = |c: char| c.is_control() && c != '\n' && c != '\t';
+    if !text.chars().any(is_stripped) {
+        return Cow::Borrowed(text);
+    }
+    let mut cleaned = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if !is_stripped(c) {
+                cleaned.push(c);
+            }
+            continue;
+        }
+        let mut introducer = chars.next();
+        // Docs re-serialized as Markdown have the introducer escaped (`\[`, `\]`)
+        if introducer == Some('\\') && matches!(chars.peek(), Some('[' | ']')) {
+            introducer = chars.next();
+        }
+        match introducer {
+            // CSI: parameters and intermediates up to a final byte in `@`..=`~`
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or the string terminator `ESC \`
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            // Other escapes are two characters long
+            _ => {}
+        }
+    }
+    Cow::Owned(cleaned)
+}
+
+/// Doc-test attributes rustdoc accepts in the info string of a Rust code block.
+const DOCTEST_ATTRIBUTES: &[&str] = &[
+    "rust",
+    "no_run",
+    "ignore",
+    "should_panic",
+    "compile_fail",
+    "test_harness",
+    "standalone_crate",
+];
+
+/// Returns `true` if a code block with the info string `info` is a Rust doc-test,
+/// i.e. it is empty or only has doc-test attributes like `no_run` or `edition2021`.
+fn is_rust_code_block(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attr| !attr.is_empty())
+        .all(|attr| {
+            DOCTEST_ATTRIBUTES.contains(&attr)
+                || attr.starts_with("edition")
+                || attr.starts_with("ignore-")
+                || attr.starts_with('{') // Attribute sets like `{.class}`
+        })
+}
+
+/// The opening fence of a fenced code block: three or more backticks or tildes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CodeFence {
+    fence_char: char,
+    len: usize,
+}
+
+impl CodeFence {
+    /// Parses `line` as an opening fence, returning the fence and its info string.
+    fn open(line: &str) -> Option<(CodeFence, &str)> {
+        let content = line.trim();
+        let fence_char = content.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+        let len = content.chars().take_while(|c| *c == fence_char).count();
+        (len >= 3).then_some((CodeFence { fence_char, len }, &content[len..]))
+    }
+
+    /// Returns `true` if `line` closes the code block opened by this fence, i.e. it
+    /// only holds at least as many of the same fence characters.
+    fn is_closed_by(self, line: &str) -> bool {
+        let content = line.trim();
+        content.len() >= self.len && content.chars().all(|c| c == self.fence_char)
+    }
+}
+
+/// Removes the lines hidden by rustdoc (`# let x = 1;` or a bare `#`) from the Rust
+/// code blocks of `docs`, and unescapes `##` to `#`, as rustdoc does when rendering.
+/// The info strings of the code blocks are kept as is.
+fn strip_hidden_doc_lines(docs: &str) -> Cow<'_, str> {
+    if !docs.contains('#') {
+        return Cow::Borrowed(docs);
+    }
+    let mut stripped = String::with_capacity(docs.len());
+    let mut open_fence: Option<(CodeFence, bool)> = None; // (Fence, is Rust)
+    for line in docs.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let content = trimmed.trim_end();
+        match open_fence {
+            None => {
+                if let Some((fence, info)) = CodeFence::open(line) {
+                    open_fence = Some((fence, is_rust_code_block(info)));
+                }
+            }
+            Some((fence, _)) if fence.is_closed_by(line) => {
+                open_fence = None; // Closing fence
+            }
+            Some((_, true)) => {
+                if content == "#" || content.starts_with("# ") || content.starts_with("#\t") {
+                    continue; // Hidden line
+                }
+                if let Some(rest) = trimmed.strip_prefix("##") {
+                    stripped.push_str(&line[..line.len() - trimmed.len()]);
+                    stripped.push('#');
+                    stripped.push_str(rest);
+                    continue;
+                }
+            }
+            Some((_, false)) => {}
+        }
+        stripped.push_str(line);
+    }
+    Cow::Owned(stripped)
+}
+
 /// Helper to check if an item has non-empty documentation.
 fn has_docs(item: &Item) -> bool {
     item.docs.as_ref().is_some_and(|d| !d.trim().is_empty())
 }
 
+/// How an intra-doc link of an item's docs is rendered, see [`adjust_markdown_headers_with_links`].
+enum DocLink {
+    /// Link to an in-document anchor, e.g. `#fx-foo`.
+    Anchor(String),
+    /// Link to the online docs of an item in another crate.
+    Url(String),
+    /// Unlink, then refer to the section the target was printed in.
+    Section(String),
+    /// Unlink, then add a marker for the cross-reference footnote with this label.
+    Footnote(String),
+    /// Unlink, keeping the link text (the target is not documented).
+    Unlinked,
+    /// Unlink, rendering bare `[Name]` links as inline code (the target is in another crate).
+    External,
+}
+
 /// Adjusts the markdown header levels in a string using pulldown-cmark.
 /// Increases the level of each header (e.g., `#` -> `###`) based on the base level.
 /// Caps the maximum level at 6 (`######`).
 fn adjust_markdown_headers(markdown: &str, base_level: usize) -> String {
-    let parser = CmarkParser::new(markdown);
-    let transformed_events = parser.map(|event| match event {
+    adjust_markdown_headers_with_links(markdown, base_level, &HashMap::new())
+}
+
+/// Like [`adjust_markdown_headers`], but also rewrites the intra-doc links whose
+/// destination (e.g. `` `Foo` `` or `crate::Foo`) is a key of `links`.
+fn adjust_markdown_headers_with_links(
+    markdown: &str,
+    base_level: usize,
+    links: &HashMap<String, DocLink>,
+) -> String {
+    // Intra-doc links like [`Foo`] have no link definition; resolve the known ones
+    // to links so they can be rewritten below instead of being escaped as text.
+    let mut resolve_broken_link = |link: BrokenLink<'_>| {
+        links
+            .contains_key(link.reference.as_ref())
+            .then(|| (CowStr::from(link.reference.to_string()), CowStr::from("")))
+    };
+    // Footnotes must be parsed as such, otherwise `[^1]` references and their
+    // definitions are escaped as literal text on re-serialization. Heading
+    // attributes (`# Title {#id .class}`) are parsed so they are kept on the
+    // re-leveled heading instead of being treated as part of its text.
+    let parser = CmarkParser::new_with_broken_link_callback(
+        markdown,
+        Options::ENABLE_FOOTNOTES | Options::ENABLE_HEADING_ATTRIBUTES,
+        Some(&mut resolve_broken_link),
+    );
+    // The rewrite of the link currently open if it is being unlinked, and whether
+    // it is a bare `[Name]` link
+    let mut open_link: Option<(&DocLink, bool)> = None;
+    // Attributes of the heading currently open, re-emitted verbatim at its end
+    let mut heading_attrs = String::new();
+    let transformed_events = parser.flat_map(|event| match event {
+        Event::Start(Tag::Heading {
+            level,
+            id,
+            classes,
+            attrs,
+        }) => {
+            heading_attrs = format_heading_attributes(id, &classes, &attrs);
+            let start = Event::Start(Tag::Heading {
+                level,
+                id: None,
+                classes: Vec::new(),
+                attrs: Vec::new(),
+            });
+            vec![adjust_heading_event(start, base_level)]
+        }
+        Event::End(TagEnd::Heading(_)) if !heading_attrs.is_empty() => vec![
+            Event::Text(CowStr::from(std::mem::take(&mut heading_attrs))),
+            adjust_heading_event(event, base_level),
+        ],
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => match links.get(dest_url.as_ref()) {
+            Some(DocLink::Anchor(url) | DocLink::Url(url)) => vec![Event::Start(Tag::Link {
+                link_type: LinkType::Inline,
+                dest_url: CowStr::from(url.clone()),
+                title,
+                id,
+            })],
+            Some(link) => {
+                let is_bare = matches!(link_type, LinkType::Shortcut | LinkType::ShortcutUnknown);
+                open_link = Some((link, is_bare));
+                vec![]
+            }
+            None => vec![Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            })],
+        },
+        Event::End(TagEnd::Link) => match open_link.take() {
+            Some((DocLink::Section(prefix), _)) => vec![Event::Text(CowStr::from(format!(
+                " (See section {} for details)",
+                prefix
+            )))],
+            Some((DocLink::Footnote(label), _)) => {
+                vec![Event::FootnoteReference(CowStr::from(label.clone()))]
+            }
+            Some(_) => vec![],
+            None => vec![event],
+        },
+        Event::Text(text) if matches!(open_link, Some((DocLink::External, true))) => {
+            vec![Event::Code(text)]
+        }
+        event => vec![adjust_heading_event(event, base_level)],
+    });
+
+    let mut out_buf = String::with_capacity(markdown.len() + 128); // Pre-allocate slightly
+    cmark(transformed_events, &mut out_buf).expect("Markdown formatting failed");
+    out_buf
+}
+
+/// Formats heading attributes in the compact `{#id .class key=value}` syntax,
+/// preceded by a space, or returns an empty string if there are none.
+///
+/// `pulldown_cmark_to_cmark` would pad the braces with spaces (`{ #id }`); writing
+/// the attributes as text keeps them in the form doc comments use.
+fn format_heading_attributes(
+    id: Option<CowStr<'_>>,
+    classes: &[CowStr<'_>],
+    attrs: &[(CowStr<'_>, Option<CowStr<'_>>)],
+) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    parts.extend(id.map(|id| format!("#{}", id)));
+    parts.extend(classes.iter().map(|class| format!(".{}", class)));
+    parts.extend(attrs.iter().map(|(key, value)| match value {
+        Some(value) => format!("{}={}", key, value),
+        None => key.to_string(),
+    }));
+    if parts.is_empty() {
+        return String::new();
+    }
+    format!(" {{{}}}", parts.join(" "))
+}
+
+/// Shifts the level of heading start/end events by `base_level`, capped at 6.
+fn adjust_heading_event(event: Event<'_>, base_level: usize) -> Event<'_> {
+    match event {
         Event::Start(Tag::Heading {
             level,
             id,
@@ -557,11 +1184,7 @@ fn adjust_markdown_headers(markdown: &str, base_level: usize) -> String {
             Event::End(pulldown_cmark::TagEnd::Heading(new_level))
         }
         _ => event,
-    });
-
-    let mut out_buf = String::with_capacity(markdown.len() + 128); // Pre-allocate slightly
-    cmark(transformed_events, &mut out_buf).expect("Markdown formatting failed");
-    out_buf
+    }
 }
 
 /// Indents each line of a string by the specified amount.
@@ -573,114 +1196,866 @@ fn indent_string(s: &str, amount: usize) -> String {
         .join("\n")
 }
 
-/// Cleans common prefixes like `core::marker::`, `core::ops::`, `alloc::`, `std::` from a path string.
-fn clean_trait_path(path_str: &str) -> String {
-    path_str
-        .replace("core::marker::", "")
-        .replace("core::ops::", "") // Add common core paths
-        .replace("core::fmt::", "")
-        .replace("core::cmp::", "")
-        .replace("core::clone::", "")
-        .replace("core::hash::", "")
-        .replace("core::panic::unwind_safe::", "") // For UnwindSafe/RefUnwindSafe
-        // Keep core::option::Option ? Maybe not needed as often in where clauses.
-        .replace("core::", "") // General core removal last
-        .replace("alloc::string::", "") // Clean alloc paths too
-        .replace("alloc::vec::", "")
-        .replace("alloc::boxed::", "")
-        .replace("alloc::borrow::", "") // For Borrow/BorrowMut/ToOwned
-        .replace("alloc::", "") // General alloc removal
-        .replace("std::", "") // Also clean std paths potentially used via prelude
+/// Returns `true` if the item has a `#[cfg(<predicate>)]` attribute with exactly the
+/// given predicate, e.g. `has_cfg_attr(item, "doc")` for `#[cfg(doc)]`.
+fn has_cfg_attr(item: &Item, predicate: &str) -> bool {
+    item.attrs
+        .iter()
+        .any(|attr| attr.split_whitespace().collect::<String>() == format!("#[cfg({})]", predicate))
 }
 
-/// Formats the canonical path to an item ID, using its path from krate.paths.
-fn format_id_path_canonical(id: &Id, krate: &Crate) -> String {
-    krate
-        .paths
-        .get(id)
-        .map(|p| p.path.join("::"))
-        .unwrap_or_else(|| {
-            // Fallback if not in paths (e.g., some external or generated IDs)
-            krate
-                .index
-                .get(id)
-                .and_then(|item| item.name.as_deref())
-                .map_or_else(|| format!("{{id:{}}}", id.0), |name| name.to_string())
-        })
+/// Formats the `#[cfg(...)]` attributes of an item as a note, e.g.
+/// `_Requires feature: `serde`_` for `#[cfg(feature = "serde")]`. Predicates other
+/// than a single feature are quoted, e.g. `_Requires `cfg(unix)`_`.
+///
+/// `#[cfg(doc)]` is left out, it gets its own "documentation-only" note.
+fn format_cfg_note(attrs: &[String]) -> Option<String> {
+    let mut features = Vec::new();
+    let mut other_predicates = Vec::new();
+    for attr in attrs {
+        let Some(predicate) = cfg_attr_predicate(attr, false) else {
+            continue;
+        };
+        match CfgPredicate::parse(predicate) {
+            Some(CfgPredicate::Name(name)) if name == "doc" => {}
+            Some(CfgPredicate::KeyValue(key, feature)) if key == "feature" => {
+                features.push(format!("`{}`", feature))
+            }
+            _ => other_predicates.push(format!("`cfg({})`", predicate)),
+        }
+    }
+    let mut requirements = Vec::new();
+    match features.len() {
+        0 => {}
+        1 => requirements.push(format!("feature: {}", features[0])),
+        _ => requirements.push(format!("features: {}", features.join(", "))),
+    }
+    requirements.extend(other_predicates);
+    if requirements.is_empty() {
+        return None;
+    }
+    Some(format!("_Requires {}_", requirements.join("; ")))
 }
 
-/// Formats a Path struct, trying to use the canonical path for the ID.
-fn format_path(path: &Path, krate: &Crate) -> String {
-    // Use the canonical path if available, otherwise use the path string in the struct
-    let base_path = format_id_path_canonical(&path.id, krate);
+/// Returns `true` if the item is marked `#[doc(hidden)]`.
+fn is_doc_hidden(item: &Item) -> bool {
+    item.attrs
+        .iter()
+        .any(|attr| attr.split_whitespace().collect::<String>() == "#[doc(hidden)]")
+}
 
-    let cleaned_base_path = clean_trait_path(&base_path); // Clean the base path
-                                                          // Use as_ref() to get Option<&GenericArgs> from Option<Box<GenericArgs>>
-    if let Some(args) = path.args.as_ref() {
-        let args_str = format_generic_args(args, krate);
-        if !args_str.is_empty() {
-            format!("{}<{}>", cleaned_base_path, args_str) // Use cleaned path
-        } else {
-            cleaned_base_path // Use cleaned path
+/// A parsed `cfg` predicate, e.g. `all(unix, feature = "serde")`.
+#[derive(Debug, PartialEq)]
+enum CfgPredicate {
+    /// A name, e.g. `unix` or `doc`.
+    Name(String),
+    /// A key-value pair, e.g. `feature = "serde"`.
+    KeyValue(String, String),
+    All(Vec<CfgPredicate>),
+    Any(Vec<CfgPredicate>),
+    Not(Box<CfgPredicate>),
+}
+
+impl CfgPredicate {
+    /// Parses a predicate such as `not(any(unix, target_os = "wasi"))`, returning
+    /// `None` if it is malformed.
+    fn parse(predicate: &str) -> Option<CfgPredicate> {
+        let mut rest = predicate;
+        let parsed = Self::parse_next(&mut rest)?;
+        rest.trim().is_empty().then_some(parsed)
+    }
+
+    /// Parses the predicate at the start of `rest`, advancing past it.
+    fn parse_next(rest: &mut &str) -> Option<CfgPredicate> {
+        let input = rest.trim_start();
+        let name_len = input
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(input.len());
+        let (name, after_name) = input.split_at(name_len);
+        if name.is_empty() {
+            return None;
+        }
+        let after_name = after_name.trim_start();
+        if let Some(value) = after_name.strip_prefix('=') {
+            let value = value.trim_start().strip_prefix('"')?;
+            // Find the closing quote, skipping escaped characters
+            let mut chars = value.char_indices();
+            let end = loop {
+                match chars.next()? {
+                    (_, '\\') => {
+                        chars.next();
+                    }
+                    (end, '"') => break end,
+                    _ => {}
+                }
+            };
+            *rest = &value[end + 1..];
+            return Some(CfgPredicate::KeyValue(
+                name.to_string(),
+                value[..end].to_string(),
+            ));
+        }
+        let Some(mut args) = after_name.strip_prefix('(') else {
+            *rest = after_name;
+            return Some(CfgPredicate::Name(name.to_string()));
+        };
+        let mut predicates = Vec::new();
+        loop {
+            args = args.trim_start();
+            if let Some(after_args) = args.strip_prefix(')') {
+                *rest = after_args;
+                break;
+            }
+            predicates.push(Self::parse_next(&mut args)?);
+            args = args.trim_start();
+            if let Some(after_comma) = args.strip_prefix(',') {
+                args = after_comma;
+            } else if !args.starts_with(')') {
+                return None;
+            }
+        }
+        match name {
+            "all" => Some(CfgPredicate::All(predicates)),
+            "any" => Some(CfgPredicate::Any(predicates)),
+            "not" if predicates.len() == 1 => Some(CfgPredicate::Not(Box::new(predicates.pop()?))),
+            _ => None,
         }
-    } else {
-        cleaned_base_path // Use cleaned path
     }
-}
 
-fn format_poly_trait(poly_trait: &PolyTrait, krate: &Crate) -> String {
-    let hrtb = if poly_trait.generic_params.is_empty() {
-        "".to_string()
-    } else {
-        format!(
-            "for<{}> ",
-            poly_trait
-                .generic_params
+    /// Returns the Cargo features this predicate requires to be enabled: the
+    /// `feature = "..."` pairs it holds directly or through `all(...)`. Features
+    /// under `any(...)` or `not(...)` are not required, nor is `target_feature`.
+    fn required_features(&self) -> Vec<&str> {
+        match self {
+            CfgPredicate::KeyValue(key, value) if key == "feature" => vec![value.as_str()],
+            CfgPredicate::All(predicates) => predicates
                 .iter()
-                .map(|p| format_generic_param_def(p, krate)) // Format full param def
-                .collect::<Vec<_>>()
-                .join(", ")
-        )
+                .flat_map(CfgPredicate::required_features)
+                .collect(),
+            CfgPredicate::Any(predicates) if predicates.len() == 1 => {
+                predicates[0].required_features()
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Returns the predicate of a `#[cfg(...)]` attribute, or of a `#[doc(cfg(...))]`
+/// attribute if `include_doc_cfg` is set.
+fn cfg_attr_predicate(attr: &str, include_doc_cfg: bool) -> Option<&str> {
+    let predicate = match attr.strip_prefix("#[cfg(") {
+        Some(rest) => rest.strip_suffix(")]"),
+        None if include_doc_cfg => attr
+            .strip_prefix("#[doc(cfg(")
+            .and_then(|rest| rest.strip_suffix("))]")),
+        None => None,
     };
-    format!("{}{}", hrtb, format_path(&poly_trait.trait_, krate)) // Use format_path for the Path struct
+    predicate.map(str::trim)
 }
 
-fn format_type(ty: &Type, krate: &Crate) -> String {
-    match ty {
-        Type::ResolvedPath(p) => format_path(p, krate),
-        Type::DynTrait(dt) => {
-            let lifetime_bound = dt
-                .lifetime
-                .as_ref()
-                .map(|lt| format!(" + {}", lt)) // Add quote for lifetime
-                .unwrap_or_default();
-            format!(
-                "dyn {}{}",
-                dt.traits
-                    .iter()
-                    .map(|pt| format_poly_trait(pt, krate))
-                    .collect::<Vec<_>>()
-                    .join(" + "),
-                lifetime_bound
-            )
+/// Extracts the Cargo features an item requires from its `#[cfg(...)]` and
+/// `#[doc(cfg(...))]` attributes (e.g. `#[cfg(feature = "serde")]` yields `serde`),
+/// see [`CfgPredicate::required_features`].
+fn cfg_features(attrs: &[String]) -> Vec<String> {
+    let mut features = Vec::new();
+    for attr in attrs {
+        let Some(predicate) = cfg_attr_predicate(attr, true).and_then(CfgPredicate::parse) else {
+            continue;
+        };
+        for feature in predicate.required_features() {
+            if !features.iter().any(|f| f == feature) {
+                features.push(feature.to_string());
+            }
         }
-        Type::Generic(name) => name.clone(),
-        Type::Primitive(name) => name.clone(),
-        Type::FunctionPointer(fp) => {
-            let hrtb = if fp.generic_params.is_empty() {
-                "".to_string()
-            } else {
+    }
+    features
+}
+
+/// Formats the `**Deprecated**` callout for an item, e.g.
+/// `> **Deprecated** since 0.1.0: use f instead`.
+fn format_deprecation(deprecation: &Deprecation) -> String {
+    let mut callout = String::from("> **Deprecated**");
+    if let Some(since) = &deprecation.since {
+        write!(callout, " since {}", since).unwrap();
+    }
+    if let Some(note) = &deprecation.note {
+        write!(callout, ": {}", note).unwrap();
+    }
+    callout
+}
+
+/// Returns the minimum Rust version an item declares for itself, read from a
+/// `#[clippy::msrv = "..."]` override or the `since` of a `#[stable(...)]` /
+/// `#[rustc_const_stable(...)]` attribute (the highest one wins).
+fn item_required_rust_version(item: &Item) -> Option<String> {
+    let mut required: Option<String> = None;
+    for attr in &item.attrs {
+        let attr = attr.trim();
+        let value = if let Some(rest) = attr.strip_prefix("#[clippy::msrv") {
+            rest.trim_start()
+                .strip_prefix('=')
+                .and_then(|v| v.split('"').nth(1))
+        } else if attr.starts_with("#[stable(") || attr.starts_with("#[rustc_const_stable(") {
+            attr.split_once("since")
+                .and_then(|(_, rest)| rest.trim_start().strip_prefix('='))
+                .and_then(|v| v.split('"').nth(1))
+        } else {
+            None
+        };
+        let Some(version) = value.filter(|v| parse_rust_version(v).is_some()) else {
+            continue;
+        };
+        if required
+            .as_deref()
+            .is_none_or(|current| parse_rust_version(version) > parse_rust_version(current))
+        {
+            required = Some(version.to_string());
+        }
+    }
+    required
+}
+
+/// Parses a `major.minor[.patch]` Rust version, padding missing parts with zero.
+fn parse_rust_version(version: &str) -> Option<[u64; 3]> {
+    let mut parts = [0; 3];
+    for (i, part) in version.trim().split('.').enumerate() {
+        *parts.get_mut(i)? = part.parse().ok()?;
+    }
+    Some(parts)
+}
+
+/// Builds an anchor slug from path segments, e.g. `["foo", "MyEnum", "Variant"]`
+/// becomes `foo-myenum-variant`.
+fn anchor_slug(segments: &[String]) -> String {
+    let mut slug = String::new();
+    for c in segments.join("-").chars() {
+        if c.is_alphanumeric() || c == '_' {
+            slug.extend(c.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Returns the `(level, number, title)` of a numbered section header like
+/// `### 1.2: Structs`, or `None` for any other line.
+fn parse_numbered_header(line: &str) -> Option<(usize, &str, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let (number, title) = line.get(level..)?.strip_prefix(' ')?.split_once(": ")?;
+    if level == 0 || number.is_empty() || !number.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    Some((level, number, title))
+}
+
+/// Builds the anchor id of a numbered section, e.g. `1.2.1` becomes `section-1-2-1`.
+fn section_anchor(number: &str) -> String {
+    format!("section-{}", number.replace('.', "-"))
+}
+
+/// A top-level module rendered into its own file by [`Printer::print_to_dir`].
+struct ModuleFile {
+    /// File name relative to the output directory, e.g. `my_crate-fmt.md`.
+    name: String,
+    content: String,
+}
+
+/// Makes anchor links (`](#anchor)`) pointing into another of `files` relative to
+/// that file (`](other.md#anchor)`).
+fn link_anchors_across_files(files: &mut [ModuleFile]) {
+    let mut anchor_files: HashMap<String, usize> = HashMap::new();
+    for (index, file) in files.iter().enumerate() {
+        for (start, _) in file.content.match_indices("<a id=\"") {
+            let rest = &file.content[start + "<a id=\"".len()..];
+            if let Some(end) = rest.find('"') {
+                anchor_files.insert(rest[..end].to_string(), index);
+            }
+        }
+    }
+    let names: Vec<String> = files.iter().map(|file| file.name.clone()).collect();
+    for (index, file) in files.iter_mut().enumerate() {
+        let mut linked = String::with_capacity(file.content.len());
+        let mut rest = file.content.as_str();
+        while let Some(start) = rest.find("](#") {
+            let (before, after) = rest.split_at(start + "](".len());
+            linked.push_str(before);
+            let anchor = after[1..].split(')').next().unwrap_or("");
+            if let Some(&target) = anchor_files.get(anchor).filter(|&&target| target != index) {
+                linked.push_str(&names[target]);
+            }
+            rest = after;
+        }
+        linked.push_str(rest);
+        file.content = linked;
+    }
+}
+
+/// Inserts a table of contents linking every top-level (`top_level`, normally H2)
+/// and second-level section of `markdown` before its first top-level section, and
+/// writes an `<a id="section-..."></a>` anchor before each linked header.
+///
+/// Headers inside fenced code blocks are ignored.
+fn insert_table_of_contents(markdown: &str, top_level: usize) -> String {
+    let mut toc = String::new();
+    let mut body = String::new();
+    let mut toc_position = None;
+    let mut open_fence: Option<CodeFence> = None;
+    for line in markdown.lines() {
+        match open_fence {
+            Some(fence) if fence.is_closed_by(line) => open_fence = None,
+            Some(_) => {}
+            None => open_fence = CodeFence::open(line).map(|(fence, _)| fence),
+        }
+        let section = open_fence
+            .is_none()
+            .then(|| parse_numbered_header(line))
+            .flatten()
+            .filter(|(level, _, _)| *level == top_level || *level == top_level + 1);
+        if let Some((level, number, title)) = section {
+            toc_position.get_or_insert(body.len());
+            let anchor = section_anchor(number);
+            writeln!(
+                toc,
+                "{}- [{}: {}](#{})",
+                "  ".repeat(level - top_level),
+                number,
+                title,
+                anchor
+            )
+            .unwrap();
+            writeln!(body, "<a id=\"{}\"></a>", anchor).unwrap();
+        }
+        writeln!(body, "{}", line).unwrap();
+    }
+    let Some(position) = toc_position else {
+        return markdown.to_string();
+    };
+    body.insert_str(position, &format!("**Contents**\n\n{}\n", toc));
+    body
+}
+
+/// Longest default method body (in characters) that is rendered inline when
+/// [`Printer::show_default_bodies`] is enabled.
+const MAX_DEFAULT_BODY_LEN: usize = 80;
+
+/// Returns the text of `content` (a source file) covered by `span`.
+///
+/// Span lines are 1-based, columns are 0-based character (not byte) offsets and the
+/// end column is exclusive.
+fn span_text(content: &str, span: &Span) -> Option<String> {
+    let lines: Vec<&str> = content
+        .lines()
+        .skip(span.begin.0.checked_sub(1)?)
+        .take(span.end.0.checked_sub(span.begin.0)? + 1)
+        .collect();
+    let byte_offset = |line: &str, column: usize| {
+        line.char_indices()
+            .nth(column)
+            .map_or(line.len(), |(offset, _)| offset)
+    };
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let start = if i == 0 {
+            byte_offset(line, span.begin.1)
+        } else {
+            0
+        };
+        let end = if i == lines.len() - 1 {
+            byte_offset(line, span.end.1)
+        } else {
+            line.len()
+        };
+        text.push_str(line.get(start..end.max(start))?);
+        if i != lines.len() - 1 {
+            text.push('\n');
+        }
+    }
+    Some(text)
+}
+
+/// Returns the byte offset of the `{` opening the block that ends `source`, e.g. the
+/// body of a function.
+///
+/// Braces in string and character literals and in comments are skipped.
+fn find_trailing_block_start(source: &str) -> Option<usize> {
+    let source = source.trim_end();
+    if !source.ends_with('}') {
+        return None;
+    }
+    let bytes = source.as_bytes();
+    let is_ident = |i: usize| bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric();
+    let mut open_braces = Vec::new();
+    let mut last_block_start = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = source.get(i..).unwrap_or_default(); // Empty inside a multi-byte char
+        if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+            continue;
+        }
+        if rest.starts_with("/*") {
+            // Block comments nest
+            let mut depth = 0;
+            while i < bytes.len() {
+                if bytes[i..].starts_with(b"/*") {
+                    depth += 1;
+                    i += 2;
+                } else if bytes[i..].starts_with(b"*/") {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+            continue;
+        }
+        // Raw (byte) strings: `r"..."`, `r#"..."#`, `br"..."`
+        let raw_start = match bytes[i] {
+            b'r' if i == 0 || !is_ident(i - 1) => Some(i + 1),
+            b'b' if (i == 0 || !is_ident(i - 1)) && bytes.get(i + 1) == Some(&b'r') => Some(i + 2),
+            _ => None,
+        };
+        if let Some(start) = raw_start {
+            let hashes = source[start..].len() - source[start..].trim_start_matches('#').len();
+            if source[start + hashes..].starts_with('"') {
+                let terminator = format!("\"{}", "#".repeat(hashes));
+                let body_start = start + hashes + 1;
+                i = source[body_start..]
+                    .find(&terminator)
+                    .map_or(bytes.len(), |end| body_start + end + terminator.len());
+                continue;
+            }
+        }
+        match bytes[i] {
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+            }
+            b'\'' => {
+                // A character literal, or else a lifetime or label
+                let literal_len = if rest[1..].starts_with('\\') {
+                    // Skip the escaped character, which may be a quote
+                    rest.get(3..)
+                        .and_then(|escaped| escaped.find('\''))
+                        .map(|end| end + 4)
+                } else {
+                    rest[1..]
+                        .chars()
+                        .next()
+                        .map(char::len_utf8)
+                        .filter(|len| rest[1 + len..].starts_with('\''))
+                        .map(|len| len + 2)
+                };
+                i += literal_len.unwrap_or(1);
+                continue;
+            }
+            b'{' => open_braces.push(i),
+            b'}' => last_block_start = open_braces.pop(),
+            _ => {}
+        }
+        i += 1;
+    }
+    last_block_start
+}
+
+/// Returns the source text of a function up to (not including) its body block.
+fn strip_fn_body(source: &str) -> &str {
+    match find_trailing_block_start(source) {
+        Some(block_start) => &source[..block_start],
+        None => source,
+    }
+}
+
+/// Extracts the body of a function from its source text if it is a single short expression.
+///
+/// Returns `None` for bodies spanning multiple lines, containing statements or
+/// comments, or longer than [`MAX_DEFAULT_BODY_LEN`].
+fn extract_short_fn_body(source: &str) -> Option<String> {
+    let block_start = find_trailing_block_start(source)?;
+    let source = source.trim_end();
+    let body = source[block_start + 1..source.len() - 1].trim();
+    if body.is_empty()
+        || body.contains('\n')
+        || body.contains(';')
+        || body.contains("//")
+        || body.contains("/*")
+        || body.len() > MAX_DEFAULT_BODY_LEN
+    {
+        return None;
+    }
+    Some(body.to_string())
+}
+
+/// Extracts the initializer of a `const` or `static` item from its source text, e.g.
+/// `10 * 2` from `pub const MAX: u32 = 10 * 2;`.
+///
+/// Returns `None` for initializers spanning multiple lines or longer than
+/// [`MAX_DEFAULT_BODY_LEN`].
+fn extract_short_initializer(source: &str) -> Option<String> {
+    let bytes = source.as_bytes();
+    let mut depth = 0usize;
+    let mut eq = None;
+    for (idx, &byte) in bytes.iter().enumerate() {
+        match byte {
+            b'(' | b'[' | b'{' | b'<' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            // `->` of a fn pointer type doesn't close a bracket
+            b'>' if idx == 0 || bytes[idx - 1] != b'-' => depth = depth.saturating_sub(1),
+            b'=' if depth == 0 => {
+                eq = Some(idx);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = source[eq? + 1..].trim().strip_suffix(';')?.trim();
+    if value.is_empty() || value.contains('\n') || value.len() > MAX_DEFAULT_BODY_LEN {
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/// Placeholder rustdoc emits for const expressions that are not a literal or path.
+const ELIDED_CONST_EXPR: &str = "{ _ }";
+
+/// Const expressions in a declaration's source that rustdoc may elide as
+/// [`ELIDED_CONST_EXPR`], in source order.
+#[derive(Debug, Default, PartialEq)]
+struct ElidedConstExprs {
+    /// Array lengths that are not a plain literal or path (`4`, `N`, `consts::LEN`
+    /// are kept by rustdoc), without the brackets: `N / 2`.
+    array_lengths: Vec<String>,
+    /// Braced const generic arguments, with the braces: `{ N + 1 }`.
+    generic_args: Vec<String>,
+}
+
+/// Collects the const expressions in `source` that rustdoc elides as
+/// [`ELIDED_CONST_EXPR`], with whitespace collapsed.
+fn elided_const_exprs(source: &str) -> ElidedConstExprs {
+    let collapse = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ");
+    // Open delimiters, with the position of the first top-level `;` inside a `[`, or
+    // the start of a `{` that is a generic argument
+    let mut stack: Vec<(char, Option<usize>)> = Vec::new();
+    let mut exprs = ElidedConstExprs::default();
+    let mut prev = ' '; // Previous non-whitespace character
+    for (idx, ch) in source.char_indices() {
+        match ch {
+            '{' if matches!(stack.last(), Some(('<', _))) && matches!(prev, '<' | ',') => {
+                stack.push((ch, Some(idx)))
+            }
+            '[' | '(' | '{' | '<' => stack.push((ch, None)),
+            // Not closing a `<` for `->`, `=>` and comparisons
+            '>' if !matches!(prev, '-' | '=') && matches!(stack.last(), Some(('<', _))) => {
+                stack.pop();
+            }
+            ';' => {
+                if let Some(('[', semi @ None)) = stack.last_mut() {
+                    *semi = Some(idx);
+                }
+            }
+            ']' | ')' | '}' => {
+                // Pop up to the matching delimiter, skipping unbalanced `<` (comparisons)
+                while let Some((open, start)) = stack.pop() {
+                    match (open, ch, start) {
+                        ('[', ']', Some(semi)) => {
+                            let len = source[semi + 1..idx].trim();
+                            let is_simple = len
+                                .chars()
+                                .all(|c| c.is_alphanumeric() || c == '_' || c == ':');
+                            if !is_simple {
+                                exprs.array_lengths.push(collapse(len));
+                            }
+                        }
+                        ('{', '}', Some(start)) => {
+                            exprs.generic_args.push(collapse(&source[start..=idx]))
+                        }
+                        _ => {}
+                    }
+                    if open != '<' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+        if !ch.is_whitespace() {
+            prev = ch;
+        }
+    }
+    exprs
+}
+
+/// Formats a generated code block with the `rustfmt` binary found on `PATH`.
+///
+/// The `{ ... }` body placeholders are not valid Rust, so they are swapped for a
+/// body holding only a marker comment while formatting, which `rustfmt` always
+/// leaves as `{`, the marker line and `}`, and restored afterwards. `edition` is the
+/// crate's edition (Cargo's default of 2015 if `None`). Returns `None` if `rustfmt`
+/// cannot be run or fails to format the block.
+#[cfg(feature = "rustfmt")]
+fn rustfmt_code_block(code: &str, edition: Option<&str>) -> Option<String> {
+    use std::io::Write as IoWrite;
+    use std::process::{Command, Stdio};
+
+    const BODY_PLACEHOLDER: &str = "{ ... }";
+    const BODY_MARKER: &str = "// __RUSTDOC_MARKDOWN_BODY__";
+    let mut child = Command::new("rustfmt")
+        .args(["--edition", edition.unwrap_or("2015")])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(
+            code.replace(BODY_PLACEHOLDER, &format!("{{\n{}\n}}", BODY_MARKER))
+                .as_bytes(),
+        )
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        debug!("rustfmt failed to format code block:\n{}", code);
+        return None;
+    }
+    let formatted = String::from_utf8(output.stdout).ok()?;
+    let mut lines: Vec<String> = Vec::new();
+    let mut formatted_lines = formatted.trim_end().lines();
+    while let Some(line) = formatted_lines.next() {
+        if line.trim() != BODY_MARKER {
+            lines.push(line.to_string());
+            continue;
+        }
+        // The marker is always preceded by the line opening the body and followed
+        // by the closing brace.
+        let closing = formatted_lines.next().map(str::trim);
+        match lines.last_mut() {
+            Some(opening) if opening.ends_with('{') && closing == Some("}") => {
+                opening.pop();
+                opening.push_str(BODY_PLACEHOLDER);
+            }
+            _ => {
+                debug!("Unexpected body marker in rustfmt output:\n{}", formatted);
+                return None;
+            }
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+/// Standard library crates whose leading path segment is stripped by [`clean_trait_path`].
+const STD_ROOT_SEGMENTS: &[&str] = &["core", "std", "alloc"];
+
+/// Well-known standard library module paths stripped after a [`STD_ROOT_SEGMENTS`] root.
+/// Longer paths must come first so the most specific match wins.
+const STD_MODULE_SEGMENTS: &[&[&str]] = &[
+    &["panic", "unwind_safe"], // For UnwindSafe/RefUnwindSafe
+    &["marker"],
+    &["ops"],
+    &["fmt"],
+    &["cmp"],
+    &["clone"],
+    &["hash"],
+    &["borrow"], // For Borrow/BorrowMut/ToOwned
+    &["string"],
+    &["vec"],
+    &["boxed"],
+];
+
+/// Cleans common prefixes like `core::marker::`, `core::ops::`, `alloc::`, `std::` from a path string.
+///
+/// Only leading segments are considered: `core::marker::Send` becomes `Send`, while
+/// `my_crate::core::Widget` and `my_core::Thing` are left untouched.
+fn clean_trait_path(path_str: &str) -> String {
+    let segments: Vec<&str> = path_str.split("::").collect();
+    let Some((root, rest)) = segments.split_first() else {
+        return path_str.to_string();
+    };
+    if rest.is_empty() || !STD_ROOT_SEGMENTS.contains(root) {
+        return path_str.to_string();
+    }
+    let rest = STD_MODULE_SEGMENTS
+        .iter()
+        .find_map(|module| {
+            rest.strip_prefix(*module)
+                .filter(|remaining| !remaining.is_empty())
+        })
+        .unwrap_or(rest);
+    rest.join("::")
+}
+
+/// Formats the canonical path to an item ID, using its path from krate.paths.
+///
+/// Items defined in a private module and re-exported from a public one use the
+/// re-export path when [`Printer::redact_private_paths`] is enabled, see
+/// [`Printer::reexported_public_paths`].
+fn format_id_path_canonical(id: &Id, printer: &Printer) -> String {
+    if let Some(path) = printer.public_paths.get(id) {
+        return path.join("::");
+    }
+    printer
+        .krate
+        .paths
+        .get(id)
+        .map(|p| p.path.join("::"))
+        .unwrap_or_else(|| {
+            // Fallback if not in paths (e.g., some external or generated IDs)
+            printer
+                .krate
+                .index
+                .get(id)
+                .and_then(|item| item.name.as_deref())
+                .map_or_else(|| format!("{{id:{}}}", id.0), |name| name.to_string())
+        })
+}
+
+/// Formats a Path struct, trying to use the canonical path for the ID.
+fn format_path(path: &Path, printer: &Printer) -> String {
+    // Use the canonical path if available, otherwise use the path string in the struct
+    let base_path = format_id_path_canonical(&path.id, printer);
+
+    let cleaned_base_path = clean_trait_path(&base_path); // Clean the base path
+                                                          // Use as_ref() to get Option<&GenericArgs> from Option<Box<GenericArgs>>
+    if let Some(args) = path.args.as_ref() {
+        format!(
+            "{}{}",
+            cleaned_base_path,
+            format_generic_args_suffix(args, printer)
+        )
+    } else {
+        cleaned_base_path // Use cleaned path
+    }
+}
+
+/// Formats a function parameter as `name: Type`, using the shorthand receiver forms
+/// `self`, `&self`, `&'a self` and `&mut self` where they apply. Other receivers,
+/// like `self: Box<Self>`, keep the explicit form.
+fn format_fn_input(name: &str, ty: &Type, printer: &Printer) -> String {
+    if name == "self" {
+        match ty {
+            Type::Generic(g) if g == "Self" => return "self".to_string(),
+            Type::BorrowedRef {
+                lifetime,
+                is_mutable,
+                type_,
+            } if matches!(&**type_, Type::Generic(g) if g == "Self") => {
+                return format!(
+                    "&{}{}self",
+                    lifetime
+                        .as_ref()
+                        .map(|lt| format!("{} ", lt))
+                        .unwrap_or_default(),
+                    if *is_mutable { "mut " } else { "" }
+                );
+            }
+            _ => {}
+        }
+    }
+    format!("{}: {}", name, format_type(ty, printer))
+}
+
+/// Formats the type behind a reference or raw pointer, parenthesizing `dyn` types with
+/// several bounds (`&(dyn Trait + Send)`), which would otherwise not parse.
+fn format_pointee_type(ty: &Type, printer: &Printer) -> String {
+    match ty {
+        Type::DynTrait(dt) if dt.traits.len() > 1 || dt.lifetime.is_some() => {
+            format!("({})", format_type(ty, printer))
+        }
+        _ => format_type(ty, printer),
+    }
+}
+
+/// Standard library auto traits, which follow the principal trait of a `dyn` type.
+const AUTO_TRAITS: &[&str] = &["RefUnwindSafe", "Send", "Sync", "Unpin", "UnwindSafe"];
+
+/// Returns `true` if `poly_trait` names one of the [`AUTO_TRAITS`].
+fn is_auto_trait(poly_trait: &PolyTrait) -> bool {
+    let name = poly_trait.trait_.path.rsplit("::").next().unwrap_or("");
+    AUTO_TRAITS.contains(&name)
+}
+
+fn format_poly_trait(poly_trait: &PolyTrait, printer: &Printer) -> String {
+    let hrtb = if poly_trait.generic_params.is_empty() {
+        "".to_string()
+    } else {
+        format!(
+            "for<{}> ",
+            poly_trait
+                .generic_params
+                .iter()
+                .map(|p| format_generic_param_def(p, printer)) // Format full param def
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    format!("{}{}", hrtb, format_path(&poly_trait.trait_, printer)) // Use format_path for the Path struct
+}
+
+fn format_type(ty: &Type, printer: &Printer) -> String {
+    match ty {
+        Type::ResolvedPath(p) => {
+            let path_str = format_path(p, printer);
+            match expand_type_alias(p, printer) {
+                Some(expanded) => format!("{} /* = {} */", path_str, expanded),
+                None => path_str,
+            }
+        }
+        Type::DynTrait(dt) => {
+            let lifetime_bound = dt
+                .lifetime
+                .as_ref()
+                .map(|lt| format!(" + {}", lt)) // Add quote for lifetime
+                .unwrap_or_default();
+            // Canonical order: principal trait, then auto traits alphabetically
+            let (auto_traits, principal): (Vec<_>, Vec<_>) =
+                dt.traits.iter().partition(|pt| is_auto_trait(pt));
+            let mut auto_traits: Vec<String> = auto_traits
+                .into_iter()
+                .map(|pt| format_poly_trait(pt, printer))
+                .collect();
+            auto_traits.sort();
+            format!(
+                "dyn {}{}",
+                principal
+                    .into_iter()
+                    .map(|pt| format_poly_trait(pt, printer))
+                    .chain(auto_traits)
+                    .collect::<Vec<_>>()
+                    .join(" + "),
+                lifetime_bound
+            )
+        }
+        Type::Generic(name) => printer
+            .alias_substitutions
+            .borrow()
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| name.clone()),
+        // rustdoc represents the never type as the primitive "never"
+        Type::Primitive(name) if name == "never" => "!".to_string(),
+        Type::Primitive(name) => name.clone(),
+        Type::FunctionPointer(fp) => {
+            let hrtb = if fp.generic_params.is_empty() {
+                "".to_string()
+            } else {
                 format!(
                     "for<{}> ",
                     fp.generic_params
                         .iter()
-                        .map(|p| format_generic_param_def(p, krate))
+                        .map(|p| format_generic_param_def(p, printer))
                         .collect::<Vec<_>>()
                         .join(", ")
                 )
             };
             let abi = if !matches!(fp.header.abi, Abi::Rust) {
-                format!("extern \"{:?}\" ", fp.header.abi) // Use Debug for Abi for now
+                format!("extern \"{}\" ", format_abi(&fp.header.abi))
             } else {
                 "".to_string()
             };
@@ -693,13 +2068,13 @@ fn format_type(ty: &Type, krate: &Crate) -> String {
                 fp.sig
                     .inputs
                     .iter()
-                    .map(|(_name, type_)| format_type(type_, krate)) // Ignore name pattern for now
+                    .map(|(_name, type_)| format_type(type_, printer)) // Ignore name pattern for now
                     .collect::<Vec<_>>()
                     .join(", "),
                 fp.sig
                     .output
                     .as_ref()
-                    .map(|t| format!(" -> {}", format_type(t, krate)))
+                    .map(|t| format!(" -> {}", format_type(t, printer)))
                     .unwrap_or_default()
             )
         }
@@ -712,21 +2087,38 @@ fn format_type(ty: &Type, krate: &Crate) -> String {
                     "({})",
                     types
                         .iter()
-                        .map(|t| format_type(t, krate))
+                        .map(|t| format_type(t, printer))
                         .collect::<Vec<_>>()
                         .join(", ")
                 )
             }
         }
-        Type::Slice(inner) => format!("[{}]", format_type(inner, krate)),
-        Type::Array { type_, len } => format!("[{}; {}]", format_type(type_, krate), len),
-        Type::Pat { type_, .. } => format!("pat {}", format_type(type_, krate)), // Placeholder
+        Type::Slice(inner) => format!("[{}]", format_type(inner, printer)),
+        Type::Array { type_, len } => format!("[{}; {}]", format_type(type_, printer), len),
+        Type::Pat {
+            type_,
+            __pat_unstable_do_not_use: pat,
+        } => {
+            let inner = format_type(type_, printer);
+            // Current nightlies emit a compiler-internal dump (`TyPat { .. }`) rather
+            // than pattern syntax; only render patterns that read like Rust.
+            if pat.is_empty() || pat.contains('{') {
+                inner
+            } else {
+                format!("{} is {}", inner, pat)
+            }
+        }
         Type::ImplTrait(bounds) => {
+            // Precise-capturing `use<..>` bounds conventionally come last
+            let (captures, bounds): (Vec<_>, Vec<_>) = bounds
+                .iter()
+                .partition(|b| matches!(b, GenericBound::Use(_)));
             format!(
                 "impl {}",
                 bounds
-                    .iter()
-                    .map(|b| format_generic_bound(b, krate))
+                    .into_iter()
+                    .chain(captures)
+                    .map(|b| format_generic_bound(b, printer))
                     .collect::<Vec<_>>()
                     .join(" + ")
             )
@@ -736,7 +2128,7 @@ fn format_type(ty: &Type, krate: &Crate) -> String {
             format!(
                 "*{}{}",
                 if *is_mutable { "mut " } else { "const " },
-                format_type(type_, krate)
+                format_pointee_type(type_, printer)
             )
         }
         Type::BorrowedRef {
@@ -750,7 +2142,7 @@ fn format_type(ty: &Type, krate: &Crate) -> String {
                 .map(|lt| format!("{} ", lt)) // Add quote
                 .unwrap_or_default(),
             if *is_mutable { "mut " } else { "" },
-            format_type(type_, krate)
+            format_pointee_type(type_, printer)
         ),
         Type::QualifiedPath {
             name,
@@ -758,13 +2150,13 @@ fn format_type(ty: &Type, krate: &Crate) -> String {
             self_type,
             trait_,
         } => {
-            let self_type_str = format_type(self_type, krate);
+            let self_type_str = format_type(self_type, printer);
             let trait_str = trait_
                 .as_ref()
-                .map(|t| format_path(t, krate)) // Use format_path
+                .map(|t| format_path(t, printer)) // Use format_path
                 .unwrap_or("_".to_string());
             // Args here are for the associated type, not the trait bound
-            let args_str = format_generic_args(args, krate);
+            let args_str = format_generic_args_suffix(args, printer);
 
             format!(
                 "<{} as {}>::{}{}",
@@ -774,52 +2166,126 @@ fn format_type(ty: &Type, krate: &Crate) -> String {
                 if args_str.is_empty() {
                     "".to_string()
                 } else {
-                    format!("<{}>", args_str)
+                    args_str
                 }
             )
         }
     }
 }
 
-fn format_generic_args(args: &GenericArgs, krate: &Crate) -> String {
-    match args {
-        GenericArgs::AngleBracketed {
-            args, constraints, ..
-        } => {
-            let arg_strs: Vec<String> = args.iter().map(|a| format_generic_arg(a, krate)).collect();
-            let constraint_strs: Vec<String> = constraints
-                .iter()
-                .map(|c| match c {
-                    rustdoc_types::AssocItemConstraint {
-                        name,
-                        args: assoc_args,
-                        binding: rustdoc_types::AssocItemConstraintKind::Equality(term),
-                    } => {
-                        let assoc_args_str = format_generic_args(assoc_args, krate);
-                        format!(
-                            "{}{}{}{} = {}",
-                            name,
-                            if assoc_args_str.is_empty() { "" } else { "<" },
-                            assoc_args_str,
-                            if assoc_args_str.is_empty() { "" } else { ">" },
-                            format_term(term, krate)
-                        )
-                    }
-                    rustdoc_types::AssocItemConstraint {
-                        name,
-                        args: assoc_args,
-                        binding: rustdoc_types::AssocItemConstraintKind::Constraint(bounds),
-                    } => {
-                        let assoc_args_str = format_generic_args(assoc_args, krate);
-                        format!(
-                            "{}{}{}{}: {}",
-                            name,
-                            if assoc_args_str.is_empty() { "" } else { "<" },
-                            assoc_args_str,
-                            if assoc_args_str.is_empty() { "" } else { ">" },
-                            bounds
+/// Expands a path to a locally defined type alias into the aliased type, when
+/// [`Printer::inline_type_aliases`] is enabled.
+///
+/// The alias's type parameters are substituted with the path's generic arguments
+/// (or their defaults). Aliases that are already being expanded are skipped to
+/// guard against recursion.
+fn expand_type_alias(path: &Path, printer: &Printer) -> Option<String> {
+    if !printer.inline_type_aliases {
+        return None;
+    }
+    let ItemEnum::TypeAlias(alias) = &printer.krate.index.get(&path.id)?.inner else {
+        return None;
+    };
+    if printer.expanding_aliases.borrow().contains(&path.id) {
+        return None;
+    }
+
+    let mut type_args = match path.args.as_deref() {
+        Some(GenericArgs::AngleBracketed { args, .. }) => args
+            .iter()
+            .filter_map(|a| match a {
+                GenericArg::Type(t) => Some(t),
+                _ => None,
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    }
+    .into_iter();
+    let mut substitutions = HashMap::new();
+    for param in &alias.generics.params {
+        if let rustdoc_types::GenericParamDefKind::Type { default, .. } = &param.kind {
+            if let Some(ty) = type_args.next().or(default.as_ref()) {
+                substitutions.insert(param.name.clone(), format_type(ty, printer));
+            }
+        }
+    }
+
+    printer.expanding_aliases.borrow_mut().push(path.id);
+    let outer_substitutions = printer.alias_substitutions.replace(substitutions);
+    let expanded = format_type(&alias.type_, printer);
+    printer.alias_substitutions.replace(outer_substitutions);
+    printer.expanding_aliases.borrow_mut().pop();
+    Some(expanded)
+}
+
+/// Formats generic arguments, collapsing them to `…` once the nesting depth exceeds
+/// [`Printer::max_generic_depth`].
+fn format_generic_args(args: &GenericArgs, printer: &Printer) -> String {
+    let is_empty = matches!(
+        args,
+        GenericArgs::AngleBracketed { args, constraints } if args.is_empty() && constraints.is_empty()
+    );
+    let depth = printer.generic_depth.get();
+    if !is_empty && printer.max_generic_depth.is_some_and(|max| depth >= max) {
+        return match args {
+            GenericArgs::Parenthesized { .. } => "(…)".to_string(),
+            _ => "…".to_string(),
+        };
+    }
+    printer.generic_depth.set(depth + 1);
+    let formatted = format_generic_args_inner(args, printer);
+    printer.generic_depth.set(depth);
+    formatted
+}
+
+/// Formats generic arguments as they follow a path: `<T, Item = U>` for angle
+/// brackets, or the `Fn` sugar `(A, B) -> R` as is. Returns an empty string when
+/// there are no arguments.
+fn format_generic_args_suffix(args: &GenericArgs, printer: &Printer) -> String {
+    let args_str = format_generic_args(args, printer);
+    if args_str.is_empty() || matches!(args, GenericArgs::Parenthesized { .. }) {
+        args_str
+    } else {
+        format!("<{}>", args_str)
+    }
+}
+
+fn format_generic_args_inner(args: &GenericArgs, printer: &Printer) -> String {
+    match args {
+        GenericArgs::AngleBracketed {
+            args, constraints, ..
+        } => {
+            let arg_strs: Vec<String> = args
+                .iter()
+                .map(|a| format_generic_arg(a, printer))
+                .collect();
+            let constraint_strs: Vec<String> = constraints
+                .iter()
+                .map(|c| match c {
+                    rustdoc_types::AssocItemConstraint {
+                        name,
+                        args: assoc_args,
+                        binding: rustdoc_types::AssocItemConstraintKind::Equality(term),
+                    } => {
+                        format!(
+                            "{}{} = {}",
+                            name,
+                            format_generic_args_suffix(assoc_args, printer),
+                            format_term(term, printer)
+                        )
+                    }
+                    rustdoc_types::AssocItemConstraint {
+                        name,
+                        args: assoc_args,
+                        binding: rustdoc_types::AssocItemConstraintKind::Constraint(bounds),
+                    } => {
+                        format!(
+                            "{}{}: {}",
+                            name,
+                            format_generic_args_suffix(assoc_args, printer),
+                            bounds
                                 .iter()
-                                .map(|bnd| format_generic_bound(bnd, krate))
+                                .map(|bnd| format_generic_bound(bnd, printer))
                                 .collect::<Vec<_>>()
                                 .join(" + ")
                         )
@@ -831,16 +2297,18 @@ fn format_generic_args(args: &GenericArgs, krate: &Crate) -> String {
             all_strs.join(", ")
         }
         GenericArgs::Parenthesized { inputs, output, .. } => {
+            // A missing output is the unit type, which the sugar omits: `Fn(u8)`
             format!(
-                "({}) -> {}",
+                "({}){}",
                 inputs
                     .iter()
-                    .map(|t| format_type(t, krate))
+                    .map(|t| format_type(t, printer))
                     .collect::<Vec<_>>()
                     .join(", "),
-                output
-                    .as_ref()
-                    .map_or("()".to_string(), |t| format_type(t, krate))
+                output.as_ref().map_or(String::new(), |t| format!(
+                    " -> {}",
+                    format_type(t, printer)
+                ))
             )
         }
         GenericArgs::ReturnTypeNotation => String::new(),
@@ -857,6 +2325,29 @@ fn format_const_expr(constant: &Constant) -> String {
     constant.expr.clone()
 }
 
+/// Formats an ABI as it is spelled in source, without quotes (e.g. `C`, `system`,
+/// `C-unwind`).
+fn format_abi(abi: &Abi) -> String {
+    let (name, unwind) = match abi {
+        Abi::Rust => ("Rust", false),
+        Abi::C { unwind } => ("C", *unwind),
+        Abi::Cdecl { unwind } => ("cdecl", *unwind),
+        Abi::Stdcall { unwind } => ("stdcall", *unwind),
+        Abi::Fastcall { unwind } => ("fastcall", *unwind),
+        Abi::Aapcs { unwind } => ("aapcs", *unwind),
+        Abi::Win64 { unwind } => ("win64", *unwind),
+        Abi::SysV64 { unwind } => ("sysv64", *unwind),
+        Abi::System { unwind } => ("system", *unwind),
+        // rustdoc renders other ABIs with their quotes
+        Abi::Other(other) => (other.trim_matches('"'), false),
+    };
+    if unwind {
+        format!("{}-unwind", name)
+    } else {
+        name.to_string()
+    }
+}
+
 /// Formats a discriminant expression, potentially showing the value if different.
 fn format_discriminant_expr(discr: &Discriminant) -> String {
     if discr.value != discr.expr {
@@ -866,16 +2357,33 @@ fn format_discriminant_expr(discr: &Discriminant) -> String {
     }
 }
 
-fn format_generic_arg(arg: &GenericArg, krate: &Crate) -> String {
+/// Formats an associated constant as `const NAME: Type`, with ` = value` if given.
+///
+/// `rustdoc` only provides the default (or assigned) value as a rendered string, so
+/// unlike [`format_const_expr`] there is no separate evaluated value to show.
+fn format_assoc_const_decl(
+    name: &str,
+    type_: &Type,
+    value: Option<&str>,
+    printer: &Printer,
+) -> String {
+    let mut decl = format!("const {}: {}", name, format_type(type_, printer));
+    if let Some(val) = value {
+        write!(decl, " = {}", val).unwrap();
+    }
+    decl
+}
+
+fn format_generic_arg(arg: &GenericArg, printer: &Printer) -> String {
     match arg {
         GenericArg::Lifetime(lt) => lt.to_string(), // Add quote
-        GenericArg::Type(ty) => format_type(ty, krate),
+        GenericArg::Type(ty) => format_type(ty, printer),
         GenericArg::Const(c) => format_const_expr(c),
         GenericArg::Infer => "_".to_string(),
     }
 }
 
-fn format_generic_bound(bound: &GenericBound, krate: &Crate) -> String {
+fn format_generic_bound(bound: &GenericBound, printer: &Printer) -> String {
     match bound {
         GenericBound::TraitBound {
             trait_,         // Path struct
@@ -890,7 +2398,7 @@ fn format_generic_bound(bound: &GenericBound, krate: &Crate) -> String {
                     "for<{}> ",
                     generic_params
                         .iter()
-                        .map(|p| format_generic_param_def(p, krate)) // Format full param def
+                        .map(|p| format_generic_param_def(p, printer)) // Format full param def
                         .collect::<Vec<_>>()
                         .join(", ")
                 )
@@ -900,7 +2408,7 @@ fn format_generic_bound(bound: &GenericBound, krate: &Crate) -> String {
                 rustdoc_types::TraitBoundModifier::Maybe => "?",
                 rustdoc_types::TraitBoundModifier::MaybeConst => "?const ", // Note the space
             };
-            format!("{}{}{}", hrtb, mod_str, format_path(trait_, krate)) // Use format_path
+            format!("{}{}{}", hrtb, mod_str, format_path(trait_, printer)) // Use format_path
         }
         GenericBound::Outlives(lifetime) => lifetime.to_string(), // Add quote
         GenericBound::Use(args) => {
@@ -909,7 +2417,7 @@ fn format_generic_bound(bound: &GenericBound, krate: &Crate) -> String {
                 "use<{}>",
                 args.iter()
                     .map(|a| match a {
-                        rustdoc_types::PreciseCapturingArg::Lifetime(lt) => format!("'{}", lt),
+                        rustdoc_types::PreciseCapturingArg::Lifetime(lt) => lt.clone(), // Includes the quote
                         rustdoc_types::PreciseCapturingArg::Param(id_str) => id_str.clone(), // Use string name directly
                     })
                     .collect::<Vec<_>>()
@@ -919,14 +2427,60 @@ fn format_generic_bound(bound: &GenericBound, krate: &Crate) -> String {
     }
 }
 
-fn format_term(term: &Term, krate: &Crate) -> String {
+/// Returns `true` if `bound` is a plain (non-`?`) `Sized` trait bound.
+fn is_sized_bound(bound: &GenericBound, printer: &Printer) -> bool {
+    match bound {
+        GenericBound::TraitBound {
+            trait_, modifier, ..
+        } => {
+            matches!(modifier, rustdoc_types::TraitBoundModifier::None)
+                && printer.krate.paths.get(&trait_.id).map_or_else(
+                    || trait_.path == "Sized",
+                    |summary| summary.path == ["core", "marker", "Sized"],
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Returns `true` if a trait method has a `where Self: Sized` bound, which excludes it
+/// from trait objects.
+fn requires_self_sized(f: &Function, printer: &Printer) -> bool {
+    f.generics.where_predicates.iter().any(|predicate| {
+        matches!(
+            predicate,
+            WherePredicate::BoundPredicate { type_: Type::Generic(name), bounds, .. }
+                if name == "Self" && bounds.iter().any(|b| is_sized_bound(b, printer))
+        )
+    })
+}
+
+/// Formats a `+`-separated list of bounds on a generic parameter or where-clause type.
+///
+/// Explicit `Sized` bounds are dropped when [`Printer::omit_sized_bound`] is enabled
+/// and the bounded type is `implicitly_sized` (i.e. not `Self` in a trait).
+fn format_param_bounds(
+    bounds: &[GenericBound],
+    implicitly_sized: bool,
+    printer: &Printer,
+) -> String {
+    let omit_sized = implicitly_sized && printer.omit_sized_bound;
+    bounds
+        .iter()
+        .filter(|b| !(omit_sized && is_sized_bound(b, printer)))
+        .map(|b| format_generic_bound(b, printer))
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn format_term(term: &Term, printer: &Printer) -> String {
     match term {
-        Term::Type(t) => format_type(t, krate),
+        Term::Type(t) => format_type(t, printer),
         Term::Constant(c) => format_const_expr(c),
     }
 }
 
-fn format_generic_param_def(p: &GenericParamDef, krate: &Crate) -> String {
+fn format_generic_param_def(p: &GenericParamDef, printer: &Printer) -> String {
     match &p.kind {
         rustdoc_types::GenericParamDefKind::Lifetime { .. } => p.name.to_string(), // Add quote
         rustdoc_types::GenericParamDefKind::Type {
@@ -935,25 +2489,19 @@ fn format_generic_param_def(p: &GenericParamDef, krate: &Crate) -> String {
             is_synthetic,
             ..
         } => {
+            let bounds_str = format_param_bounds(bounds, true, printer);
             format!(
                 "{}{}{}{}",
                 if *is_synthetic { "impl " } else { "" },
                 p.name,
-                if bounds.is_empty() {
+                if bounds_str.is_empty() {
                     "".to_string()
                 } else {
-                    format!(
-                        ": {}",
-                        bounds
-                            .iter()
-                            .map(|b| format_generic_bound(b, krate))
-                            .collect::<Vec<_>>()
-                            .join(" + ")
-                    )
+                    format!(": {}", bounds_str)
                 },
                 default
                     .as_ref()
-                    .map(|t| format!(" = {}", format_type(t, krate)))
+                    .map(|t| format!(" = {}", format_type(t, printer)))
                     .unwrap_or_default()
             )
         }
@@ -961,7 +2509,7 @@ fn format_generic_param_def(p: &GenericParamDef, krate: &Crate) -> String {
             format!(
                 "const {}: {}{}",
                 p.name,
-                format_type(type_, krate),
+                format_type(type_, printer),
                 default
                     .as_deref()
                     .map(|d| format!(" = {}", d))
@@ -971,28 +2519,201 @@ fn format_generic_param_def(p: &GenericParamDef, krate: &Crate) -> String {
     }
 }
 
+/// Inline bound lists longer than this are moved to the where-clause when
+/// [`Printer::render_bounds_on_separate_lines`] is enabled, and where-clause bounds
+/// are only merged inline by [`Printer::consolidate_bounds`] if the result fits.
+const MAX_INLINE_BOUNDS: usize = 3;
+
+/// Rearranges bounds between generic parameters and the where-clause according to
+/// [`Printer::consolidate_bounds`] and [`Printer::render_bounds_on_separate_lines`].
+fn arrange_param_bounds<'g>(generics: &'g Generics, printer: &Printer) -> Cow<'g, Generics> {
+    let mut generics = Cow::Borrowed(generics);
+    if printer.consolidate_bounds {
+        consolidate_where_bounds(&mut generics);
+    }
+    if printer.render_bounds_on_separate_lines {
+        hoist_long_param_bounds(&mut generics);
+    }
+    generics
+}
+
+/// Moves simple where-clause predicates (`where T: Clone`) onto the parameter they
+/// bound (`<T: Clone>`).
+///
+/// Only predicates with a single trait bound on a plain type parameter declared in
+/// `generics` are moved, and only while the parameter keeps at most
+/// [`MAX_INLINE_BOUNDS`] bounds.
+fn consolidate_where_bounds(generics: &mut Cow<'_, Generics>) {
+    let is_simple = |p: &WherePredicate| {
+        matches!(
+            p,
+            WherePredicate::BoundPredicate { type_: Type::Generic(name), bounds, generic_params }
+                if generic_params.is_empty()
+                    && matches!(bounds.as_slice(), [GenericBound::TraitBound { .. }])
+                    && generics.params.iter().any(|param| &param.name == name)
+        )
+    };
+    if !generics.where_predicates.iter().any(is_simple) {
+        return;
+    }
+
+    let generics = generics.to_mut();
+    let predicates = std::mem::take(&mut generics.where_predicates);
+    for predicate in predicates {
+        if let WherePredicate::BoundPredicate {
+            type_: Type::Generic(name),
+            bounds,
+            generic_params,
+        } = &predicate
+        {
+            let param_bounds = generics
+                .params
+                .iter_mut()
+                .find_map(|param| match &mut param.kind {
+                    rustdoc_types::GenericParamDefKind::Type {
+                        bounds,
+                        is_synthetic: false,
+                        ..
+                    } if &param.name == name => Some(bounds),
+                    _ => None,
+                });
+            if let Some(param_bounds) = param_bounds {
+                if generic_params.is_empty()
+                    && matches!(bounds.as_slice(), [GenericBound::TraitBound { .. }])
+                    && param_bounds.len() < MAX_INLINE_BOUNDS
+                {
+                    param_bounds.extend(bounds.iter().cloned());
+                    continue;
+                }
+            }
+        }
+        generics.where_predicates.push(predicate);
+    }
+}
+
+/// Moves long inline bound lists (`<T: A + B + C + D>`) into where-clause
+/// predicates (`<T> where T: A + B + C + D`).
+///
+/// Synthetic `impl Trait` parameters are left untouched, since their bounds
+/// cannot be expressed in a where-clause.
+fn hoist_long_param_bounds(generics: &mut Cow<'_, Generics>) {
+    let is_long = |p: &GenericParamDef| {
+        matches!(
+            &p.kind,
+            rustdoc_types::GenericParamDefKind::Type { bounds, is_synthetic: false, .. }
+                if bounds.len() > MAX_INLINE_BOUNDS
+        )
+    };
+    if !generics.params.iter().any(is_long) {
+        return;
+    }
+
+    let hoisted = generics.to_mut();
+    let mut predicates = Vec::new();
+    for param in hoisted.params.iter_mut().filter(|p| is_long(p)) {
+        if let rustdoc_types::GenericParamDefKind::Type { bounds, .. } = &mut param.kind {
+            predicates.push(WherePredicate::BoundPredicate {
+                type_: Type::Generic(param.name.clone()),
+                bounds: std::mem::take(bounds),
+                generic_params: Vec::new(),
+            });
+        }
+    }
+    // Hoisted bounds come first, in parameter order, before any explicit predicates
+    predicates.append(&mut hoisted.where_predicates);
+    hoisted.where_predicates = predicates;
+}
+
+/// Rewrites argument-position `impl Trait` types into explicit generic parameters
+/// (`fn f(x: impl Display)` to `fn f<T: Display>(x: T)`) when
+/// [`Printer::render_impl_trait_as_generic`] is enabled.
+///
+/// New parameter names are picked from `T`, `U`, `V`, ... skipping names already in use.
+fn impl_trait_as_generics<'f>(f: &'f Function, printer: &Printer) -> Cow<'f, Function> {
+    let is_synthetic = |p: &GenericParamDef| {
+        matches!(
+            p.kind,
+            rustdoc_types::GenericParamDefKind::Type {
+                is_synthetic: true,
+                ..
+            }
+        )
+    };
+    if !printer.render_impl_trait_as_generic || !f.generics.params.iter().any(is_synthetic) {
+        return Cow::Borrowed(f);
+    }
+
+    let mut f = f.clone();
+    let mut candidates = ["T", "U", "V", "W", "X", "Y", "Z"]
+        .into_iter()
+        .map(str::to_string)
+        .chain((1..).map(|n| format!("T{}", n)));
+    let mut names = Vec::new();
+    for i in 0..f.generics.params.len() {
+        if !is_synthetic(&f.generics.params[i]) {
+            continue;
+        }
+        let name = candidates
+            .find(|name| f.generics.params.iter().all(|p| &p.name != name))
+            .expect("candidate names are unbounded");
+        let param = &mut f.generics.params[i];
+        param.name = name.clone();
+        if let rustdoc_types::GenericParamDefKind::Type { is_synthetic, .. } = &mut param.kind {
+            *is_synthetic = false;
+        }
+        names.push(name);
+    }
+    // rustdoc declares synthetic params in the order their `impl Trait` appears
+    let mut names = names.into_iter();
+    for (_, ty) in &mut f.sig.inputs {
+        replace_impl_trait(ty, &mut names);
+    }
+    Cow::Owned(f)
+}
+
+/// Replaces each `impl Trait` within `ty` by the next generic parameter name.
+fn replace_impl_trait(ty: &mut Type, names: &mut impl Iterator<Item = String>) {
+    match ty {
+        Type::ImplTrait(_) => {
+            if let Some(name) = names.next() {
+                *ty = Type::Generic(name);
+            }
+        }
+        Type::BorrowedRef { type_, .. }
+        | Type::RawPointer { type_, .. }
+        | Type::Slice(type_)
+        | Type::Array { type_, .. } => replace_impl_trait(type_, names),
+        Type::Tuple(types) => {
+            for ty in types {
+                replace_impl_trait(ty, names);
+            }
+        }
+        Type::ResolvedPath(path) => {
+            if let Some(args) = &mut path.args {
+                if let GenericArgs::AngleBracketed { args, .. } = args.as_mut() {
+                    for arg in args {
+                        if let GenericArg::Type(ty) = arg {
+                            replace_impl_trait(ty, names);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 // Formats generics like <T: Bound> where T: OtherBound
-fn format_generics_full(generics: &Generics, krate: &Crate) -> String {
+fn format_generics_full(generics: &Generics, printer: &Printer) -> String {
+    let generics = arrange_param_bounds(generics, printer);
     if generics.params.is_empty() && generics.where_predicates.is_empty() {
         return String::new();
     }
 
     let mut s = String::new();
-    let params_str = if !generics.params.is_empty() {
-        format!(
-            "<{}>",
-            generics
-                .params
-                .iter()
-                .map(|p| format_generic_param_def(p, krate))
-                .collect::<Vec<_>>()
-                .join(", ")
-        )
-    } else {
-        String::new()
-    };
+    let params_str = format_generics_params_only(&generics.params, printer);
 
-    let where_clause = format_generics_where_only(&generics.where_predicates, krate);
+    let where_clause = format_generics_where_only(&generics.where_predicates, printer);
 
     if !params_str.is_empty() {
         write!(s, "{}", params_str).unwrap();
@@ -1010,34 +2731,64 @@ fn format_generics_full(generics: &Generics, krate: &Crate) -> String {
 }
 
 // Formats generics like <T: Bound>
-fn format_generics_params_only(params: &[GenericParamDef], krate: &Crate) -> String {
+// Synthetic `impl Trait` params are omitted, as they are shown in argument position
+fn format_generics_params_only(params: &[GenericParamDef], printer: &Printer) -> String {
+    let params: Vec<String> = params
+        .iter()
+        .filter(|p| {
+            !matches!(
+                p.kind,
+                rustdoc_types::GenericParamDefKind::Type {
+                    is_synthetic: true,
+                    ..
+                }
+            )
+        })
+        .map(|p| format_generic_param_def(p, printer))
+        .collect();
     if params.is_empty() {
         return String::new();
     }
-    format!(
-        "<{}>",
-        params
-            .iter()
-            .map(|p| format_generic_param_def(p, krate))
-            .collect::<Vec<_>>()
-            .join(", ")
-    )
+    format!("<{}>", params.join(", "))
+}
+
+/// Returns the generics as shown in item headers, with where-clause bounds moved
+/// inline if [`Printer::consolidate_bounds`] is enabled.
+///
+/// Unlike [`arrange_param_bounds`], long bound lists are never hoisted out of
+/// headers, which omit the where clause.
+fn header_generics<'g>(generics: &'g Generics, printer: &Printer) -> Cow<'g, Generics> {
+    let mut generics = Cow::Borrowed(generics);
+    if printer.consolidate_bounds {
+        consolidate_where_bounds(&mut generics);
+    }
+    generics
+}
+
+// Formats generic params for an item header, like <T: Bound>
+fn format_generics_header(generics: &Generics, printer: &Printer) -> String {
+    format_generics_params_only(&header_generics(generics, printer).params, printer)
 }
 
 // Formats only the where clause: "where T: Bound" or multi-line
-fn format_generics_where_only(predicates: &[WherePredicate], krate: &Crate) -> String {
+fn format_generics_where_only(predicates: &[WherePredicate], printer: &Printer) -> String {
     if predicates.is_empty() {
         return String::new();
     }
     let clauses: Vec<String> = predicates
         .iter()
-        .map(|p| match p {
+        .filter_map(|p| match p {
             WherePredicate::BoundPredicate {
                 type_,
                 bounds,
                 generic_params,
                 ..
             } => {
+                let is_self = matches!(type_, Type::Generic(name) if name == "Self");
+                let bounds_str = format_param_bounds(bounds, !is_self, printer);
+                if bounds_str.is_empty() && !bounds.is_empty() {
+                    return None; // Every bound was an omitted `Sized`
+                }
                 let hrtb = if generic_params.is_empty() {
                     "".to_string()
                 } else {
@@ -1045,44 +2796,47 @@ fn format_generics_where_only(predicates: &[WherePredicate], krate: &Crate) -> S
                         "for<{}> ",
                         generic_params
                             .iter()
-                            .map(|gp| format_generic_param_def(gp, krate))
+                            .map(|gp| format_generic_param_def(gp, printer))
                             .collect::<Vec<_>>()
                             .join(", ")
                     )
                 };
-                format!(
+                Some(format!(
                     "{}{}: {}",
                     hrtb,
-                    format_type(type_, krate),
-                    bounds
-                        .iter()
-                        .map(|b| format_generic_bound(b, krate))
-                        .collect::<Vec<_>>()
-                        .join(" + ")
-                )
+                    format_type(type_, printer),
+                    bounds_str
+                ))
             }
             WherePredicate::LifetimePredicate {
                 lifetime, outlives, ..
-            } => {
-                format!(
-                    "{}: {}",
-                    lifetime,
-                    outlives
-                        .iter()
-                        .map(|lt| lt.to_string()) // Add quotes
-                        .collect::<Vec<_>>()
-                        .join(" + ")
-                )
-            }
-            WherePredicate::EqPredicate { lhs, rhs, .. } => {
-                format!("{} == {}", format_type(lhs, krate), format_term(rhs, krate))
-            }
+            } => Some(format!(
+                "{}: {}",
+                lifetime,
+                outlives
+                    .iter()
+                    .map(|lt| lt.to_string()) // Add quotes
+                    .collect::<Vec<_>>()
+                    .join(" + ")
+            )),
+            WherePredicate::EqPredicate { lhs, rhs, .. } => Some(format!(
+                "{} == {}",
+                format_type(lhs, printer),
+                format_term(rhs, printer)
+            )),
         })
         .collect();
+    if clauses.is_empty() {
+        return String::new();
+    }
 
     // Determine if multi-line formatting is needed
     let total_len = clauses.iter().map(|s| s.len()).sum::<usize>();
-    let is_multiline = clauses.len() > 1 || total_len > 60; // Heuristic for multi-line
+    let has_long_bounds = printer.render_bounds_on_separate_lines
+        && predicates.iter().any(|p| {
+            matches!(p, WherePredicate::BoundPredicate { bounds, .. } if bounds.len() > MAX_INLINE_BOUNDS)
+        });
+    let is_multiline = clauses.len() > 1 || total_len > 60 || has_long_bounds; // Heuristic for multi-line
 
     if is_multiline {
         format!("where\n    {}", clauses.join(",\n    ")) // Indent contents
@@ -1093,6 +2847,39 @@ fn format_generics_where_only(predicates: &[WherePredicate], krate: &Crate) -> S
 
 // --- Structured Printing Logic ---
 
+/// Ranks a trait by likely reader interest for
+/// [`Printer::sort_trait_impls_by_usefulness`]; lower ranks sort first.
+///
+/// Conversions and constructors come first, then behavioral traits (formatting,
+/// iteration, comparison), then traits outside the standard library, then
+/// operator traits, and finally marker traits.
+fn trait_usefulness_rank(path: &[String]) -> u8 {
+    let (Some(root), Some(name)) = (path.first(), path.last()) else {
+        return 2;
+    };
+    if !matches!(root.as_str(), "core" | "alloc" | "std") {
+        return 2;
+    }
+    let module = path.get(1).map(String::as_str).unwrap_or_default();
+    match (module, name.as_str()) {
+        ("convert" | "default" | "str" | "string" | "borrow", _)
+        | ("iter", "FromIterator")
+        | (_, "ToOwned") => 0,
+        ("marker" | "panic" | "any", _) => 4,
+        ("ops", "Deref" | "DerefMut" | "Drop" | "Fn" | "FnMut" | "FnOnce") => 1,
+        ("ops", _) => 3,
+        _ => 1,
+    }
+}
+
+/// Returns the ids of a trait's direct supertraits.
+fn supertrait_ids(t: &Trait) -> impl Iterator<Item = Id> + '_ {
+    t.bounds.iter().filter_map(|bound| match bound {
+        GenericBound::TraitBound { trait_, .. } => Some(trait_.id),
+        _ => None,
+    })
+}
+
 /// Category of a trait implementation for display purposes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum TraitImplCategory {
@@ -1123,6 +2910,9 @@ struct FormattedTraitImpl {
     is_negative: bool,
     /// The category this trait implementation falls into.
     category: TraitImplCategory,
+    /// The trait path with its generic args, e.g. `convert::Into<U>`, used for sorting
+    /// and in short trait lists.
+    display_path: String,
     /// The pre-formatted Markdown list entry for this trait.
     formatted_markdown_list_entry: String,
     /// Optionally link back to the real Impl item in the krate index.
@@ -1134,7 +2924,8 @@ struct FormattedTraitImpl {
 
 impl PartialEq for FormattedTraitImpl {
     /// Compares two FormattedTraitImpl instances for equality.
-    /// For common trait identification, `impl_id` and `formatted_markdown_list_entry` are ignored.
+    /// For common trait identification, `impl_id`, `display_path` and
+    /// `formatted_markdown_list_entry` are ignored.
     fn eq(&self, other: &Self) -> bool {
         self.trait_id == other.trait_id
             && self.trait_generics == other.trait_generics // Compare trait generics structure
@@ -1147,7 +2938,8 @@ impl Eq for FormattedTraitImpl {}
 
 impl Hash for FormattedTraitImpl {
     /// Hashes the FormattedTraitImpl instance.
-    /// For common trait identification, `impl_id` and `formatted_markdown_list_entry` are ignored.
+    /// For common trait identification, `impl_id`, `display_path` and
+    /// `formatted_markdown_list_entry` are ignored.
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.trait_id.hash(state);
         self.trait_generics.hash(state); // Hash trait generics structure
@@ -1160,101 +2952,98 @@ impl Hash for FormattedTraitImpl {
 // Helper function to convert GenericArgs to Generics
 // This function attempts to create a Generics struct from GenericArgs.
 // It's a simplification, primarily for representing the generics *of a path* (like a trait path).
-fn generic_args_to_generics(args_opt: Option<Box<GenericArgs>>, krate: &Crate) -> Generics {
+fn generic_args_to_generics(args_opt: Option<Box<GenericArgs>>, printer: &Printer) -> Generics {
     let mut params = Vec::new();
     let mut where_predicates = Vec::new(); // Not typically part of GenericArgs directly
 
     if let Some(args_box) = args_opt {
-        match *args_box {
-            GenericArgs::AngleBracketed {
-                args, constraints, ..
-            } => {
-                for arg in args {
-                    match arg {
-                        GenericArg::Type(t) => {
-                            let name = match t {
-                                Type::Generic(g_name) => g_name,
-                                _ => format_type(&t, krate), // Fallback to formatted type if not simple generic.
-                            };
-                            params.push(GenericParamDef {
-                                name,
-                                kind: rustdoc_types::GenericParamDefKind::Type {
-                                    bounds: vec![], // Bounds are in `constraints` or `where_predicates`
-                                    default: None,
-                                    is_synthetic: false,
-                                },
-                            });
-                        }
-                        GenericArg::Lifetime(lt_name) => {
-                            params.push(GenericParamDef {
-                                name: lt_name,
-                                kind: rustdoc_types::GenericParamDefKind::Lifetime {
-                                    outlives: vec![],
-                                },
-                            });
-                        }
-                        GenericArg::Const(c) => {
-                            params.push(GenericParamDef {
-                                name: c.expr,
-                                kind: rustdoc_types::GenericParamDefKind::Const {
-                                    type_: Type::Infer, // Type info might be lost here or in `c.type_`
-                                    default: None,
-                                },
-                            });
-                        }
-                        GenericArg::Infer => {
-                            params.push(GenericParamDef {
-                                name: "_".to_string(),
-                                kind: rustdoc_types::GenericParamDefKind::Type {
-                                    bounds: vec![],
-                                    default: None,
-                                    is_synthetic: true,
-                                },
-                            });
-                        }
+        // Parenthesized and ReturnTypeNotation don't map cleanly to Generics params/predicates
+        if let GenericArgs::AngleBracketed {
+            args, constraints, ..
+        } = *args_box
+        {
+            for arg in args {
+                match arg {
+                    GenericArg::Type(t) => {
+                        let name = match t {
+                            Type::Generic(g_name) => g_name,
+                            _ => format_type(&t, printer), // Fallback to formatted type if not simple generic.
+                        };
+                        params.push(GenericParamDef {
+                            name,
+                            kind: rustdoc_types::GenericParamDefKind::Type {
+                                bounds: vec![], // Bounds are in `constraints` or `where_predicates`
+                                default: None,
+                                is_synthetic: false,
+                            },
+                        });
+                    }
+                    GenericArg::Lifetime(lt_name) => {
+                        params.push(GenericParamDef {
+                            name: lt_name,
+                            kind: rustdoc_types::GenericParamDefKind::Lifetime { outlives: vec![] },
+                        });
+                    }
+                    GenericArg::Const(c) => {
+                        params.push(GenericParamDef {
+                            name: c.expr,
+                            kind: rustdoc_types::GenericParamDefKind::Const {
+                                type_: Type::Infer, // Type info might be lost here or in `c.type_`
+                                default: None,
+                            },
+                        });
+                    }
+                    GenericArg::Infer => {
+                        params.push(GenericParamDef {
+                            name: "_".to_string(),
+                            kind: rustdoc_types::GenericParamDefKind::Type {
+                                bounds: vec![],
+                                default: None,
+                                is_synthetic: true,
+                            },
+                        });
                     }
                 }
-                // Convert AssocItemConstraints to WherePredicates (simplified)
-                for constraint in constraints {
-                    match constraint {
-                        rustdoc_types::AssocItemConstraint {
+            }
+            // Convert AssocItemConstraints to WherePredicates (simplified)
+            for constraint in constraints {
+                match constraint {
+                    rustdoc_types::AssocItemConstraint {
+                        name: assoc_name,
+                        args: assoc_args, // GenericArgs for the associated type itself
+                        binding: rustdoc_types::AssocItemConstraintKind::Equality(term),
+                    } => {
+                        // Construct a Type for the LHS: Self::AssocName<Args>
+                        let lhs_type = Type::QualifiedPath {
                             name: assoc_name,
-                            args: assoc_args, // GenericArgs for the associated type itself
-                            binding: rustdoc_types::AssocItemConstraintKind::Equality(term),
-                        } => {
-                            // Construct a Type for the LHS: Self::AssocName<Args>
-                            let lhs_type = Type::QualifiedPath {
-                                name: assoc_name,
-                                args: Box::new(assoc_args),
-                                self_type: Box::new(Type::Generic("Self".to_string())), // Placeholder "Self"
-                                trait_: None, // Assuming it's an associated type on "Self"
-                            };
-                            where_predicates.push(WherePredicate::EqPredicate {
-                                lhs: lhs_type,
-                                rhs: term,
-                            });
-                        }
-                        rustdoc_types::AssocItemConstraint {
+                            args: Box::new(assoc_args),
+                            self_type: Box::new(Type::Generic("Self".to_string())), // Placeholder "Self"
+                            trait_: None, // Assuming it's an associated type on "Self"
+                        };
+                        where_predicates.push(WherePredicate::EqPredicate {
+                            lhs: lhs_type,
+                            rhs: term,
+                        });
+                    }
+                    rustdoc_types::AssocItemConstraint {
+                        name: assoc_name,
+                        args: assoc_args,
+                        binding: rustdoc_types::AssocItemConstraintKind::Constraint(bounds),
+                    } => {
+                        let for_type = Type::QualifiedPath {
                             name: assoc_name,
-                            args: assoc_args,
-                            binding: rustdoc_types::AssocItemConstraintKind::Constraint(bounds),
-                        } => {
-                            let for_type = Type::QualifiedPath {
-                                name: assoc_name,
-                                args: Box::new(assoc_args),
-                                self_type: Box::new(Type::Generic("Self".to_string())),
-                                trait_: None,
-                            };
-                            where_predicates.push(WherePredicate::BoundPredicate {
-                                type_: for_type,
-                                bounds,
-                                generic_params: vec![], // HRTBs not directly in constraints
-                            });
-                        }
+                            args: Box::new(assoc_args),
+                            self_type: Box::new(Type::Generic("Self".to_string())),
+                            trait_: None,
+                        };
+                        where_predicates.push(WherePredicate::BoundPredicate {
+                            type_: for_type,
+                            bounds,
+                            generic_params: vec![], // HRTBs not directly in constraints
+                        });
                     }
                 }
             }
-            _ => {} // Parenthesized and ReturnTypeNotation don't map cleanly to Generics params/predicates
         }
     }
 
@@ -1290,7 +3079,7 @@ fn trait_impl_has_associated_items(imp: &Impl, krate: &Crate) -> bool {
 /// This means the `impl` block's generics directly mirror the generics of the type it's for,
 /// and the `impl` doesn't introduce its own `where` clauses or complex associated type bindings
 /// on the trait path itself.
-fn is_passthrough_generic_impl_check(imp: &Impl, trait_path: &Path, krate: &Crate) -> bool {
+fn is_passthrough_generic_impl_check(imp: &Impl, trait_path: &Path, printer: &Printer) -> bool {
     // Trait path must have no args or empty angle bracketed args/constraints
     let trait_path_is_simple = trait_path.args.as_ref().is_none_or(|ga| {
         matches!(ga.as_ref(), GenericArgs::AngleBracketed { args, constraints } if args.is_empty() && constraints.is_empty())
@@ -1300,7 +3089,7 @@ fn is_passthrough_generic_impl_check(imp: &Impl, trait_path: &Path, krate: &Crat
     }
 
     // Impl must not have associated items (types/consts)
-    if trait_impl_has_associated_items(imp, krate) {
+    if trait_impl_has_associated_items(imp, printer.krate) {
         return false;
     }
 
@@ -1310,7 +3099,7 @@ fn is_passthrough_generic_impl_check(imp: &Impl, trait_path: &Path, krate: &Crat
     };
 
     // The `for_` type item must exist and have generics
-    let Some(for_type_item) = krate.index.get(&for_path.id) else {
+    let Some(for_type_item) = printer.krate.index.get(&for_path.id) else {
         return false;
     };
     let Some(for_generics) = get_trait_for_type_generics(for_type_item) else {
@@ -1407,26 +3196,22 @@ impl FormattedTraitImpl {
         krate: &Crate,
         printer: &Printer, // Pass printer for generate_impl_trait_block
     ) -> Self {
-        let trait_path_str = format_id_path_canonical(&trait_path.id, krate);
+        let trait_path_str = format_id_path_canonical(&trait_path.id, printer);
         let cleaned_trait_path = clean_trait_path(&trait_path_str);
 
         let display_path_with_generics = format!(
             "{}{}{}",
-            imp.is_negative.then_some("!").unwrap_or_default(),
+            if imp.is_negative { "!" } else { "" },
             cleaned_trait_path,
             if let Some(args) = &trait_path.args {
-                let args_str = format_generic_args(args, krate);
-                if !args_str.is_empty() {
-                    format!("<{}>", args_str)
-                } else {
-                    String::new()
-                }
+                format_generic_args_suffix(args, printer)
             } else {
                 String::new()
             }
         );
 
-        let is_passthrough_generic_impl = is_passthrough_generic_impl_check(imp, trait_path, krate);
+        let is_passthrough_generic_impl =
+            is_passthrough_generic_impl_check(imp, trait_path, printer);
 
         let category = if imp.is_synthetic {
             TraitImplCategory::Auto
@@ -1438,55 +3223,82 @@ impl FormattedTraitImpl {
             TraitImplCategory::GenericOrComplex
         };
 
+        // Without a code block, impls with generic parameters that don't appear in the
+        // implementing type (like `'a` in `impl<'a, T> From<&'a T> for Wrapper<T>`)
+        // show the whole header, so those parameters are introduced and bounded
+        let for_type = format_type(&imp.for_, printer);
+        let for_type_tokens: HashSet<&str> = for_type
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '\''))
+            .collect();
+        let has_unconstrained_params = imp
+            .generics
+            .params
+            .iter()
+            .any(|param| !for_type_tokens.contains(param.name.as_str()));
+        let short_entry = if has_unconstrained_params && !imp.is_negative {
+            format!("- `{}`", format_impl_decl(imp, printer).replace('\n', " "))
+        } else {
+            format!("- `{}`", display_path_with_generics)
+        };
+
         let mut list_entry = String::new();
         match category {
             TraitImplCategory::Simple | TraitImplCategory::Auto => {
                 write!(list_entry, "- `{}`", display_path_with_generics).unwrap();
             }
             TraitImplCategory::GenericOrComplex => {
-                if let Some(impl_block_str) = generate_impl_trait_block(imp, printer.krate) {
+                if let Some(impl_block_str) = generate_impl_trait_block(imp, printer) {
                     if !impl_block_str.trim_end_matches("{\n}").trim().is_empty() {
                         writeln!(list_entry, "- `{}`", display_path_with_generics).unwrap();
                         writeln!(list_entry).unwrap();
-                        let full_code_block = format!("```rust\n{}\n```", impl_block_str);
+                        let full_code_block =
+                            format!("```{}\n{}\n```", printer.code_fence_lang, impl_block_str);
                         let indented_block = indent_string(&full_code_block, 4);
                         writeln!(list_entry, "{}", indented_block).unwrap(); // Keep trailing newline from indent
                     } else {
-                        write!(list_entry, "- `{}`", display_path_with_generics).unwrap();
+                        write!(list_entry, "{}", short_entry).unwrap();
                     }
                 } else {
-                    write!(list_entry, "- `{}`", display_path_with_generics).unwrap();
+                    write!(list_entry, "{}", short_entry).unwrap();
                 }
             }
             TraitImplCategory::Blanket => {
-                let where_clause =
-                    format_generics_where_only(&imp.generics.where_predicates, krate);
+                // Show the impl's own generics and the blanket type, e.g.
+                // `impl<T: Debug> Trait for T`, rather than the type it was applied to
+                let generics = arrange_param_bounds(&imp.generics, printer);
+                let blanket_header = format!(
+                    "{}impl{} {} for {}",
+                    if imp.is_unsafe { "unsafe " } else { "" },
+                    format_generics_params_only(&generics.params, printer),
+                    display_path_with_generics,
+                    format_type(imp.blanket_impl.as_ref().unwrap_or(&imp.for_), printer)
+                );
+                // A reference to the trait's own section is added when printing, see
+                // `Printer::trait_list_entry`
+                let where_clause = format_generics_where_only(&generics.where_predicates, printer);
                 if !where_clause.is_empty() {
                     if where_clause.lines().count() == 1 {
-                        write!(
-                            list_entry,
-                            "- `{}` (`{}`)",
-                            display_path_with_generics, where_clause,
-                        )
-                        .unwrap();
+                        write!(list_entry, "- `{}` (`{}`)", blanket_header, where_clause).unwrap();
                     } else {
-                        writeln!(list_entry, "- `{}`", display_path_with_generics).unwrap();
-                        let code_block = format!("```rust\n{}\n```", where_clause);
+                        writeln!(list_entry, "- `{}`", blanket_header).unwrap();
+                        let code_block =
+                            format!("```{}\n{}\n```", printer.code_fence_lang, where_clause);
                         let indented_block = indent_string(&code_block, 4);
                         write!(list_entry, "\n{}\n", indented_block).unwrap(); // Keep trailing newline
                     }
                 } else {
-                    write!(list_entry, "- `{}`", display_path_with_generics).unwrap();
+                    write!(list_entry, "- `{}`", blanket_header).unwrap();
                 }
             }
         }
 
         FormattedTraitImpl {
             trait_id: trait_path.id,
-            trait_generics: generic_args_to_generics(trait_path.args.clone(), krate),
+            trait_generics: generic_args_to_generics(trait_path.args.clone(), printer),
             is_unsafe_impl: imp.is_unsafe,
             is_negative: imp.is_negative,
             category,
+            display_path: display_path_with_generics,
             formatted_markdown_list_entry: list_entry.trim_end().to_string(), // Trim trailing newline for consistency
             impl_id,
         }
@@ -1529,7 +3341,11 @@ impl FormattedTraitImpl {
 /// Generates the primary declaration string for an item (e.g., `struct Foo`, `fn bar()`).
 /// For functions, this is deliberately simplified (no attrs, no where clause).
 /// For traits, structs, and enums, prepends the current module path.
-fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[String]) -> String {
+fn generate_item_declaration(
+    item: &Item,
+    printer: &Printer,
+    current_module_path: &[String],
+) -> String {
     let name = item.name.as_deref().unwrap_or(match &item.inner {
         ItemEnum::StructField(_) => "{unnamed_field}", // Special case for unnamed fields
         _ => "{unnamed}",
@@ -1544,7 +3360,7 @@ fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[
             format!(
                 "struct {}{}",
                 fq_path,
-                format_generics_params_only(&s.generics.params, krate)
+                format_generics_header(&s.generics, printer)
             )
         }
         ItemEnum::Enum(e) => {
@@ -1556,7 +3372,7 @@ fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[
             format!(
                 "enum {}{}",
                 fq_path,
-                format_generics_params_only(&e.generics.params, krate)
+                format_generics_header(&e.generics, printer)
             )
         }
         ItemEnum::Union(u) => {
@@ -1568,7 +3384,7 @@ fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[
             format!(
                 "union {}{}",
                 fq_path,
-                format_generics_params_only(&u.generics.params, krate)
+                format_generics_header(&u.generics, printer)
             )
         }
         ItemEnum::Trait(t) => {
@@ -1580,33 +3396,30 @@ fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[
             }
             let fq_path = fq_path_parts.join("::");
 
+            // Canonical keyword order is `unsafe auto trait`
             format!(
                 "{}{}{}{}{}",
-                auto,
                 unsafe_kw,
+                auto,
                 "trait ",
                 fq_path, // Use fully qualified path
-                format_generics_params_only(&t.generics.params, krate)
+                format_generics_header(&t.generics, printer)
             )
         }
         ItemEnum::Function(f) => {
+            let f = impl_trait_as_generics(f, printer);
             // Simplified version for the header: no where clause, but include attributes
             let mut code = String::new();
             write!(code, "{}", format_attributes_inline(&item.attrs)).unwrap(); // Add attributes
             write!(code, "fn {}", name).unwrap();
             // Include only param generics here
-            write!(
-                code,
-                "{}",
-                format_generics_params_only(&f.generics.params, krate)
-            )
-            .unwrap();
+            write!(code, "{}", format_generics_header(&f.generics, printer)).unwrap();
             write!(code, "(").unwrap();
             let args_str = f
                 .sig
                 .inputs
                 .iter()
-                .map(|(n, t)| format!("{}: {}", n, format_type(t, krate))) // Use arg name from tuple
+                .map(|(n, t)| format_fn_input(n, t, printer))
                 .collect::<Vec<_>>()
                 .join(", ");
             write!(code, "{}", args_str).unwrap();
@@ -1615,19 +3428,19 @@ fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[
             }
             write!(code, ")").unwrap();
             if let Some(output_type) = &f.sig.output {
-                write!(code, " -> {}", format_type(output_type, krate)).unwrap();
+                write!(code, " -> {}", format_type(output_type, printer)).unwrap();
             }
             code
         }
         ItemEnum::TypeAlias(ta) => format!(
             "type {}{}",
             name,
-            format_generics_params_only(&ta.generics.params, krate)
+            format_generics_header(&ta.generics, printer)
         ),
         ItemEnum::TraitAlias(ta) => format!(
             "trait {}{}",
             name,
-            format_generics_params_only(&ta.generics.params, krate)
+            format_generics_header(&ta.generics, printer)
         ),
         ItemEnum::Constant { .. } => format!("const {}", name), // Type/value in code block
         ItemEnum::Static(s) => format!("static {}{}", if s.is_mutable { "mut " } else { "" }, name),
@@ -1647,16 +3460,16 @@ fn generate_item_declaration(item: &Item, krate: &Crate, current_module_path: &[
         } => format!("extern crate {}", crate_name),
         ItemEnum::Use(_) => format!("use {}", name), // Basic format for Use items
         ItemEnum::ExternType => format!("extern type {}", name),
-        ItemEnum::Variant(v) => format_variant_signature(item, v, krate), // Use helper
+        ItemEnum::Variant(v) => format_variant_signature(item, v, printer), // Use helper
         ItemEnum::StructField(_) => name.to_string(), // Field name only for header
-        ItemEnum::AssocConst { .. } => format!("const {}", name),
+        ItemEnum::AssocConst { type_, .. } => format_assoc_const_decl(name, type_, None, printer),
         ItemEnum::AssocType { .. } => format!("type {}", name),
         ItemEnum::Impl(_) => "impl".to_string(), // Impls handled specially
     }
 }
 
 /// Generates the `struct { ... }` code block.
-fn generate_struct_code_block(item: &Item, s: &Struct, krate: &Crate) -> String {
+fn generate_struct_code_block(item: &Item, s: &Struct, printer: &Printer) -> String {
     let name = item
         .name
         .as_deref()
@@ -1664,13 +3477,14 @@ fn generate_struct_code_block(item: &Item, s: &Struct, krate: &Crate) -> String
     let mut code = String::new();
     write!(
         code,
-        "{}pub struct {}",
+        "{}{}struct {}",
         format_attributes(&item.attrs), // Use multi-line attributes
+        format_visibility(&item.visibility, printer),
         name
     )
     .unwrap();
     // Use full generics here, including where clause
-    let generics_str = format_generics_full(&s.generics, krate);
+    let generics_str = format_generics_full(&s.generics, printer);
     let where_is_multiline = generics_str.contains("where\n");
     write!(code, "{}", generics_str).unwrap();
 
@@ -1678,7 +3492,7 @@ fn generate_struct_code_block(item: &Item, s: &Struct, krate: &Crate) -> String
         StructKind::Plain { fields, .. } => {
             // fields_stripped ignored
             if where_is_multiline {
-                write!(code, " {{").unwrap(); // Open brace on same line as multiline where
+                write!(code, "\n{{").unwrap(); // Open brace on its own line after multiline where
             } else {
                 write!(code, " {{").unwrap(); // Open brace on same line as generics or no generics
             }
@@ -1687,15 +3501,19 @@ fn generate_struct_code_block(item: &Item, s: &Struct, krate: &Crate) -> String
                 writeln!(code).unwrap();
             }
             for field_id in fields {
-                if let Some(field_item) = krate.index.get(field_id) {
+                if let Some(field_item) = printer.krate.index.get(field_id) {
+                    if printer.is_skipped_hidden(field_item) {
+                        continue;
+                    }
                     if let ItemEnum::StructField(field_type) = &field_item.inner {
                         let field_name = field_item.name.as_deref().unwrap_or("_");
                         writeln!(
                             code,
-                            "    {}pub {}: {},",
+                            "    {}{}{}: {},",
                             format_attributes_inline(&field_item.attrs), // Use multi-line attributes
+                            format_visibility(&field_item.visibility, printer),
                             field_name,
-                            format_type(field_type, krate)
+                            format_type(field_type, printer)
                         )
                         .unwrap();
                     }
@@ -1714,13 +3532,15 @@ fn generate_struct_code_block(item: &Item, s: &Struct, krate: &Crate) -> String
                 .filter_map(|opt_id| {
                     opt_id
                         .as_ref()
-                        .and_then(|id| krate.index.get(id))
+                        .and_then(|id| printer.krate.index.get(id))
+                        .filter(|field_item| !printer.is_skipped_hidden(field_item))
                         .and_then(|field_item| {
                             if let ItemEnum::StructField(field_type) = &field_item.inner {
                                 Some(format!(
-                                    "{}pub {}",
+                                    "{}{}{}",
                                     format_attributes_inline(&field_item.attrs), // Use multi-line attributes
-                                    format_type(field_type, krate)
+                                    format_visibility(&field_item.visibility, printer),
+                                    format_type(field_type, printer)
                                 ))
                             } else {
                                 None
@@ -1746,30 +3566,38 @@ fn generate_struct_code_block(item: &Item, s: &Struct, krate: &Crate) -> String
 }
 
 /// Generates the `enum { ... }` code block.
-fn generate_enum_code_block(item: &Item, e: &Enum, krate: &Crate) -> String {
+fn generate_enum_code_block(item: &Item, e: &Enum, printer: &Printer) -> String {
     let name = item.name.as_deref().expect("Enum item should have a name");
     let mut code = String::new();
     write!(
         code,
-        "{}pub enum {}",
+        "{}{}enum {}",
         format_attributes(&item.attrs), // Use multi-line attributes
+        format_visibility(&item.visibility, printer),
         name
     )
     .unwrap();
-    let generics_str = format_generics_full(&e.generics, krate);
+    let generics_str = format_generics_full(&e.generics, printer);
     write!(code, "{}", generics_str).unwrap();
-    write!(code, " {{").unwrap();
+    if generics_str.contains("where\n") {
+        write!(code, "\n{{").unwrap(); // Open brace on its own line after multiline where
+    } else {
+        write!(code, " {{").unwrap();
+    }
 
     if !e.variants.is_empty() {
         writeln!(code).unwrap();
     }
     for variant_id in &e.variants {
-        if let Some(variant_item) = krate.index.get(variant_id) {
+        if let Some(variant_item) = printer.krate.index.get(variant_id) {
+            if printer.is_skipped_hidden(variant_item) {
+                continue;
+            }
             if let ItemEnum::Variant(variant_data) = &variant_item.inner {
                 write!(
                     code,
                     "    {}",
-                    format_variant_definition(variant_item, variant_data, krate) // Pass variant_item
+                    format_variant_definition(variant_item, variant_data, printer) // Pass variant_item
                 )
                 .unwrap();
                 // Add discriminant if present
@@ -1789,33 +3617,42 @@ fn generate_enum_code_block(item: &Item, e: &Enum, krate: &Crate) -> String {
 }
 
 /// Generates the `union { ... }` code block.
-fn generate_union_code_block(item: &Item, u: &Union, krate: &Crate) -> String {
+fn generate_union_code_block(item: &Item, u: &Union, printer: &Printer) -> String {
     let name = item.name.as_deref().expect("Union item should have a name");
     let mut code = String::new();
     write!(
         code,
-        "{}pub union {}",
+        "{}{}union {}",
         format_attributes(&item.attrs), // Use multi-line attributes
+        format_visibility(&item.visibility, printer),
         name
     )
     .unwrap();
-    let generics_str = format_generics_full(&u.generics, krate);
+    let generics_str = format_generics_full(&u.generics, printer);
     write!(code, "{}", generics_str).unwrap();
-    write!(code, " {{").unwrap();
+    if generics_str.contains("where\n") {
+        write!(code, "\n{{").unwrap(); // Open brace on its own line after multiline where
+    } else {
+        write!(code, " {{").unwrap();
+    }
 
     if !u.fields.is_empty() {
         writeln!(code).unwrap();
     }
     for field_id in &u.fields {
-        if let Some(field_item) = krate.index.get(field_id) {
+        if let Some(field_item) = printer.krate.index.get(field_id) {
+            if printer.is_skipped_hidden(field_item) {
+                continue;
+            }
             if let ItemEnum::StructField(field_type) = &field_item.inner {
                 let field_name = field_item.name.as_deref().unwrap_or("_");
                 writeln!(
                     code,
-                    "    {}pub {}: {},",
+                    "    {}{}{}: {},",
                     format_attributes_inline(&field_item.attrs), // Use multi-line attributes
+                    format_visibility(&field_item.visibility, printer),
                     field_name,
-                    format_type(field_type, krate)
+                    format_type(field_type, printer)
                 )
                 .unwrap();
             }
@@ -1828,44 +3665,105 @@ fn generate_union_code_block(item: &Item, u: &Union, krate: &Crate) -> String {
     code
 }
 
-/// Generates the full trait declaration code block.
-fn generate_trait_code_block(item: &Item, t: &Trait, krate: &Crate) -> String {
-    let name = item.name.as_deref().expect("Trait item should have a name");
-    let mut code = String::new();
-
-    write!(code, "{}", format_attributes(&item.attrs)).unwrap(); // Use multi-line attributes
-
-    if t.is_auto {
-        write!(code, "pub auto ").unwrap();
+/// Generates the `pub const NAME: Type = value;` code block of a constant.
+fn generate_constant_code_block(
+    item: &Item,
+    type_: &Type,
+    constant: &Constant,
+    printer: &Printer,
+) -> String {
+    let name = item
+        .name
+        .as_deref()
+        .expect("Constant item should have a name");
+    let constant = Constant {
+        expr: printer.restore_elided_initializer(item, &constant.expr),
+        ..constant.clone()
+    };
+    format!(
+        "{}{}{};",
+        format_attributes(&item.attrs),
+        format_visibility(&item.visibility, printer),
+        format_assoc_const_decl(name, type_, Some(&format_const_expr(&constant)), printer)
+    )
+}
+
+/// Generates the `pub static [mut] NAME: Type = value;` code block of a static.
+/// Statics of `extern` blocks have no value.
+fn generate_static_code_block(
+    item: &Item,
+    st: &rustdoc_types::Static,
+    printer: &Printer,
+) -> String {
+    let name = item
+        .name
+        .as_deref()
+        .expect("Static item should have a name");
+    let mut code = format!(
+        "{}{}{}static {}{}: {}",
+        format_attributes(&item.attrs),
+        format_visibility(&item.visibility, printer),
+        if st.is_unsafe { "unsafe " } else { "" },
+        if st.is_mutable { "mut " } else { "" },
+        name,
+        format_type(&st.type_, printer)
+    );
+    if !st.expr.is_empty() {
+        let expr = printer.restore_elided_initializer(item, &st.expr);
+        write!(code, " = {}", expr).unwrap();
     }
+    code.push(';');
+    code
+}
+
+/// Generates the full trait declaration code block.
+fn generate_trait_code_block(item: &Item, t: &Trait, printer: &Printer) -> String {
+    let name = item.name.as_deref().expect("Trait item should have a name");
+    let mut code = String::new();
+
+    write!(code, "{}", format_attributes(&item.attrs)).unwrap(); // Use multi-line attributes
+
+    // Canonical keyword order is `pub unsafe auto trait`
+    write!(code, "{}", format_visibility(&item.visibility, printer)).unwrap();
     if t.is_unsafe {
-        write!(code, "pub unsafe ").unwrap();
-    } else if !t.is_auto {
-        // Add pub if not auto or unsafe (which imply pub sometimes)
-        write!(code, "pub ").unwrap();
+        write!(code, "unsafe ").unwrap();
+    }
+    if t.is_auto {
+        write!(code, "auto ").unwrap();
     }
     write!(code, "trait {}", name).unwrap();
+    let generics = arrange_param_bounds(&t.generics, printer);
     // Add generics params and supertraits (bounds)
     write!(
         code,
         "{}",
-        format_generics_params_only(&t.generics.params, krate)
+        format_generics_params_only(&generics.params, printer)
     )
     .unwrap();
+    let mut bounds_are_multiline = false;
     if !t.bounds.is_empty() {
-        write!(
-            code,
-            ": {}",
-            t.bounds
-                .iter()
-                .map(|b| format_generic_bound(b, krate))
-                .collect::<Vec<_>>()
-                .join(" + ")
-        )
-        .unwrap();
+        let bounds: Vec<String> = t
+            .bounds
+            .iter()
+            .map(|b| format_generic_bound(b, printer))
+            .collect();
+        let declaration_width = code.lines().last().unwrap_or("").len()
+            + ": ".len()
+            + bounds.join(" + ").len()
+            + " {".len();
+        bounds_are_multiline = bounds.len() > 1
+            && printer
+                .max_line_width
+                .is_some_and(|max_width| declaration_width > max_width);
+        if bounds_are_multiline {
+            // One supertrait per line, e.g. `pub trait Foo:\n    A +\n    B`
+            write!(code, ":\n    {}", bounds.join(" +\n    ")).unwrap();
+        } else {
+            write!(code, ": {}", bounds.join(" + ")).unwrap();
+        }
     }
     // Add where clause
-    let where_clause = format_generics_where_only(&t.generics.where_predicates, krate);
+    let where_clause = format_generics_where_only(&generics.where_predicates, printer);
     if !where_clause.is_empty() {
         if where_clause.contains('\n') {
             write!(code, "\n  {}", where_clause).unwrap(); // Multiline where
@@ -1875,11 +3773,13 @@ fn generate_trait_code_block(item: &Item, t: &Trait, krate: &Crate) -> String {
     }
 
     // Body
+    let brace_on_own_line = where_clause.contains('\n') || bounds_are_multiline;
+    let brace_sep = if brace_on_own_line { "\n" } else { " " };
     if t.items.is_empty() {
-        write!(code, " {{}}").unwrap();
+        write!(code, "{}{{}}", brace_sep).unwrap();
     } else {
-        if where_clause.contains('\n') {
-            write!(code, " {{").unwrap(); // Open brace on same line as multiline where
+        if brace_on_own_line {
+            write!(code, "\n{{").unwrap(); // Open brace on its own line after multiline where
         } else {
             write!(code, " {{").unwrap(); // Open brace on same line as signature
         }
@@ -1887,30 +3787,33 @@ fn generate_trait_code_block(item: &Item, t: &Trait, krate: &Crate) -> String {
 
         // Print associated items (simple versions)
         for item_id in &t.items {
-            if let Some(assoc_item) = krate.index.get(item_id) {
+            if let Some(assoc_item) = printer.krate.index.get(item_id) {
                 match &assoc_item.inner {
                     ItemEnum::AssocConst { type_, value, .. } => {
-                        write!(
+                        writeln!(
                             code,
-                            "    {}const {}: {}",
+                            "    {}{};",
                             format_attributes_inline(&assoc_item.attrs), // Use multi-line attributes
-                            assoc_item.name.as_deref().unwrap_or("_"),
-                            format_type(type_, krate)
+                            format_assoc_const_decl(
+                                assoc_item.name.as_deref().unwrap_or("_"),
+                                type_,
+                                value.as_deref(),
+                                printer
+                            )
                         )
                         .unwrap();
-                        if let Some(val) = value {
-                            write!(code, " = {};", val).unwrap(); // Use raw default string
-                        } else {
-                            write!(code, ";").unwrap();
-                        }
-                        writeln!(code).unwrap();
                     }
-                    ItemEnum::AssocType { bounds, type_, .. } => {
+                    ItemEnum::AssocType {
+                        generics,
+                        bounds,
+                        type_,
+                    } => {
                         write!(
                             code,
-                            "    {}type {}",
+                            "    {}type {}{}",
                             format_attributes_inline(&assoc_item.attrs), // Use multi-line attributes
-                            assoc_item.name.as_deref().unwrap_or("_")
+                            assoc_item.name.as_deref().unwrap_or("_"),
+                            format_generics_params_only(&generics.params, printer)
                         )
                         .unwrap();
                         if !bounds.is_empty() {
@@ -1919,27 +3822,33 @@ fn generate_trait_code_block(item: &Item, t: &Trait, krate: &Crate) -> String {
                                 ": {}",
                                 bounds
                                     .iter()
-                                    .map(|b| format_generic_bound(b, krate))
+                                    .map(|b| format_generic_bound(b, printer))
                                     .collect::<Vec<_>>()
                                     .join(" + ")
                             )
                             .unwrap();
                         }
                         if let Some(ty) = type_ {
-                            write!(code, " = {};", format_type(ty, krate)).unwrap();
-                        } else {
-                            write!(code, ";").unwrap();
+                            write!(code, " = {}", format_type(ty, printer)).unwrap();
                         }
-                        writeln!(code).unwrap();
+                        // GATs may carry their own where clause, e.g. `where Self: 'a`
+                        let where_clause =
+                            format_generics_where_only(&generics.where_predicates, printer);
+                        if !where_clause.is_empty() {
+                            write!(code, " {}", where_clause.replace('\n', "\n    ")).unwrap();
+                        }
+                        writeln!(code, ";").unwrap();
                     }
                     ItemEnum::Function(f) => {
-                        // Print simple function signature within trait def
-                        writeln!(
-                            code,
-                            "    {};",
-                            generate_function_code_block(assoc_item, f, krate)
-                        )
-                        .unwrap();
+                        // The signature already ends in `;` or `{ ... }` unless a
+                        // multi-line where clause closes it
+                        let signature = generate_function_code_block(assoc_item, f, printer)
+                            .replace('\n', "\n    ");
+                        write!(code, "    {}", signature).unwrap();
+                        if !signature.ends_with(';') && !signature.ends_with('}') {
+                            write!(code, ";").unwrap();
+                        }
+                        writeln!(code).unwrap();
                     }
                     _ => {} // Ignore others
                 }
@@ -1954,27 +3863,28 @@ fn generate_trait_code_block(item: &Item, t: &Trait, krate: &Crate) -> String {
 }
 
 /// Helper to format an impl block or trait impl declaration line.
-fn format_impl_decl(imp: &Impl, krate: &Crate) -> String {
+fn format_impl_decl(imp: &Impl, printer: &Printer) -> String {
     let mut decl = String::new();
     if imp.is_unsafe {
         write!(decl, "unsafe ").unwrap();
     }
     write!(decl, "impl").unwrap();
+    let generics = arrange_param_bounds(&imp.generics, printer);
 
     // Add generics params <...>
-    let generics_params = format_generics_params_only(&imp.generics.params, krate);
+    let generics_params = format_generics_params_only(&generics.params, printer);
     if !generics_params.is_empty() {
         write!(decl, "{}", generics_params).unwrap();
     }
 
     // Add Trait for Type
     if let Some(trait_path) = &imp.trait_ {
-        write!(decl, " {} for", format_path(trait_path, krate)).unwrap();
+        write!(decl, " {} for", format_path(trait_path, printer)).unwrap();
     }
-    write!(decl, " {}", format_type(&imp.for_, krate)).unwrap();
+    write!(decl, " {}", format_type(&imp.for_, printer)).unwrap();
 
     // Add where clause
-    let where_clause = format_generics_where_only(&imp.generics.where_predicates, krate);
+    let where_clause = format_generics_where_only(&generics.where_predicates, printer);
     if !where_clause.is_empty() {
         if where_clause.contains('\n') {
             write!(decl, "\n  {}", where_clause).unwrap(); // Multiline where
@@ -1986,7 +3896,7 @@ fn format_impl_decl(imp: &Impl, krate: &Crate) -> String {
 }
 
 /// Helper to format only the header part of an impl declaration (e.g., `impl MyTrait for MyStruct<T>`)
-fn format_impl_decl_header_only(imp: &Impl, krate: &Crate) -> String {
+fn format_impl_decl_header_only(imp: &Impl, printer: &Printer) -> String {
     let mut decl = String::new();
     if imp.is_unsafe {
         write!(decl, "unsafe ").unwrap();
@@ -1994,7 +3904,7 @@ fn format_impl_decl_header_only(imp: &Impl, krate: &Crate) -> String {
     write!(decl, "impl").unwrap();
 
     // Add generics params <...> to the impl block itself (not the trait part)
-    let generics_params = format_generics_params_only(&imp.generics.params, krate);
+    let generics_params = format_generics_header(&imp.generics, printer);
     if !generics_params.is_empty() {
         write!(decl, "{}", generics_params).unwrap();
     }
@@ -2002,11 +3912,11 @@ fn format_impl_decl_header_only(imp: &Impl, krate: &Crate) -> String {
     // Add Trait (if it's a trait impl)
     if let Some(trait_path) = &imp.trait_ {
         // For trait impl header, format trait_path with its own generics
-        write!(decl, " {} for", format_path(trait_path, krate)).unwrap();
+        write!(decl, " {} for", format_path(trait_path, printer)).unwrap();
     }
 
     // Add Type it's for
-    write!(decl, " {}", format_type(&imp.for_, krate)).unwrap();
+    write!(decl, " {}", format_type(&imp.for_, printer)).unwrap();
 
     // DO NOT add where clause here
     decl
@@ -2015,9 +3925,9 @@ fn format_impl_decl_header_only(imp: &Impl, krate: &Crate) -> String {
 /// Generates the full code block string for a trait impl, including associated items.
 /// Returns None if the impl block was already printed or is effectively empty.
 /// Skips methods within the impl block.
-fn generate_impl_trait_block(imp: &Impl, krate: &Crate) -> Option<String> {
+fn generate_impl_trait_block(imp: &Impl, printer: &Printer) -> Option<String> {
     let mut code = String::new();
-    let impl_header = format_impl_decl(imp, krate);
+    let impl_header = format_impl_decl(imp, printer);
     writeln!(code, "{} {{", impl_header).unwrap();
 
     let mut assoc_items_content = String::new();
@@ -2026,44 +3936,58 @@ fn generate_impl_trait_block(imp: &Impl, krate: &Crate) -> Option<String> {
     // Note: we assume that the caller already checked that the given Impl is selected
     // for printing so we don't also check the individual items.
     for assoc_item_id in &imp.items {
-        if let Some(assoc_item) = krate.index.get(assoc_item_id) {
+        if let Some(assoc_item) = printer.krate.index.get(assoc_item_id) {
             match &assoc_item.inner {
                 ItemEnum::AssocConst { type_, value, .. } => {
                     has_printable_assoc_items = true;
-                    write!(
+                    writeln!(
                         assoc_items_content,
-                        "    {}const {}: {}",
+                        "    {}{};",
                         format_attributes_inline(&assoc_item.attrs), // Use multi-line attributes
-                        assoc_item.name.as_deref().unwrap_or("_"),
-                        format_type(type_, krate)
+                        format_assoc_const_decl(
+                            assoc_item.name.as_deref().unwrap_or("_"),
+                            type_,
+                            value.as_deref(),
+                            printer
+                        )
                     )
                     .unwrap();
-                    if let Some(val) = value {
-                        write!(assoc_items_content, " = {};", val).unwrap();
-                    } else {
-                        write!(assoc_items_content, ";").unwrap();
-                    }
-                    writeln!(assoc_items_content).unwrap();
                 }
-                ItemEnum::AssocType { bounds, type_, .. } => {
+                ItemEnum::AssocType {
+                    generics,
+                    bounds,
+                    type_,
+                } => {
                     has_printable_assoc_items = true;
                     write!(
                         assoc_items_content,
-                        "    {}type {}",
+                        "    {}type {}{}",
                         format_attributes_inline(&assoc_item.attrs), // Use multi-line attributes
-                        assoc_item.name.as_deref().unwrap_or("_")
+                        assoc_item.name.as_deref().unwrap_or("_"),
+                        format_generics_params_only(&generics.params, printer)
                     )
                     .unwrap();
                     if !bounds.is_empty() {
                         let bounds_str = bounds
                             .iter()
-                            .map(|b| format_generic_bound(b, krate))
+                            .map(|b| format_generic_bound(b, printer))
                             .collect::<Vec<_>>()
                             .join(" + ");
                         write!(assoc_items_content, ": {}", bounds_str).unwrap();
                     }
                     if let Some(ty) = type_ {
-                        write!(assoc_items_content, " = {}", format_type(ty, krate)).unwrap();
+                        write!(assoc_items_content, " = {}", format_type(ty, printer)).unwrap();
+                    }
+                    // GATs may carry their own where clause, e.g. `where Self: 'a`
+                    let where_clause =
+                        format_generics_where_only(&generics.where_predicates, printer);
+                    if !where_clause.is_empty() {
+                        write!(
+                            assoc_items_content,
+                            " {}",
+                            where_clause.replace('\n', "\n    ")
+                        )
+                        .unwrap();
                     }
                     write!(assoc_items_content, ";").unwrap();
                     writeln!(assoc_items_content).unwrap();
@@ -2093,13 +4017,14 @@ fn generate_impl_trait_block(imp: &Impl, krate: &Crate) -> Option<String> {
 }
 
 /// Generates the full function signature for a code block.
-fn generate_function_code_block(item: &Item, f: &Function, krate: &Crate) -> String {
+fn generate_function_code_block(item: &Item, f: &Function, printer: &Printer) -> String {
     let name = item.name.as_deref().expect("Function should have a name");
+    let f = impl_trait_as_generics(f, printer);
     let mut code = String::new();
 
     // Attributes/Keywords
     write!(code, "{}", format_attributes_inline(&item.attrs)).unwrap(); // Use inline attributes
-    write!(code, "pub ").unwrap();
+    write!(code, "{}", format_visibility(&item.visibility, printer)).unwrap();
     if f.header.is_const {
         write!(code, "const ").unwrap();
     }
@@ -2110,15 +4035,18 @@ fn generate_function_code_block(item: &Item, f: &Function, krate: &Crate) -> Str
         write!(code, "unsafe ").unwrap();
     }
     if !matches!(f.header.abi, Abi::Rust) {
-        write!(code, "extern \"{:?}\" ", f.header.abi).unwrap(); // Use Debug for Abi
+        write!(code, "extern \"{}\" ", format_abi(&f.header.abi)).unwrap();
     }
 
     // Core signature
     write!(code, "fn {}", name).unwrap();
-    // Include full generics here, including where clause
-    let generics_str = format_generics_full(&f.generics, krate);
-    let where_is_multiline = generics_str.contains("where\n");
-    write!(code, "{}", generics_str).unwrap();
+    let generics = arrange_param_bounds(&f.generics, printer);
+    write!(
+        code,
+        "{}",
+        format_generics_params_only(&generics.params, printer)
+    )
+    .unwrap();
 
     // Parameters
     write!(code, "(").unwrap();
@@ -2126,7 +4054,7 @@ fn generate_function_code_block(item: &Item, f: &Function, krate: &Crate) -> Str
         .sig
         .inputs
         .iter()
-        .map(|(n, t)| format!("{}: {}", n, format_type(t, krate))) // Use name from tuple
+        .map(|(n, t)| format_fn_input(n, t, printer))
         .collect::<Vec<_>>()
         .join(", ");
     write!(code, "{}", args_str).unwrap();
@@ -2138,13 +4066,22 @@ fn generate_function_code_block(item: &Item, f: &Function, krate: &Crate) -> Str
 
     // Return type
     if let Some(output_type) = &f.sig.output {
-        write!(code, " -> {}", format_type(output_type, krate)).unwrap();
+        write!(code, " -> {}", format_type(output_type, printer)).unwrap();
+    }
+
+    // Where clause follows the return type
+    let where_clause = format_generics_where_only(&generics.where_predicates, printer);
+    let where_is_multiline = where_clause.contains('\n');
+    if where_is_multiline {
+        write!(code, "\n{}", where_clause).unwrap();
+    } else if !where_clause.is_empty() {
+        write!(code, " {}", where_clause).unwrap();
     }
 
     // Add semicolon or body indicator based on if it has implementation
     if f.has_body {
         if where_is_multiline {
-            write!(code, " {{ ... }}").unwrap(); // Body on same line as multiline where
+            write!(code, "\n{{ ... }}").unwrap(); // Body on its own line after multiline where
         } else {
             write!(code, " {{ ... }}").unwrap(); // Body on same line
         }
@@ -2157,7 +4094,7 @@ fn generate_function_code_block(item: &Item, f: &Function, krate: &Crate) -> Str
 }
 
 /// Formats a single enum variant's definition for the code block.
-fn format_variant_definition(item: &Item, v: &Variant, krate: &Crate) -> String {
+fn format_variant_definition(item: &Item, v: &Variant, printer: &Printer) -> String {
     let name = item.name.as_deref().unwrap_or("{Unnamed}");
     let attrs_str = format_attributes_inline(&item.attrs); // Use multi-line attributes
     match &v.kind {
@@ -2169,13 +4106,13 @@ fn format_variant_definition(item: &Item, v: &Variant, krate: &Crate) -> String
                 .filter_map(|opt_id| {
                     opt_id
                         .as_ref()
-                        .and_then(|id| krate.index.get(id))
+                        .and_then(|id| printer.krate.index.get(id))
                         .and_then(|field_item| {
                             if let ItemEnum::StructField(ty) = &field_item.inner {
                                 Some(format!(
                                     "{}{}",                                      // No pub for tuple variant fields
                                     format_attributes_inline(&field_item.attrs), // Use multi-line attributes
-                                    format_type(ty, krate)
+                                    format_type(ty, printer)
                                 ))
                             } else {
                                 None
@@ -2190,14 +4127,14 @@ fn format_variant_definition(item: &Item, v: &Variant, krate: &Crate) -> String
             let fields_str: Vec<String> = fields
                 .iter()
                 .filter_map(|id| {
-                    krate.index.get(id).and_then(|field_item| {
+                    printer.krate.index.get(id).and_then(|field_item| {
                         if let ItemEnum::StructField(ty) = &field_item.inner {
                             let field_name = field_item.name.as_deref().unwrap_or("_");
                             Some(format!(
                                 "{}{}: {}",                                  // No pub for struct variant fields
                                 format_attributes_inline(&field_item.attrs), // Use multi-line attributes
                                 field_name,
-                                format_type(ty, krate)
+                                format_type(ty, printer)
                             ))
                         } else {
                             None
@@ -2211,7 +4148,7 @@ fn format_variant_definition(item: &Item, v: &Variant, krate: &Crate) -> String
 }
 
 /// Formats an enum variant's signature for the `#####` header.
-fn format_variant_signature(item: &Item, v: &Variant, krate: &Crate) -> String {
+fn format_variant_signature(item: &Item, v: &Variant, printer: &Printer) -> String {
     // Similar to definition but potentially simpler, without pub, maybe add discriminant visually
     // Attributes are NOT included in the Hx header for variants.
     let name = item.name.as_deref().unwrap_or("{Unnamed}");
@@ -2223,10 +4160,10 @@ fn format_variant_signature(item: &Item, v: &Variant, krate: &Crate) -> String {
                 .filter_map(|opt_id| {
                     opt_id
                         .as_ref()
-                        .and_then(|id| krate.index.get(id))
+                        .and_then(|id| printer.krate.index.get(id))
                         .and_then(|field_item| {
                             if let ItemEnum::StructField(ty) = &field_item.inner {
-                                Some(format_type(ty, krate)) // No attributes here
+                                Some(format_type(ty, printer)) // No attributes here
                             } else {
                                 None
                             }
@@ -2239,10 +4176,10 @@ fn format_variant_signature(item: &Item, v: &Variant, krate: &Crate) -> String {
             let fields_str: Vec<String> = fields
                 .iter()
                 .filter_map(|id| {
-                    krate.index.get(id).and_then(|field_item| {
+                    printer.krate.index.get(id).and_then(|field_item| {
                         if let ItemEnum::StructField(ty) = &field_item.inner {
                             let field_name = field_item.name.as_deref().unwrap_or("_");
-                            Some(format!("{}: {}", field_name, format_type(ty, krate)))
+                            Some(format!("{}: {}", field_name, format_type(ty, printer)))
                         // No attributes here
                         } else {
                             None
@@ -2272,6 +4209,234 @@ struct ModuleTree {
     top_level_modules: Vec<Id>,
 }
 
+/// How struct fields and enum variants are laid out, see [`Printer::field_layout`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldLayout {
+    /// Each documented field or variant gets its own header section.
+    #[default]
+    Headers,
+    /// Compact Markdown tables where the item's shape allows it. Currently applies to
+    /// enum variants; the documented fields of tuple and struct variants are listed
+    /// in their variant's row. Struct fields, and enums with multi-paragraph variant or
+    /// field docs, use headers.
+    Table,
+}
+
+/// Where items reachable from several modules get their full details, see
+/// [`Printer::reexport_mode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReexportMode {
+    /// In the module that defines the item. Items defined in an undocumented (e.g.
+    /// private) module fall back to the first module that reaches them.
+    Definition,
+    /// In the module that re-exports the item with a `pub use`, preferring the
+    /// shortest module path when there are several.
+    ReexportSite,
+    /// In the first module that reaches the item; every other module lists it under
+    /// "Re-exports".
+    #[default]
+    Both,
+}
+
+/// How items are ordered within their section, see [`Printer::sort_order`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// By name.
+    #[default]
+    Alphabetical,
+    /// By source file and position; items without a span go last.
+    SourceSpan,
+    /// In the order the module declares them, re-exported items coming in at their
+    /// `use`; associated items in the order of their trait.
+    AsDeclared,
+}
+
+/// Extra documentation for an item, see [`Printer::doc_overrides`].
+///
+/// In a JSON overrides file a plain string is appended to the item's docs, while
+/// `{"replace": "..."}` replaces them:
+///
+/// ```json
+/// {
+///     "my_crate::parse": "See also the [guide](https://example.com/guide).",
+///     "my_crate::Config::new": { "replace": "Creates the default configuration." }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(untagged)]
+pub enum DocOverride {
+    /// Markdown appended after the item's own docs.
+    Append(String),
+    /// Markdown rendered instead of the item's own docs.
+    Replace {
+        /// The replacement Markdown.
+        replace: String,
+    },
+}
+
+/// Wall-clock time spent in each phase of a run, in the order the phases were
+/// recorded, e.g. through [`Printer::phase_timings`].
+///
+/// The `Display` implementation renders one `phase: duration` line per phase.
+#[derive(Debug, Clone, Default)]
+pub struct PhaseTimings {
+    phases: Vec<(String, std::time::Duration)>,
+}
+
+impl PhaseTimings {
+    /// Records that `phase` took `elapsed`.
+    pub fn record(&mut self, phase: &str, elapsed: std::time::Duration) {
+        self.phases.push((phase.to_string(), elapsed));
+    }
+
+    /// Runs `f`, recording its wall-clock time under `phase`.
+    pub fn time<T>(&mut self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let start = std::time::Instant::now();
+        let result = f();
+        self.record(phase, start.elapsed());
+        result
+    }
+
+    /// Returns the recorded phases and their durations.
+    pub fn phases(&self) -> &[(String, std::time::Duration)] {
+        &self.phases
+    }
+}
+
+/// Receives the name and duration of each phase, see [`Printer::phase_timings`].
+type PhaseSink<'a> = Box<dyn FnMut(&str, std::time::Duration) + 'a>;
+
+impl std::fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let width = self.phases.iter().map(|(phase, _)| phase.len()).max();
+        for (phase, elapsed) in &self.phases {
+            writeln!(
+                f,
+                "{:<width$}  {:>10.3}s",
+                format!("{}:", phase),
+                elapsed.as_secs_f64(),
+                width = width.unwrap_or(0) + 1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Formats a visibility as the prefix of a declaration, e.g. `pub(crate) `.
+///
+/// Items without a visibility of their own (trait items, trait impl items and enum
+/// variants) get an empty prefix, as do private items: rustdoc describes those as
+/// restricted to their module, which is the module being printed.
+fn format_visibility(visibility: &rustdoc_types::Visibility, printer: &Printer) -> String {
+    let module_path = &printer.current_module_path;
+    match visibility {
+        rustdoc_types::Visibility::Public => "pub ".to_string(),
+        rustdoc_types::Visibility::Default => String::new(),
+        rustdoc_types::Visibility::Crate if module_path.len() <= 1 => String::new(),
+        rustdoc_types::Visibility::Crate => "pub(crate) ".to_string(),
+        rustdoc_types::Visibility::Restricted { path, .. } => {
+            // rustdoc writes crate-relative paths as `::module`
+            let path = match path.strip_prefix("::") {
+                Some(relative) => format!("crate::{}", relative),
+                None => path.clone(),
+            };
+            let module = std::iter::once("crate")
+                .chain(module_path.iter().skip(1).map(String::as_str))
+                .collect::<Vec<_>>()
+                .join("::");
+            if path == module {
+                String::new()
+            } else {
+                format!("pub(in {}) ", path)
+            }
+        }
+    }
+}
+
+/// Formats the visibility of a `use` declaration, e.g. `pub(crate) use`.
+fn format_use_visibility(visibility: &rustdoc_types::Visibility, printer: &Printer) -> String {
+    format!("{}use", format_visibility(visibility, printer))
+}
+
+/// The most common [`Printer`] settings, bundled for [`render_crate`].
+///
+/// Options not covered here are available through the [`Printer`] builder.
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    /// Item paths to document, see [`Printer::paths`]. Empty documents everything.
+    pub paths: Vec<String>,
+    /// Items always documented, see [`Printer::allow_list`].
+    pub allow_list: Vec<String>,
+    /// Items left out of the document, see [`Printer::exclude_paths`].
+    pub exclude_paths: Vec<String>,
+    /// README and examples to include, see [`Printer::crate_extra`].
+    pub crate_extra: Option<CrateExtra>,
+    /// See [`Printer::include_other`].
+    pub include_other: bool,
+    /// See [`Printer::template_mode`].
+    pub template_mode: bool,
+    /// See [`Printer::no_common_traits`].
+    pub no_common_traits: bool,
+    /// See [`Printer::anchors`].
+    pub anchors: bool,
+    /// See [`Printer::toc`].
+    pub toc: bool,
+}
+
+/// Renders the Markdown documentation of an already parsed crate, e.g. the output
+/// of [`read_rustdoc_json`] or a build script, without running `rustdoc`.
+///
+/// This is a shorthand for configuring a [`Printer`] from `options` and calling
+/// [`Printer::print`].
+///
+/// # Example
+///
+/// ```no_run
+/// use cargo_manifest::Manifest;
+/// use rustdoc_markdown::{read_rustdoc_json, render_crate, RenderOptions};
+/// use std::path::Path;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let manifest = Manifest::from_path("Cargo.toml")?;
+/// let krate = read_rustdoc_json(Path::new("target/doc/my_crate.json"), "my_crate")?;
+/// let options = RenderOptions {
+///     paths: vec!["my_crate::api".to_string()],
+///     no_common_traits: true,
+///     ..RenderOptions::default()
+/// };
+/// let markdown = render_crate(&manifest, &krate, options)?;
+/// std::fs::write("my_crate.md", markdown)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn render_crate(
+    manifest: &CargoManifest,
+    krate: &Crate,
+    options: RenderOptions,
+) -> Result<String> {
+    let mut printer = Printer::new(manifest, krate)
+        .paths(&options.paths)
+        .allow_list(&options.allow_list)
+        .exclude_paths(&options.exclude_paths)
+        .toc(options.toc);
+    if let Some(crate_extra) = options.crate_extra {
+        printer = printer.crate_extra(crate_extra);
+    }
+    if options.include_other {
+        printer = printer.include_other();
+    }
+    if options.template_mode {
+        printer = printer.template_mode();
+    }
+    if options.no_common_traits {
+        printer = printer.no_common_traits();
+    }
+    if options.anchors {
+        printer = printer.anchors();
+    }
+    printer.print()
+}
+
 /// `Printer` is responsible for generating Markdown documentation from a [`rustdoc_types::Crate`].
 ///
 /// It uses a builder pattern for configuration. The typical workflow is:
@@ -2304,16 +4469,80 @@ pub struct Printer<'a> {
     manifest_data: CrateManifestData,
     // Builder options
     paths: Vec<String>,
+    allow_list: Vec<String>,
+    exclude_paths: Vec<String>,
+    changed_files: Option<Vec<PathBuf>>,
     crate_extra: Option<CrateExtra>,
     include_other: bool,
     template_mode: bool,
     no_common_traits: bool,
+    code_fence_lang: String,
+    show_default_bodies: bool,
+    source_root: Option<PathBuf>,
+    omit_sized_bound: bool,
+    #[cfg(feature = "rustfmt")]
+    rustfmt_signatures: bool,
+    show_deref_methods: bool,
+    max_generic_depth: Option<usize>,
+    metadata_comments: bool,
+    inline_type_aliases: bool,
+    anchors: bool,
+    see_also_methods: bool,
+    render_bounds_on_separate_lines: bool,
+    consolidate_bounds: bool,
+    field_layout: FieldLayout,
+    render_impl_trait_as_generic: bool,
+    collapse_single_variant_enums: bool,
+    sort_trait_impls_by_usefulness: bool,
+    license_footer: bool,
+    show_inherited_trait_items: bool,
+    toc: bool,
+    references_as_footnotes: bool,
+    group_free_functions_by_first_arg: bool,
+    common_traits_threshold: f32,
+    heading_offset: usize,
+    doc_overrides: HashMap<String, DocOverride>,
+    max_module_depth: Option<usize>,
+    reexport_visibility: bool,
+    all_features: bool,
+    emit_item_count_per_section: bool,
+    phase_timings: Option<PhaseSink<'a>>,
+    include_hidden: bool,
+    redact_private_paths: bool,
+    reexport_mode: ReexportMode,
+    sanitize_docs: bool,
+    sort_order: SortOrder,
+    signature_index: bool,
+    include_sealed_details: bool,
+    keep_hidden_doc_lines: bool,
+    max_line_width: Option<usize>,
+    dependency_order: bool,
     // Internal state
+    dependency_ranks: HashMap<Id, usize>, // Dependency ranks of the items, see dependency_order
+    declaration_order: HashMap<Id, usize>, // Positions of the current module's items
+    source_files: RefCell<HashMap<PathBuf, Option<String>>>, // Contents read from source_root
+    trait_item_ids: OnceCell<HashSet<Id>>, // Associated items of all traits
+    generic_depth: Cell<usize>, // Current generic argument nesting while formatting a type
+    alias_substitutions: RefCell<HashMap<String, String>>, // Alias params -> args while expanding
+    expanding_aliases: RefCell<Vec<Id>>, // Type aliases currently being expanded
+    reference_footnotes: RefCell<Vec<String>>, // Footnote definitions not yet written
+    footnote_count: Cell<usize>, // Number of cross-reference footnotes created so far
+    item_anchors: HashMap<Id, String>, // Anchor id of each documented item and member
+    anchor_ids: HashSet<String>, // Anchor ids assigned so far, for deduplication
+    emitted_anchors: HashSet<String>, // Anchor ids written to the output
+    anchor_index: HashMap<String, String>, // Path -> first anchor id emitted for it
+    anchor_scope: Vec<String>,  // Path of the item whose fields/variants are printed
+    sibling_methods: Vec<Id>,   // Inherent methods of the type being printed
     selected_ids: HashSet<Id>,
     resolved_modules: HashMap<Id, ResolvedModule>,
+    public_paths: HashMap<Id, Vec<String>>, // Re-export paths of items defined in private modules
+    detail_modules: HashMap<Id, Id>, // Module printing the details of multiply reachable items
+    item_doc_overrides: HashMap<Id, DocOverride>, // `doc_overrides` resolved to item ids
     graph: IdGraph,
     printed_ids: HashMap<Id, String>, // Stores ID and the header prefix where it was first printed
     output: String,
+    split_modules: bool, // Render top-level modules into separate files (`print_to_dir`)
+    module_files: Vec<ModuleFile>, // Top-level modules rendered so far when splitting
     module_tree: ModuleTree,
     doc_path: Vec<usize>,
     current_module_path: Vec<String>,
@@ -2334,15 +4563,79 @@ impl<'a> Printer<'a> {
             krate,
             manifest_data: CrateManifestData::from_cargo_manifest(manifest),
             paths: Vec::new(),
+            allow_list: Vec::new(),
+            exclude_paths: Vec::new(),
+            changed_files: None,
             crate_extra: None,
             include_other: false,
             template_mode: false,
             no_common_traits: false,
+            code_fence_lang: "rust".to_string(),
+            show_default_bodies: false,
+            source_root: None,
+            omit_sized_bound: false,
+            #[cfg(feature = "rustfmt")]
+            rustfmt_signatures: false,
+            show_deref_methods: false,
+            max_generic_depth: None,
+            metadata_comments: false,
+            inline_type_aliases: false,
+            anchors: false,
+            see_also_methods: false,
+            render_bounds_on_separate_lines: false,
+            consolidate_bounds: false,
+            field_layout: FieldLayout::default(),
+            render_impl_trait_as_generic: false,
+            collapse_single_variant_enums: false,
+            sort_trait_impls_by_usefulness: false,
+            license_footer: false,
+            show_inherited_trait_items: false,
+            toc: false,
+            references_as_footnotes: false,
+            group_free_functions_by_first_arg: false,
+            common_traits_threshold: 0.5,
+            heading_offset: 0,
+            doc_overrides: HashMap::new(),
+            max_module_depth: None,
+            reexport_visibility: false,
+            all_features: false,
+            emit_item_count_per_section: false,
+            phase_timings: None,
+            include_hidden: false,
+            redact_private_paths: false,
+            reexport_mode: ReexportMode::default(),
+            sanitize_docs: true,
+            sort_order: SortOrder::default(),
+            signature_index: false,
+            include_sealed_details: false,
+            keep_hidden_doc_lines: false,
+            max_line_width: None,
+            dependency_order: false,
+            dependency_ranks: HashMap::new(),
+            declaration_order: HashMap::new(),
+            source_files: RefCell::new(HashMap::new()),
+            trait_item_ids: OnceCell::new(),
+            generic_depth: Cell::new(0),
+            alias_substitutions: RefCell::new(HashMap::new()),
+            expanding_aliases: RefCell::new(Vec::new()),
+            reference_footnotes: RefCell::new(Vec::new()),
+            footnote_count: Cell::new(0),
+            item_anchors: HashMap::new(),
+            anchor_ids: HashSet::new(),
+            emitted_anchors: HashSet::new(),
+            anchor_index: HashMap::new(),
+            anchor_scope: Vec::new(),
+            sibling_methods: Vec::new(),
             selected_ids: HashSet::new(), // Will be populated by print()
             resolved_modules: HashMap::new(), // Will be populated by print()
+            public_paths: HashMap::new(), // Will be populated by print()
+            detail_modules: HashMap::new(), // Will be populated by print()
+            item_doc_overrides: HashMap::new(), // Will be populated by print()
             graph: IdGraph::default(),    // Will be populated by print()
             printed_ids: HashMap::new(),  // Changed to HashMap
             output: String::new(),
+            split_modules: false,
+            module_files: Vec::new(),
             module_tree: Self::build_module_tree(krate), // Initial build based on krate
             doc_path: Vec::new(),
             current_module_path: vec![],
@@ -2373,6 +4666,42 @@ impl<'a> Printer<'a> {
         self
     }
 
+    /// Restricts documentation to exactly the items at these canonical paths.
+    ///
+    /// Unlike [`paths()`](Printer::paths), entries are not prefixes: `"::style"` selects
+    /// the `style` module item itself, not everything beneath it. Paths are normalized
+    /// the same way, and the rendering dependencies of each allowed item are still
+    /// included. Items matching either the path filters or the allow-list are selected.
+    pub fn allow_list(mut self, paths: &[String]) -> Self {
+        self.allow_list = paths.to_vec();
+        self
+    }
+
+    /// Drops selected items whose canonical path matches one of these prefixes.
+    ///
+    /// Prefixes are normalized and matched like those of [`paths()`](Printer::paths).
+    /// Exclusion is applied after selection and wins over it, so `--path ::api` combined
+    /// with an exclusion of `::api::internal` documents everything in `api` except the
+    /// `internal` module. Dependencies are dropped too when they match.
+    pub fn exclude_paths(mut self, paths: &[String]) -> Self {
+        self.exclude_paths = paths.to_vec();
+        self
+    }
+
+    /// Restricts documentation to items defined in these source files, plus their
+    /// dependencies.
+    ///
+    /// Item source locations are resolved against the
+    /// [`source_root()`](Printer::source_root) when set. Methods and other
+    /// associated items select the type they are implemented for. If no item lives in
+    /// the given files, nothing is documented.
+    ///
+    /// See [`changed_files_since`] for obtaining the files changed since a git ref.
+    pub fn changed_files(mut self, files: &[PathBuf]) -> Self {
+        self.changed_files = Some(files.to_vec());
+        self
+    }
+
     /// Adds [`CrateExtra`] data (README, examples) to be included in the documentation.
     ///
     /// Use [`CrateExtraReader`] to obtain the `CrateExtra` instance.
@@ -2419,2467 +4748,8474 @@ impl<'a> Printer<'a> {
         self
     }
 
-    /// Generates the Markdown documentation based on the configured options.
+    /// Sets the language tag used for generated code fences.
     ///
-    /// This method consumes the `Printer` and returns the generated Markdown as a `String`.
-    /// It performs several steps:
-    /// 1. Resolves module items (handling `use` statements).
-    /// 2. Selects items based on path filters and builds a dependency graph.
-    /// 3. Calculates common traits for the crate.
-    /// 4. Prints the crate header, README (if any), and common traits.
-    /// 5. Recursively prints modules and their contents.
-    /// 6. Prints any remaining "other" items if configured.
-    /// 7. Appends examples if configured.
+    /// Every code block emitted by the printer (declarations, associated item
+    /// signatures, impl blocks, where clauses and examples) is opened with
+    /// this tag, e.g. `rs` or `rust,ignore` for renderers that expect them.
     ///
-    /// # Returns
+    /// The default is `rust`.
+    pub fn code_fence_lang(mut self, lang: impl Into<String>) -> Self {
+        self.code_fence_lang = lang.into();
+        self
+    }
+
+    /// Sets the directory that item source spans are resolved against.
     ///
-    /// A `Result` containing the generated Markdown `String`, or an error if
-    /// any step fails.
-    pub fn print(mut self) -> Result<String> {
-        self.resolved_modules = graph::build_resolved_module_index(self.krate);
-        let (selected_ids, graph) =
-            graph::select_items(self.krate, &self.paths, &self.resolved_modules)?;
-        self.selected_ids = selected_ids;
-        self.graph = graph;
+    /// This is normally the package directory `rustdoc` was run in. It is only
+    /// needed by features that read the crate's source, such as
+    /// [`show_default_bodies()`](Printer::show_default_bodies).
+    pub fn source_root(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.source_root = Some(dir.into());
+        self
+    }
 
-        info!(
-            "Generating documentation for {} selected items.",
-            self.selected_ids.len()
-        );
-        if self.selected_ids.is_empty()
-            && self
-                .crate_extra
-                .as_ref()
-                .is_none_or(|ce| ce.examples.is_empty())
-        {
-            return Ok("No items selected for documentation and no examples found.".to_string());
-        }
+    /// Renders the bodies of short provided trait methods.
+    ///
+    /// `rustdoc` JSON does not include function bodies, so when enabled the body is
+    /// read from the source file referenced by the method's span (see
+    /// [`source_root()`](Printer::source_root)). Only single-expression bodies of up
+    /// to 80 characters are rendered; other methods keep the `{ ... }` placeholder.
+    ///
+    /// The default is `false`.
+    pub fn show_default_bodies(mut self, show: bool) -> Self {
+        self.show_default_bodies = show;
+        self
+    }
 
-        let (crate_common_traits, all_type_ids_with_impls) = Self::calculate_crate_common_traits(
-            self.krate,
-            &self.selected_ids, // Pass reference directly
-            self.no_common_traits,
-            &self, // Pass self for FormattedTraitImpl::from_impl
-        );
-        self.crate_common_traits = crate_common_traits;
-        self.all_type_ids_with_impls = all_type_ids_with_impls;
+    /// Hides explicit `Sized` bounds on generic parameters and in where clauses.
+    ///
+    /// `Sized` is implied for generic parameters, so e.g. `T: Sized + Clone` is
+    /// rendered as `T: Clone`. The `?Sized` relaxation is always kept since it is
+    /// the notable case.
+    ///
+    /// The default is `false`.
+    pub fn omit_sized_bound(mut self, omit: bool) -> Self {
+        self.omit_sized_bound = omit;
+        self
+    }
 
-        // The finalize method consumes self and returns the String
-        Ok(self.finalize())
+    /// Lists methods reachable through `Deref` on the implementing type.
+    ///
+    /// For a type with a documented `Deref<Target = T>` implementation, a
+    /// "Methods from `Deref<Target = T>`" section lists `T`'s inherent methods that
+    /// take `&self` or `&mut self`, referring back to `T`'s own documentation. This
+    /// mirrors rustdoc's HTML output.
+    ///
+    /// The default is `false`.
+    pub fn show_deref_methods(mut self, show: bool) -> Self {
+        self.show_deref_methods = show;
+        self
     }
 
-    /// Pre-calculates common traits for the entire crate.
-    fn calculate_crate_common_traits(
-        krate: &Crate,
-        selected_ids: &HashSet<Id>,
-        no_common_traits: bool,
-        printer: &Printer,
-    ) -> (HashSet<FormattedTraitImpl>, HashSet<Id>) {
-        let mut all_type_ids_with_impls = HashSet::new();
-        if no_common_traits {
-            for item in krate.index.values() {
-                if let ItemEnum::Impl(imp) = &item.inner {
-                    if let Some(for_type_id) = get_type_id(&imp.for_) {
-                        if selected_ids.contains(&for_type_id) {
-                            all_type_ids_with_impls.insert(for_type_id);
-                        }
-                    }
-                }
-            }
-            return (HashSet::new(), all_type_ids_with_impls);
-        }
+    /// Truncates deeply nested generic types beyond `depth` levels of arguments.
+    ///
+    /// Generic arguments nested deeper than `depth` are replaced with `…`, e.g. with
+    /// a depth of 2, `Result<Vec<HashMap<K, V>>, E>` is rendered as
+    /// `Result<Vec<HashMap<…>>, E>`.
+    ///
+    /// The default is unlimited.
+    pub fn max_generic_depth(mut self, depth: usize) -> Self {
+        self.max_generic_depth = Some(depth);
+        self
+    }
 
-        // Trait Path ID -> (FormattedTraitImpl -> Count)
-        let mut trait_format_counts: HashMap<Id, HashMap<FormattedTraitImpl, usize>> =
-            HashMap::new();
+    /// Precedes each item section with a machine-readable HTML comment.
+    ///
+    /// The comment has the form
+    /// `<!-- item: path=crate::module::Item kind=function deprecated=false feature=none -->`,
+    /// where `feature` lists the Cargo features the item requires to be enabled (so not
+    /// those under `not(...)` or `any(...)`, nor `target_feature`). This lets
+    /// tools that parse the Markdown extract structured data without a separate JSON
+    /// output.
+    ///
+    /// The default is `false`.
+    pub fn metadata_comments(mut self, enable: bool) -> Self {
+        self.metadata_comments = enable;
+        self
+    }
 
-        for item in krate.index.values() {
-            if let ItemEnum::Impl(imp) = &item.inner {
-                if let Some(for_type_id) = get_type_id(&imp.for_) {
-                    if selected_ids.contains(&for_type_id) {
-                        all_type_ids_with_impls.insert(for_type_id);
-                        if let Some(trait_path) = &imp.trait_ {
-                            let norm_impl = FormattedTraitImpl::from_impl(
-                                imp, None, trait_path, krate, printer,
-                            );
-                            *trait_format_counts
-                                .entry(trait_path.id)
-                                .or_default()
-                                .entry(norm_impl)
-                                .or_insert(0) += 1;
-                        }
-                    }
-                }
-            }
-        }
-        debug!(
-            "Found {} types with trait implementations for crate-level common trait calculation.",
-            all_type_ids_with_impls.len()
-        );
+    /// Expands uses of locally defined type aliases in signatures.
+    ///
+    /// The alias is kept and annotated with the aliased type, with the alias's
+    /// generic parameters substituted, e.g. `Result<u8> /* = core::result::Result<u8,
+    /// MyError> */`.
+    ///
+    /// The default is `false`.
+    pub fn inline_type_aliases(mut self, inline: bool) -> Self {
+        self.inline_type_aliases = inline;
+        self
+    }
 
-        if all_type_ids_with_impls.len() < 2 {
-            debug!("Too few trait implementations, skipping crate 'Common Traits'");
+    /// Emits HTML anchors for deep-linking into the generated documentation.
+    ///
+    /// An `<a id="..."></a>` anchor is written right before the header of each item,
+    /// enum variant and documented field. Ids are slugs of the item's path, e.g.
+    /// `my_crate-myenum-variantname`, and are unique within the document: paths whose
+    /// slugs collide (e.g. `fn config` and `struct Config`) get a `-1`, `-2`, ...
+    /// suffix, and cross-references and links always point at their item's own id.
+    ///
+    /// The default is `false` (no anchors).
+    pub fn anchors(mut self) -> Self {
+        self.anchors = true;
+        self
+    }
+
+    /// Adds "See also" links between related methods of the same type.
+    ///
+    /// Methods are considered related when one name extends the other with a
+    /// `_`-separated prefix or suffix, e.g. `get` and `get_mut`, or `iter` and
+    /// `iter_mut`. Links target the method anchors when [`anchors()`](Printer::anchors)
+    /// is enabled, and are plain method names otherwise.
+    ///
+    /// The default is `false`.
+    pub fn see_also_methods(mut self, enable: bool) -> Self {
+        self.see_also_methods = enable;
+        self
+    }
+
+    /// Moves long bound lists on generic parameters into a multi-line where-clause.
+    ///
+    /// Type parameters with more than three bounds, such as
+    /// `T: Clone + Debug + Send + Sync + 'static`, are declared bare and their
+    /// bounds are listed in the where-clause instead. A where-clause holding such a
+    /// bound list is rendered with one predicate per line, even if it is short.
+    ///
+    /// The default is `false`.
+    pub fn render_bounds_on_separate_lines(mut self, enable: bool) -> Self {
+        self.render_bounds_on_separate_lines = enable;
+        self
+    }
+
+    /// Moves simple where-clause bounds inline, rendering `fn f<T>() where T: Clone`
+    /// as `fn f<T: Clone>()`.
+    ///
+    /// Only single-trait bounds on the item's own type parameters are moved, and only
+    /// while the parameter has at most three bounds. This is the inverse of
+    /// [`render_bounds_on_separate_lines()`](Printer::render_bounds_on_separate_lines);
+    /// when both are enabled, bounds are consolidated first.
+    ///
+    /// The default is `false`.
+    pub fn consolidate_bounds(mut self, enable: bool) -> Self {
+        self.consolidate_bounds = enable;
+        self
+    }
+
+    /// Sets how fields and variants are laid out.
+    ///
+    /// With [`FieldLayout::Table`], enums list their variants in a
+    /// `| Variant | Description |` table instead of one header per variant, with a
+    /// `Discriminant` column for C-like enums and the docs of tuple and struct variant
+    /// fields appended to their variant's description. Enums whose variant or field
+    /// docs have several paragraphs keep the header layout, since a table cell only
+    /// fits one.
+    ///
+    /// The default is [`FieldLayout::Headers`].
+    pub fn field_layout(mut self, layout: FieldLayout) -> Self {
+        self.field_layout = layout;
+        self
+    }
+
+    /// Renders argument-position `impl Trait` as explicit generic parameters, e.g.
+    /// `fn f(x: impl Display)` as `fn f<T: Display>(x: T)`.
+    ///
+    /// The default is `false`.
+    pub fn render_impl_trait_as_generic(mut self, enable: bool) -> Self {
+        self.render_impl_trait_as_generic = enable;
+        self
+    }
+
+    /// Renders the variant of a single-variant enum as a compact note under the enum's
+    /// header instead of a full "Variants" section.
+    ///
+    /// Enums with hidden variants, or whose variant has documented fields, keep the
+    /// full layout.
+    ///
+    /// The default is `false`.
+    pub fn collapse_single_variant_enums(mut self, enable: bool) -> Self {
+        self.collapse_single_variant_enums = enable;
+        self
+    }
+
+    /// Orders trait implementation lists by likely reader interest instead of
+    /// alphabetically.
+    ///
+    /// Conversion and constructor traits (`From`, `Default`, `FromStr`, ...) come
+    /// first, followed by behavioral traits such as `Display` and `Iterator`, then
+    /// traits from other crates, then operator traits, with marker traits last.
+    /// Entries with the same rank remain alphabetical.
+    ///
+    /// The default is `false`.
+    pub fn sort_trait_impls_by_usefulness(mut self, enable: bool) -> Self {
+        self.sort_trait_impls_by_usefulness = enable;
+        self
+    }
+
+    /// Appends a "License" section with the crate's license and repository from
+    /// its manifest, and a note that the documentation was generated from the
+    /// crate's source.
+    ///
+    /// This helps satisfy attribution requirements when redistributing the output.
+    ///
+    /// The default is `false`.
+    pub fn license_footer(mut self, enable: bool) -> Self {
+        self.license_footer = enable;
+        self
+    }
+
+    /// Lists methods a trait inherits from its supertraits in an "Inherited Methods"
+    /// section of the trait, each noting the supertrait that provides it.
+    ///
+    /// Only supertraits defined in the documented crate are followed; methods of
+    /// external supertraits such as `Clone` are not listed.
+    ///
+    /// The default is `false`.
+    pub fn show_inherited_trait_items(mut self, enable: bool) -> Self {
+        self.show_inherited_trait_items = enable;
+        self
+    }
+
+    /// Emits a table of contents after the crate header, linking every H2 and H3
+    /// section by its number (e.g. `3.1: Structs`).
+    ///
+    /// Each linked header is preceded by an `<a id="section-3-1"></a>` anchor, so the
+    /// links work regardless of how the Markdown renderer slugs headers.
+    ///
+    /// The default is `false`.
+    pub fn toc(mut self, enable: bool) -> Self {
+        self.toc = enable;
+        self
+    }
+
+    /// Renders cross-references to already printed sections as GFM footnote markers
+    /// (`[^ref-1]`) instead of inline "(See section N for details)" text.
+    ///
+    /// The footnote definitions naming the target sections are written at the end of
+    /// each module and of the document, and in the same file as their markers with
+    /// [`Printer::print_to_dir`].
+    ///
+    /// The default is `false`.
+    pub fn references_as_footnotes(mut self, enable: bool) -> Self {
+        self.references_as_footnotes = enable;
+        self
+    }
+
+    /// Groups the free functions of a module whose first parameter is (a reference
+    /// to) the same local type into a "Functions operating on `Type`" subsection of
+    /// the module's "Functions" section.
+    ///
+    /// Only types taken first by at least two functions get a subsection.
+    ///
+    /// The default is `false`.
+    pub fn group_free_functions_by_first_arg(mut self, enable: bool) -> Self {
+        self.group_free_functions_by_first_arg = enable;
+        self
+    }
+
+    /// Sets the fraction of types (with trait implementations) that must implement a
+    /// trait for it to be listed as a crate or module "Common Trait".
+    ///
+    /// A ratio of `1.0` means "implemented by every type"; lower ratios list more
+    /// traits as common.
+    ///
+    /// The default is `0.5`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ratio` is not in the range `0.0..=1.0`.
+    pub fn common_traits_threshold(mut self, ratio: f32) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&ratio),
+            "common traits threshold must be between 0 and 1, got {}",
+            ratio
+        );
+        self.common_traits_threshold = ratio;
+        self
+    }
+
+    /// Shifts every header of the document down by `offset` levels, e.g. an offset of
+    /// `1` makes the crate header `##` and module headers `###`.
+    ///
+    /// Useful when embedding the output in an existing document. Shifted headers are
+    /// capped at `######`.
+    ///
+    /// The default is `0`.
+    pub fn heading_offset(mut self, offset: usize) -> Self {
+        self.heading_offset = offset;
+        self
+    }
+
+    /// Sets extra documentation for items, keyed by canonical path (e.g.
+    /// `my_crate::Config` or, for methods, associated items, fields and variants,
+    /// `my_crate::Config::new`). Members without docs of their own are documented
+    /// by their override.
+    ///
+    /// Useful to enrich sparse upstream docs without forking the crate. Paths that
+    /// don't name a documented item are reported as warnings. Overrides are not
+    /// applied in [`template_mode()`](Printer::template_mode).
+    pub fn doc_overrides(mut self, overrides: HashMap<String, DocOverride>) -> Self {
+        self.doc_overrides = overrides;
+        self
+    }
+
+    /// Limits how deep the module hierarchy is documented, for a shallow overview of
+    /// a large crate.
+    ///
+    /// Depth `0` documents only the items of the crate root, depth `1` also the items
+    /// of its direct submodules, and so on. Modules past the limit are listed as
+    /// ``- `mod name` (depth limit reached)`` in their parent's section.
+    ///
+    /// The default is `None` (no limit).
+    pub fn max_module_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_module_depth = depth;
+        self
+    }
+
+    /// Annotates each entry of a module's "Re-exports" list with the visibility of
+    /// its `use` declaration, e.g. `` (`pub(crate) use`) ``.
+    ///
+    /// Intended for crates documented with private items (see [`run_rustdoc`]), where
+    /// re-exports that aren't part of the public API are listed too.
+    ///
+    /// The default is `false`.
+    pub fn reexport_visibility(mut self, annotate: bool) -> Self {
+        self.reexport_visibility = annotate;
+        self
+    }
+
+    /// Marks every feature in the "Features" section as enabled, for crates whose
+    /// rustdoc JSON was built with all features active (see [`run_rustdoc`]).
+    ///
+    /// The default is `false`.
+    pub fn all_features(mut self, all_features: bool) -> Self {
+        self.all_features = all_features;
+        self
+    }
+
+    /// Appends the number of items a section lists to its header, e.g.
+    /// `### 2.3: Functions (12)`, for item kind sections as well as "Fields" and
+    /// "Variants" sections. Cross-references to items documented elsewhere are not
+    /// counted.
+    ///
+    /// The default is `false`.
+    pub fn emit_item_count_per_section(mut self, emit: bool) -> Self {
+        self.emit_item_count_per_section = emit;
+        self
+    }
+
+    /// Reports the time [`print()`](Printer::print) spends selecting items,
+    /// calculating the crate's common traits and rendering to `sink`, called with
+    /// each phase's name and duration, e.g. to [`PhaseTimings::record`].
+    pub fn phase_timings(mut self, sink: impl FnMut(&str, std::time::Duration) + 'a) -> Self {
+        self.phase_timings = Some(Box::new(sink));
+        self
+    }
+
+    /// Documents items marked `#[doc(hidden)]` instead of skipping them.
+    ///
+    /// rustdoc strips hidden items from its JSON unless it runs with
+    /// `--document-hidden-items` (see [`run_rustdoc`]), so this only has an effect on
+    /// JSON built that way. Skipped struct fields are reported like private ones.
+    ///
+    /// The default is `false`.
+    pub fn include_hidden(mut self, include: bool) -> Self {
+        self.include_hidden = include;
+        self
+    }
+
+    /// Names types defined in a private module by their shortest public re-export
+    /// path, e.g. `my_crate::Thing` instead of `my_crate::internal::Thing`, so
+    /// signatures don't leak internal module names. Types without a public
+    /// re-export keep their canonical path.
+    ///
+    /// The default is `false`, which keeps the canonical paths rustdoc reports.
+    pub fn redact_private_paths(mut self, redact: bool) -> Self {
+        self.redact_private_paths = redact;
+        self
+    }
+
+    /// Sets where items reachable from several modules, e.g. a type defined in
+    /// `inner` and re-exported with `pub use inner::Foo;`, get their full details.
+    /// The other modules list them under "Re-exports".
+    ///
+    /// The default is [`ReexportMode::Both`].
+    pub fn reexport_mode(mut self, mode: ReexportMode) -> Self {
+        self.reexport_mode = mode;
+        self
+    }
+
+    /// Strips ANSI escape sequences and control characters other than tabs and
+    /// newlines from the output, so text taken from the crate (docs, README, examples,
+    /// deprecation notes, manifest fields) can't corrupt a terminal the Markdown is
+    /// printed to.
+    ///
+    /// The default is `true`.
+    pub fn sanitize_docs(mut self, sanitize: bool) -> Self {
+        self.sanitize_docs = sanitize;
+        self
+    }
+
+    /// Sets how items are ordered within module sections and the associated item
+    /// sections of traits.
+    ///
+    /// The default is [`SortOrder::Alphabetical`].
+    pub fn sort_order(mut self, order: SortOrder) -> Self {
+        self.sort_order = order;
+        self
+    }
+
+    /// Emits a compact signature index at the start of each module's items: a single
+    /// `rust` code block with the one-line signature of every item documented in the
+    /// module, without docs or bodies. This is the densest view of a module's API,
+    /// e.g. for feeding a crate's shape to an LLM.
+    ///
+    /// The default is `false`.
+    pub fn signature_index(mut self, enable: bool) -> Self {
+        self.signature_index = enable;
+        self
+    }
+
+    /// Explains how sealed traits are sealed: for traits with a supertrait that users
+    /// cannot name (e.g. `private::Sealed`), adds a note naming that supertrait and the
+    /// types implementing it, which are the only types that can implement the trait.
+    ///
+    /// The implementors of the sealing trait are only known if the rustdoc JSON was
+    /// built with private items documented.
+    ///
+    /// The default is `false`.
+    pub fn include_sealed_details(mut self, enable: bool) -> Self {
+        self.include_sealed_details = enable;
+        self
+    }
+
+    /// Keeps the lines of doc examples that rustdoc hides (those starting with `# `,
+    /// e.g. `# use my_crate::Foo;`) instead of removing them like rendered docs do.
+    ///
+    /// The default is `false`.
+    pub fn keep_hidden_doc_lines(mut self, keep: bool) -> Self {
+        self.keep_hidden_doc_lines = keep;
+        self
+    }
+
+    /// Sets the width above which declarations in code blocks are wrapped. Currently
+    /// this puts the supertraits of a trait whose declaration line would be wider
+    /// on separate lines:
+    ///
+    /// ```text
+    /// pub trait Foo:
+    ///     Clone +
+    ///     Debug +
+    ///     Send
+    /// {
+    /// ```
+    ///
+    /// The default is `None` (no wrapping).
+    pub fn max_line_width(mut self, width: Option<usize>) -> Self {
+        self.max_line_width = width;
+        self
+    }
+
+    /// Orders items by their dependencies instead of by [`Printer::sort_order`]: an
+    /// item comes after the items it uses (e.g. a struct after the types of its
+    /// fields), so a crate can be read bottom-up, from its building blocks to the APIs
+    /// composed of them. Items that depend on each other in a cycle, and items at the
+    /// same depth, are ordered by path.
+    ///
+    /// Items are still grouped into sections by kind; the order applies within each
+    /// section.
+    ///
+    /// The default is `false`.
+    pub fn dependency_order(mut self, enable: bool) -> Self {
+        self.dependency_order = enable;
+        self
+    }
+
+    /// Sorts `entries` by their item (given by `id_of`) according to
+    /// [`Printer::sort_order`], or [`Printer::dependency_order`] if set.
+    fn sort_items_by<T>(&self, entries: &mut [T], id_of: impl Fn(&T) -> Id) {
+        let name = |id: &Id| self.krate.index.get(id).and_then(|item| item.name.clone());
+        if self.dependency_order {
+            entries.sort_by_key(|entry| {
+                let id = id_of(entry);
+                (
+                    self.dependency_ranks.get(&id).copied().unwrap_or(0),
+                    self.krate
+                        .paths
+                        .get(&id)
+                        .map(|summary| summary.path.clone()),
+                    name(&id),
+                )
+            });
+            return;
+        }
+        match self.sort_order {
+            SortOrder::Alphabetical => entries.sort_by_key(|entry| name(&id_of(entry))),
+            SortOrder::SourceSpan => entries.sort_by_key(|entry| {
+                let id = id_of(entry);
+                let span = self
+                    .krate
+                    .index
+                    .get(&id)
+                    .and_then(|item| item.span.as_ref());
+                (
+                    span.is_none(),
+                    span.map(|span| (span.filename.clone(), span.begin)),
+                    name(&id),
+                )
+            }),
+            // Stable, so items the module doesn't declare (e.g. associated items) keep
+            // the order they were collected in
+            SortOrder::AsDeclared => entries.sort_by_key(|entry| {
+                self.declaration_order
+                    .get(&id_of(entry))
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            }),
+        }
+    }
+
+    /// Returns the position of each item the module `module_id` declares or
+    /// re-exports, in the order of its `items`; items of glob imports come in at
+    /// their `use`.
+    fn declaration_positions(&self, module_id: &Id) -> HashMap<Id, usize> {
+        fn collect(krate: &Crate, module_id: &Id, positions: &mut HashMap<Id, usize>) {
+            let Some(ItemEnum::Module(module)) = krate.index.get(module_id).map(|i| &i.inner)
+            else {
+                return;
+            };
+            for id in &module.items {
+                let target = match krate.index.get(id).map(|item| &item.inner) {
+                    Some(ItemEnum::Use(u)) => match u.id {
+                        Some(target) if u.is_glob => {
+                            // Guard against glob import cycles
+                            if positions.insert(target, positions.len()).is_none() {
+                                collect(krate, &target, positions);
+                            }
+                            continue;
+                        }
+                        Some(target) => target,
+                        None => continue,
+                    },
+                    _ => *id,
+                };
+                let position = positions.len();
+                positions.entry(target).or_insert(position);
+            }
+        }
+        let mut positions = HashMap::new();
+        collect(self.krate, module_id, &mut positions);
+        positions
+    }
+
+    /// Returns `true` if `item` is `#[doc(hidden)]` and hidden items are skipped.
+    fn is_skipped_hidden(&self, item: &Item) -> bool {
+        !self.include_hidden && is_doc_hidden(item)
+    }
+
+    /// Reports that `phase` took the time since `start`, if
+    /// [`Printer::phase_timings`] is set.
+    fn record_phase(&mut self, phase: &str, start: std::time::Instant) {
+        if let Some(sink) = &mut self.phase_timings {
+            sink(phase, start.elapsed());
+        }
+    }
+
+    /// Returns the title of a section header listing `count` items, with the count
+    /// appended if [`Printer::emit_item_count_per_section`] is enabled.
+    fn section_title(&self, name: &str, count: usize) -> String {
+        if self.emit_item_count_per_section {
+            format!("{} ({})", name, count)
+        } else {
+            name.to_string()
+        }
+    }
+
+    /// Reformats generated declaration code blocks with `rustfmt`.
+    ///
+    /// Each struct, enum, union, trait and function block is piped through the
+    /// `rustfmt` found on `PATH` so signatures match the canonical style users see in
+    /// their editors. If `rustfmt` is not installed (or cannot parse a block), the
+    /// internal formatting is kept.
+    ///
+    /// Requires the `rustfmt` feature. The default is `false`.
+    #[cfg(feature = "rustfmt")]
+    pub fn rustfmt_signatures(mut self, enable: bool) -> Self {
+        self.rustfmt_signatures = enable;
+        self
+    }
+
+    /// Generates the Markdown documentation based on the configured options.
+    ///
+    /// This method consumes the `Printer` and returns the generated Markdown as a `String`.
+    /// It performs several steps:
+    /// 1. Resolves module items (handling `use` statements).
+    /// 2. Selects items based on path filters and builds a dependency graph.
+    /// 3. Calculates common traits for the crate.
+    /// 4. Prints the crate header, crate-level (`//!`) docs, manifest, README (if any),
+    ///    and common traits.
+    /// 5. Recursively prints modules and their contents.
+    /// 6. Prints any remaining "other" items if configured.
+    /// 7. Appends examples if configured.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the generated Markdown `String`, or an error if
+    /// any step fails.
+    pub fn print(self) -> Result<String> {
+        self.print_with_anchor_index().map(|(output, _)| output)
+    }
+
+    /// Like [`Printer::print`], but also returns the anchor index: a map from the
+    /// canonical path of each anchored item, variant or field (e.g.
+    /// `my_crate::Msg::Move`) to the id of its `<a id="...">` anchor, for deep-linking
+    /// from external tools.
+    ///
+    /// The index is empty unless [`anchors()`](Printer::anchors) is enabled.
+    pub fn print_with_anchor_index(self) -> Result<(String, HashMap<String, String>)> {
+        let mut buffer = Vec::new();
+        let anchor_index = self.print_to_writer_with_anchor_index(&mut buffer)?;
+        let output = String::from_utf8(buffer).context("Generated documentation is not UTF-8")?;
+        Ok((output, anchor_index))
+    }
+
+    /// Like [`Printer::print`], but streams the Markdown into `w` section by section
+    /// instead of building the whole document in memory, which matters for large
+    /// crates.
+    ///
+    /// The document is still buffered completely when the
+    /// [table of contents](Printer::toc) is enabled, since it is inserted at the top.
+    pub fn print_to_writer(self, w: &mut impl std::io::Write) -> Result<()> {
+        self.print_to_writer_with_anchor_index(w).map(|_| ())
+    }
+
+    /// Like [`Printer::print_to_writer`], but also returns the anchor index (see
+    /// [`Printer::print_with_anchor_index`]).
+    pub fn print_to_writer_with_anchor_index(
+        self,
+        w: &mut dyn std::io::Write,
+    ) -> Result<HashMap<String, String>> {
+        self.render(w).map(|(anchor_index, _)| anchor_index)
+    }
+
+    /// Writes the documentation into `dir` as one Markdown file per top-level module
+    /// (including the crate root module), named after the module path (e.g.
+    /// `my_crate-fmt.md`), plus an `index.md` with the crate header, manifest, common
+    /// traits and the other crate-level sections, linking to each module file. The root
+    /// module of a crate named `index` is written to `index-crate.md` instead.
+    ///
+    /// [`anchors()`](Printer::anchors) are always enabled, since section numbers alone
+    /// don't tell which file a cross-reference points into; anchor links into another
+    /// file are made relative to that file.
+    ///
+    /// # Returns
+    ///
+    /// The paths of the written files, starting with `index.md`.
+    pub fn print_to_dir(mut self, dir: &FilePath) -> Result<Vec<PathBuf>> {
+        self.split_modules = true;
+        self.anchors = true;
+        let sanitize = self.sanitize_docs;
+        let mut index = Vec::new();
+        let (_, mut module_files) = self.render(&mut index)?;
+        if sanitize {
+            for file in &mut module_files {
+                if let Cow::Owned(cleaned) = strip_control_chars(&file.content) {
+                    file.content = cleaned;
+                }
+            }
+        }
+        let index = String::from_utf8(index).context("Generated documentation is not UTF-8")?;
+
+        let mut files = vec![ModuleFile {
+            name: "index.md".to_string(),
+            content: index,
+        }];
+        files.extend(module_files);
+        link_anchors_across_files(&mut files);
+
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create output directory: {}", dir.display()))?;
+        let mut paths = Vec::with_capacity(files.len());
+        for file in files {
+            let path = dir.join(&file.name);
+            fs::write(&path, file.content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Renders the documentation into `w`, returning the anchor index and, when
+    /// splitting modules into files, the rendered module files.
+    fn render(
+        mut self,
+        w: &mut dyn std::io::Write,
+    ) -> Result<(HashMap<String, String>, Vec<ModuleFile>)> {
+        let selection_start = std::time::Instant::now();
+        self.resolved_modules = graph::build_resolved_module_index(self.krate);
+        if self.redact_private_paths {
+            self.public_paths = self.reexported_public_paths();
+        }
+        self.item_doc_overrides = self.resolve_doc_overrides();
+        let mut allow_list = self.allow_list.clone();
+        let changed_paths = self
+            .changed_files
+            .as_ref()
+            .map(|files| self.item_paths_in_files(files));
+        if let Some(changed_paths) = &changed_paths {
+            allow_list.extend(changed_paths.iter().cloned());
+        }
+        let (mut selected_ids, graph) =
+            graph::select_items(self.krate, &self.paths, &allow_list, &self.resolved_modules)?;
+        if changed_paths.is_some_and(|paths| paths.is_empty()) {
+            warn!("No documented items are defined in the changed files.");
+            if allow_list.is_empty() && self.paths.is_empty() {
+                selected_ids.clear(); // Without any filters everything would be selected
+            }
+        }
+        graph::exclude_items(self.krate, &mut selected_ids, &self.exclude_paths);
+        self.selected_ids = selected_ids;
+        self.graph = graph;
+        if self.dependency_order {
+            self.dependency_ranks = self.graph.dependency_ranks(self.krate);
+        }
+        self.exclude_test_only_items();
+        self.exclude_hidden_items();
+        self.detail_modules = self.resolve_detail_modules();
+        self.assign_item_anchors();
+        self.record_phase("item selection", selection_start);
+
+        info!(
+            "Generating documentation for {} selected items.",
+            self.selected_ids.len()
+        );
+        if self.selected_ids.is_empty()
+            && self
+                .crate_extra
+                .as_ref()
+                .is_none_or(|ce| ce.examples.is_empty())
+        {
+            w.write_all(b"No items selected for documentation and no examples found.")?;
+            return Ok((HashMap::new(), Vec::new()));
+        }
+
+        let common_traits_start = std::time::Instant::now();
+        let (crate_common_traits, all_type_ids_with_impls) = Self::calculate_crate_common_traits(
+            self.krate,
+            &self.selected_ids, // Pass reference directly
+            self.no_common_traits,
+            &self, // Pass self for FormattedTraitImpl::from_impl
+        );
+        self.crate_common_traits = crate_common_traits;
+        self.all_type_ids_with_impls = all_type_ids_with_impls;
+        self.record_phase("common traits", common_traits_start);
+
+        // The finalize method consumes self and streams the output into `w`
+        let rendering_start = std::time::Instant::now();
+        let phase_timings = self.phase_timings.take();
+        let rendered = self.finalize(w)?;
+        if let Some(mut sink) = phase_timings {
+            sink("rendering", rendering_start.elapsed());
+        }
+        Ok(rendered)
+    }
+
+    /// Renders the documentation of the single item `id` (and the items it depends on,
+    /// such as its impls and fields) without the crate header, manifest, common traits
+    /// or examples appendix.
+    ///
+    /// The item's section starts at H2, with its associated items and trait
+    /// implementations nested below it. All trait implementations are listed with the
+    /// item since there is no "Common Traits" section to refer to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `id` is not in the crate index or the item (e.g. a module
+    /// or `use` declaration) has no section of its own.
+    pub fn print_item(mut self, id: Id) -> Result<String> {
+        let Some(item) = self.krate.index.get(&id) else {
+            bail!("Item {:?} not found in the crate index", id);
+        };
+        self.resolved_modules = graph::build_resolved_module_index(self.krate);
+        if self.redact_private_paths {
+            self.public_paths = self.reexported_public_paths();
+        }
+        self.item_doc_overrides = self.resolve_doc_overrides();
+        let (selected_ids, graph) = graph::select_items_by_id(self.krate, &[id]);
+        self.selected_ids = selected_ids;
+        self.graph = graph;
+        let (_, all_type_ids_with_impls) =
+            Self::calculate_crate_common_traits(self.krate, &self.selected_ids, true, &self);
+        self.all_type_ids_with_impls = all_type_ids_with_impls;
+        self.assign_item_anchors();
+
+        if let Some(summary) = self.krate.paths.get(&id) {
+            self.current_module_path = summary.path[..summary.path.len() - 1].to_vec();
+        }
+        self.doc_path.clear();
+        self.push_level();
+        if !self.print_item_details(&id) {
+            bail!(
+                "Item {:?} ({}) cannot be printed on its own",
+                id,
+                item.name.as_deref().unwrap_or("unnamed")
+            );
+        }
+        self.write_reference_footnotes();
+        Ok(self.sanitize_output(&self.output).into_owned())
+    }
+
+    /// Maps items whose canonical path goes through a private module (e.g.
+    /// `crate::private::Item`) to the path of a public module re-exporting them
+    /// (e.g. `crate::Item`), which is the path users can actually name.
+    ///
+    /// The shortest re-export path is used if an item is re-exported several times.
+    fn reexported_public_paths(&self) -> HashMap<Id, Vec<String>> {
+        let module_path = |id: &Id| {
+            self.krate
+                .paths
+                .get(id)
+                .filter(|summary| summary.kind == ItemKind::Module)
+                .map(|summary| &summary.path)
+        };
+        let documented_modules: HashSet<&Vec<String>> = self
+            .resolved_modules
+            .keys()
+            .filter_map(module_path)
+            .collect();
+        let mut public_paths: HashMap<Id, Vec<String>> = HashMap::new();
+        for (module_id, module) in &self.resolved_modules {
+            let Some(parent) = module_path(module_id) else {
+                continue;
+            };
+            // `pub use private::Foo as Bar` makes the item visible as `Bar`
+            let mut use_names: HashMap<Id, &String> = HashMap::new();
+            if let Some(ItemEnum::Module(m)) = self.krate.index.get(module_id).map(|i| &i.inner) {
+                for use_id in &m.items {
+                    if let Some(ItemEnum::Use(u)) = self.krate.index.get(use_id).map(|i| &i.inner) {
+                        if let (Some(target), false) = (u.id, u.is_glob) {
+                            use_names.insert(target, &u.name);
+                        }
+                    }
+                }
+            }
+            for item_id in &module.items {
+                let Some(summary) = self.krate.paths.get(item_id) else {
+                    continue;
+                };
+                let Some((name, defining_module)) = summary.path.split_last() else {
+                    continue;
+                };
+                if summary.crate_id != 0 || documented_modules.contains(&defining_module.to_vec()) {
+                    continue;
+                }
+                let mut path = parent.clone();
+                path.push(use_names.get(item_id).map_or(name, |name| *name).clone());
+                public_paths
+                    .entry(*item_id)
+                    .and_modify(|existing| {
+                        if (path.len(), &path) < (existing.len(), existing) {
+                            *existing = path.clone();
+                        }
+                    })
+                    .or_insert(path);
+            }
+        }
+        public_paths
+    }
+
+    /// Maps items reachable from several printed modules to the module printing
+    /// their details, according to [`Printer::reexport_mode`].
+    ///
+    /// Items without an entry are detailed in the first module that reaches them.
+    fn resolve_detail_modules(&self) -> HashMap<Id, Id> {
+        if self.reexport_mode == ReexportMode::Both {
+            return HashMap::new();
+        }
+        let printed_modules = self.printed_modules();
+        let mut sites: HashMap<Id, Vec<(&[String], Id)>> = HashMap::new(); // (Module path, Module)
+        for (module_id, module) in &self.resolved_modules {
+            if !printed_modules.contains(module_id) {
+                continue;
+            }
+            let Some(module_path) = self.krate.paths.get(module_id).map(|s| &s.path) else {
+                continue;
+            };
+            for item_id in &module.items {
+                if !self.selected_ids.contains(item_id) {
+                    continue;
+                }
+                sites
+                    .entry(*item_id)
+                    .or_default()
+                    .push((module_path.as_slice(), *module_id));
+            }
+        }
+        sites
+            .into_iter()
+            .filter(|(_, sites)| sites.len() > 1)
+            .filter_map(|(item_id, sites)| {
+                let defining_module = self
+                    .krate
+                    .paths
+                    .get(&item_id)
+                    .and_then(|summary| summary.path.split_last())
+                    .map(|(_, defining_module)| defining_module);
+                let wants_definition = self.reexport_mode == ReexportMode::Definition;
+                sites
+                    .into_iter()
+                    .filter(|(path, _)| (Some(*path) == defining_module) == wants_definition)
+                    .min_by_key(|(path, _)| (path.len(), *path))
+                    .map(|(_, module_id)| (item_id, module_id))
+            })
+            .collect()
+    }
+
+    /// Returns the modules whose contents are printed, following
+    /// [`Printer::print_module_recursive`]: the selected modules reachable from the
+    /// crate root through selected modules, up to [`Printer::max_module_depth`].
+    fn printed_modules(&self) -> HashSet<Id> {
+        let mut printed = HashSet::new();
+        let mut stack = vec![(self.krate.root, 1)]; // (Module, length of its path)
+        while let Some((module_id, depth)) = stack.pop() {
+            if module_id != self.krate.root && !self.selected_ids.contains(&module_id) {
+                continue;
+            }
+            printed.insert(module_id);
+            if self.max_module_depth.is_some_and(|max| depth > max) {
+                continue; // Children are only listed as stubs
+            }
+            if let Some(children) = self.module_tree.children.get(&module_id) {
+                stack.extend(children.iter().map(|child_id| (*child_id, depth + 1)));
+            }
+        }
+        printed
+    }
+
+    /// Maps the paths of [`Printer::doc_overrides`] to the ids of the items they name.
+    ///
+    /// Associated items are named by the path of their trait or (inherent impl) type
+    /// followed by their own name.
+    fn resolve_doc_overrides(&self) -> HashMap<Id, DocOverride> {
+        if self.doc_overrides.is_empty() {
+            return HashMap::new();
+        }
+        let mut paths: HashMap<String, Id> = HashMap::new();
+        for (id, summary) in &self.krate.paths {
+            if summary.crate_id == 0 {
+                paths.insert(summary.path.join("::"), *id);
+            }
+        }
+        for item in self.krate.index.values() {
+            let (owner_id, children) = match &item.inner {
+                ItemEnum::Trait(t) => (Some(item.id), &t.items),
+                ItemEnum::Impl(imp) if imp.trait_.is_none() => (get_type_id(&imp.for_), &imp.items),
+                ItemEnum::Struct(Struct {
+                    kind: StructKind::Plain { fields, .. },
+                    ..
+                })
+                | ItemEnum::Union(Union { fields, .. })
+                | ItemEnum::Variant(Variant {
+                    kind: VariantKind::Struct { fields, .. },
+                    ..
+                }) => (Some(item.id), fields),
+                _ => continue,
+            };
+            let Some(owner) = owner_id.and_then(|id| self.krate.paths.get(&id)) else {
+                continue;
+            };
+            for child_id in children {
+                if let Some(name) = self.krate.index.get(child_id).and_then(|c| c.name.as_ref()) {
+                    paths.insert(format!("{}::{}", owner.path.join("::"), name), *child_id);
+                }
+            }
+        }
+
+        let mut resolved = HashMap::new();
+        for (path, doc_override) in &self.doc_overrides {
+            match paths.get(path) {
+                Some(id) => {
+                    resolved.insert(*id, doc_override.clone());
+                }
+                None => warn!("Doc override for `{}` does not match any item", path),
+            }
+        }
+        resolved
+    }
+
+    /// Returns the canonical paths of local items whose source is in one of `files`.
+    ///
+    /// Impls map to the type they implement, so changed methods select their type.
+    fn item_paths_in_files(&self, files: &[PathBuf]) -> Vec<String> {
+        let canonical =
+            |path: &FilePath| path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let files: HashSet<PathBuf> = files.iter().map(|f| canonical(f)).collect();
+        let mut paths = Vec::new();
+        for item in self.krate.index.values() {
+            let Some(span) = &item.span else {
+                continue;
+            };
+            let filename = match &self.source_root {
+                Some(root) => root.join(&span.filename),
+                None => span.filename.clone(),
+            };
+            if !files.contains(&canonical(&filename)) {
+                continue;
+            }
+            let id = match &item.inner {
+                ItemEnum::Impl(imp) => get_type_id(&imp.for_),
+                _ => Some(item.id),
+            };
+            if let Some(summary) = id.and_then(|id| self.krate.paths.get(&id)) {
+                if summary.crate_id == 0 {
+                    paths.push(summary.path.join("::"));
+                }
+            }
+        }
+        paths.sort();
+        paths.dedup();
+        info!("{} items defined in changed files", paths.len());
+        paths
+    }
+
+    /// Removes `#[cfg(test)]` items (and the contents of `#[cfg(test)]` modules) from the
+    /// selection, in case they leak into the rustdoc output.
+    fn exclude_test_only_items(&mut self) {
+        let mut queue: Vec<Id> = self
+            .krate
+            .index
+            .values()
+            .filter(|item| has_cfg_attr(item, "test"))
+            .map(|item| item.id)
+            .collect();
+        while let Some(id) = queue.pop() {
+            if self.selected_ids.remove(&id) {
+                debug!("Excluding #[cfg(test)] item {:?}", id);
+                if let Some(resolved_mod) = self.resolved_modules.get(&id) {
+                    queue.extend(resolved_mod.items.iter().copied());
+                }
+            }
+        }
+    }
+
+    /// Removes `#[doc(hidden)]` items from the selection, along with the contents of
+    /// hidden modules, unless [`Printer::include_hidden`] is set.
+    fn exclude_hidden_items(&mut self) {
+        if self.include_hidden {
+            return;
+        }
+        let mut queue: Vec<Id> = self
+            .krate
+            .index
+            .values()
+            .filter(|item| is_doc_hidden(item))
+            .map(|item| item.id)
+            .collect();
+        while let Some(id) = queue.pop() {
+            if self.selected_ids.remove(&id) {
+                debug!("Excluding #[doc(hidden)] item {:?}", id);
+                if let Some(resolved_mod) = self.resolved_modules.get(&id) {
+                    queue.extend(resolved_mod.items.iter().copied());
+                }
+            }
+        }
+    }
+
+    /// Pre-calculates common traits for the entire crate.
+    fn calculate_crate_common_traits(
+        krate: &Crate,
+        selected_ids: &HashSet<Id>,
+        no_common_traits: bool,
+        printer: &Printer,
+    ) -> (HashSet<FormattedTraitImpl>, HashSet<Id>) {
+        let mut all_type_ids_with_impls = HashSet::new();
+        if no_common_traits {
+            for item in krate.index.values() {
+                if let ItemEnum::Impl(imp) = &item.inner {
+                    if let Some(for_type_id) = get_type_id(&imp.for_) {
+                        if selected_ids.contains(&for_type_id) {
+                            all_type_ids_with_impls.insert(for_type_id);
+                        }
+                    }
+                }
+            }
             return (HashSet::new(), all_type_ids_with_impls);
         }
 
-        let mut common_traits_set = HashSet::new();
-        if all_type_ids_with_impls.is_empty() {
-            return (common_traits_set, all_type_ids_with_impls);
+        // Trait Path ID -> (FormattedTraitImpl -> Count)
+        let mut trait_format_counts: HashMap<Id, HashMap<FormattedTraitImpl, usize>> =
+            HashMap::new();
+
+        for item in krate.index.values() {
+            if let ItemEnum::Impl(imp) = &item.inner {
+                if let Some(for_type_id) = get_type_id(&imp.for_) {
+                    if selected_ids.contains(&for_type_id) {
+                        all_type_ids_with_impls.insert(for_type_id);
+                        if let Some(trait_path) = &imp.trait_ {
+                            let norm_impl = FormattedTraitImpl::from_impl(
+                                imp, None, trait_path, krate, printer,
+                            );
+                            *trait_format_counts
+                                .entry(trait_path.id)
+                                .or_default()
+                                .entry(norm_impl)
+                                .or_insert(0) += 1;
+                        }
+                    }
+                }
+            }
+        }
+        debug!(
+            "Found {} types with trait implementations for crate-level common trait calculation.",
+            all_type_ids_with_impls.len()
+        );
+
+        if all_type_ids_with_impls.len() < 2 {
+            debug!("Too few trait implementations, skipping crate 'Common Traits'");
+            return (HashSet::new(), all_type_ids_with_impls);
+        }
+
+        let mut common_traits_set = HashSet::new();
+        if all_type_ids_with_impls.is_empty() {
+            return (common_traits_set, all_type_ids_with_impls);
+        }
+
+        let type_count_threshold = (all_type_ids_with_impls.len() as f32
+            * printer.common_traits_threshold)
+            .ceil() as usize;
+        debug!(
+            "Crate common trait threshold (types implementing): {} (out of {} types)",
+            type_count_threshold,
+            all_type_ids_with_impls.len()
+        );
+
+        for (trait_id, format_map) in trait_format_counts {
+            let total_implementations_for_trait = format_map.values().sum::<usize>();
+
+            if total_implementations_for_trait < type_count_threshold {
+                trace!(
+                    "Trait ID {:?} not common enough ({} implementations, need {})",
+                    trait_id,
+                    total_implementations_for_trait,
+                    type_count_threshold
+                );
+                continue;
+            }
+
+            // Check for mixed positive/negative implementations
+            let mut has_positive = false;
+            let mut has_negative = false;
+            for f_impl in format_map.keys() {
+                if f_impl.is_negative {
+                    has_negative = true;
+                } else {
+                    has_positive = true;
+                }
+            }
+            if has_positive && has_negative {
+                warn!(
+                    "Trait ID {:?} has mixed positive and negative implementations, cannot be common.",
+                    trait_id
+                );
+                continue;
+            }
+
+            if format_map.len() == 1 {
+                // Only one format for this trait path
+                if let Some(formatted_impl) = format_map.keys().next() {
+                    common_traits_set.insert(formatted_impl.clone());
+                    debug!(
+                        "Identified crate-common trait (single format): {:?} for trait ID {:?}",
+                        formatted_impl.formatted_markdown_list_entry, trait_id
+                    );
+                }
+            } else {
+                // Multiple formats, check if "Simple" is predominant
+                let simple_format_count = format_map
+                    .iter()
+                    .find(|(f_impl, _)| f_impl.category == TraitImplCategory::Simple)
+                    .map_or(0, |(_, count)| *count);
+
+                if simple_format_count * 2 > total_implementations_for_trait {
+                    // Simple format is implemented by >50% of *this trait's* implementors
+                    if let Some(simple_impl) = format_map
+                        .keys()
+                        .find(|f_impl| f_impl.category == TraitImplCategory::Simple)
+                    {
+                        common_traits_set.insert(simple_impl.clone());
+                        debug!(
+                            "Identified crate-common trait (simple format predominant): {:?} for trait ID {:?}",
+                            simple_impl.formatted_markdown_list_entry, trait_id
+                        );
+                    }
+                } else {
+                    trace!(
+                        "Trait ID {:?} has multiple formats, but Simple is not predominant ({} of {}).",
+                        trait_id, simple_format_count, total_implementations_for_trait
+                    );
+                }
+            }
+        }
+        (common_traits_set, all_type_ids_with_impls)
+    }
+
+    /// Calculates common traits for a specific module.
+    fn calculate_module_common_traits(&self, module_id: &Id) -> HashSet<FormattedTraitImpl> {
+        if self.no_common_traits {
+            return HashSet::new();
+        }
+
+        let mut module_common_traits = self.crate_common_traits.clone();
+        let mut module_types_considered = HashSet::new();
+
+        if let Some(resolved_mod) = self.resolved_modules.get(module_id) {
+            for item_id_in_mod in &resolved_mod.items {
+                if let Some(item) = self.krate.index.get(item_id_in_mod) {
+                    if matches!(
+                        item.inner,
+                        ItemEnum::Struct(_)
+                            | ItemEnum::Enum(_)
+                            | ItemEnum::Union(_)
+                            | ItemEnum::Primitive(_)
+                    ) && self.selected_ids.contains(item_id_in_mod)
+                    {
+                        let has_impls = self.krate.index.values().any(|idx_item| {
+                            if let ItemEnum::Impl(imp) = &idx_item.inner {
+                                if let Some(for_id) = get_type_id(&imp.for_) {
+                                    return for_id == *item_id_in_mod;
+                                }
+                            }
+                            false
+                        });
+                        if has_impls {
+                            module_types_considered.insert(*item_id_in_mod);
+                        }
+                    }
+                }
+            }
+        }
+
+        let module_types_with_impls_count = module_types_considered.len();
+        if module_types_with_impls_count <= 1 {
+            return module_common_traits;
+        }
+
+        let mut trait_format_counts: HashMap<Id, HashMap<FormattedTraitImpl, usize>> =
+            HashMap::new();
+
+        for item_id_in_mod in &module_types_considered {
+            for krate_item in self.krate.index.values() {
+                if let ItemEnum::Impl(imp) = &krate_item.inner {
+                    if let Some(for_id) = get_type_id(&imp.for_) {
+                        if for_id == *item_id_in_mod {
+                            if let Some(trait_path) = &imp.trait_ {
+                                let norm_impl = FormattedTraitImpl::from_impl(
+                                    imp, None, trait_path, self.krate, self,
+                                );
+                                *trait_format_counts
+                                    .entry(trait_path.id)
+                                    .or_default()
+                                    .entry(norm_impl)
+                                    .or_insert(0) += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let type_count_threshold =
+            (module_types_with_impls_count as f32 * self.common_traits_threshold).ceil() as usize;
+        debug!(
+            "Module {:?} common trait threshold (types implementing): {} (out of {} types in module)",
+            module_id, type_count_threshold, module_types_with_impls_count
+        );
+
+        for (trait_id, format_map) in trait_format_counts {
+            let total_implementations_for_trait = format_map.values().sum::<usize>();
+
+            if total_implementations_for_trait < type_count_threshold {
+                trace!(
+                    "Module {:?} trait ID {:?} not common enough ({} implementations, need {})",
+                    module_id,
+                    trait_id,
+                    total_implementations_for_trait,
+                    type_count_threshold
+                );
+                continue;
+            }
+
+            let mut has_positive = false;
+            let mut has_negative = false;
+            for f_impl in format_map.keys() {
+                if f_impl.is_negative {
+                    has_negative = true;
+                } else {
+                    has_positive = true;
+                }
+            }
+            if has_positive && has_negative {
+                warn!(
+                    "Module {:?} trait ID {:?} has mixed positive and negative implementations, cannot be common.",
+                    module_id, trait_id
+                );
+                continue;
+            }
+
+            if format_map.len() == 1 {
+                if let Some(formatted_impl) = format_map.keys().next() {
+                    if module_common_traits.insert(formatted_impl.clone()) {
+                        debug!(
+                            "Identified module-specific common trait (single format) for {:?}: {:?} for trait ID {:?}",
+                            self.krate.paths.get(module_id).map(|p|p.path.join("::")).unwrap_or_default(),
+                            formatted_impl.formatted_markdown_list_entry,
+                            trait_id
+                        );
+                    }
+                }
+            } else {
+                let simple_format_count = format_map
+                    .iter()
+                    .find(|(f_impl, _)| f_impl.category == TraitImplCategory::Simple)
+                    .map_or(0, |(_, count)| *count);
+
+                if simple_format_count * 2 > total_implementations_for_trait {
+                    if let Some(simple_impl) = format_map
+                        .keys()
+                        .find(|f_impl| f_impl.category == TraitImplCategory::Simple)
+                    {
+                        if module_common_traits.insert(simple_impl.clone()) {
+                            debug!(
+                                "Identified module-specific common trait (simple format predominant) for {:?}: {:?} for trait ID {:?}",
+                                self.krate.paths.get(module_id).map(|p|p.path.join("::")).unwrap_or_default(),
+                                simple_impl.formatted_markdown_list_entry,
+                                trait_id
+                            );
+                        }
+                    }
+                } else {
+                    trace!(
+                        "Module {:?} trait ID {:?} has multiple formats, but Simple is not predominant ({} of {}).",
+                        module_id, trait_id, simple_format_count, total_implementations_for_trait
+                    );
+                }
+            }
+        }
+        module_common_traits
+    }
+
+    /// Builds the module hierarchy tree.
+    fn build_module_tree(krate: &'a Crate) -> ModuleTree {
+        let mut tree = ModuleTree::default();
+        let mut parent_map: HashMap<Id, Id> = HashMap::new(); // Child -> Parent
+
+        for (id, item) in &krate.index {
+            if let ItemEnum::Module(module_data) = &item.inner {
+                tree.all_modules.insert(*id);
+                let mut children = Vec::new();
+                for child_id in &module_data.items {
+                    if let Some(child_item) = krate.index.get(child_id) {
+                        if let ItemEnum::Module(_) = child_item.inner {
+                            children.push(*child_id);
+                            parent_map.insert(*child_id, *id);
+                        }
+                    }
+                }
+                if !children.is_empty() {
+                    // Sort children alphabetically by name/path here for consistent ordering within a parent
+                    children.sort_by_key(|child_id| {
+                        krate
+                            .paths
+                            .get(child_id)
+                            .map(|p| p.path.join("::"))
+                            .unwrap_or_default()
+                    });
+                    tree.children.insert(*id, children);
+                }
+            }
+        }
+
+        // Identify top-level modules (excluding crate root)
+        for module_id in &tree.all_modules {
+            if *module_id != krate.root && !parent_map.contains_key(module_id) {
+                tree.top_level_modules.push(*module_id);
+            }
+        }
+
+        // Sort top-level modules alphabetically by path
+        tree.top_level_modules.sort_by_key(|id| {
+            krate
+                .paths
+                .get(id)
+                .map(|p| p.path.join("::"))
+                .unwrap_or_default()
+        });
+
+        tree
+    }
+
+    /// Gets the current markdown header level based on the doc_path length.
+    fn get_current_header_level(&self) -> usize {
+        self.doc_path.len() + 1 // H1 if path is empty, H2 if path has one element, etc.
+    }
+
+    /// Applies [`Printer::heading_offset`] to a header level. The offset never pushes
+    /// a header past H6, but headers that are already deeper keep their level.
+    fn shifted_header_level(&self, level: usize) -> usize {
+        (level + self.heading_offset).min(level.max(6))
+    }
+
+    /// Returns the `#` markers of a header at `level`, see
+    /// [`Printer::shifted_header_level`].
+    fn header_marker(&self, level: usize) -> String {
+        "#".repeat(self.shifted_header_level(level))
+    }
+
+    /// Generates the header prefix string (e.g., "1.2.1:") based on the doc_path stack.
+    /// H2 headers always get just "N:". H3+ get the full path "N.M.O:".
+    fn get_header_prefix(&self) -> String {
+        let level = self.get_current_header_level();
+        if self.doc_path.is_empty() || level < 2 {
+            return String::new(); // No prefix for H1 or if stack is empty
+        }
+
+        if level == 2 {
+            // Module headers (H2) use only the last element (the H2 counter)
+            format!("{}:", self.doc_path.last().unwrap_or(&0))
+        } else {
+            // H3+ headers use the full path stored in doc_path
+            self.doc_path
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(".")
+                + ":"
+        }
+    }
+
+    /// Generates the template marker string (e.g., "{{MISSING_DOCS_1_2_1}}")
+    fn get_template_marker(&self) -> String {
+        if self.doc_path.is_empty() {
+            "{{MISSING_DOCS}}".to_string()
+        } else {
+            format!(
+                "{{{{MISSING_DOCS_{}}}}}", // Double {{ for literal {
+                self.doc_path
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join("_")
+            )
+        }
+    }
+
+    /// Increments the counter for the current document level.
+    /// Note: we increment _after_ outputting something at a given level because
+    /// we always initialize the level to 1 via `doc_path.push(1)`
+    fn post_increment_current_level(&mut self) {
+        if let Some(last) = self.doc_path.last_mut() {
+            *last += 1;
+        } else {
+            warn!("Attempted to increment document path level when path was empty.");
+        }
+    }
+
+    /// Pushes a new level (starting at 1) onto the document path.
+    fn push_level(&mut self) {
+        self.doc_path.push(1);
+    }
+
+    /// Pops the last level from the document path.
+    fn pop_level(&mut self) {
+        self.doc_path.pop();
+    }
+
+    fn get_item_kind(&self, id: &Id) -> Option<ItemKind> {
+        // Prefer index over paths for kind, as paths might be missing for some items?
+        self.krate
+            .index
+            .get(id)
+            .map(infer_item_kind)
+            .or_else(|| self.krate.paths.get(id).map(|summary| summary.kind))
+    }
+
+    /// Returns the anchor path of each selected item and member (field, variant or
+    /// associated item) that gets an anchor, e.g. `["my_crate", "Msg", "Move"]`.
+    /// Members are named by their owner's path and their name.
+    fn anchor_paths(&self) -> Vec<(Vec<String>, Id)> {
+        let local_path = |id: &Id| {
+            self.krate
+                .paths
+                .get(id)
+                .filter(|summary| summary.crate_id == 0)
+                .map(|summary| summary.path.clone())
+        };
+        let mut paths = Vec::new();
+        for id in &self.selected_ids {
+            let Some(item) = self.krate.index.get(id) else {
+                continue;
+            };
+            if let Some(path) = local_path(id) {
+                paths.push((path, *id));
+            }
+            let (owner_path, members): (Option<Vec<String>>, Vec<Id>) = match &item.inner {
+                ItemEnum::Struct(st) => (
+                    local_path(id),
+                    match &st.kind {
+                        StructKind::Plain { fields, .. } => fields.clone(),
+                        StructKind::Tuple(fields) => fields.iter().flatten().copied().collect(),
+                        StructKind::Unit => Vec::new(),
+                    },
+                ),
+                ItemEnum::Union(u) => (local_path(id), u.fields.clone()),
+                ItemEnum::Variant(v) => (
+                    local_path(id),
+                    match &v.kind {
+                        VariantKind::Plain => Vec::new(),
+                        VariantKind::Tuple(fields) => fields.iter().flatten().copied().collect(),
+                        VariantKind::Struct { fields, .. } => fields.clone(),
+                    },
+                ),
+                ItemEnum::Trait(t) => (local_path(id), t.items.clone()),
+                ItemEnum::Impl(imp) => (
+                    get_type_id(&imp.for_).and_then(|type_id| local_path(&type_id)),
+                    imp.items.clone(),
+                ),
+                _ => continue,
+            };
+            let Some(owner_path) = owner_path else {
+                continue;
+            };
+            for member_id in members {
+                let name = self
+                    .krate
+                    .index
+                    .get(&member_id)
+                    .and_then(|m| m.name.clone());
+                if let (Some(name), true) = (name, self.selected_ids.contains(&member_id)) {
+                    let mut path = owner_path.clone();
+                    path.push(name);
+                    paths.push((path, member_id));
+                }
+            }
+        }
+        paths
+    }
+
+    /// Assigns the anchor ids of all documented items and members up front if
+    /// [`anchors`](Printer::anchors) is enabled, so links can point at items that are
+    /// printed later.
+    ///
+    /// Ids are deduplicated across the document by appending `-1`, `-2`, ... on
+    /// collision (e.g. `fn config` and `struct Config`), in path order so the same
+    /// crate always gets the same ids.
+    fn assign_item_anchors(&mut self) {
+        if !self.anchors {
+            return;
+        }
+        let mut paths = self.anchor_paths();
+        paths.sort_by(|(a_path, a_id), (b_path, b_id)| (a_path, a_id.0).cmp(&(b_path, b_id.0)));
+        for (path, id) in paths {
+            if self.item_anchors.contains_key(&id) {
+                continue; // An impl item reached through several impls
+            }
+            let anchor = self.unique_anchor_id(&path);
+            self.item_anchors.insert(id, anchor);
+        }
+    }
+
+    /// Returns an anchor id for the path `segments` that was not assigned yet, and
+    /// reserves it.
+    fn unique_anchor_id(&mut self, segments: &[String]) -> String {
+        let base = anchor_slug(segments);
+        let mut anchor = base.clone();
+        let mut suffix = 1;
+        while !self.anchor_ids.insert(anchor.clone()) {
+            anchor = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        anchor
+    }
+
+    /// Returns the anchor id of the documented item `id`, if
+    /// [`anchors`](Printer::anchors) is enabled.
+    fn item_anchor(&self, id: &Id) -> Option<&str> {
+        if !self.anchors || !self.selected_ids.contains(id) {
+            return None;
+        }
+        self.item_anchors.get(id).map(String::as_str)
+    }
+
+    /// Formats a link to the anchor of the documented item `id` showing `text` as
+    /// code, or `None` without an anchor to link to. All links to items go through
+    /// here so they agree with the anchors that are written.
+    fn anchor_link(&self, id: &Id, text: &str) -> Option<String> {
+        self.item_anchor(id)
+            .map(|anchor| format!("[`{}`](#{})", text, anchor))
+    }
+
+    /// Writes an `<a id="..."></a>` anchor for the item `id` at the path `segments`
+    /// if [`anchors`](Printer::anchors) is enabled, returning the anchor id.
+    ///
+    /// The anchor assigned by [`Printer::assign_item_anchors`] is used the first time
+    /// an item is printed; items without one, or printed again (e.g. the items of a
+    /// blanket impl), get a new deduplicated id.
+    fn write_anchor(&mut self, id: &Id, segments: &[String]) -> Option<String> {
+        if !self.anchors {
+            return None;
+        }
+        let anchor = match self.item_anchors.get(id) {
+            Some(anchor) if !self.emitted_anchors.contains(anchor) => anchor.clone(),
+            _ => self.unique_anchor_id(segments),
+        };
+        self.emitted_anchors.insert(anchor.clone());
+        writeln!(self.output, "<a id=\"{}\"></a>", anchor).unwrap();
+        self.anchor_index
+            .entry(segments.join("::"))
+            .or_insert_with(|| anchor.clone());
+        Some(anchor)
+    }
+
+    /// Writes an anchor for the member `id` named `name`, nested under the current
+    /// item (e.g. a variant or field).
+    fn write_scoped_anchor(&mut self, id: &Id, name: &str) -> Option<String> {
+        let mut segments = self.anchor_scope.clone();
+        segments.push(name.to_string());
+        self.write_anchor(id, &segments)
+    }
+
+    /// Builds the machine-readable `<!-- item: ... -->` comment emitted before an item
+    /// section when [`metadata_comments`](Printer::metadata_comments) is enabled.
+    fn item_metadata_comment(&self, item: &Item) -> String {
+        let path = if self.krate.paths.contains_key(&item.id) {
+            format_id_path_canonical(&item.id, self)
+        } else {
+            item.name.clone().unwrap_or_default()
+        };
+        let kind = serde_json::to_value(infer_item_kind(item))
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let features = cfg_features(&item.attrs);
+        format!(
+            "<!-- item: path={} kind={} deprecated={} feature={} -->",
+            path,
+            kind,
+            item.deprecation.is_some(),
+            if features.is_empty() {
+                "none".to_string()
+            } else {
+                features.join(",")
+            }
+        )
+    }
+
+    /// Prints the documentation string for an item, applying template mode if active.
+    /// Header level is determined internally by the doc_path.
+    fn print_docs(&mut self, item: &Item) {
+        let mut output = std::mem::take(&mut self.output);
+        self.write_docs(item, &mut output);
+        self.output = output;
+    }
+
+    /// Returns `true` if `item` has documentation to print: non-empty docs, a doc
+    /// override (see [`Printer::doc_overrides`]), or any docs in template mode.
+    fn has_printable_docs(&self, item: &Item) -> bool {
+        (self.template_mode && item.docs.is_some())
+            || has_docs(item)
+            || (!self.template_mode && self.item_doc_overrides.contains_key(&item.id))
+    }
+
+    /// Returns the docs of `item` with its doc override applied, or `None` if both are
+    /// missing or empty.
+    fn doc_text(&self, item: &Item) -> Option<String> {
+        let docs = item
+            .docs
+            .as_deref()
+            .map(str::trim)
+            .filter(|d| !d.is_empty());
+        let text = match self.item_doc_overrides.get(&item.id) {
+            Some(DocOverride::Replace { replace }) => Some(replace.trim().to_string()),
+            Some(DocOverride::Append(extra)) => match docs {
+                Some(docs) => Some(format!("{}\n\n{}", docs, extra.trim())),
+                None => Some(extra.trim().to_string()),
+            },
+            None => docs.map(str::to_string),
+        };
+        text.filter(|text| !text.is_empty())
+    }
+
+    /// Writes the documentation string for an item into `out`, applying template mode
+    /// if active. Used to render docs into a separate buffer without touching the
+    /// printer's output.
+    fn write_docs(&self, item: &Item, out: &mut String) {
+        let header_level = self.get_current_header_level(); // Level of the item owning the docs
+        match (&item.docs, self.template_mode) {
+            // Template mode and docs exist: Print mustache marker
+            (Some(_), true) => {
+                let marker = self.get_template_marker();
+                writeln!(out, "{}\n", marker).unwrap();
+            }
+            // Not template mode or no docs: Print original docs if non-empty
+            (Some(docs), false) => {
+                let replaced = matches!(
+                    self.item_doc_overrides.get(&item.id),
+                    Some(DocOverride::Replace { .. })
+                );
+                if !docs.trim().is_empty() && !replaced {
+                    let docs = if self.keep_hidden_doc_lines {
+                        Cow::Borrowed(docs.as_str())
+                    } else {
+                        strip_hidden_doc_lines(docs)
+                    };
+                    // Use the new adjust_markdown_headers function
+                    let links = self.resolve_doc_links(item);
+                    let adjusted_docs = adjust_markdown_headers_with_links(
+                        docs.trim(),
+                        self.shifted_header_level(header_level),
+                        &links,
+                    );
+                    writeln!(out, "{}\n", adjusted_docs).unwrap();
+                }
+                // If docs are Some but empty, print nothing (existing behavior)
+            }
+            // Docs are None: Print nothing
+            (None, _) => {}
+        }
+        if let (false, Some(DocOverride::Append(extra) | DocOverride::Replace { replace: extra })) =
+            (self.template_mode, self.item_doc_overrides.get(&item.id))
+        {
+            if !extra.trim().is_empty() {
+                let adjusted =
+                    adjust_markdown_headers(extra.trim(), self.shifted_header_level(header_level));
+                writeln!(out, "{}\n", adjusted).unwrap();
+            }
+        }
+    }
+
+    /// Prints the details of a single selected item.
+    /// Manages the doc_path stack for the item's header.
+    /// Returns true if a header was emitted (full details or a cross-reference), in which
+    /// case the caller advances the section counter, and false if the item was skipped.
+    fn print_item_details(&mut self, id: &Id) -> bool {
+        if !self.selected_ids.contains(id) {
+            return false; // Skip unselected items
+        }
+
+        let Some(item) = self.krate.index.get(id) else {
+            warn!("Item details for ID {id:?} not found in index");
+            return false;
+        };
+
+        // Skip printing details for 'Use' items, they are handled by resolution
+        // Also skip Modules here, they are handled by the main traversal in finalize
+        if matches!(item.inner, ItemEnum::Use(_) | ItemEnum::Module(_)) {
+            return false;
+        }
+
+        let item_header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
+        let declaration = generate_item_declaration(item, self, &self.current_module_path);
+        let declaration = self.restore_elided_header_exprs(item, declaration);
+
+        if let Some(existing_prefix) = self.printed_ids.get(id) {
+            // Item already printed, print cross-reference instead of full details
+            // This case is primarily for when print_item_details is called directly
+            // (e.g., from print_items_of_kind) for an item that was already
+            // printed via a different module path.
+            let reference = self.format_cross_reference(id, &declaration, "", existing_prefix);
+            writeln!(
+                self.output,
+                "\n{} {} {}\n",
+                self.header_marker(item_header_level),
+                header_prefix,
+                reference
+            )
+            .unwrap();
+            // Do not push/pop level or print further details for cross-referenced item
+            return true; // A header was emitted, so it takes a number
+        }
+
+        // Store the prefix *before* printing details, as this is its first detailed print
+        self.printed_ids.insert(*id, header_prefix.clone());
+
+        writeln!(self.output).unwrap();
+        if self.metadata_comments {
+            writeln!(self.output, "{}", self.item_metadata_comment(item)).unwrap();
+        }
+        let item_path = self.krate.paths.get(id).map_or_else(
+            || {
+                let mut path = self.current_module_path.clone();
+                path.extend(item.name.clone());
+                path
+            },
+            |summary| summary.path.clone(),
+        );
+        self.write_anchor(id, &item_path);
+        let outer_anchor_scope = std::mem::replace(&mut self.anchor_scope, item_path);
+
+        // Print Header (e.g. `### 1.1.1: `declaration``)
+        writeln!(
+            self.output,
+            "{} {} `{}`\n", // Add newline after header
+            self.header_marker(item_header_level),
+            header_prefix,
+            declaration
+        )
+        .unwrap();
+
+        self.push_level();
+
+        if let Some(deprecation) = &item.deprecation {
+            writeln!(self.output, "{}\n", format_deprecation(deprecation)).unwrap();
+        }
+
+        if let Some(note) = self.rust_version_note(item) {
+            writeln!(self.output, "{}\n", note).unwrap();
+        }
+
+        if has_cfg_attr(item, "doc") {
+            writeln!(
+                self.output,
+                "_(documentation-only; not present in normal builds)_\n"
+            )
+            .unwrap();
+        }
+
+        self.print_cfg_note(item);
+
+        if let Some(attributes_note) = format_metadata_attributes_note(&item.attrs) {
+            writeln!(self.output, "{}\n", attributes_note).unwrap();
+        }
+
+        if item.attrs.iter().any(|attr| attr == "#[fundamental]") {
+            let kind = if matches!(item.inner, ItemEnum::Trait(_)) {
+                "trait"
+            } else {
+                "type"
+            };
+            writeln!(
+                self.output,
+                "_This {} is `#[fundamental]` (affects coherence)._\n",
+                kind
+            )
+            .unwrap();
+        }
+
+        if let Some(note) = self.sealed_trait_note(item) {
+            writeln!(self.output, "{}\n", note).unwrap();
+        }
+
+        // Print Code Block for Struct/Enum/Trait/Function (if needed)
+        let code_block = match &item.inner {
+            ItemEnum::Struct(s) => Some(generate_struct_code_block(item, s, self)),
+            ItemEnum::Enum(e) => Some(generate_enum_code_block(item, e, self)),
+            ItemEnum::Union(u) => Some(generate_union_code_block(item, u, self)),
+            ItemEnum::Trait(t) => Some(generate_trait_code_block(item, t, self)),
+            ItemEnum::Function(f) => {
+                // Check if function has attrs or where clause
+                let has_attrs = f.header.is_const
+                    || f.header.is_async
+                    || f.header.is_unsafe
+                    || !matches!(f.header.abi, Abi::Rust)
+                    || item.attrs.iter().any(|attr| is_declaration_attribute(attr));
+                let has_where = !arrange_param_bounds(&f.generics, self)
+                    .where_predicates
+                    .is_empty();
+                if has_attrs || has_where {
+                    Some(generate_function_code_block(item, f, self))
+                } else {
+                    None // No code block needed for simple function
+                }
+            }
+            ItemEnum::Constant { type_, const_ } => {
+                Some(generate_constant_code_block(item, type_, const_, self))
+            }
+            ItemEnum::Static(st) => Some(generate_static_code_block(item, st, self)),
+            // TODO: Add code blocks for other types like TypeAlias if desired
+            _ => None,
+        };
+
+        if let Some(code) = code_block {
+            let code = self.finish_code_block(item, code);
+            writeln!(self.output, "```{}\n{}\n```\n", self.code_fence_lang, code).unwrap();
+        }
+
+        let has_stripped = match &item.inner {
+            ItemEnum::Struct(Struct {
+                kind:
+                    StructKind::Plain {
+                        fields,
+                        has_stripped_fields,
+                    },
+                ..
+            }) => *has_stripped_fields || self.has_skipped_hidden_field(fields),
+            _ => false,
+        };
+
+        if has_stripped {
+            writeln!(self.output, "_[Private fields hidden]_\n").unwrap();
+        }
+
+        // Print Documentation (using the helper method)
+        self.print_docs(item);
+
+        match &item.inner {
+            ItemEnum::Struct(s) => self.print_struct_fields(item, s),
+            ItemEnum::Enum(e) => self.print_enum_variants(item, e),
+            ItemEnum::Union(u) => self.print_union_fields(item, u),
+            ItemEnum::Trait(t) => {
+                self.print_trait_object_note(item, t);
+                self.print_trait_associated_items(item, t);
+                if self.show_inherited_trait_items {
+                    self.print_inherited_trait_items(t);
+                }
+            }
+            // Add other kinds requiring detailed sections if necessary
+            _ => {}
+        }
+
+        // Print Implementations (common to Struct, Enum, Trait, Primitive, etc.)
+        let impl_ids = match &item.inner {
+            ItemEnum::Struct(s) => Some(&s.impls),
+            ItemEnum::Enum(e) => Some(&e.impls),
+            ItemEnum::Trait(t) => Some(&t.implementations), // Traits list implementors
+            ItemEnum::Union(u) => Some(&u.impls),
+            ItemEnum::Primitive(p) => Some(&p.impls),
+            _ => None,
+        };
+
+        if let Some(ids) = impl_ids {
+            match &item.inner {
+                ItemEnum::Trait(_) => self.print_trait_implementors(ids, item),
+                _ => self.print_item_implementations(ids, item),
+            }
+        }
+
+        self.pop_level();
+        self.anchor_scope = outer_anchor_scope;
+
+        true // Full details were printed
+    }
+
+    /// Checks if any selected field within a struct has documentation or if template mode is on.
+    #[allow(unused)]
+    fn has_documented_fields(&self, s: &Struct) -> bool {
+        let field_ids = match &s.kind {
+            StructKind::Plain { fields, .. } => fields.clone(),
+            StructKind::Tuple(fields) => fields.iter().filter_map(|opt_id| *opt_id).collect(),
+            StructKind::Unit => vec![],
+        };
+        field_ids.iter().any(|field_id| {
+            self.selected_ids.contains(field_id)
+                && self.krate.index.get(field_id).is_some_and(|item| {
+                    // Consider it "documented" if template mode is on and docs are Some
+                    self.has_printable_docs(item)
+                })
+        })
+    }
+
+    /// Prints the "Fields" section for a struct, only if needed.
+    /// Also marks fields without documentation as printed.
+    fn print_struct_fields(&mut self, _item: &Item, s: &Struct) {
+        let all_field_ids: Vec<Id> = match &s.kind {
+            StructKind::Plain { fields, .. } => fields.clone(),
+            StructKind::Tuple(fields) => fields.iter().filter_map(|opt_id| *opt_id).collect(),
+            StructKind::Unit => vec![],
+        };
+
+        let mut printable_field_count = 0;
+
+        // First pass: Mark unselected/undocumented/non-templated fields printed and check if any are printable.
+        for field_id in &all_field_ids {
+            if !self.selected_ids.contains(field_id) {
+                continue; // Skip unselected fields
+            }
+
+            if let Some(item) = self.krate.index.get(field_id) {
+                let field_has_printable_docs = self.has_printable_docs(item);
+                if field_has_printable_docs {
+                    // Check if it's already printed to avoid double counting
+                    if !self.printed_ids.contains_key(field_id) {
+                        printable_field_count += 1;
+                    }
+                } else {
+                    // Mark non-printable field as printed immediately
+                    self.printed_ids.insert(*field_id, self.get_header_prefix());
+                }
+            } else {
+                // If item doesn't exist in index but ID was present, mark it printed to avoid issues
+                self.printed_ids.insert(*field_id, self.get_header_prefix());
+            }
+        }
+
+        // Only print the "Fields" section if there's a printable field
+        if printable_field_count == 0 {
+            return;
+        }
+
+        let fields_header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} {}\n", // Add newline after header
+            self.header_marker(fields_header_level),
+            header_prefix,
+            self.section_title("Fields", printable_field_count)
+        )
+        .unwrap();
+
+        // Push a new level for the field items themselves
+        self.push_level();
+        for field_id in &all_field_ids {
+            if self.print_field_details(field_id) {
+                self.post_increment_current_level();
+            }
+        }
+        self.pop_level(); // Pop the field item level
+
+        self.post_increment_current_level();
+    }
+
+    /// Returns `true` if any of the fields (or variants) `ids` is skipped as
+    /// `#[doc(hidden)]`.
+    fn has_skipped_hidden_field(&self, ids: &[Id]) -> bool {
+        ids.iter().any(|id| {
+            self.krate
+                .index
+                .get(id)
+                .is_some_and(|field| self.is_skipped_hidden(field))
+        })
+    }
+
+    /// Prints the "Fields" section for a union, only if needed.
+    fn print_union_fields(&mut self, _item: &Item, u: &Union) {
+        let all_field_ids: Vec<Id> = u.fields.clone();
+        let mut printable_field_count = 0;
+
+        for field_id in &all_field_ids {
+            if !self.selected_ids.contains(field_id) {
+                continue;
+            }
+            if let Some(item) = self.krate.index.get(field_id) {
+                let field_has_printable_docs = self.has_printable_docs(item);
+                if field_has_printable_docs {
+                    if !self.printed_ids.contains_key(field_id) {
+                        printable_field_count += 1;
+                    }
+                } else {
+                    self.printed_ids.insert(*field_id, self.get_header_prefix());
+                }
+            } else {
+                self.printed_ids.insert(*field_id, self.get_header_prefix());
+            }
+        }
+
+        let has_stripped_fields = u.has_stripped_fields || self.has_skipped_hidden_field(&u.fields);
+        if printable_field_count == 0 && !has_stripped_fields {
+            return;
+        }
+
+        let fields_header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} {}\n",
+            self.header_marker(fields_header_level),
+            header_prefix,
+            self.section_title("Fields", printable_field_count)
+        )
+        .unwrap();
+
+        self.push_level();
+        for field_id in &all_field_ids {
+            if self.print_field_details(field_id) {
+                self.post_increment_current_level();
+            }
+        }
+        if has_stripped_fields {
+            writeln!(self.output, "_[Private fields hidden]_").unwrap();
+        }
+        self.pop_level();
+        self.post_increment_current_level();
+    }
+
+    /// Prints the details for a single struct field, only if it has printable documentation.
+    /// Returns true if the field was printed, false otherwise.
+    fn print_field_details(&mut self, field_id: &Id) -> bool {
+        if !self.selected_ids.contains(field_id) || self.printed_ids.contains_key(field_id) {
+            return false; // Skip unselected or already printed
+        }
+
+        if let Some(item) = self.krate.index.get(field_id) {
+            let field_has_printable_docs = self.has_printable_docs(item);
+
+            // Only proceed if the field has printable documentation
+            if !field_has_printable_docs {
+                // Should already be marked printed in print_struct_fields
+                return false;
+            }
+
+            let header_prefix = self.get_header_prefix();
+            // Mark as printed *before* printing details
+            self.printed_ids.insert(*field_id, header_prefix.clone());
+
+            if let ItemEnum::StructField(_field_type) = &item.inner {
+                let name = item.name.as_deref().unwrap_or("_");
+                let field_header_level = self.get_current_header_level();
+                self.write_scoped_anchor(field_id, name);
+
+                // Header: e.g., ##### 1.1.1.1: `field_name`
+                writeln!(
+                    self.output,
+                    "{} {} `{}`\n", // Add newline after header
+                    self.header_marker(field_header_level),
+                    header_prefix,
+                    name
+                )
+                .unwrap();
+                self.print_cfg_note(item);
+
+                // Print docs (using helper, handles template mode)
+                self.print_docs(item);
+
+                // Type (optional, could add here if needed)
+                // writeln!(self.output, "_Type: `{}`_\n", format_type(field_type, self)).unwrap();
+                return true; // Field was printed
+            }
+        }
+        // Mark as printed even if item lookup failed (shouldn't happen ideally)
+        self.printed_ids.insert(*field_id, self.get_header_prefix());
+        false
+    }
+
+    /// Prints the details for a single enum variant field, only if it has printable documentation.
+    /// Returns true if the field was printed, false otherwise.
+    fn print_variant_field_details(&mut self, field_id: &Id) -> bool {
+        if !self.selected_ids.contains(field_id) || self.printed_ids.contains_key(field_id) {
+            return false; // Skip unselected or already printed
+        }
+
+        if let Some(item) = self.krate.index.get(field_id) {
+            let field_has_printable_docs = self.has_printable_docs(item);
+
+            // Only proceed if the field has printable documentation
+            if !field_has_printable_docs {
+                // If no docs, the ID should already be marked printed in print_variant_details
+                return false;
+            }
+            let header_prefix = self.get_header_prefix();
+            // Mark as printed *before* printing details
+            self.printed_ids.insert(*field_id, header_prefix.clone());
+
+            if let ItemEnum::StructField(_field_type) = &item.inner {
+                let name = item.name.as_deref().unwrap_or("_"); // Might be _ for tuple fields
+                let field_header_level = self.get_current_header_level();
+
+                // Header: e.g., ###### 1.1.1.1.1: `field_name`
+                // Use field index for tuple fields if name is "_" (name is often '0', '1' etc.)
+                let header_name = if name == "_" || name.chars().all(|c| c.is_ascii_digit()) {
+                    format!("Field {}", name)
+                } else {
+                    name.to_string()
+                };
+                self.write_scoped_anchor(field_id, name);
+                writeln!(
+                    self.output,
+                    "{} {} `{}`\n", // Add newline after header
+                    self.header_marker(field_header_level),
+                    header_prefix,
+                    header_name
+                )
+                .unwrap();
+                self.print_cfg_note(item);
+
+                // Print Docs (using helper, handles template mode)
+                self.print_docs(item);
+
+                // Increment level counter for this field item
+                self.post_increment_current_level();
+
+                // Type (optional)
+                // writeln!(self.output, "_Type: `{}`_\n", format_type(field_type, self)).unwrap();
+                return true; // Field was printed
+            }
+        }
+        // Mark as printed even if item lookup failed
+        self.printed_ids.insert(*field_id, self.get_header_prefix());
+        false
+    }
+
+    /// Checks if any selected variant or its fields have printable documentation.
+    #[allow(unused)]
+    fn has_printable_variants(&self, e: &Enum) -> bool {
+        e.variants.iter().any(|variant_id| {
+            if !self.selected_ids.contains(variant_id) {
+                return false;
+            }
+            if let Some(item) = self.krate.index.get(variant_id) {
+                // Check variant itself
+                if self.has_printable_docs(item) {
+                    return true;
+                }
+                // Check fields within the variant
+                if let ItemEnum::Variant(v) = &item.inner {
+                    let field_ids: Vec<Id> = match &v.kind {
+                        VariantKind::Plain => vec![],
+                        VariantKind::Tuple(fields) => {
+                            fields.iter().filter_map(|opt_id| *opt_id).collect()
+                        }
+                        VariantKind::Struct { fields, .. } => fields.clone(),
+                    };
+                    for field_id in field_ids {
+                        if self.selected_ids.contains(&field_id) {
+                            if let Some(f_item) = self.krate.index.get(&field_id) {
+                                if self.has_printable_docs(f_item) {
+                                    return true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            false
+        })
+    }
+
+    /// Prints the "Variants" section for an enum, only if needed.
+    /// Also marks variants *and their fields* without printable documentation as printed.
+    fn print_enum_variants(&mut self, _item: &Item, e: &Enum) {
+        let mut printable_variant_count = 0;
+        let mut printed_any_variant = false;
+
+        // First pass: Mark non-printable variants/fields printed and check if any are printable.
+        for variant_id in &e.variants {
+            if !self.selected_ids.contains(variant_id) {
+                continue; // Skip unselected variants
+            }
+
+            if let Some(item) = self.krate.index.get(variant_id) {
+                let variant_has_printable_docs = self.has_printable_docs(item);
+                let mut variant_has_printable_field = false;
+
+                // Check fields within the variant
+                if let ItemEnum::Variant(v) = &item.inner {
+                    let field_ids: Vec<Id> = match &v.kind {
+                        VariantKind::Plain => vec![],
+                        VariantKind::Tuple(fields) => {
+                            fields.iter().filter_map(|opt_id| *opt_id).collect()
+                        }
+                        VariantKind::Struct { fields, .. } => fields.clone(),
+                    };
+
+                    for field_id in field_ids {
+                        if self.selected_ids.contains(&field_id) {
+                            let field_has_printable_docs = self
+                                .krate
+                                .index
+                                .get(&field_id)
+                                .is_some_and(|f_item| self.has_printable_docs(f_item));
+                            if field_has_printable_docs {
+                                if !self.printed_ids.contains_key(&field_id) {
+                                    variant_has_printable_field = true;
+                                }
+                            } else {
+                                self.printed_ids.insert(field_id, self.get_header_prefix());
+                                // Mark non-printable field printed
+                            }
+                        } else {
+                            // Mark unselected field id printed if present
+                            self.printed_ids.insert(field_id, self.get_header_prefix());
+                        }
+                    }
+                }
+
+                if variant_has_printable_docs || variant_has_printable_field {
+                    // Check if the variant itself is already printed to avoid double counting
+                    if !self.printed_ids.contains_key(variant_id) {
+                        printable_variant_count += 1;
+                    }
+                } else {
+                    // Mark non-printable variant (with no printable fields) as printed immediately
+                    self.printed_ids
+                        .insert(*variant_id, self.get_header_prefix());
+                }
+            } else {
+                // If item doesn't exist in index but ID was present, mark it printed
+                self.printed_ids
+                    .insert(*variant_id, self.get_header_prefix());
+            }
+        }
+
+        if self.collapse_single_variant_enums && self.print_single_variant_note(e) {
+            return;
+        }
+        if self.field_layout == FieldLayout::Table && self.print_variants_table(e) {
+            return;
+        }
+
+        // Only print the "Variants" section if there's a printable variant/field or stripped variants exist
+        let has_stripped_variants =
+            e.has_stripped_variants || self.has_skipped_hidden_field(&e.variants);
+        if printable_variant_count == 0 && !has_stripped_variants {
+            return;
+        }
+
+        let variants_header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} {}\n", // Add newline after header
+            self.header_marker(variants_header_level),
+            header_prefix,
+            self.section_title("Variants", printable_variant_count)
+        )
+        .unwrap();
+
+        // Push a new level for the variant items themselves
+        self.push_level();
+        // Second pass: Print details for variants that have printable docs or contain printable fields
+        for variant_id in &e.variants {
+            if self.print_variant_details(variant_id) {
+                printed_any_variant = true;
+            }
+        }
+
+        if has_stripped_variants {
+            // Add newline before stripped message only if variants were printed
+            if printed_any_variant {
+                writeln!(self.output).unwrap();
+            }
+            writeln!(self.output, "_[Private variants hidden]_").unwrap();
+        }
+        self.pop_level(); // Pop the variant item level
+        self.post_increment_current_level();
+    }
+
+    /// Prints the only variant of a single-variant enum as a compact note, followed by
+    /// the variant's docs, instead of a full "Variants" section.
+    ///
+    /// Returns `false` without printing anything if the enum has hidden variants or the
+    /// variant has documented fields, which need the full layout.
+    fn print_single_variant_note(&mut self, e: &Enum) -> bool {
+        let [variant_id] = e.variants.as_slice() else {
+            return false;
+        };
+        if e.has_stripped_variants
+            || self.has_skipped_hidden_field(&e.variants)
+            || !self.selected_ids.contains(variant_id)
+        {
+            return false;
+        }
+        let Some(item) = self.krate.index.get(variant_id) else {
+            return false;
+        };
+        let ItemEnum::Variant(variant) = &item.inner else {
+            return false;
+        };
+        let field_ids: Vec<Id> = match &variant.kind {
+            VariantKind::Plain => vec![],
+            VariantKind::Tuple(fields) => fields.iter().filter_map(|id| *id).collect(),
+            VariantKind::Struct { fields, .. } => fields.clone(),
+        };
+        let has_documented_field = field_ids
+            .iter()
+            .filter_map(|id| self.krate.index.get(id))
+            .any(has_docs);
+        if has_documented_field {
+            return false;
+        }
+
+        let signature = format_variant_signature(item, variant, self);
+        writeln!(self.output, "_Single variant: `{}`_\n", signature).unwrap();
+        self.print_docs(item);
+        let prefix = self.get_header_prefix();
+        self.printed_ids.insert(*variant_id, prefix.clone());
+        for field_id in field_ids {
+            self.printed_ids.insert(field_id, prefix.clone());
+        }
+        true
+    }
+
+    /// Prints the variants of an enum as a `| Variant | Description |` table.
+    ///
+    /// C-like enums get a `Discriminant` column, with implicit discriminants computed
+    /// from the preceding variant. Tuple and struct variants show their signature, and
+    /// the first paragraph of each documented field follows the variant's description
+    /// on its own line, e.g. `` Moves.<br>`x`: Horizontal offset. ``
+    ///
+    /// Returns `false` without printing anything if no variant is selected, or if the
+    /// docs of a variant or field have more than one paragraph, which a table cell
+    /// would truncate.
+    fn print_variants_table(&mut self, e: &Enum) -> bool {
+        let variants: Vec<&Item> = e
+            .variants
+            .iter()
+            .filter(|id| self.selected_ids.contains(id))
+            .filter_map(|id| self.krate.index.get(id))
+            .collect();
+        if variants.is_empty() {
+            return false;
+        }
+        let has_multi_paragraph_docs = variants
+            .iter()
+            .flat_map(|item| {
+                let field_ids = match &item.inner {
+                    ItemEnum::Variant(Variant {
+                        kind: VariantKind::Tuple(fields),
+                        ..
+                    }) => fields.iter().filter_map(|id| *id).collect(),
+                    ItemEnum::Variant(Variant {
+                        kind: VariantKind::Struct { fields, .. },
+                        ..
+                    }) => fields.clone(),
+                    _ => vec![],
+                };
+                std::iter::once(*item).chain(
+                    field_ids
+                        .into_iter()
+                        .filter(|id| self.selected_ids.contains(id))
+                        .filter_map(|id| self.krate.index.get(&id)),
+                )
+            })
+            .filter_map(|item| self.doc_text(item))
+            .any(|docs| docs.trim().contains("\n\n"));
+        if has_multi_paragraph_docs {
+            return false;
+        }
+        let all_plain = variants.iter().all(|item| {
+            matches!(&item.inner, ItemEnum::Variant(v) if matches!(v.kind, VariantKind::Plain))
+        });
+
+        let variants_header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} Variants\n",
+            self.header_marker(variants_header_level),
+            header_prefix
+        )
+        .unwrap();
+        if all_plain {
+            writeln!(self.output, "| Variant | Discriminant | Description |").unwrap();
+            writeln!(self.output, "|---|---|---|").unwrap();
+        } else {
+            writeln!(self.output, "| Variant | Description |").unwrap();
+            writeln!(self.output, "|---|---|").unwrap();
+        }
+
+        // Implicit discriminants continue from the previous variant's value
+        let mut next_value: Option<i128> = Some(0);
+        for item in variants {
+            let ItemEnum::Variant(v) = &item.inner else {
+                continue;
+            };
+            let discriminant = match &v.discriminant {
+                Some(discr) => {
+                    next_value = discr.value.parse::<i128>().ok().map(|n| n + 1);
+                    format!("`{}`", format_discriminant_expr(discr))
+                }
+                None => match next_value {
+                    Some(value) => {
+                        next_value = Some(value + 1);
+                        format!("`{}`", value)
+                    }
+                    None => String::new(),
+                },
+            };
+            let mut description = self.table_cell_summary(item);
+            if let Some(cfg_note) = format_cfg_note(&item.attrs) {
+                if !description.is_empty() {
+                    description.push_str("<br>");
+                }
+                description.push_str(&cfg_note.replace('|', "\\|"));
+            }
+            let field_ids: Vec<Id> = match &v.kind {
+                VariantKind::Plain => vec![],
+                VariantKind::Tuple(fields) => fields.iter().filter_map(|id| *id).collect(),
+                VariantKind::Struct { fields, .. } => fields.clone(),
+            };
+            for (index, field_id) in field_ids.iter().enumerate() {
+                if self.selected_ids.contains(field_id) {
+                    if let Some(field) = self
+                        .krate
+                        .index
+                        .get(field_id)
+                        .filter(|f| self.has_printable_docs(f))
+                    {
+                        let field_name = field.name.clone().unwrap_or_else(|| index.to_string());
+                        if !description.is_empty() {
+                            description.push_str("<br>");
+                        }
+                        write!(
+                            description,
+                            "`{}`: {}",
+                            field_name,
+                            self.table_cell_summary(field)
+                        )
+                        .unwrap();
+                    }
+                }
+                self.printed_ids.insert(*field_id, header_prefix.clone());
+            }
+            if all_plain {
+                writeln!(
+                    self.output,
+                    "| `{}` | {} | {} |",
+                    item.name.as_deref().unwrap_or_default(),
+                    discriminant,
+                    description
+                )
+                .unwrap();
+            } else {
+                writeln!(
+                    self.output,
+                    "| `{}` | {} |",
+                    format_variant_signature(item, v, self).replace('|', "\\|"),
+                    description
+                )
+                .unwrap();
+            }
+            self.printed_ids.insert(item.id, header_prefix.clone());
+        }
+        writeln!(self.output).unwrap();
+
+        if e.has_stripped_variants || self.has_skipped_hidden_field(&e.variants) {
+            writeln!(self.output, "_[Private variants hidden]_\n").unwrap();
+        }
+        self.post_increment_current_level();
+        true
+    }
+
+    /// Prints the [`format_cfg_note`] of `item`, if it is `cfg`-gated.
+    fn print_cfg_note(&mut self, item: &Item) {
+        if let Some(cfg_note) = format_cfg_note(&item.attrs) {
+            writeln!(self.output, "{}\n", cfg_note).unwrap();
+        }
+    }
+
+    /// Returns the first paragraph of `item`'s docs on a single line, for a Markdown
+    /// table cell.
+    fn table_cell_summary(&self, item: &Item) -> String {
+        if self.template_mode && item.docs.is_some() {
+            return self.get_template_marker();
+        }
+        self.doc_text(item)
+            .as_deref()
+            .and_then(|docs| docs.trim().split("\n\n").next())
+            .map(|summary| {
+                summary
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .replace('|', "\\|")
+            })
+            .unwrap_or_default()
+    }
+
+    /// Prints the details for a single enum variant. Includes variant docs and docs for its fields if present.
+    /// Returns true if the variant was printed (because it or its fields had printable docs), false otherwise.
+    fn print_variant_details(&mut self, variant_id: &Id) -> bool {
+        if !self.selected_ids.contains(variant_id) {
+            // If already printed, do not print full details again.
+            // Cross-referencing for variants re-exported in other modules is not typical.
+            // If a variant is listed in a module, it's usually because its enum is listed.
+            if self.printed_ids.contains_key(variant_id) {
+                return false;
+            }
+        } else if self.printed_ids.contains_key(variant_id) {
+            // Already printed, skip
+            return false;
+        }
+
+        if let Some(item) = self.krate.index.get(variant_id) {
+            if let ItemEnum::Variant(variant_data) = &item.inner {
+                let variant_has_printable_docs = self.has_printable_docs(item);
+                let mut printable_fields = Vec::new();
+                let mut printed_any_field = false;
+
+                // Determine fields and check their printable docs
+                let (field_ids, stripped) = match &variant_data.kind {
+                    VariantKind::Plain => (vec![], false),
+                    VariantKind::Tuple(fields) => {
+                        (fields.iter().filter_map(|opt_id| *opt_id).collect(), false)
+                    }
+                    VariantKind::Struct {
+                        fields,
+                        has_stripped_fields: s,
+                    } => (fields.clone(), *s || self.has_skipped_hidden_field(fields)),
+                };
+
+                for field_id in &field_ids {
+                    if self.selected_ids.contains(field_id) {
+                        let field_has_printable_docs = self
+                            .krate
+                            .index
+                            .get(field_id)
+                            .is_some_and(|f_item| self.has_printable_docs(f_item));
+                        if field_has_printable_docs && !self.printed_ids.contains_key(field_id) {
+                            printable_fields.push(*field_id);
+                        } else {
+                            // Mark unselected or non-printable field printed
+                            self.printed_ids.insert(*field_id, self.get_header_prefix());
+                        }
+                    } else {
+                        // Mark unselected field printed
+                        self.printed_ids.insert(*field_id, self.get_header_prefix());
+                    }
+                }
+
+                // Only print the variant if it has printable docs OR it has printable fields to print
+                if !variant_has_printable_docs && printable_fields.is_empty() {
+                    // Mark variant as printed if skipped
+                    self.printed_ids
+                        .insert(*variant_id, self.get_header_prefix());
+                    return false;
+                }
+
+                let header_prefix = self.get_header_prefix();
+                // Mark as printed *before* printing details
+                self.printed_ids.insert(*variant_id, header_prefix.clone());
+
+                let signature = format_variant_signature(item, variant_data, self);
+                let variant_header_level = self.get_current_header_level();
+                let variant_name = item.name.clone().unwrap_or_default();
+                self.write_scoped_anchor(variant_id, &variant_name);
+                self.anchor_scope.push(variant_name);
+
+                // Header: e.g., ##### 1.1.1.1: `VariantSignature`
+                writeln!(
+                    self.output,
+                    "{} {} `{}`\n", // Add newline after header
+                    self.header_marker(variant_header_level),
+                    header_prefix,
+                    signature
+                )
+                .unwrap();
+                self.push_level();
+                self.print_cfg_note(item);
+
+                // Print Variant Docs (using helper)
+                self.print_docs(item);
+
+                // Print documented fields (if any)
+                if !printable_fields.is_empty() || stripped {
+                    let field_section_level = self.get_current_header_level();
+                    let fields_header_prefix = self.get_header_prefix();
+                    writeln!(
+                        self.output,
+                        "{} {} Fields\n", // Add newline after header
+                        self.header_marker(field_section_level),
+                        fields_header_prefix
+                    )
+                    .unwrap();
+                    self.push_level();
+
+                    for field_id in printable_fields {
+                        if self.print_variant_field_details(&field_id) {
+                            printed_any_field = true;
+                        }
+                    }
+
+                    if stripped {
+                        if printed_any_field {
+                            writeln!(self.output).unwrap(); // Add newline before stripped message
+                        }
+                        writeln!(self.output, "_[Private fields hidden]_").unwrap();
+                    }
+                    self.pop_level();
+                }
+
+                self.pop_level();
+                self.anchor_scope.pop();
+                self.post_increment_current_level();
+
+                return true; // Variant (or its fields) was printed
+            }
+        }
+        // Mark as printed even if item lookup failed
+        self.printed_ids
+            .insert(*variant_id, self.get_header_prefix());
+        false
+    }
+
+    /// Prints a note listing which methods of a dyn-compatible trait can be called on a
+    /// trait object and which are excluded from it by a `Self: Sized` bound.
+    ///
+    /// Nothing is printed unless some selected method requires `Self: Sized`.
+    fn print_trait_object_note(&mut self, trait_item: &Item, t: &Trait) {
+        if !t.is_dyn_compatible {
+            return;
+        }
+        let mut dyn_callable = Vec::new();
+        let mut sized_only = Vec::new();
+        for item in t
+            .items
+            .iter()
+            .filter(|id| self.selected_ids.contains(id))
+            .filter_map(|id| self.krate.index.get(id))
+        {
+            if let (ItemEnum::Function(f), Some(name)) = (&item.inner, &item.name) {
+                if requires_self_sized(f, self) {
+                    sized_only.push(format!("`{}`", name));
+                } else {
+                    dyn_callable.push(format!("`{}`", name));
+                }
+            }
+        }
+        if sized_only.is_empty() {
+            return;
         }
+        dyn_callable.sort();
+        sized_only.sort();
 
-        let type_count_threshold = (all_type_ids_with_impls.len() as f32 * 0.5).ceil() as usize;
-        debug!(
-            "Crate common trait threshold (types implementing): {} (out of {} types)",
-            type_count_threshold,
-            all_type_ids_with_impls.len()
-        );
+        let trait_name = trait_item.name.as_deref().unwrap_or("_");
+        let mut note = String::new();
+        if !dyn_callable.is_empty() {
+            write!(
+                note,
+                "Callable on `dyn {}`: {}. ",
+                trait_name,
+                dyn_callable.join(", ")
+            )
+            .unwrap();
+        }
+        write!(
+            note,
+            "Requires `Self: Sized` (not callable on `dyn {}`): {}.",
+            trait_name,
+            sized_only.join(", ")
+        )
+        .unwrap();
+        writeln!(self.output, "_{}_\n", note).unwrap();
+    }
 
-        for (trait_id, format_map) in trait_format_counts {
-            let total_implementations_for_trait = format_map.values().sum::<usize>();
+    /// Returns the note for an associated function without a `self` receiver (e.g.
+    /// `fn new() -> Self`) of a dyn-compatible trait, which can't be called through a
+    /// trait object. Traits that aren't dyn-compatible have no trait objects to note.
+    fn trait_object_availability_note(&self, t: &Trait, id: &Id) -> Option<&'static str> {
+        if !t.is_dyn_compatible {
+            return None;
+        }
+        let item = self.krate.index.get(id)?;
+        let ItemEnum::Function(f) = &item.inner else {
+            return None;
+        };
+        let has_receiver = f.sig.inputs.first().is_some_and(|(name, _)| name == "self");
+        (!has_receiver).then_some("_(not available on trait objects)_")
+    }
 
-            if total_implementations_for_trait < type_count_threshold {
-                trace!(
-                    "Trait ID {:?} not common enough ({} implementations, need {})",
-                    trait_id,
-                    total_implementations_for_trait,
-                    type_count_threshold
-                );
+    /// Prints the "Associated Items" section for a trait, categorized.
+    fn print_trait_associated_items(&mut self, _trait_item: &Item, t: &Trait) {
+        let mut required_types = Vec::new();
+        let mut required_methods = Vec::new();
+        let mut provided_methods = Vec::new();
+        let mut has_printable_assoc_item = false;
+
+        // Filter and categorize selected associated items.
+        for item_id in &t.items {
+            if !self.selected_ids.contains(item_id) {
                 continue;
             }
+            // Mark the item as printed now, regardless of docs, to prevent it from going to "Other"
+            // Only mark if not already printed elsewhere with a different prefix.
+            // This is tricky because an assoc item's "primary" print location is under its trait.
+            if !self.printed_ids.contains_key(item_id) {
+                self.printed_ids.insert(*item_id, self.get_header_prefix());
+            }
 
-            // Check for mixed positive/negative implementations
-            let mut has_positive = false;
-            let mut has_negative = false;
-            for f_impl in format_map.keys() {
-                if f_impl.is_negative {
-                    has_negative = true;
-                } else {
-                    has_positive = true;
+            if let Some(assoc_item) = self.krate.index.get(item_id) {
+                let item_has_printable_docs = self.has_printable_docs(assoc_item);
+                if item_has_printable_docs {
+                    has_printable_assoc_item = true;
+                }
+
+                match &assoc_item.inner {
+                    ItemEnum::AssocType { .. } => {
+                        required_types.push((*item_id, item_has_printable_docs));
+                    }
+                    ItemEnum::Function(f) => {
+                        if !f.has_body {
+                            required_methods.push((*item_id, item_has_printable_docs));
+                        } else {
+                            provided_methods.push((*item_id, item_has_printable_docs));
+                        }
+                    }
+                    ItemEnum::AssocConst { .. } => {
+                        // For now, treat associated consts similarly to required types or methods.
+                        // Could be a separate category if needed.
+                        // Let's put them with required types for now as they don't have 'body'.
+                        required_types.push((*item_id, item_has_printable_docs));
+                    }
+                    _ => {} // Ignore others
                 }
             }
-            if has_positive && has_negative {
-                warn!(
-                    "Trait ID {:?} has mixed positive and negative implementations, cannot be common.",
-                    trait_id
-                );
-                continue;
+        }
+
+        // If no selected associated item has printable documentation, skip printing the entire section
+        if !has_printable_assoc_item {
+            return;
+        }
+
+        // Sort items within each category
+        self.sort_items_by(&mut required_types, |(id, _)| *id);
+        self.sort_items_by(&mut required_methods, |(id, _)| *id);
+        self.sort_items_by(&mut provided_methods, |(id, _)| *id);
+
+        let documented_count = required_types
+            .iter()
+            .filter(|(_, has_docs)| *has_docs)
+            .count();
+        if documented_count > 0 {
+            let sub_level = self.get_current_header_level();
+            let sub_prefix = self.get_header_prefix();
+            writeln!(
+                self.output,
+                "{} {} {}\n",
+                self.header_marker(sub_level),
+                sub_prefix,
+                self.section_title("Required Associated Types", documented_count)
+            )
+            .unwrap();
+            self.push_level();
+            for (id, has_docs) in required_types {
+                if has_docs {
+                    self.print_associated_item_summary(&id);
+                }
             }
+            self.pop_level();
+            self.post_increment_current_level();
+        }
 
-            if format_map.len() == 1 {
-                // Only one format for this trait path
-                if let Some(formatted_impl) = format_map.keys().next() {
-                    common_traits_set.insert(formatted_impl.clone());
-                    debug!(
-                        "Identified crate-common trait (single format): {:?} for trait ID {:?}",
-                        formatted_impl.formatted_markdown_list_entry, trait_id
-                    );
+        let documented_count = required_methods
+            .iter()
+            .filter(|(_, has_docs)| *has_docs)
+            .count();
+        if documented_count > 0 {
+            let sub_level = self.get_current_header_level();
+            let sub_prefix = self.get_header_prefix();
+            writeln!(
+                self.output,
+                "{} {} {}\n",
+                self.header_marker(sub_level),
+                sub_prefix,
+                self.section_title("Required Methods", documented_count)
+            )
+            .unwrap();
+            self.push_level();
+            for (id, has_docs) in required_methods {
+                if has_docs {
+                    let note = self.trait_object_availability_note(t, &id);
+                    self.print_associated_item_summary_with_note(&id, note);
                 }
-            } else {
-                // Multiple formats, check if "Simple" is predominant
-                let simple_format_count = format_map
-                    .iter()
-                    .find(|(f_impl, _)| f_impl.category == TraitImplCategory::Simple)
-                    .map_or(0, |(_, count)| *count);
+            }
+            self.pop_level();
+            self.post_increment_current_level();
+        }
 
-                if simple_format_count * 2 > total_implementations_for_trait {
-                    // Simple format is implemented by >50% of *this trait's* implementors
-                    if let Some(simple_impl) = format_map
-                        .keys()
-                        .find(|f_impl| f_impl.category == TraitImplCategory::Simple)
-                    {
-                        common_traits_set.insert(simple_impl.clone());
-                        debug!(
-                            "Identified crate-common trait (simple format predominant): {:?} for trait ID {:?}",
-                            simple_impl.formatted_markdown_list_entry, trait_id
-                        );
+        let documented_count = provided_methods
+            .iter()
+            .filter(|(_, has_docs)| *has_docs)
+            .count();
+        if documented_count > 0 {
+            let sub_level = self.get_current_header_level();
+            let sub_prefix = self.get_header_prefix();
+            writeln!(
+                self.output,
+                "{} {} {}\n",
+                self.header_marker(sub_level),
+                sub_prefix,
+                self.section_title("Provided Methods", documented_count)
+            )
+            .unwrap();
+            self.push_level();
+            for (id, has_docs) in provided_methods {
+                if has_docs {
+                    let note = self.trait_object_availability_note(t, &id);
+                    self.print_associated_item_summary_with_note(&id, note);
+                }
+            }
+            self.pop_level();
+            self.post_increment_current_level();
+        }
+    }
+
+    /// Generates the formatted summary string for an associated item (for use within impl blocks or trait defs).
+    /// Does NOT include the markdown header. Includes docs with adjusted headers, respecting template mode.
+    fn generate_associated_item_summary(&mut self, assoc_item_id: &Id) -> Option<String> {
+        if !self.selected_ids.contains(assoc_item_id) {
+            return None;
+        }
+        if let Some(item) = self.krate.index.get(assoc_item_id) {
+            let mut summary = String::new();
+            // Get level AFTER incrementing for the item header
+            // let assoc_item_header_level = self.get_current_header_level(); // Level not needed here
+
+            // Add code block for associated functions if they have attrs/where clauses
+            if let ItemEnum::Function(f) = &item.inner {
+                let has_attrs = f.header.is_const
+                    || f.header.is_async
+                    || f.header.is_unsafe
+                    || !matches!(f.header.abi, Abi::Rust)
+                    || item.attrs.iter().any(|attr| is_declaration_attribute(attr));
+                let has_where = !arrange_param_bounds(&f.generics, self)
+                    .where_predicates
+                    .is_empty();
+                let default_body = self.provided_method_body(assoc_item_id, item, f);
+                if has_attrs || has_where || default_body.is_some() {
+                    let mut code = generate_function_code_block(item, f, self);
+                    if let Some(body) = default_body {
+                        if let Some(stripped) = code.strip_suffix(" { ... }") {
+                            code = format!("{} {{ {} }}", stripped, body);
+                        }
+                    }
+                    let code = self.finish_code_block(item, code);
+                    writeln!(summary, "```{}\n{}\n```\n", self.code_fence_lang, code).unwrap();
+                }
+            }
+
+            // Print Documentation (using helper)
+            self.write_docs(item, &mut summary);
+
+            // Potentially add default values/bounds for assoc const/type here
+            match &item.inner {
+                // Use correct fields { type_, value }
+                // The type is part of the item's declaration header
+                ItemEnum::AssocConst {
+                    value: Some(val), ..
+                } => {
+                    writeln!(summary, "_Default: `{}`_\n", val).unwrap(); // Add newline
+                }
+                ItemEnum::AssocType { bounds, type_, .. } => {
+                    // Use renamed field type_
+                    if !bounds.is_empty() {
+                        let bounds_str = bounds
+                            .iter()
+                            .map(|b| format_generic_bound(b, self))
+                            .collect::<Vec<_>>()
+                            .join(" + ");
+                        writeln!(summary, "_Bounds: `{}`_", bounds_str).unwrap();
+                    }
+                    if let Some(ty) = type_ {
+                        writeln!(summary, "_Default: `{}`_\n", format_type(ty, self)).unwrap();
+                        // Add newline
                     }
-                } else {
-                    trace!(
-                        "Trait ID {:?} has multiple formats, but Simple is not predominant ({} of {}).",
-                        trait_id, simple_format_count, total_implementations_for_trait
-                    );
                 }
+                _ => {}
             }
+            Some(summary)
+        } else {
+            None
         }
-        (common_traits_set, all_type_ids_with_impls)
     }
 
-    /// Calculates common traits for a specific module.
-    fn calculate_module_common_traits(&self, module_id: &Id) -> HashSet<FormattedTraitImpl> {
-        if self.no_common_traits {
-            return HashSet::new();
+    /// Applies final formatting to a generated declaration code block.
+    ///
+    /// With the `rustfmt` feature and [`rustfmt_signatures`](Printer::rustfmt_signatures)
+    /// enabled, the block is reformatted by `rustfmt`. The block is returned unchanged
+    /// otherwise, or if `rustfmt` is unavailable or rejects it.
+    fn finish_code_block(&self, item: &Item, code: String) -> String {
+        let code = self.restore_elided_const_exprs(item, code);
+        #[cfg(feature = "rustfmt")]
+        if self.rustfmt_signatures {
+            if let Some(formatted) =
+                rustfmt_code_block(&code, self.manifest_data.edition.as_deref())
+            {
+                return formatted;
+            }
         }
+        code
+    }
 
-        let mut module_common_traits = self.crate_common_traits.clone();
-        let mut module_types_considered = HashSet::new();
+    /// Replaces array lengths and const generic arguments that rustdoc elided as
+    /// `{ _ }` (e.g. in `where [(); N / 2]: Sized` or `Buf<{ N + 1 }>`) with the
+    /// expressions read from the item's source.
+    ///
+    /// Requires a source root. Placeholders of either kind are left as they are if the
+    /// elided expressions of that kind in the source don't line up one-to-one with them.
+    fn restore_elided_const_exprs(&self, item: &Item, code: String) -> String {
+        self.restore_elided_const_exprs_in(item, code, false)
+    }
 
-        if let Some(resolved_mod) = self.resolved_modules.get(module_id) {
-            for item_id_in_mod in &resolved_mod.items {
-                if let Some(item) = self.krate.index.get(item_id_in_mod) {
-                    if matches!(
-                        item.inner,
-                        ItemEnum::Struct(_)
-                            | ItemEnum::Enum(_)
-                            | ItemEnum::Union(_)
-                            | ItemEnum::Primitive(_)
-                    ) && self.selected_ids.contains(item_id_in_mod)
-                    {
-                        let has_impls = self.krate.index.values().any(|idx_item| {
-                            if let ItemEnum::Impl(imp) = &idx_item.inner {
-                                if let Some(for_id) = get_type_id(&imp.for_) {
-                                    return for_id == *item_id_in_mod;
-                                }
-                            }
-                            false
-                        });
-                        if has_impls {
-                            module_types_considered.insert(*item_id_in_mod);
-                        }
-                    }
-                }
+    /// Like [`restore_elided_const_exprs`](Printer::restore_elided_const_exprs), but for
+    /// item headers, which leave out the trailing `where` clause and may therefore
+    /// contain only the leading elided lengths of the source.
+    fn restore_elided_header_exprs(&self, item: &Item, header: String) -> String {
+        self.restore_elided_const_exprs_in(item, header, true)
+    }
+
+    /// Replaces the `_` rustdoc emits for the non-trivial initializer of a `const` or
+    /// `static` item with the expression read from its source, if it is short.
+    fn restore_elided_initializer(&self, item: &Item, expr: &str) -> String {
+        if expr != "_" {
+            return expr.to_string();
+        }
+        item.span
+            .as_ref()
+            .and_then(|span| self.read_span_source(span))
+            .and_then(|source| extract_short_initializer(&source))
+            .unwrap_or_else(|| expr.to_string())
+    }
+
+    fn restore_elided_const_exprs_in(&self, item: &Item, code: String, prefix: bool) -> String {
+        let placeholders = code.matches(ELIDED_CONST_EXPR).count();
+        if placeholders == 0 {
+            return code;
+        }
+        let Some(source) = item
+            .span
+            .as_ref()
+            .and_then(|span| self.read_span_source(span))
+        else {
+            return code;
+        };
+        // Array lengths in a function body are not part of the signature
+        let signature = match &item.inner {
+            ItemEnum::Function(f) if f.has_body => strip_fn_body(&source),
+            _ => &source,
+        };
+        let exprs = elided_const_exprs(signature);
+
+        // Array length placeholders follow the `;`, generic argument ones don't
+        let parts: Vec<&str> = code.split(ELIDED_CONST_EXPR).collect();
+        let is_array_length = |part: &str| part.trim_end().ends_with(';');
+        let array_lengths = parts[..placeholders]
+            .iter()
+            .filter(|part| is_array_length(part))
+            .count();
+        let generic_args = placeholders - array_lengths;
+        // Rustdoc evaluates some expressions instead of eliding them, so only restore a
+        // kind whose placeholders line up with the source
+        let lines_up = |found: usize, elided: usize| {
+            if prefix {
+                found >= elided
+            } else {
+                found == elided
+            }
+        };
+        let mut array_lengths =
+            lines_up(exprs.array_lengths.len(), array_lengths).then(|| exprs.array_lengths.iter());
+        let mut generic_args =
+            lines_up(exprs.generic_args.len(), generic_args).then(|| exprs.generic_args.iter());
+
+        let mut restored = String::new();
+        for (idx, part) in parts.iter().enumerate() {
+            restored.push_str(part);
+            if idx == placeholders {
+                break;
+            }
+            let exprs = if is_array_length(part) {
+                &mut array_lengths
+            } else {
+                &mut generic_args
+            };
+            match exprs.as_mut().and_then(|exprs| exprs.next()) {
+                Some(expr) => restored.push_str(expr),
+                None => restored.push_str(ELIDED_CONST_EXPR),
             }
         }
+        restored
+    }
 
-        let module_types_with_impls_count = module_types_considered.len();
-        if module_types_with_impls_count <= 1 {
-            return module_common_traits;
+    /// Returns the short default body of a provided trait method, read from source.
+    ///
+    /// Only applies when [`show_default_bodies`](Printer::show_default_bodies) is enabled,
+    /// a source root is configured, and `f` is a provided method of a trait.
+    fn provided_method_body(&self, id: &Id, item: &Item, f: &Function) -> Option<String> {
+        if !self.show_default_bodies || !f.has_body {
+            return None;
+        }
+        let trait_item_ids = self.trait_item_ids.get_or_init(|| {
+            self.krate
+                .index
+                .values()
+                .filter_map(|item| match &item.inner {
+                    ItemEnum::Trait(t) => Some(&t.items),
+                    _ => None,
+                })
+                .flatten()
+                .copied()
+                .collect()
+        });
+        if !trait_item_ids.contains(id) {
+            return None;
         }
+        let source = self.read_span_source(item.span.as_ref()?)?;
+        extract_short_fn_body(&source)
+    }
 
-        let mut trait_format_counts: HashMap<Id, HashMap<FormattedTraitImpl, usize>> =
-            HashMap::new();
+    /// Reads the source text covered by `span` from [`Printer::source_root`], caching
+    /// the files read.
+    fn read_span_source(&self, span: &Span) -> Option<String> {
+        let path = self.source_root.as_ref()?.join(&span.filename);
+        let mut source_files = self.source_files.borrow_mut();
+        let content = source_files
+            .entry(path)
+            .or_insert_with_key(|path| fs::read_to_string(path).ok());
+        span_text(content.as_deref()?, span)
+    }
 
-        for item_id_in_mod in &module_types_considered {
-            for krate_item in self.krate.index.values() {
-                if let ItemEnum::Impl(imp) = &krate_item.inner {
-                    if let Some(for_id) = get_type_id(&imp.for_) {
-                        if for_id == *item_id_in_mod {
-                            if let Some(trait_path) = &imp.trait_ {
-                                let norm_impl = FormattedTraitImpl::from_impl(
-                                    imp, None, trait_path, self.krate, self,
-                                );
-                                *trait_format_counts
-                                    .entry(trait_path.id)
-                                    .or_default()
-                                    .entry(norm_impl)
-                                    .or_insert(0) += 1;
-                            }
-                        }
-                    }
+    /// Prints the header and summary for a single associated item (const, type, function).
+    fn print_associated_item_summary(&mut self, assoc_item_id: &Id) {
+        self.print_associated_item_summary_with_note(assoc_item_id, None);
+    }
+
+    /// Like [`Printer::print_associated_item_summary`], with `note` printed between
+    /// the header and the docs.
+    fn print_associated_item_summary_with_note(&mut self, assoc_item_id: &Id, note: Option<&str>) {
+        if let Some(item) = self.krate.index.get(assoc_item_id) {
+            // Generate summary first (handles template mode internally)
+            if let Some(summary) = self.generate_associated_item_summary(assoc_item_id) {
+                let declaration = generate_item_declaration(item, self, &self.current_module_path);
+                let declaration = self.restore_elided_header_exprs(item, declaration);
+                let assoc_item_header_level = self.get_current_header_level();
+                let header_prefix = self.get_header_prefix();
+                if let Some(name) = &item.name {
+                    self.write_scoped_anchor(assoc_item_id, name);
+                }
+                // Print Header (e.g. ##### 1.1.1.1: `declaration`)
+                writeln!(
+                    self.output,
+                    "{} {} `{}`\n", // Add newline after header
+                    self.header_marker(assoc_item_header_level),
+                    header_prefix,
+                    declaration
+                )
+                .unwrap();
+                if let Some(deprecation) = &item.deprecation {
+                    writeln!(self.output, "{}\n", format_deprecation(deprecation)).unwrap();
+                }
+                if let Some(rust_version_note) = self.rust_version_note(item) {
+                    writeln!(self.output, "{}\n", rust_version_note).unwrap();
+                }
+                self.print_cfg_note(item);
+                if let Some(attributes_note) = format_metadata_attributes_note(&item.attrs) {
+                    writeln!(self.output, "{}\n", attributes_note).unwrap();
+                }
+                if let Some(note) = note {
+                    writeln!(self.output, "{}\n", note).unwrap();
+                }
+                // Print the generated summary
+                if !summary.trim().is_empty() {
+                    writeln!(self.output, "{}", summary.trim()).unwrap();
+                }
+                if let Some(see_also) = self.format_see_also(item) {
+                    writeln!(self.output, "\n{}", see_also).unwrap();
                 }
+                writeln!(self.output).unwrap(); // Ensure a blank line afterwards
+
+                self.post_increment_current_level();
             }
+            // If generate_associated_item_summary returns None, the item wasn't selected,
+            // so we don't print anything, and the level increment effectively skips it.
         }
+    }
 
-        let type_count_threshold = (module_types_with_impls_count as f32 * 0.5).ceil() as usize;
-        debug!(
-            "Module {:?} common trait threshold (types implementing): {} (out of {} types in module)",
-            module_id, type_count_threshold, module_types_with_impls_count
-        );
+    /// Formats a "See also" line linking a method to its sibling methods on the same
+    /// type whose names extend it with a prefix or suffix (e.g. `get` and `get_mut`).
+    ///
+    /// Links point at the method anchors when [`anchors`](Printer::anchors) is enabled.
+    fn format_see_also(&self, item: &Item) -> Option<String> {
+        if !self.see_also_methods || !matches!(item.inner, ItemEnum::Function(_)) {
+            return None;
+        }
+        let name = item.name.as_deref()?;
+        let is_related =
+            |a: &str, b: &str| b.starts_with(&format!("{}_", a)) || b.ends_with(&format!("_{}", a));
+        let mut related: Vec<(&str, &Id)> = self
+            .sibling_methods
+            .iter()
+            .filter_map(|id| Some((self.krate.index.get(id)?.name.as_deref()?, id)))
+            .filter(|(other, _)| {
+                *other != name && (is_related(name, other) || is_related(other, name))
+            })
+            .collect();
+        if related.is_empty() {
+            return None;
+        }
+        related.sort_by_key(|(other, id)| (*other, id.0));
+        related.dedup_by_key(|(other, _)| *other);
+        let links: Vec<String> = related
+            .iter()
+            .map(|(other, id)| {
+                self.anchor_link(id, other)
+                    .unwrap_or_else(|| format!("`{}`", other))
+            })
+            .collect();
+        Some(format!("_See also: {}_", links.join(", ")))
+    }
 
-        for (trait_id, format_map) in trait_format_counts {
-            let total_implementations_for_trait = format_map.values().sum::<usize>();
+    /// Helper to categorize and format a list of FormattedTraitImpls for display.
+    fn format_trait_list(&mut self, traits_to_format: &[FormattedTraitImpl]) -> String {
+        if traits_to_format.is_empty() {
+            return String::new();
+        }
 
-            if total_implementations_for_trait < type_count_threshold {
-                trace!(
-                    "Module {:?} trait ID {:?} not common enough ({} implementations, need {})",
-                    module_id,
-                    trait_id,
-                    total_implementations_for_trait,
-                    type_count_threshold
-                );
-                continue;
-            }
+        let mut output = String::new();
+        let mut simple_impls = Vec::new();
+        let mut generic_or_complex_impls = Vec::new();
+        let mut auto_traits = Vec::new();
+        let mut blanket_impls = Vec::new();
 
-            let mut has_positive = false;
-            let mut has_negative = false;
-            for f_impl in format_map.keys() {
-                if f_impl.is_negative {
-                    has_negative = true;
-                } else {
-                    has_positive = true;
-                }
+        for norm_trait in traits_to_format {
+            match norm_trait.category {
+                TraitImplCategory::Simple => simple_impls.push(norm_trait),
+                TraitImplCategory::GenericOrComplex => generic_or_complex_impls.push(norm_trait),
+                TraitImplCategory::Auto => auto_traits.push(norm_trait),
+                TraitImplCategory::Blanket => blanket_impls.push(norm_trait),
             }
-            if has_positive && has_negative {
-                warn!(
-                    "Module {:?} trait ID {:?} has mixed positive and negative implementations, cannot be common.",
-                    module_id, trait_id
-                );
-                continue;
+        }
+
+        // Sort each category by the pre-formatted list entry string, optionally
+        // ranking by usefulness first
+        let rank = |t: &FormattedTraitImpl| {
+            if !self.sort_trait_impls_by_usefulness {
+                return 0;
             }
+            self.krate
+                .paths
+                .get(&t.trait_id)
+                .map_or(2, |summary| trait_usefulness_rank(&summary.path))
+        };
+        for impls in [
+            &mut simple_impls,
+            &mut generic_or_complex_impls,
+            &mut auto_traits,
+            &mut blanket_impls,
+        ] {
+            impls.sort_by(|a, b| {
+                (rank(a), &a.display_path, &a.formatted_markdown_list_entry).cmp(&(
+                    rank(b),
+                    &b.display_path,
+                    &b.formatted_markdown_list_entry,
+                ))
+            });
+        }
 
-            if format_map.len() == 1 {
-                if let Some(formatted_impl) = format_map.keys().next() {
-                    if module_common_traits.insert(formatted_impl.clone()) {
-                        debug!(
-                            "Identified module-specific common trait (single format) for {:?}: {:?} for trait ID {:?}",
-                            self.krate.paths.get(module_id).map(|p|p.path.join("::")).unwrap_or_default(),
-                            formatted_impl.formatted_markdown_list_entry,
-                            trait_id
-                        );
-                    }
-                }
-            } else {
-                let simple_format_count = format_map
-                    .iter()
-                    .find(|(f_impl, _)| f_impl.category == TraitImplCategory::Simple)
-                    .map_or(0, |(_, count)| *count);
+        self.push_level();
+        let mut preceding_section = false;
 
-                if simple_format_count * 2 > total_implementations_for_trait {
-                    if let Some(simple_impl) = format_map
-                        .keys()
-                        .find(|f_impl| f_impl.category == TraitImplCategory::Simple)
-                    {
-                        if module_common_traits.insert(simple_impl.clone()) {
-                            debug!(
-                                "Identified module-specific common trait (simple format predominant) for {:?}: {:?} for trait ID {:?}",
-                                self.krate.paths.get(module_id).map(|p|p.path.join("::")).unwrap_or_default(),
-                                simple_impl.formatted_markdown_list_entry,
-                                trait_id
-                            );
+        let mut print_section =
+            |traits: &[&FormattedTraitImpl], current_output: &mut String, _section_name: &str| {
+                if !traits.is_empty() {
+                    if preceding_section {
+                        writeln!(current_output).unwrap();
+                    }
+                    for norm_trait in traits {
+                        writeln!(current_output, "{}", self.trait_list_entry(norm_trait)).unwrap();
+                        if let Some((trait_impl, impl_id)) = norm_trait.get_impl_data(self.krate) {
+                            self.printed_ids.insert(impl_id, self.get_header_prefix());
+                            for assoc_item_id in &trait_impl.items {
+                                if self.selected_ids.contains(assoc_item_id) {
+                                    self.printed_ids
+                                        .insert(*assoc_item_id, self.get_header_prefix());
+                                }
+                            }
                         }
+                        self.post_increment_current_level();
                     }
-                } else {
-                    trace!(
-                        "Module {:?} trait ID {:?} has multiple formats, but Simple is not predominant ({} of {}).",
-                        module_id, trait_id, simple_format_count, total_implementations_for_trait
-                    );
+                    preceding_section = true;
                 }
-            }
-        }
-        module_common_traits
+            };
+
+        print_section(&simple_impls, &mut output, "Simple");
+        print_section(&generic_or_complex_impls, &mut output, "Generic or Complex");
+        print_section(&auto_traits, &mut output, "Auto");
+        print_section(&blanket_impls, &mut output, "Blanket");
+
+        self.pop_level();
+        output
     }
 
-    /// Builds the module hierarchy tree.
-    fn build_module_tree(krate: &'a Crate) -> ModuleTree {
-        let mut tree = ModuleTree::default();
-        let mut parent_map: HashMap<Id, Id> = HashMap::new(); // Child -> Parent
+    /// Prints Inherent and Trait Implementations *for* an item (Struct, Enum, Union, Primitive).
+    fn print_item_implementations(&mut self, impl_ids: &[Id], target_item: &Item) {
+        let target_item_id = target_item.id;
+        let target_name = target_item
+            .name
+            .as_deref()
+            .unwrap_or(match &target_item.inner {
+                ItemEnum::Primitive(Primitive { name, .. }) => name.as_str(),
+                _ => "{unknown_item_type}",
+            });
 
-        for (id, item) in &krate.index {
-            if let ItemEnum::Module(module_data) = &item.inner {
-                tree.all_modules.insert(*id);
-                let mut children = Vec::new();
-                for child_id in &module_data.items {
-                    if let Some(child_item) = krate.index.get(child_id) {
-                        if let ItemEnum::Module(_) = child_item.inner {
-                            children.push(*child_id);
-                            parent_map.insert(*child_id, *id);
+        let mut item_specific_impl_data = Vec::new();
+        for impl_id in impl_ids {
+            if let Some(impl_item) = self.krate.index.get(impl_id) {
+                if self.selected_ids.contains(&impl_item.id) {
+                    if let ItemEnum::Impl(imp) = &impl_item.inner {
+                        // Critical: Only consider this impl if it's FOR the target_item_id
+                        if get_type_id(&imp.for_) == Some(target_item_id) {
+                            item_specific_impl_data.push((impl_item, imp.clone()));
                         }
                     }
                 }
-                if !children.is_empty() {
-                    // Sort children alphabetically by name/path here for consistent ordering within a parent
-                    children.sort_by_key(|child_id| {
-                        krate
-                            .paths
-                            .get(child_id)
-                            .map(|p| p.path.join("::"))
-                            .unwrap_or_default()
-                    });
-                    tree.children.insert(*id, children);
-                }
             }
         }
 
-        // Identify top-level modules (excluding crate root)
-        for module_id in &tree.all_modules {
-            if *module_id != krate.root && !parent_map.contains_key(module_id) {
-                tree.top_level_modules.push(*module_id);
-            }
-        }
-
-        // Sort top-level modules alphabetically by path
-        tree.top_level_modules.sort_by_key(|id| {
-            krate
-                .paths
-                .get(id)
-                .map(|p| p.path.join("::"))
-                .unwrap_or_default()
-        });
-
-        tree
-    }
-
-    /// Gets the current markdown header level based on the doc_path length.
-    fn get_current_header_level(&self) -> usize {
-        self.doc_path.len() + 1 // H1 if path is empty, H2 if path has one element, etc.
-    }
+        // --- Inherent Impls ---
+        let inherent_impl_items: Vec<_> = item_specific_impl_data
+            .iter()
+            .filter(|(_, imp)| imp.trait_.is_none())
+            .collect();
 
-    /// Generates the header prefix string (e.g., "1.2.1:") based on the doc_path stack.
-    /// H2 headers always get just "N:". H3+ get the full path "N.M.O:".
-    fn get_header_prefix(&self) -> String {
-        let level = self.get_current_header_level();
-        if self.doc_path.is_empty() || level < 2 {
-            return String::new(); // No prefix for H1 or if stack is empty
+        if !inherent_impl_items.is_empty() {
+            if self.see_also_methods {
+                self.sibling_methods = inherent_impl_items
+                    .iter()
+                    .flat_map(|(_, imp)| imp.items.iter())
+                    .filter(|id| {
+                        self.krate
+                            .index
+                            .get(id)
+                            .is_some_and(|item| matches!(item.inner, ItemEnum::Function(_)))
+                    })
+                    .copied()
+                    .collect();
+            }
+            for (impl_item, imp) in inherent_impl_items {
+                if self.printed_ids.contains_key(&impl_item.id) {
+                    continue;
+                }
+                self.print_impl_block_details(impl_item, imp);
+            }
+            self.sibling_methods.clear();
         }
 
-        if level == 2 {
-            // Module headers (H2) use only the last element (the H2 counter)
-            format!("{}:", self.doc_path.last().unwrap_or(&0))
-        } else {
-            // H3+ headers use the full path stored in doc_path
-            self.doc_path
+        // --- Methods from Deref ---
+        if self.show_deref_methods {
+            if let Some(deref_target) = item_specific_impl_data
                 .iter()
-                .map(|n| n.to_string())
-                .collect::<Vec<_>>()
-                .join(".")
-                + ":"
+                .find_map(|(_, imp)| self.deref_target(imp))
+            {
+                self.print_deref_methods(deref_target);
+            }
         }
-    }
 
-    /// Generates the template marker string (e.g., "{{MISSING_DOCS_1_2_1}}")
-    fn get_template_marker(&self) -> String {
-        if self.doc_path.is_empty() {
-            "{{MISSING_DOCS}}".to_string()
-        } else {
-            format!(
-                "{{{{MISSING_DOCS_{}}}}}", // Double {{ for literal {
-                self.doc_path
-                    .iter()
-                    .map(|n| n.to_string())
-                    .collect::<Vec<_>>()
-                    .join("_")
-            )
-        }
-    }
+        // --- Trait Impls ---
+        let trait_impl_data: Vec<FormattedTraitImpl> = item_specific_impl_data
+            .iter()
+            .filter_map(|(impl_item, imp)| {
+                if self.printed_ids.contains_key(&impl_item.id) {
+                    return None; // Skip already printed impls
+                }
+                imp.trait_.as_ref().map(|tp| {
+                    FormattedTraitImpl::from_impl(imp, Some(impl_item.id), tp, self.krate, self)
+                })
+            })
+            .collect();
 
-    /// Increments the counter for the current document level.
-    /// Note: we increment _after_ outputting something at a given level because
-    /// we always initialize the level to 1 via `doc_path.push(1)`
-    fn post_increment_current_level(&mut self) {
-        if let Some(last) = self.doc_path.last_mut() {
-            *last += 1;
-        } else {
-            warn!("Attempted to increment document path level when path was empty.");
+        // Without any impls left to list there is nothing to compare against, and a
+        // section holding only a "Does not implement" note would be misleading.
+        if trait_impl_data.is_empty() {
+            return;
         }
-    }
 
-    /// Pushes a new level (starting at 1) onto the document path.
-    fn push_level(&mut self) {
-        self.doc_path.push(1);
-    }
+        let current_module_id = self
+            .current_module_path
+            .last()
+            .and_then(|mod_name| {
+                self.resolved_modules
+                    .values()
+                    .find(|rm| {
+                        self.krate
+                            .paths
+                            .get(&rm.id)
+                            .is_some_and(|p| p.path.last() == Some(mod_name))
+                    })
+                    .map(|rm| rm.id)
+            })
+            .unwrap_or(self.krate.root);
 
-    /// Pops the last level from the document path.
-    fn pop_level(&mut self) {
-        self.doc_path.pop();
-    }
+        // Module-common traits normally already include the crate-common ones, but check
+        // both so a type lacking a crate-wide common trait is always flagged.
+        let mut module_common_traits = self
+            .module_common_traits
+            .get(&current_module_id)
+            .cloned()
+            .unwrap_or_default();
+        module_common_traits.extend(self.crate_common_traits.iter().cloned());
 
-    fn get_item_kind(&self, id: &Id) -> Option<ItemKind> {
-        // Prefer index over paths for kind, as paths might be missing for some items?
-        self.krate
-            .index
-            .get(id)
-            .map(Printer::infer_item_kind) // Use associated function syntax
-            .or_else(|| self.krate.paths.get(id).map(|summary| summary.kind))
-    }
+        let mut non_common_trait_impls = Vec::new();
+        let mut missing_module_common_trait_paths = HashSet::new(); // Store trait_id of common traits
 
-    // Fallback for inferring ItemKind if not found in paths map (should be equivalent to index anyway)
-    pub(crate) fn infer_item_kind(item: &Item) -> ItemKind {
-        match item.inner {
-            ItemEnum::Module(_) => ItemKind::Module,
-            ItemEnum::ExternCrate { .. } => ItemKind::ExternCrate,
-            ItemEnum::Use { .. } => ItemKind::Use, // Keep Use kind for completeness
-            ItemEnum::Union(_) => ItemKind::Union,
-            ItemEnum::Struct(_) => ItemKind::Struct,
-            ItemEnum::StructField(_) => ItemKind::StructField,
-            ItemEnum::Enum(_) => ItemKind::Enum,
-            ItemEnum::Variant(_) => ItemKind::Variant,
-            ItemEnum::Function(_) => ItemKind::Function,
-            ItemEnum::Trait(_) => ItemKind::Trait,
-            ItemEnum::TraitAlias(_) => ItemKind::TraitAlias,
-            ItemEnum::Impl { .. } => ItemKind::Impl,
-            ItemEnum::TypeAlias(_) => ItemKind::TypeAlias,
-            ItemEnum::Constant { .. } => ItemKind::Constant, // Use struct pattern
-            ItemEnum::Static(_) => ItemKind::Static,
-            ItemEnum::ExternType => ItemKind::ExternType, // Renamed
-            ItemEnum::Macro(_) => ItemKind::Macro,
-            ItemEnum::ProcMacro(ref pm) => match pm.kind {
-                rustdoc_types::MacroKind::Bang => ItemKind::Macro, // Treat bang proc macro as Macro kind
-                rustdoc_types::MacroKind::Attr => ItemKind::ProcAttribute,
-                rustdoc_types::MacroKind::Derive => ItemKind::ProcDerive,
-            },
-            ItemEnum::Primitive(_) => ItemKind::Primitive,
-            ItemEnum::AssocConst { .. } => ItemKind::AssocConst,
-            ItemEnum::AssocType { .. } => ItemKind::AssocType,
+        for common_trait_format in &module_common_traits {
+            missing_module_common_trait_paths.insert(common_trait_format.trait_id);
         }
-    }
 
-    /// Prints the documentation string for an item, applying template mode if active.
-    /// Header level is determined internally by the doc_path.
-    fn print_docs(&mut self, item: &Item) {
-        let header_level = self.get_current_header_level(); // Level of the item owning the docs
-        match (&item.docs, self.template_mode) {
-            // Template mode and docs exist: Print mustache marker
-            (Some(_), true) => {
-                let marker = self.get_template_marker();
-                writeln!(self.output, "{}\n", marker).unwrap();
+        for norm_trait in &trait_impl_data {
+            // Check if this trait's path (trait_id) is among the common trait paths
+            if module_common_traits
+                .iter()
+                .any(|ct| ct.trait_id == norm_trait.trait_id)
+            {
+                // It implements a trait that *could* be common. Remove it from missing.
+                missing_module_common_trait_paths.remove(&norm_trait.trait_id);
+
+                // Now check if the *specific format* of this impl is common
+                if !module_common_traits.contains(norm_trait) {
+                    // The specific format is not common, so list it individually
+                    non_common_trait_impls.push(norm_trait.clone());
+                } else {
+                    // The specific format *is* common, mark it printed
+                    if let Some((trait_impl, impl_id)) = norm_trait.get_impl_data(self.krate) {
+                        self.printed_ids.insert(impl_id, self.get_header_prefix());
+                        for assoc_item_id in &trait_impl.items {
+                            if self.selected_ids.contains(assoc_item_id) {
+                                self.printed_ids
+                                    .insert(*assoc_item_id, self.get_header_prefix());
+                            }
+                        }
+                    }
+                }
+            } else {
+                // This trait path was never common, so list this specific impl
+                non_common_trait_impls.push(norm_trait.clone());
             }
-            // Not template mode or no docs: Print original docs if non-empty
-            (Some(docs), false) => {
-                if !docs.trim().is_empty() {
-                    // Use the new adjust_markdown_headers function
-                    let adjusted_docs = adjust_markdown_headers(docs.trim(), header_level);
-                    writeln!(self.output, "{}\n", adjusted_docs).unwrap();
+        }
+
+        if !non_common_trait_impls.is_empty() || !missing_module_common_trait_paths.is_empty() {
+            let trait_impl_header_level = self.get_current_header_level();
+            let header_prefix = self.get_header_prefix();
+            writeln!(
+                self.output,
+                "{} {} Trait Implementations for `{}`\n",
+                self.header_marker(trait_impl_header_level),
+                header_prefix,
+                target_name
+            )
+            .unwrap();
+
+            if !missing_module_common_trait_paths.is_empty() {
+                let mut sorted_missing_common_trait_names: Vec<String> =
+                    missing_module_common_trait_paths
+                        .iter()
+                        .filter_map(|trait_id| {
+                            // Find the corresponding FormattedTraitImpl from module_common_traits
+                            // to get its display name
+                            module_common_traits
+                                .iter()
+                                .find(|ct| ct.trait_id == *trait_id)
+                                .map(|ct| ct.display_path.clone())
+                        })
+                        .collect();
+                sorted_missing_common_trait_names.sort_unstable();
+                if !sorted_missing_common_trait_names.is_empty() {
+                    writeln!(
+                        self.output,
+                        "**(Note: Does not implement common trait(s): `{}`)**\n",
+                        sorted_missing_common_trait_names.join("`, `")
+                    )
+                    .unwrap();
                 }
-                // If docs are Some but empty, print nothing (existing behavior)
             }
-            // Docs are None: Print nothing
-            (None, _) => {}
+
+            let formatted_list = self.format_trait_list(&non_common_trait_impls);
+            if !formatted_list.is_empty() {
+                write!(self.output, "{}", formatted_list).unwrap();
+            }
+
+            self.post_increment_current_level();
         }
     }
 
-    /// Prints the details of a single selected item.
-    /// Manages the doc_path stack for the item's header.
-    /// Returns true if full details were printed, false if a cross-reference was printed or skipped.
-    fn print_item_details(&mut self, id: &Id) -> bool {
-        if !self.selected_ids.contains(id) {
-            return false; // Skip unselected items
+    /// Returns the `Target` type if `imp` is an implementation of `core::ops::Deref`.
+    fn deref_target(&self, imp: &Impl) -> Option<&'a Type> {
+        let trait_path = imp.trait_.as_ref()?;
+        let is_deref = self.krate.paths.get(&trait_path.id).map_or_else(
+            || trait_path.path == "Deref",
+            |summary| summary.path == ["core", "ops", "deref", "Deref"],
+        );
+        if !is_deref {
+            return None;
         }
+        imp.items.iter().find_map(|id| {
+            let item = self.krate.index.get(id)?;
+            match &item.inner {
+                ItemEnum::AssocType {
+                    type_: Some(ty), ..
+                } if item.name.as_deref() == Some("Target") => Some(ty),
+                _ => None,
+            }
+        })
+    }
 
-        let Some(item) = self.krate.index.get(id) else {
-            warn!("Item details for ID {id:?} not found in index");
-            return false;
+    /// Prints a "Methods from `Deref<Target = T>`" section listing the inherent methods
+    /// of `target` that are reachable through deref coercion (those taking `&self`
+    /// or `&mut self`).
+    fn print_deref_methods(&mut self, target: &Type) {
+        let Some(target_id) = get_type_id(target) else {
+            return;
         };
-
-        // Skip printing details for 'Use' items, they are handled by resolution
-        // Also skip Modules here, they are handled by the main traversal in finalize
-        if matches!(item.inner, ItemEnum::Use(_) | ItemEnum::Module(_)) {
-            return false;
+        let mut methods: Vec<&Item> = self
+            .krate
+            .index
+            .values()
+            .filter_map(|item| match &item.inner {
+                ItemEnum::Impl(imp)
+                    if imp.trait_.is_none()
+                        && get_type_id(&imp.for_) == Some(target_id)
+                        && self.selected_ids.contains(&item.id) =>
+                {
+                    Some(imp)
+                }
+                _ => None,
+            })
+            .flat_map(|imp| imp.items.iter())
+            .filter(|id| self.selected_ids.contains(id))
+            .filter_map(|id| self.krate.index.get(id))
+            .filter(|item| match &item.inner {
+                ItemEnum::Function(f) => f.sig.inputs.first().is_some_and(|(name, ty)| {
+                    name == "self" && matches!(ty, Type::BorrowedRef { .. })
+                }),
+                _ => false,
+            })
+            .collect();
+        if methods.is_empty() {
+            return;
         }
+        methods.sort_by_key(|item| item.name.clone());
 
-        let item_header_level = self.get_current_header_level();
+        let target_str = format_type(target, self);
+        let header_level = self.get_current_header_level();
         let header_prefix = self.get_header_prefix();
-        let declaration = generate_item_declaration(item, self.krate, &self.current_module_path);
-
-        if let Some(existing_prefix) = self.printed_ids.get(id) {
-            // Item already printed, print cross-reference instead of full details
-            // This case is primarily for when print_item_details is called directly
-            // (e.g., from print_items_of_kind) for an item that was already
-            // printed via a different module path.
-            writeln!(
+        writeln!(
+            self.output,
+            "{} {} Methods from `Deref<Target = {}>`\n",
+            self.header_marker(header_level),
+            header_prefix,
+            target_str
+        )
+        .unwrap();
+        match self.printed_ids.get(&target_id) {
+            Some(target_prefix) => writeln!(
                 self.output,
-                "\n{} {} `{}` (See section {} for details)\n",
-                "#".repeat(item_header_level),
-                header_prefix,
-                declaration,
-                existing_prefix
-            )
-            .unwrap();
-            // Do not push/pop level or print further details for cross-referenced item
-            return false; // Indicate that full details were not printed
+                "_Methods of `{}` {}:_\n",
+                target_str,
+                self.section_reference(target_prefix)
+            ),
+            None => writeln!(
+                self.output,
+                "_Methods of `{}`, see its documentation for details:_\n",
+                format_id_path_canonical(&target_id, self)
+            ),
+        }
+        .unwrap();
+        for method in methods {
+            let declaration = generate_item_declaration(method, self, &self.current_module_path);
+            writeln!(self.output, "- `{}`", declaration).unwrap();
         }
+        writeln!(self.output).unwrap();
 
-        // Store the prefix *before* printing details, as this is its first detailed print
-        self.printed_ids.insert(*id, header_prefix.clone());
+        self.post_increment_current_level();
+    }
 
-        // Print Header (e.g. `### 1.1.1: `declaration``)
+    /// Lists the methods a trait inherits from its supertraits (transitively),
+    /// each noting and linking the supertrait that provides it.
+    fn print_inherited_trait_items(&mut self, t: &Trait) {
+        let mut queue: VecDeque<Id> = supertrait_ids(t).collect();
+        let mut visited = HashSet::new();
+        let mut methods: Vec<(String, &Item)> = Vec::new();
+        while let Some(super_id) = queue.pop_front() {
+            if !visited.insert(super_id) {
+                continue;
+            }
+            let Some(super_item) = self.krate.index.get(&super_id) else {
+                continue; // External supertraits are not in the index
+            };
+            let ItemEnum::Trait(super_trait) = &super_item.inner else {
+                continue;
+            };
+            let super_link = self.format_supertrait_link(&super_id);
+            for assoc_id in &super_trait.items {
+                if let Some(assoc_item) = self.krate.index.get(assoc_id) {
+                    if matches!(assoc_item.inner, ItemEnum::Function(_)) {
+                        methods.push((super_link.clone(), assoc_item));
+                    }
+                }
+            }
+            queue.extend(supertrait_ids(super_trait));
+        }
+        if methods.is_empty() {
+            return;
+        }
+        methods.sort_by_key(|(_, item)| item.name.clone());
+
+        let header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
         writeln!(
             self.output,
-            "\n{} {} `{}`\n", // Add newline after header
-            "#".repeat(item_header_level),
-            header_prefix,
-            declaration
+            "{} {} Inherited Methods\n",
+            self.header_marker(header_level),
+            header_prefix
         )
         .unwrap();
+        for (super_link, method) in methods {
+            let declaration = generate_item_declaration(method, self, &self.current_module_path);
+            writeln!(
+                self.output,
+                "- `{}` _(from supertrait {})_",
+                declaration, super_link
+            )
+            .unwrap();
+        }
+        writeln!(self.output).unwrap();
 
-        self.push_level();
+        self.post_increment_current_level();
+    }
 
-        // Print Code Block for Struct/Enum/Trait/Function (if needed)
-        let code_block = match &item.inner {
-            ItemEnum::Struct(s) => Some(generate_struct_code_block(item, s, self.krate)),
-            ItemEnum::Enum(e) => Some(generate_enum_code_block(item, e, self.krate)),
-            ItemEnum::Union(u) => Some(generate_union_code_block(item, u, self.krate)),
-            ItemEnum::Trait(t) => Some(generate_trait_code_block(item, t, self.krate)),
-            ItemEnum::Function(f) => {
-                // Check if function has attrs or where clause
-                let has_attrs = f.header.is_const
-                    || f.header.is_async
-                    || f.header.is_unsafe
-                    || !matches!(f.header.abi, Abi::Rust)
-                    || !item.attrs.is_empty(); // Check item.attrs for function attributes
-                let has_where = !f.generics.where_predicates.is_empty();
-                if has_attrs || has_where {
-                    Some(generate_function_code_block(item, f, self.krate))
-                } else {
-                    None // No code block needed for simple function
-                }
-            }
-            // TODO: Add code blocks for other types like TypeAlias, Constant if desired
-            _ => None,
-        };
+    /// Formats a reference to the section numbered `prefix`, either inline as
+    /// `(See section 1.2 for details)` or, with
+    /// [`references_as_footnotes`](Printer::references_as_footnotes), as a footnote
+    /// marker whose definition is written by [`Printer::write_reference_footnotes`].
+    fn section_reference(&self, prefix: &str) -> String {
+        if !self.references_as_footnotes {
+            return format!("(See section {} for details)", prefix);
+        }
+        format!("[^{}]", self.reference_footnote(prefix))
+    }
 
-        if let Some(code) = code_block {
-            writeln!(self.output, "```rust\n{}\n```\n", code).unwrap();
+    /// Returns a note describing how `item` is sealed if it is a trait with a local
+    /// supertrait that is not reachable from any public module, when
+    /// [`Printer::include_sealed_details`] is enabled.
+    fn sealed_trait_note(&self, item: &Item) -> Option<String> {
+        if !self.include_sealed_details {
+            return None;
+        }
+        let ItemEnum::Trait(t) = &item.inner else {
+            return None;
+        };
+        let sealing_id = supertrait_ids(t).find(|super_id| {
+            let is_local = self
+                .krate
+                .paths
+                .get(super_id)
+                .is_some_and(|summary| summary.crate_id == 0);
+            is_local
+                && !self.resolved_modules.iter().any(|(module_id, module)| {
+                    module.items.contains(super_id) && self.is_public_module(module_id)
+                })
+        })?;
+        let sealing_path = format_id_path_canonical(&sealing_id, self);
+        let mut implementors: Vec<String> = self
+            .krate
+            .index
+            .values()
+            .filter_map(|impl_item| match &impl_item.inner {
+                ItemEnum::Impl(imp)
+                    if imp.trait_.as_ref().is_some_and(|tr| tr.id == sealing_id) =>
+                {
+                    Some(format!("`{}`", format_type(&imp.for_, self)))
+                }
+                _ => None,
+            })
+            .collect();
+        implementors.sort();
+        implementors.dedup();
+        if implementors.is_empty() {
+            Some(format!(
+                "_Sealed: requires the private supertrait `{}`, so it cannot be implemented outside this crate._",
+                sealing_path
+            ))
+        } else {
+            Some(format!(
+                "_Sealed: requires the private supertrait `{}`, implemented for {}._",
+                sealing_path,
+                implementors.join(", ")
+            ))
         }
+    }
 
-        let has_stripped = matches!(
-            &item.inner,
-            ItemEnum::Struct(Struct {
-                kind: StructKind::Plain {
-                    has_stripped_fields: true,
-                    ..
-                },
-                ..
+    /// Returns `true` if `module_id` and all its ancestors are `pub`, so that users can
+    /// name the items it contains. Modules missing from the index (private modules when
+    /// private items are not documented) are not public.
+    fn is_public_module(&self, module_id: &Id) -> bool {
+        let Some(summary) = self.krate.paths.get(module_id) else {
+            return false;
+        };
+        (2..=summary.path.len()).all(|len| {
+            let prefix = &summary.path[..len];
+            self.krate.paths.iter().any(|(id, ancestor)| {
+                ancestor.kind == ItemKind::Module
+                    && ancestor.path == prefix
+                    && self
+                        .krate
+                        .index
+                        .get(id)
+                        .is_some_and(|item| item.visibility == rustdoc_types::Visibility::Public)
             })
-        );
+        })
+    }
 
-        if has_stripped {
-            writeln!(self.output, "_[Private fields hidden]_\n").unwrap();
+    /// Returns a compatibility note when `item` declares a minimum Rust version
+    /// (see [`item_required_rust_version`]) newer than the crate's `rust-version`,
+    /// or when the crate does not declare one.
+    fn rust_version_note(&self, item: &Item) -> Option<String> {
+        let required = item_required_rust_version(item)?;
+        match self.manifest_data.rust_version.as_deref() {
+            Some(msrv) if parse_rust_version(msrv) >= parse_rust_version(&required) => None,
+            Some(msrv) => Some(format!(
+                "_Requires Rust {} (the crate's `rust-version` is {})._",
+                required, msrv
+            )),
+            None => Some(format!("_Requires Rust {}._", required)),
         }
+    }
 
-        // Print Documentation (using the helper method)
-        self.print_docs(item);
+    /// Formats a reference to the already documented item `id`, shown as
+    /// `declaration`: a link to the item's anchor when [`anchors()`](Printer::anchors)
+    /// is enabled, which stays valid when sections are renumbered, or otherwise the
+    /// declaration followed by a reference to the section numbered `prefix`.
+    ///
+    /// `annotation` (e.g. a re-export's visibility) directly follows the declaration.
+    fn format_cross_reference(
+        &self,
+        id: &Id,
+        declaration: &str,
+        annotation: &str,
+        prefix: &str,
+    ) -> String {
+        if let Some(link) = self.anchor_link(id, declaration) {
+            return format!("{}{}", link, annotation);
+        }
+        format!(
+            "`{}`{} {}",
+            declaration,
+            annotation,
+            self.section_reference(prefix)
+        )
+    }
 
-        match &item.inner {
-            ItemEnum::Struct(s) => self.print_struct_fields(item, s),
-            ItemEnum::Enum(e) => self.print_enum_variants(item, e),
-            ItemEnum::Union(u) => self.print_union_fields(item, u),
-            ItemEnum::Trait(t) => self.print_trait_associated_items(item, t),
-            // Add other kinds requiring detailed sections if necessary
-            _ => {}
+    /// Formats a reference to the item `id`, shown as `name`, that may not have been
+    /// documented: like [`Printer::format_cross_reference`] if its section was
+    /// already printed (or it has an anchor), or otherwise just `name`.
+    fn format_item_reference(&self, id: &Id, name: &str) -> String {
+        match self.printed_ids.get(id) {
+            Some(prefix) => self.format_cross_reference(id, name, "", prefix),
+            None => self
+                .anchor_link(id, name)
+                .unwrap_or_else(|| format!("`{}`", name)),
         }
+    }
 
-        // Print Implementations (common to Struct, Enum, Trait, Primitive, etc.)
-        let impl_ids = match &item.inner {
-            ItemEnum::Struct(s) => Some(&s.impls),
-            ItemEnum::Enum(e) => Some(&e.impls),
-            ItemEnum::Trait(t) => Some(&t.implementations), // Traits list implementors
-            ItemEnum::Union(u) => Some(&u.impls),
-            ItemEnum::Primitive(p) => Some(&p.impls),
-            _ => None,
-        };
+    /// Formats the item `id`, shown as `declaration`, whose details
+    /// are printed in the not yet numbered module `module_id`: a link to the item's
+    /// anchor when [`anchors()`](Printer::anchors) is enabled, or otherwise the
+    /// declaration followed by the module's path. `annotation` is placed as in
+    /// [`Printer::format_cross_reference`].
+    fn format_module_reference(
+        &self,
+        id: &Id,
+        declaration: &str,
+        annotation: &str,
+        module_id: &Id,
+    ) -> String {
+        if let Some(link) = self.anchor_link(id, declaration) {
+            return format!("{}{}", link, annotation);
+        }
+        format!(
+            "`{}`{} (See module `{}` for details)",
+            declaration,
+            annotation,
+            format_id_path_canonical(module_id, self)
+        )
+    }
 
-        if let Some(ids) = impl_ids {
-            match &item.inner {
-                ItemEnum::Trait(_) => self.print_trait_implementors(ids, item),
-                _ => self.print_item_implementations(ids, item),
+    /// Creates a cross-reference footnote to the section numbered `prefix`, returning
+    /// its label.
+    fn reference_footnote(&self, prefix: &str) -> String {
+        let number = self.footnote_count.get() + 1;
+        self.footnote_count.set(number);
+        let label = format!("ref-{}", number);
+        self.reference_footnotes.borrow_mut().push(format!(
+            "[^{}]: See section {} for details.",
+            label,
+            prefix.trim_end_matches(':')
+        ));
+        label
+    }
+
+    /// Resolves the intra-doc links of `item`'s docs to how they are rendered.
+    ///
+    /// Links to documented items point at their anchor when
+    /// [`anchors()`](Printer::anchors) is enabled, or otherwise refer to the section
+    /// they were printed in. Links to items of other crates point at their online docs
+    /// (see [`Printer::external_item_url`]), and links to items that are not
+    /// documented are unlinked.
+    fn resolve_doc_links(&self, item: &Item) -> HashMap<String, DocLink> {
+        // Visit links in the order they appear so footnotes are numbered in reading order
+        let docs = item.docs.as_deref().unwrap_or_default();
+        let mut links: Vec<(&String, &Id)> = item.links.iter().collect();
+        links.sort_by_key(|(text, _)| (docs.find(text.as_str()), text.as_str()));
+        links
+            .into_iter()
+            .map(|(text, target)| {
+                let summary = self.krate.paths.get(target);
+                let link = if let Some(summary) = summary.filter(|s| s.crate_id != 0) {
+                    self.external_item_url(summary)
+                        .map_or(DocLink::External, DocLink::Url)
+                } else if !self.selected_ids.contains(target) {
+                    DocLink::Unlinked
+                } else if let Some(anchor) = self.item_anchor(target) {
+                    DocLink::Anchor(format!("#{}", anchor))
+                } else {
+                    match self.printed_ids.get(target) {
+                        Some(prefix) if self.references_as_footnotes => {
+                            DocLink::Footnote(self.reference_footnote(prefix))
+                        }
+                        Some(prefix) => DocLink::Section(prefix.clone()),
+                        None => DocLink::Unlinked,
+                    }
+                };
+                (text.clone(), link)
+            })
+            .collect()
+    }
+
+    /// Returns the URL of the docs of an item of another crate: under the crate's
+    /// `html_root_url` (doc.rust-lang.org for the standard library), or on docs.rs.
+    ///
+    /// Returns `None` for kinds without a page of their own, such as methods or fields.
+    fn external_item_url(&self, summary: &rustdoc_types::ItemSummary) -> Option<String> {
+        let external_crate = self.krate.external_crates.get(&summary.crate_id)?;
+        let root = match &external_crate.html_root_url {
+            Some(url) => format!("{}/", url.trim_end_matches('/')),
+            None => format!("https://docs.rs/{}/latest/", external_crate.name),
+        };
+        let (name, parents) = summary.path.split_last()?;
+        let page = match summary.kind {
+            ItemKind::Module => {
+                return Some(format!("{}{}/index.html", root, summary.path.join("/")))
             }
+            ItemKind::Struct => "struct",
+            ItemKind::Enum => "enum",
+            ItemKind::Union => "union",
+            ItemKind::Trait => "trait",
+            ItemKind::TraitAlias => "traitalias",
+            ItemKind::Function => "fn",
+            ItemKind::TypeAlias => "type",
+            ItemKind::Constant => "constant",
+            ItemKind::Static => "static",
+            ItemKind::Macro => "macro",
+            ItemKind::Primitive => "primitive",
+            _ => return None,
+        };
+        let mut url = root;
+        for parent in parents {
+            write!(url, "{}/", parent).unwrap();
         }
+        write!(url, "{}.{}.html", page, name).unwrap();
+        Some(url)
+    }
 
-        self.pop_level();
+    /// Writes the definitions of the cross-reference footnotes created since the last
+    /// call.
+    fn write_reference_footnotes(&mut self) {
+        let footnotes = self.reference_footnotes.take();
+        if footnotes.is_empty() {
+            return;
+        }
+        if !self.output.ends_with("\n\n") {
+            writeln!(self.output).unwrap();
+        }
+        for footnote in footnotes {
+            writeln!(self.output, "{}", footnote).unwrap();
+        }
+        writeln!(self.output).unwrap();
+    }
 
-        true // Full details were printed
+    /// Formats a reference to a supertrait, see [`Printer::format_item_reference`].
+    fn format_supertrait_link(&self, super_id: &Id) -> String {
+        self.format_item_reference(super_id, &format_id_path_canonical(super_id, self))
     }
 
-    /// Checks if any selected field within a struct has documentation or if template mode is on.
-    #[allow(unused)]
-    fn has_documented_fields(&self, s: &Struct) -> bool {
-        let field_ids = match &s.kind {
-            StructKind::Plain { fields, .. } => fields.clone(),
-            StructKind::Tuple(fields) => fields.iter().filter_map(|opt_id| *opt_id).collect(),
-            StructKind::Unit => vec![],
+    /// Returns the list entry of `trait_impl`, with the header of a blanket impl (e.g.
+    /// `` `impl<T> Trait for T` ``) referring to the trait's own section if it is
+    /// documented here, see [`Printer::format_item_reference`].
+    ///
+    /// The reference is only added when the entry is printed, so that it reflects what
+    /// was printed so far and footnotes are only created for printed entries.
+    fn trait_list_entry<'t>(&self, trait_impl: &'t FormattedTraitImpl) -> Cow<'t, str> {
+        let entry = &trait_impl.formatted_markdown_list_entry;
+        if trait_impl.category != TraitImplCategory::Blanket {
+            return Cow::Borrowed(entry);
+        }
+        let Some(header) = entry
+            .strip_prefix("- `")
+            .and_then(|rest| rest.split_once('`'))
+            .map(|(header, _)| header)
+        else {
+            return Cow::Borrowed(entry);
         };
-        field_ids.iter().any(|field_id| {
-            self.selected_ids.contains(field_id)
-                && self.krate.index.get(field_id).is_some_and(|item| {
-                    // Consider it "documented" if template mode is on and docs are Some
-                    (self.template_mode && item.docs.is_some()) || has_docs(item)
-                })
-        })
+        let reference = self.format_item_reference(&trait_impl.trait_id, header);
+        let code_len = "- `".len() + header.len() + "`".len();
+        Cow::Owned(format!("- {}{}", reference, &entry[code_len..]))
     }
 
-    /// Prints the "Fields" section for a struct, only if needed.
-    /// Also marks fields without documentation as printed.
-    fn print_struct_fields(&mut self, _item: &Item, s: &Struct) {
-        let all_field_ids: Vec<Id> = match &s.kind {
-            StructKind::Plain { fields, .. } => fields.clone(),
-            StructKind::Tuple(fields) => fields.iter().filter_map(|opt_id| *opt_id).collect(),
-            StructKind::Unit => vec![],
-        };
+    /// Prints implementors *of* a trait. Handles template mode for the impl docs.
+    fn print_trait_implementors(&mut self, impl_ids: &[Id], _trait_item: &Item) {
+        let implementors: Vec<&Item> = impl_ids
+            .iter()
+            .filter_map(|id| self.krate.index.get(id))
+            .filter(|item| {
+                self.selected_ids.contains(&item.id) && matches!(item.inner, ItemEnum::Impl(_))
+            })
+            .collect();
 
-        let mut has_printable_field = false;
+        if !implementors.is_empty() {
+            let implementors_section_level = self.get_current_header_level();
+            let header_prefix = self.get_header_prefix();
+            writeln!(
+                self.output,
+                "{} {} {}\n",
+                self.header_marker(implementors_section_level),
+                header_prefix,
+                self.section_title("Implementors", implementors.len())
+            )
+            .unwrap();
 
-        // First pass: Mark unselected/undocumented/non-templated fields printed and check if any are printable.
-        for field_id in &all_field_ids {
-            if !self.selected_ids.contains(field_id) {
-                continue; // Skip unselected fields
-            }
+            self.push_level();
+            for impl_item in implementors {
+                if let ItemEnum::Impl(imp) = &impl_item.inner {
+                    let impl_header_only = format_impl_decl_header_only(imp, self);
+                    let impl_header_level = self.get_current_header_level();
+                    let impl_prefix = self.get_header_prefix();
+
+                    writeln!(
+                        self.output,
+                        "{} {} `{}`\n",
+                        self.header_marker(impl_header_level),
+                        impl_prefix,
+                        impl_header_only.trim()
+                    )
+                    .unwrap();
+
+                    // Print where clause if it exists
+                    let generics = header_generics(&imp.generics, self);
+                    if !generics.where_predicates.is_empty() {
+                        let where_clause =
+                            format_generics_where_only(&generics.where_predicates, self);
+                        writeln!(
+                            self.output,
+                            "```{}\n{}\n```\n",
+                            self.code_fence_lang, where_clause
+                        )
+                        .unwrap();
+                    }
 
-            if let Some(item) = self.krate.index.get(field_id) {
-                let field_has_printable_docs =
-                    (self.template_mode && item.docs.is_some()) || has_docs(item);
-                if field_has_printable_docs {
-                    // Check if it's already printed to avoid double counting
-                    if !self.printed_ids.contains_key(field_id) {
-                        has_printable_field = true;
+                    // Print docs for the impl block itself
+                    self.print_docs(impl_item);
+
+                    // Mark the impl_item ID and its associated items as printed
+                    self.printed_ids
+                        .insert(impl_item.id, self.get_header_prefix());
+                    for assoc_item_id in &imp.items {
+                        if self.selected_ids.contains(assoc_item_id) {
+                            self.printed_ids
+                                .insert(*assoc_item_id, self.get_header_prefix());
+                        }
                     }
-                } else {
-                    // Mark non-printable field as printed immediately
-                    self.printed_ids.insert(*field_id, self.get_header_prefix());
+
+                    self.post_increment_current_level();
                 }
-            } else {
-                // If item doesn't exist in index but ID was present, mark it printed to avoid issues
-                self.printed_ids.insert(*field_id, self.get_header_prefix());
             }
+            self.pop_level();
+            self.post_increment_current_level();
         }
+    }
 
-        // Only print the "Fields" section if there's a printable field
-        if !has_printable_field {
+    /// Prints the details of a specific impl block (header, associated items).
+    /// Handles template mode for the impl block's docs.
+    fn print_impl_block_details(&mut self, impl_item: &Item, imp: &Impl) {
+        let header_prefix = self.get_header_prefix();
+        // Mark as printed *now* before printing details
+        if self
+            .printed_ids
+            .insert(impl_item.id, header_prefix.clone())
+            .is_some()
+        {
+            // Already printed with a (potentially different) prefix, skip full details.
+            // This case should ideally be caught by the caller, but as a safeguard.
             return;
         }
 
-        let fields_header_level = self.get_current_header_level();
-        let header_prefix = self.get_header_prefix();
+        // Increment level counter for this impl block
+        self.post_increment_current_level();
+        let impl_header_level = self.get_current_header_level();
+        let impl_header = format_impl_decl(imp, self);
+
+        // Print the impl block header (e.g. #### 1.1.1: `impl ...`)
         writeln!(
             self.output,
-            "{} {} Fields\n", // Add newline after header
-            "#".repeat(fields_header_level),
-            header_prefix
+            "{} {} `{}`\n", // Add newline after header
+            self.header_marker(impl_header_level),
+            header_prefix,      // Use the stored/current prefix
+            impl_header.trim()  // Trim potential trailing space if no where clause added
         )
         .unwrap();
 
-        // Push a new level for the field items themselves
-        self.push_level();
-        for field_id in &all_field_ids {
-            if self.print_field_details(field_id) {
-                self.post_increment_current_level();
+        // Print impl block docs (using helper)
+        self.print_docs(impl_item);
+
+        // Print associated items within this impl block
+        let mut assoc_consts = vec![];
+        let mut assoc_types = vec![];
+        let mut assoc_fns = vec![];
+        for assoc_item_id in &imp.items {
+            // Important: Only process associated items that are *selected*
+            if !self.selected_ids.contains(assoc_item_id) {
+                continue;
             }
-        }
-        self.pop_level(); // Pop the field item level
 
-        self.post_increment_current_level();
-    }
+            if let Some(assoc_item) = self.krate.index.get(assoc_item_id) {
+                match &assoc_item.inner {
+                    ItemEnum::AssocConst { .. } => assoc_consts.push(assoc_item_id),
+                    ItemEnum::AssocType { .. } => assoc_types.push(assoc_item_id),
+                    ItemEnum::Function(_) => assoc_fns.push(assoc_item_id),
+                    _ => {} // Should not happen in impl block
+                }
+            }
+        }
 
-    /// Prints the "Fields" section for a union, only if needed.
-    fn print_union_fields(&mut self, _item: &Item, u: &Union) {
-        let all_field_ids: Vec<Id> = u.fields.clone();
-        let mut has_printable_field = false;
+        // Push level for associated items within the impl block
+        self.push_level();
 
-        for field_id in &all_field_ids {
-            if !self.selected_ids.contains(field_id) {
-                continue;
+        if !assoc_consts.is_empty() {
+            for id in assoc_consts {
+                self.print_associated_item_summary(id);
+                if !self.printed_ids.contains_key(id) {
+                    self.printed_ids.insert(*id, self.get_header_prefix());
+                }
             }
-            if let Some(item) = self.krate.index.get(field_id) {
-                let field_has_printable_docs =
-                    (self.template_mode && item.docs.is_some()) || has_docs(item);
-                if field_has_printable_docs {
-                    if !self.printed_ids.contains_key(field_id) {
-                        has_printable_field = true;
-                    }
-                } else {
-                    self.printed_ids.insert(*field_id, self.get_header_prefix());
+        }
+        if !assoc_types.is_empty() {
+            for id in assoc_types {
+                self.print_associated_item_summary(id);
+                if !self.printed_ids.contains_key(id) {
+                    self.printed_ids.insert(*id, self.get_header_prefix());
+                }
+            }
+        }
+        if !assoc_fns.is_empty() {
+            for id in assoc_fns {
+                self.print_associated_item_summary(id);
+                if !self.printed_ids.contains_key(id) {
+                    self.printed_ids.insert(*id, self.get_header_prefix());
                 }
-            } else {
-                self.printed_ids.insert(*field_id, self.get_header_prefix());
             }
         }
 
-        if !has_printable_field && !u.has_stripped_fields {
-            return;
+        self.pop_level(); // Pop associated item level
+    }
+
+    /// Prints items of a specific kind within a given list of IDs.
+    fn print_items_of_kind(&mut self, item_ids: &[Id], kind: ItemKind, header_name: &str) -> bool {
+        // Filter and sort items of the target kind
+        let mut items_to_print: Vec<&Id> = item_ids
+            .iter()
+            .filter(|id| self.selected_ids.contains(id))
+            .filter(|id| self.get_item_kind(id) == Some(kind))
+            .collect();
+
+        if items_to_print.is_empty() {
+            return false; // Nothing to print for this kind
         }
 
-        let fields_header_level = self.get_current_header_level();
+        self.sort_items_by(&mut items_to_print, |id| **id);
+        // Items printed elsewhere only get a cross-reference here
+        let printed_count = items_to_print
+            .iter()
+            .filter(|id| !self.printed_ids.contains_key(id))
+            .count();
+
+        let section_header_level = self.get_current_header_level();
         let header_prefix = self.get_header_prefix();
         writeln!(
             self.output,
-            "{} {} Fields\n",
-            "#".repeat(fields_header_level),
-            header_prefix
+            "\n{} {} {}",
+            self.header_marker(section_header_level),
+            header_prefix,
+            self.section_title(header_name, printed_count)
         )
         .unwrap();
 
+        let groups = if kind == ItemKind::Function && self.group_free_functions_by_first_arg {
+            self.group_by_first_arg(&mut items_to_print)
+        } else {
+            Vec::new()
+        };
+
         self.push_level();
-        for field_id in &all_field_ids {
-            if self.print_field_details(field_id) {
+        // Print item details
+        for id in items_to_print {
+            // Only advance the counter if a header was emitted, to avoid numbering gaps
+            if self.print_item_details(id) {
                 self.post_increment_current_level();
             }
         }
-        if u.has_stripped_fields {
-            writeln!(self.output, "_[Private fields hidden]_").unwrap();
-        }
-        self.pop_level();
-        self.post_increment_current_level();
-    }
-
-    /// Prints the details for a single struct field, only if it has printable documentation.
-    /// Returns true if the field was printed, false otherwise.
-    fn print_field_details(&mut self, field_id: &Id) -> bool {
-        if !self.selected_ids.contains(field_id) || self.printed_ids.contains_key(field_id) {
-            return false; // Skip unselected or already printed
+        for (type_id, functions) in groups {
+            let group_header_level = self.get_current_header_level();
+            let group_prefix = self.get_header_prefix();
+            writeln!(
+                self.output,
+                "\n{} {} Functions operating on `{}`",
+                self.header_marker(group_header_level),
+                group_prefix,
+                format_id_path_canonical(&type_id, self)
+            )
+            .unwrap();
+            self.push_level();
+            for id in functions {
+                if self.print_item_details(id) {
+                    self.post_increment_current_level();
+                }
+            }
+            self.pop_level();
+            self.post_increment_current_level();
         }
+        self.pop_level(); // Pop the item level for this section
 
-        if let Some(item) = self.krate.index.get(field_id) {
-            let field_has_printable_docs =
-                (self.template_mode && item.docs.is_some()) || has_docs(item);
+        true
+    }
 
-            // Only proceed if the field has printable documentation
-            if !field_has_printable_docs {
-                // Should already be marked printed in print_struct_fields
-                return false;
+    /// Removes the functions from `functions` whose first parameter is (a reference
+    /// to) a local type that at least one other function also takes first, returning
+    /// them grouped by that type and sorted by the type's path.
+    fn group_by_first_arg<'i>(&self, functions: &mut Vec<&'i Id>) -> Vec<(Id, Vec<&'i Id>)> {
+        let first_arg_type = |id: &Id| {
+            let item = self.krate.index.get(id)?;
+            let ItemEnum::Function(f) = &item.inner else {
+                return None;
+            };
+            let (_, first_ty) = f.sig.inputs.first()?;
+            let type_id = get_type_id(first_ty)?;
+            self.krate
+                .paths
+                .get(&type_id)
+                .is_some_and(|summary| summary.crate_id == 0)
+                .then_some(type_id)
+        };
+        let mut groups: HashMap<Id, Vec<&'i Id>> = HashMap::new();
+        for id in functions.iter() {
+            if let Some(type_id) = first_arg_type(id) {
+                groups.entry(type_id).or_default().push(id);
             }
+        }
+        groups.retain(|_, members| members.len() > 1);
+        functions
+            .retain(|id| first_arg_type(id).is_none_or(|type_id| !groups.contains_key(&type_id)));
+        let mut groups: Vec<(Id, Vec<&'i Id>)> = groups.into_iter().collect();
+        groups.sort_by_key(|(type_id, _)| format_id_path_canonical(type_id, self));
+        groups
+    }
 
-            let header_prefix = self.get_header_prefix();
-            // Mark as printed *before* printing details
-            self.printed_ids.insert(*field_id, header_prefix.clone());
+    /// Prints the non-module contents of a specific module (identified by its ID).
+    /// Uses the `resolved_modules` index to get the list of items.
+    fn print_module_contents(&mut self, module_id: &Id) {
+        if self.sort_order == SortOrder::AsDeclared {
+            self.declaration_order = self.declaration_positions(module_id);
+        }
+        if let Some(resolved_module) = self.resolved_modules.get(module_id) {
+            let mut items_by_kind: HashMap<ItemKind, Vec<Id>> = HashMap::new();
+            let mut cross_referenced_items: Vec<(Id, String, String)> = Vec::new(); // (Id, Declaration, Prefix)
+            let mut forward_referenced_items: Vec<(Id, String, Id)> = Vec::new(); // (Id, Declaration, Module)
 
-            if let ItemEnum::StructField(_field_type) = &item.inner {
-                let name = item.name.as_deref().unwrap_or("_");
-                let field_header_level = self.get_current_header_level();
+            for id in &resolved_module.items {
+                if !self.selected_ids.contains(id) {
+                    continue;
+                }
 
-                // Header: e.g., ##### 1.1.1.1: `field_name`
-                writeln!(
-                    self.output,
-                    "{} {} `{}`\n", // Add newline after header
-                    "#".repeat(field_header_level),
-                    header_prefix,
-                    name
-                )
-                .unwrap();
+                if let Some(existing_prefix) = self.printed_ids.get(id) {
+                    if let Some(item) = self.krate.index.get(id) {
+                        // Only add to cross-reference list if it's a kind we'd normally list directly
+                        if !matches!(
+                            item.inner,
+                            ItemEnum::Impl(_)
+                                | ItemEnum::Use { .. }
+                                | ItemEnum::StructField(_)
+                                | ItemEnum::Variant(_) // Variants are part of enums
+                                | ItemEnum::AssocConst { .. } // Assoc items are part of traits/impls
+                                | ItemEnum::AssocType { .. }
+                                | ItemEnum::Module(_)
+                        ) {
+                            let decl =
+                                generate_item_declaration(item, self, &self.current_module_path);
+                            cross_referenced_items.push((*id, decl, existing_prefix.clone()));
+                        }
+                    }
+                    continue; // Skip adding to items_by_kind if already printed
+                }
 
-                // Print docs (using helper, handles template mode)
-                self.print_docs(item);
+                if let Some(detail_module) = self
+                    .detail_modules
+                    .get(id)
+                    .filter(|detail_module| *detail_module != module_id)
+                {
+                    // Detailed in a module that is printed later
+                    if let Some(item) = self.krate.index.get(id) {
+                        let decl = generate_item_declaration(item, self, &self.current_module_path);
+                        forward_referenced_items.push((*id, decl, *detail_module));
+                    }
+                    continue;
+                }
 
-                // Type (optional, could add here if needed)
-                // writeln!(self.output, "_Type: `{}`_\n", format_type(field_type, self.krate)).unwrap();
-                return true; // Field was printed
+                if let Some(kind) = self.get_item_kind(id) {
+                    match kind {
+                        ItemKind::Impl
+                        | ItemKind::Variant // Handled by Enum
+                        | ItemKind::StructField // Handled by Struct/Union/Variant
+                        | ItemKind::AssocConst // Handled by Trait/Impl
+                        | ItemKind::AssocType // Handled by Trait/Impl
+                        | ItemKind::Use // Resolved, not printed directly
+                        | ItemKind::Module => continue, // Handled by main loop
+                        _ => {}
+                    }
+                    items_by_kind.entry(kind).or_default().push(*id);
+                }
             }
-        }
-        // Mark as printed even if item lookup failed (shouldn't happen ideally)
-        self.printed_ids.insert(*field_id, self.get_header_prefix());
-        false
-    }
 
-    /// Prints the details for a single enum variant field, only if it has printable documentation.
-    /// Returns true if the field was printed, false otherwise.
-    fn print_variant_field_details(&mut self, field_id: &Id) -> bool {
-        if !self.selected_ids.contains(field_id) || self.printed_ids.contains_key(field_id) {
-            return false; // Skip unselected or already printed
-        }
+            // Sort items within each kind
+            for ids in items_by_kind.values_mut() {
+                self.sort_items_by(ids, |id| *id);
+            }
+            let visibility = |id: &Id| {
+                self.reexport_visibility
+                    .then(|| self.find_reexport_visibility(module_id, id))
+                    .flatten()
+                    .map(|vis| format!(" (`{}`)", format_use_visibility(vis, self)))
+                    .unwrap_or_default()
+            };
+            // (Declaration, reference), sorted by declaration
+            let mut cross_references: Vec<(String, String)> = cross_referenced_items
+                .into_iter()
+                .map(|(id, declaration, prefix)| {
+                    let reference =
+                        self.format_cross_reference(&id, &declaration, &visibility(&id), &prefix);
+                    (declaration, reference)
+                })
+                .chain(forward_referenced_items.into_iter().map(
+                    |(id, declaration, detail_module)| {
+                        let reference = self.format_module_reference(
+                            &id,
+                            &declaration,
+                            &visibility(&id),
+                            &detail_module,
+                        );
+                        (declaration, reference)
+                    },
+                ))
+                .collect();
+            cross_references.sort();
 
-        if let Some(item) = self.krate.index.get(field_id) {
-            let field_has_printable_docs =
-                (self.template_mode && item.docs.is_some()) || has_docs(item);
+            let print_order = [
+                (ItemKind::Macro, "Macros"),
+                (ItemKind::ProcAttribute, "Attribute Macros"),
+                (ItemKind::ProcDerive, "Derive Macros"),
+                (ItemKind::Struct, "Structs"),
+                (ItemKind::Enum, "Enums"),
+                (ItemKind::Union, "Unions"),
+                (ItemKind::Trait, "Traits"),
+                (ItemKind::Function, "Functions"),
+                (ItemKind::TypeAlias, "Type Aliases"),
+                (ItemKind::TraitAlias, "Trait Aliases"),
+                (ItemKind::Static, "Statics"),
+                (ItemKind::Constant, "Constants"),
+                (ItemKind::ExternCrate, "External Crates"),
+                (ItemKind::ExternType, "External Types"),
+                (ItemKind::Primitive, "Primitives"),
+            ];
 
-            // Only proceed if the field has printable documentation
-            if !field_has_printable_docs {
-                // If no docs, the ID should already be marked printed in print_variant_details
-                return false;
+            if self.signature_index {
+                let signatures: Vec<String> = print_order
+                    .iter()
+                    .filter_map(|(kind, _)| items_by_kind.get(kind))
+                    .flatten()
+                    .filter_map(|id| self.format_index_signature(id))
+                    .collect();
+                if !signatures.is_empty() {
+                    writeln!(
+                        self.output,
+                        "```{}\n{}\n```",
+                        self.code_fence_lang,
+                        signatures.join("\n")
+                    )
+                    .unwrap();
+                }
             }
-            let header_prefix = self.get_header_prefix();
-            // Mark as printed *before* printing details
-            self.printed_ids.insert(*field_id, header_prefix.clone());
 
-            if let ItemEnum::StructField(_field_type) = &item.inner {
-                let name = item.name.as_deref().unwrap_or("_"); // Might be _ for tuple fields
-                let field_header_level = self.get_current_header_level();
+            for (kind, header_name) in print_order {
+                if let Some(ids) = items_by_kind.get(&kind) {
+                    if ids.is_empty() {
+                        continue;
+                    }
+                    if self.print_items_of_kind(ids, kind, header_name) {
+                        self.post_increment_current_level();
+                    }
+                }
+            }
 
-                // Header: e.g., ###### 1.1.1.1.1: `field_name`
-                // Use field index for tuple fields if name is "_" (name is often '0', '1' etc.)
-                let header_name = if name == "_" || name.chars().all(|c| c.is_ascii_digit()) {
-                    format!("Field {}", name)
-                } else {
-                    name.to_string()
-                };
+            // Print cross-referenced items at the end of the module's direct items
+            if !cross_references.is_empty() {
+                let re_exports_header_level = self.get_current_header_level();
+                let re_exports_prefix = self.get_header_prefix();
                 writeln!(
                     self.output,
-                    "{} {} `{}`\n", // Add newline after header
-                    "#".repeat(field_header_level),
-                    header_prefix,
-                    header_name
+                    "\n{} {} Re-exports\n",
+                    self.header_marker(re_exports_header_level),
+                    re_exports_prefix
                 )
                 .unwrap();
-
-                // Print Docs (using helper, handles template mode)
-                self.print_docs(item);
-
-                // Increment level counter for this field item
+                for (_, reference) in cross_references {
+                    writeln!(self.output, "- {}", reference).unwrap();
+                }
+                writeln!(self.output).unwrap(); // Add a blank line after the list
                 self.post_increment_current_level();
-
-                // Type (optional)
-                // writeln!(self.output, "_Type: `{}`_\n", format_type(field_type, self.krate)).unwrap();
-                return true; // Field was printed
             }
+        } else {
+            warn!(
+                "Could not find resolved module data for ID: {:?}",
+                module_id
+            );
         }
-        // Mark as printed even if item lookup failed
-        self.printed_ids.insert(*field_id, self.get_header_prefix());
-        false
     }
 
-    /// Checks if any selected variant or its fields have printable documentation.
-    #[allow(unused)]
-    fn has_printable_variants(&self, e: &Enum) -> bool {
-        e.variants.iter().any(|variant_id| {
-            if !self.selected_ids.contains(variant_id) {
-                return false;
+    /// Formats the one-line signature of `id` for the
+    /// [signature index](Printer::signature_index), e.g. `pub fn parse(s: &str) -> u8;`.
+    /// Bodies are elided, e.g. `pub struct Point { .. }` or `pub struct Meters(..);`.
+    fn format_index_signature(&self, id: &Id) -> Option<String> {
+        let item = self.krate.index.get(id)?;
+        let name = item.name.as_deref()?;
+        let visibility = format_visibility(&item.visibility, self);
+        let signature = match &item.inner {
+            ItemEnum::Constant { type_, .. } => {
+                format!(
+                    "{}const {}: {};",
+                    visibility,
+                    name,
+                    format_type(type_, self)
+                )
             }
-            if let Some(item) = self.krate.index.get(variant_id) {
-                // Check variant itself
-                if (self.template_mode && item.docs.is_some()) || has_docs(item) {
-                    return true;
-                }
-                // Check fields within the variant
-                if let ItemEnum::Variant(v) = &item.inner {
-                    let field_ids: Vec<Id> = match &v.kind {
-                        VariantKind::Plain => vec![],
-                        VariantKind::Tuple(fields) => {
-                            fields.iter().filter_map(|opt_id| *opt_id).collect()
-                        }
-                        VariantKind::Struct { fields, .. } => fields.clone(),
-                    };
-                    for field_id in field_ids {
-                        if self.selected_ids.contains(&field_id) {
-                            if let Some(f_item) = self.krate.index.get(&field_id) {
-                                if (self.template_mode && f_item.docs.is_some()) || has_docs(f_item)
-                                {
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                }
+            ItemEnum::Static(st) => format!(
+                "{}static {}{}: {};",
+                visibility,
+                if st.is_mutable { "mut " } else { "" },
+                name,
+                format_type(&st.type_, self)
+            ),
+            ItemEnum::TypeAlias(ta) => format!(
+                "{}{} = {};",
+                visibility,
+                generate_item_declaration(item, self, &[]),
+                format_type(&ta.type_, self)
+            ),
+            ItemEnum::Function(_) => {
+                let declaration = generate_item_declaration(item, self, &[]);
+                let attributes = format_attributes_inline(&item.attrs);
+                let declaration = declaration
+                    .strip_prefix(&attributes)
+                    .unwrap_or(&declaration);
+                format!("{}{};", visibility, declaration)
             }
-            false
-        })
-    }
-
-    /// Prints the "Variants" section for an enum, only if needed.
-    /// Also marks variants *and their fields* without printable documentation as printed.
-    fn print_enum_variants(&mut self, _item: &Item, e: &Enum) {
-        let mut has_printable_variant_or_field = false;
-        let mut printed_any_variant = false;
-
-        // First pass: Mark non-printable variants/fields printed and check if any are printable.
-        for variant_id in &e.variants {
-            if !self.selected_ids.contains(variant_id) {
-                continue; // Skip unselected variants
+            ItemEnum::Macro(_) | ItemEnum::ProcMacro(_) | ItemEnum::Primitive(_) => {
+                format!("{};", generate_item_declaration(item, self, &[]))
             }
+            ItemEnum::Struct(Struct {
+                kind: StructKind::Tuple(_),
+                ..
+            }) => format!(
+                "{}{}(..);",
+                visibility,
+                generate_item_declaration(item, self, &[])
+            ),
+            ItemEnum::Struct(Struct {
+                kind: StructKind::Plain { .. },
+                ..
+            })
+            | ItemEnum::Enum(_)
+            | ItemEnum::Union(_)
+            | ItemEnum::Trait(_) => format!(
+                "{}{} {{ .. }}",
+                visibility,
+                generate_item_declaration(item, self, &[])
+            ),
+            _ => format!(
+                "{}{};",
+                visibility,
+                generate_item_declaration(item, self, &[])
+            ),
+        };
+        Some(signature)
+    }
 
-            if let Some(item) = self.krate.index.get(variant_id) {
-                let variant_has_printable_docs =
-                    (self.template_mode && item.docs.is_some()) || has_docs(item);
-                let mut variant_has_printable_field = false;
+    /// Finds the visibility of the `use` declaration of `module_id` that brings `id`
+    /// into scope, preferring a direct import over a glob import.
+    fn find_reexport_visibility(
+        &self,
+        module_id: &Id,
+        id: &Id,
+    ) -> Option<&rustdoc_types::Visibility> {
+        let Some(ItemEnum::Module(module)) = self.krate.index.get(module_id).map(|m| &m.inner)
+        else {
+            return None;
+        };
+        let uses: Vec<(&Item, &rustdoc_types::Use)> = module
+            .items
+            .iter()
+            .filter_map(|use_id| self.krate.index.get(use_id))
+            .filter_map(|item| match &item.inner {
+                ItemEnum::Use(u) => Some((item, u)),
+                _ => None,
+            })
+            .collect();
+        let direct = uses
+            .iter()
+            .find(|(_, u)| !u.is_glob && u.id.as_ref() == Some(id));
+        let glob = || {
+            uses.iter().find(|(_, u)| {
+                u.is_glob
+                    && u.id
+                        .and_then(|source| self.resolved_modules.get(&source))
+                        .is_some_and(|source| source.items.contains(id))
+            })
+        };
+        direct.or_else(glob).map(|(item, _)| &item.visibility)
+    }
 
-                // Check fields within the variant
-                if let ItemEnum::Variant(v) = &item.inner {
-                    let field_ids: Vec<Id> = match &v.kind {
-                        VariantKind::Plain => vec![],
-                        VariantKind::Tuple(fields) => {
-                            fields.iter().filter_map(|opt_id| *opt_id).collect()
-                        }
-                        VariantKind::Struct { fields, .. } => fields.clone(),
-                    };
+    /// Prints graph context for an unprinted item.
+    fn print_graph_context(&mut self, id: &Id) {
+        // Collect incoming edges first to release immutable borrow on self.graph
+        let incoming_edges_data: Vec<Edge> = self
+            .graph
+            .find_incoming_edges(id)
+            .into_iter()
+            .cloned()
+            .collect();
 
-                    for field_id in field_ids {
-                        if self.selected_ids.contains(&field_id) {
-                            let field_has_printable_docs =
-                                self.krate.index.get(&field_id).is_some_and(|f_item| {
-                                    (self.template_mode && f_item.docs.is_some())
-                                        || has_docs(f_item)
-                                });
-                            if field_has_printable_docs {
-                                if !self.printed_ids.contains_key(&field_id) {
-                                    variant_has_printable_field = true;
-                                }
-                            } else {
-                                self.printed_ids.insert(field_id, self.get_header_prefix());
-                                // Mark non-printable field printed
-                            }
-                        } else {
-                            // Mark unselected field id printed if present
-                            self.printed_ids.insert(field_id, self.get_header_prefix());
-                        }
-                    }
-                }
+        if !incoming_edges_data.is_empty() {
+            writeln!(self.output, "_Referenced by:_").unwrap();
+            // Sort edges for consistent output
+            let mut sorted_edges = incoming_edges_data;
+            sorted_edges.sort_by_key(|edge| {
+                (
+                    format_id_path_canonical(&edge.source, self),
+                    format!("{:?}", edge.label),
+                )
+            });
 
-                if variant_has_printable_docs || variant_has_printable_field {
-                    // Check if the variant itself is already printed to avoid double counting
-                    if !self.printed_ids.contains_key(variant_id) {
-                        has_printable_variant_or_field = true;
-                    }
+            // Push level for this list (for template markers)
+            self.push_level();
+            for edge in sorted_edges {
+                self.post_increment_current_level(); // Increment for this list item
+                let source_path = format_id_path_canonical(&edge.source, self);
+                let template_marker = if self.template_mode
+                    && self
+                        .krate
+                        .index
+                        .get(&edge.source)
+                        .is_some_and(|i| i.docs.is_some())
+                {
+                    format!("\n  {}", self.get_template_marker())
                 } else {
-                    // Mark non-printable variant (with no printable fields) as printed immediately
-                    self.printed_ids
-                        .insert(*variant_id, self.get_header_prefix());
-                }
-            } else {
-                // If item doesn't exist in index but ID was present, mark it printed
-                self.printed_ids
-                    .insert(*variant_id, self.get_header_prefix());
-            }
-        }
-
-        // Only print the "Variants" section if there's a printable variant/field or stripped variants exist
-        if !has_printable_variant_or_field && !e.has_stripped_variants {
-            return;
-        }
-
-        let variants_header_level = self.get_current_header_level();
-        let header_prefix = self.get_header_prefix();
-        writeln!(
-            self.output,
-            "{} {} Variants\n", // Add newline after header
-            "#".repeat(variants_header_level),
-            header_prefix
-        )
-        .unwrap();
-
-        // Push a new level for the variant items themselves
-        self.push_level();
-        // Second pass: Print details for variants that have printable docs or contain printable fields
-        for variant_id in &e.variants {
-            if self.print_variant_details(variant_id) {
-                printed_any_variant = true;
-            }
-        }
-
-        if e.has_stripped_variants {
-            // Add newline before stripped message only if variants were printed
-            if printed_any_variant {
-                writeln!(self.output).unwrap();
+                    "".to_string()
+                };
+                writeln!(
+                    self.output,
+                    "- `{}` ({}){}",
+                    source_path,
+                    edge.label, // Use Display impl for EdgeLabel
+                    template_marker
+                )
+                .unwrap();
             }
-            writeln!(self.output, "_[Private variants hidden]_").unwrap();
+            self.pop_level(); // Pop list level
+            writeln!(self.output).unwrap(); // Add trailing newline
+        } else {
+            writeln!(
+                self.output,
+                "_Item has no known incoming references in the graph._\n"
+            )
+            .unwrap();
         }
-        self.pop_level(); // Pop the variant item level
-        self.post_increment_current_level();
     }
 
-    /// Prints the details for a single enum variant. Includes variant docs and docs for its fields if present.
-    /// Returns true if the variant was printed (because it or its fields had printable docs), false otherwise.
-    fn print_variant_details(&mut self, variant_id: &Id) -> bool {
-        if !self.selected_ids.contains(variant_id) {
-            // If already printed, do not print full details again.
-            // Cross-referencing for variants re-exported in other modules is not typical.
-            // If a variant is listed in a module, it's usually because its enum is listed.
-            if self.printed_ids.contains_key(variant_id) {
-                return false;
-            }
-        } else if self.printed_ids.contains_key(variant_id) {
-            // Already printed, skip
-            return false;
+    /// Recursive function to print modules and their contents depth-first.
+    fn print_module_recursive(&mut self, module_id: Id) {
+        // Skip if not selected. If already printed, we still need to list its re-exports.
+        if module_id != self.krate.root && !self.selected_ids.contains(&module_id) {
+            return;
         }
 
-        if let Some(item) = self.krate.index.get(variant_id) {
-            if let ItemEnum::Variant(variant_data) = &item.inner {
-                let variant_has_printable_docs =
-                    (self.template_mode && item.docs.is_some()) || has_docs(item);
-                let mut printable_fields = Vec::new();
-                let mut printed_any_field = false;
+        if let Some(item) = self.krate.index.get(&module_id) {
+            // Update current_module_path
+            let module_segment = item.name.as_deref().unwrap_or("").to_string();
+            if module_id == self.krate.root {
+                // For root module, use the crate name
+                self.current_module_path = vec![self
+                    .krate
+                    .index
+                    .get(&self.krate.root)
+                    .unwrap()
+                    .name
+                    .as_ref()
+                    .unwrap()
+                    .replace('-', "_")];
+            } else {
+                self.current_module_path.push(module_segment);
+            }
 
-                // Determine fields and check their printable docs
-                let (field_ids, stripped) = match &variant_data.kind {
-                    VariantKind::Plain => (vec![], false),
-                    VariantKind::Tuple(fields) => {
-                        (fields.iter().filter_map(|opt_id| *opt_id).collect(), false)
-                    }
-                    VariantKind::Struct {
-                        fields,
-                        has_stripped_fields: s,
-                    } => (fields.clone(), *s),
-                };
+            let module_header_level = self.get_current_header_level(); // Should be 2
+            let header_prefix = self.get_header_prefix();
+            let module_path_str = self.current_module_path.join("::");
+            let display_path = if module_path_str.is_empty() {
+                item.name.as_deref().unwrap_or("::")
+            } else {
+                &module_path_str
+            };
 
-                for field_id in &field_ids {
-                    if self.selected_ids.contains(field_id) {
-                        let field_has_printable_docs =
-                            self.krate.index.get(field_id).is_some_and(|f_item| {
-                                (self.template_mode && f_item.docs.is_some()) || has_docs(f_item)
-                            });
-                        if field_has_printable_docs && !self.printed_ids.contains_key(field_id) {
-                            printable_fields.push(*field_id);
-                        } else {
-                            // Mark unselected or non-printable field printed
-                            self.printed_ids.insert(*field_id, self.get_header_prefix());
-                        }
-                    } else {
-                        // Mark unselected field printed
-                        self.printed_ids.insert(*field_id, self.get_header_prefix());
-                    }
-                }
+            // Print module header (always H2)
+            writeln!(
+                self.output,
+                "\n{} {} Module: `{}`\n", // Module header uses level 2
+                self.header_marker(module_header_level),
+                header_prefix,
+                display_path
+            )
+            .unwrap();
 
-                // Only print the variant if it has printable docs OR it has printable fields to print
-                if !variant_has_printable_docs && printable_fields.is_empty() {
-                    // Mark variant as printed if skipped
-                    self.printed_ids
-                        .insert(*variant_id, self.get_header_prefix());
-                    return false;
-                }
+            // Mark module as printed only AFTER printing its header, if not already printed
+            // This ensures the first time a module is encountered, its prefix is stored.
+            self.printed_ids
+                .entry(module_id)
+                .or_insert_with(|| header_prefix.clone());
 
-                let header_prefix = self.get_header_prefix();
-                // Mark as printed *before* printing details
-                self.printed_ids.insert(*variant_id, header_prefix.clone());
+            self.push_level();
 
-                let signature = format_variant_signature(item, variant_data, self.krate);
-                let variant_header_level = self.get_current_header_level();
+            // Print module docs (using helper); the crate root's docs are printed at the
+            // top of the document by `finalize`
+            if module_id != self.krate.root {
+                self.print_docs(item);
+            }
 
-                // Header: e.g., ##### 1.1.1.1: `VariantSignature`
-                writeln!(
-                    self.output,
-                    "{} {} `{}`\n", // Add newline after header
-                    "#".repeat(variant_header_level),
-                    header_prefix,
-                    signature
-                )
-                .unwrap();
-                self.push_level();
+            // --- Module Common Traits ---
+            if !self.no_common_traits {
+                let mod_common = self.calculate_module_common_traits(&module_id);
+                self.module_common_traits
+                    .insert(module_id, mod_common.clone()); // Store for later use
 
-                // Print Variant Docs (using helper)
-                self.print_docs(item);
+                let displayable_module_common: Vec<FormattedTraitImpl> = mod_common
+                    .iter()
+                    .filter(|nt| !self.crate_common_traits.contains(nt)) // Only those not in crate common
+                    .cloned()
+                    .collect();
 
-                // Print documented fields (if any)
-                if !printable_fields.is_empty() || stripped {
-                    let field_section_level = self.get_current_header_level();
-                    let fields_header_prefix = self.get_header_prefix();
+                if !displayable_module_common.is_empty() {
+                    let common_traits_header_level = self.get_current_header_level(); // Should be H3
+                    let common_traits_prefix = self.get_header_prefix();
                     writeln!(
                         self.output,
-                        "{} {} Fields\n", // Add newline after header
-                        "#".repeat(field_section_level),
-                        fields_header_prefix
+                        "{} {} Common Traits\n",
+                        self.header_marker(common_traits_header_level),
+                        common_traits_prefix
                     )
                     .unwrap();
-                    self.push_level();
-
-                    for field_id in printable_fields {
-                        if self.print_variant_field_details(&field_id) {
-                            printed_any_field = true;
-                        }
-                    }
-
-                    if stripped {
-                        if printed_any_field {
-                            writeln!(self.output).unwrap(); // Add newline before stripped message
-                        }
-                        writeln!(self.output, "_[Private fields hidden]_").unwrap();
+                    writeln!(self.output, "In addition to the crate's 'Common Traits', the following traits are commonly implemented by types in this module. Unless otherwise noted, you can assume these traits are implemented:\n").unwrap();
+                    let formatted_list = self.format_trait_list(&displayable_module_common);
+                    if !formatted_list.is_empty() {
+                        write!(self.output, "{}", formatted_list).unwrap();
                     }
-                    self.pop_level();
+                    self.post_increment_current_level(); // Increment for this section
                 }
+            }
 
-                self.pop_level();
-                self.post_increment_current_level();
+            // Print module contents (non-module items only)
+            self.print_module_contents(&module_id);
+            self.print_depth_limited_modules(&module_id);
+            self.write_reference_footnotes();
 
-                return true; // Variant (or its fields) was printed
+            self.pop_level();
+            self.post_increment_current_level();
+
+            // Recursively print child modules, unless they are past the depth limit
+            let child_depth = self.current_module_path.len();
+            if self.max_module_depth.is_some_and(|max| child_depth > max) {
+                // Stubs were listed in this module's section
+            } else if self.split_modules && module_id == self.krate.root {
+                // Children of the root get their own files, see `finalize`
+            } else if let Some(children) = self.module_tree.children.get(&module_id).cloned() {
+                for child_id in children {
+                    self.print_module_recursive(child_id);
+                }
+            }
+
+            // Restore current_module_path
+            if module_id != self.krate.root {
+                self.current_module_path.pop();
             }
         }
-        // Mark as printed even if item lookup failed
-        self.printed_ids
-            .insert(*variant_id, self.get_header_prefix());
-        false
     }
 
-    /// Prints the "Associated Items" section for a trait, categorized.
-    fn print_trait_associated_items(&mut self, _trait_item: &Item, t: &Trait) {
-        let mut required_types = Vec::new();
-        let mut required_methods = Vec::new();
-        let mut provided_methods = Vec::new();
-        let mut has_printable_assoc_item = false;
+    /// Lists the selected submodules of `module_id` that are past
+    /// [`Printer::max_module_depth`] as stubs in a "Submodules" section, instead of
+    /// documenting them.
+    fn print_depth_limited_modules(&mut self, module_id: &Id) {
+        // The current module path includes the crate name, so its length is the depth
+        // of the submodules
+        if self
+            .max_module_depth
+            .is_none_or(|max| self.current_module_path.len() <= max)
+        {
+            return;
+        }
+        let Some(children) = self.module_tree.children.get(module_id) else {
+            return;
+        };
+        let stubs: Vec<String> = children
+            .iter()
+            .filter(|id| self.selected_ids.contains(id))
+            .filter_map(|id| self.krate.index.get(id)?.name.clone())
+            .map(|name| format!("- `mod {}` (depth limit reached)", name))
+            .collect();
+        if stubs.is_empty() {
+            return;
+        }
+        let header_level = self.get_current_header_level();
+        let header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} Submodules\n",
+            self.header_marker(header_level),
+            header_prefix
+        )
+        .unwrap();
+        writeln!(self.output, "{}\n", stubs.join("\n")).unwrap();
+        self.post_increment_current_level();
+    }
 
-        // Filter and categorize selected associated items.
-        for item_id in &t.items {
-            if !self.selected_ids.contains(item_id) {
-                continue;
-            }
-            // Mark the item as printed now, regardless of docs, to prevent it from going to "Other"
-            // Only mark if not already printed elsewhere with a different prefix.
-            // This is tricky because an assoc item's "primary" print location is under its trait.
-            if !self.printed_ids.contains_key(item_id) {
-                self.printed_ids.insert(*item_id, self.get_header_prefix());
-            }
+    /// Returns `true` if `id` belongs to a module past [`Printer::max_module_depth`],
+    /// assuming items without a path (such as methods) belong to such a module if
+    /// they were not printed.
+    fn is_beyond_module_depth(&self, id: &Id) -> bool {
+        let Some(max) = self.max_module_depth else {
+            return false;
+        };
+        self.krate
+            .paths
+            .get(id)
+            .is_none_or(|summary| summary.path.len() > max + 2)
+    }
 
-            if let Some(assoc_item) = self.krate.index.get(item_id) {
-                let item_has_printable_docs =
-                    (self.template_mode && assoc_item.docs.is_some()) || has_docs(assoc_item);
-                if item_has_printable_docs {
-                    has_printable_assoc_item = true;
-                }
+    /// Finalizes the documentation string, printing the crate header and contents.
+    /// Writes the buffered output to `w` and clears the buffer, keeping trailing
+    /// newlines so that spacing checks like `ends_with("\n\n")` keep working.
+    ///
+    /// Does nothing when the table of contents is enabled, as it needs the whole
+    /// document, or when splitting modules into files, as links are rewritten
+    /// afterwards.
+    fn flush_output(&mut self, w: &mut dyn std::io::Write) -> Result<()> {
+        if self.toc || self.split_modules {
+            return Ok(());
+        }
+        let content_len = self.output.trim_end_matches('\n').len();
+        let keep_from = content_len.max(self.output.len().saturating_sub(2));
+        self.write_output(w, &self.output[..keep_from])?;
+        self.output.drain(..keep_from);
+        Ok(())
+    }
 
-                match &assoc_item.inner {
-                    ItemEnum::AssocType { .. } => {
-                        required_types.push((*item_id, item_has_printable_docs));
-                    }
-                    ItemEnum::Function(f) => {
-                        if !f.has_body {
-                            required_methods.push((*item_id, item_has_printable_docs));
-                        } else {
-                            provided_methods.push((*item_id, item_has_printable_docs));
-                        }
-                    }
-                    ItemEnum::AssocConst { .. } => {
-                        // For now, treat associated consts similarly to required types or methods.
-                        // Could be a separate category if needed.
-                        // Let's put them with required types for now as they don't have 'body'.
-                        required_types.push((*item_id, item_has_printable_docs));
-                    }
-                    _ => {} // Ignore others
-                }
-            }
+    /// Writes generated Markdown to `w`. All output goes through here (or
+    /// [`Printer::sanitize_output`]), so text from any source (docs, README, examples,
+    /// deprecation notes, the manifest) is sanitized if
+    /// [`sanitize_docs`](Printer::sanitize_docs) is enabled.
+    fn write_output(&self, w: &mut dyn std::io::Write, text: &str) -> Result<()> {
+        w.write_all(self.sanitize_output(text).as_bytes())
+            .context("Failed to write documentation")
+    }
+
+    /// Strips control characters from generated Markdown if
+    /// [`sanitize_docs`](Printer::sanitize_docs) is enabled.
+    fn sanitize_output<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.sanitize_docs {
+            strip_control_chars(text)
+        } else {
+            Cow::Borrowed(text)
         }
+    }
 
-        // If no selected associated item has printable documentation, skip printing the entire section
-        if !has_printable_assoc_item {
+    /// Prints the module `module_id` (and its submodules). When splitting modules into
+    /// files, its section is moved into a separate [`ModuleFile`] and a link to it is
+    /// printed instead.
+    fn print_top_level_module(&mut self, module_id: Id) {
+        if !self.split_modules {
+            self.print_module_recursive(module_id);
+            return;
+        }
+        // Footnotes referenced so far belong to the preceding output
+        self.write_reference_footnotes();
+        let preceding_output = std::mem::take(&mut self.output);
+        self.print_module_recursive(module_id);
+        self.write_reference_footnotes(); // Keep definitions in the module's file
+        let content = std::mem::replace(&mut self.output, preceding_output);
+        if content.trim().is_empty() {
+            return;
+        }
+        let Some(module_path) = self.krate.paths.get(&module_id).map(|s| s.path.clone()) else {
+            warn!(
+                "No path for module {:?}; keeping it in the index",
+                module_id
+            );
+            self.output.push_str(&content);
             return;
+        };
+        // `-` cannot appear in Rust identifiers, so the file names cannot collide, and
+        // `crate` cannot name a module, so neither can the renamed root of `index`
+        let name = if module_path == ["index"] {
+            "index-crate.md".to_string()
+        } else {
+            format!("{}.md", module_path.join("-"))
+        };
+        if self.module_files.is_empty() && !self.output.ends_with("\n\n") {
+            writeln!(self.output).unwrap(); // Blank line before the list of module links
         }
+        writeln!(
+            self.output,
+            "- [Module `{}`]({})",
+            module_path.join("::"),
+            name
+        )
+        .unwrap();
+        self.module_files.push(ModuleFile {
+            name,
+            content: content.trim_start().to_string(),
+        });
+    }
 
-        // Sort items within each category
-        required_types.sort_by_key(|(id, _)| self.krate.index.get(id).and_then(|i| i.name.clone()));
-        required_methods
-            .sort_by_key(|(id, _)| self.krate.index.get(id).and_then(|i| i.name.clone()));
-        provided_methods
-            .sort_by_key(|(id, _)| self.krate.index.get(id).and_then(|i| i.name.clone()));
+    fn finalize(
+        mut self,
+        w: &mut dyn std::io::Write,
+    ) -> Result<(HashMap<String, String>, Vec<ModuleFile>)> {
+        let root_item = self.krate.index.get(&self.krate.root).unwrap(); // Assume root exists
+        let crate_name = root_item.name.as_deref().unwrap_or("Unknown Crate");
+        let crate_version = self.krate.crate_version.as_deref().unwrap_or("");
+        let crate_header_level = 1; // Level 1 for crate header
 
-        if required_types.iter().any(|(_, has_docs)| *has_docs) {
-            let sub_level = self.get_current_header_level();
-            let sub_prefix = self.get_header_prefix();
+        // Clear doc path before starting
+        self.doc_path.clear();
+
+        // Print Crate Header (# Crate Name (Version)) - No prefix
+        writeln!(
+            self.output,
+            "{} {} API ({})\n", // Add newline after header
+            self.header_marker(crate_header_level),
+            crate_name,
+            crate_version
+        )
+        .unwrap();
+        // Push H2 level before starting sections/modules
+        self.push_level();
+
+        // Print Crate Description (if available) - NEW
+        if let Some(desc) = &self.manifest_data.description {
+            writeln!(self.output, "{}\n", desc).unwrap();
+        }
+
+        // Print Crate Documentation (H2) from the root module's `//!` docs
+        if has_docs(root_item) || self.item_doc_overrides.contains_key(&root_item.id) {
+            let docs_section_level = self.get_current_header_level(); // Should be 2
+            let docs_header_prefix = self.get_header_prefix();
             writeln!(
                 self.output,
-                "{} {} Required Associated Types\n",
-                "#".repeat(sub_level),
-                sub_prefix
+                "{} {} Crate Documentation\n",
+                self.header_marker(docs_section_level),
+                docs_header_prefix
             )
             .unwrap();
             self.push_level();
-            for (id, has_docs) in required_types {
-                if has_docs {
-                    self.print_associated_item_summary(&id);
-                }
-            }
+            self.print_docs(root_item);
             self.pop_level();
             self.post_increment_current_level();
         }
 
-        if required_methods.iter().any(|(_, has_docs)| *has_docs) {
-            let sub_level = self.get_current_header_level();
-            let sub_prefix = self.get_header_prefix();
-            writeln!(
-                self.output,
-                "{} {} Required Methods\n",
-                "#".repeat(sub_level),
-                sub_prefix
-            )
-            .unwrap();
-            self.push_level();
-            for (id, has_docs) in required_methods {
-                if has_docs {
-                    self.print_associated_item_summary(&id);
+        // Print Manifest Section (H2) - NEW
+        let manifest_section_level = self.get_current_header_level(); // Should be 2
+        let manifest_header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} Manifest\n",
+            self.header_marker(manifest_section_level),
+            manifest_header_prefix
+        )
+        .unwrap();
+
+        // Use simple list format for manifest details
+        if let Some(hp) = &self.manifest_data.homepage {
+            writeln!(self.output, "- Homepage: <{}>", hp).unwrap();
+        }
+        if let Some(repo) = &self.manifest_data.repository {
+            writeln!(self.output, "- Repository: <{}>", repo).unwrap();
+        }
+        if !self.manifest_data.categories.is_empty() {
+            let links: Vec<String> = self
+                .manifest_data
+                .categories
+                .iter()
+                .map(|c| format!("[{}](https://crates.io/categories/{})", c, c))
+                .collect();
+            writeln!(self.output, "- Categories: {}", links.join(", ")).unwrap();
+        }
+        if !self.manifest_data.keywords.is_empty() {
+            let links: Vec<String> = self
+                .manifest_data
+                .keywords
+                .iter()
+                .map(|k| format!("[{}](https://crates.io/keywords/{})", k, k))
+                .collect();
+            writeln!(self.output, "- Keywords: {}", links.join(", ")).unwrap();
+        }
+        if let Some(lic) = &self.manifest_data.license {
+            writeln!(self.output, "- License: {}", lic).unwrap();
+        }
+        if let Some(rv) = &self.manifest_data.rust_version {
+            writeln!(self.output, "- rust-version: `{}`", rv).unwrap();
+        }
+        if let Some(ed) = &self.manifest_data.edition {
+            writeln!(self.output, "- edition: `{}`", ed).unwrap();
+        }
+        writeln!(self.output).unwrap(); // Add a newline after the list
+
+        // Print Features Sub-section (H3) - NEW
+        let features_section_level = self.get_current_header_level() + 1; // H3
+        self.push_level(); // Push for the H3 features section
+        let features_header_prefix = self.get_header_prefix();
+        writeln!(
+            self.output,
+            "{} {} Features\n",
+            self.header_marker(features_section_level),
+            features_header_prefix
+        )
+        .unwrap();
+
+        // List features or state None
+        if self.manifest_data.features.is_empty() {
+            writeln!(self.output, "- None").unwrap();
+        } else {
+            // Sort features for consistent output
+            let mut sorted_features: Vec<_> = self.manifest_data.features.keys().collect();
+            sorted_features.sort_unstable();
+            for feature_name in sorted_features {
+                // TODO: Maybe show what features a feature enables? Requires more parsing.
+                if self.all_features {
+                    writeln!(self.output, "- `{}` (enabled)", feature_name).unwrap();
+                } else {
+                    writeln!(self.output, "- `{}`", feature_name).unwrap();
                 }
             }
-            self.pop_level();
-            self.post_increment_current_level();
         }
+        writeln!(self.output).unwrap(); // Add newline after features list
+        self.pop_level(); // Pop H3 features level
 
-        if provided_methods.iter().any(|(_, has_docs)| *has_docs) {
-            let sub_level = self.get_current_header_level();
-            let sub_prefix = self.get_header_prefix();
+        // Increment H2 counter for the next section (README or Common Traits)
+        self.post_increment_current_level();
+
+        // Print README content if available from CrateExtra
+        if let Some(extra) = &self.crate_extra {
+            if let Some(readme) = &extra.readme_content {
+                info!("Injecting README content.");
+                let section_level = self.get_current_header_level(); // Should be 2
+                let header_prefix = self.get_header_prefix();
+                writeln!(
+                    self.output,
+                    "\n{} {} README\n",
+                    self.header_marker(section_level),
+                    header_prefix
+                )
+                .unwrap();
+                let adjusted_readme =
+                    adjust_markdown_headers(readme, self.shifted_header_level(section_level));
+                writeln!(self.output, "{}\n", adjusted_readme).unwrap();
+                self.post_increment_current_level(); // Increment H2 counter
+            }
+        }
+
+        // Print Crate Common Traits Section (H2)
+        if !self.no_common_traits && !self.crate_common_traits.is_empty() {
+            let common_traits_level = self.get_current_header_level(); // Should be 2
+            let common_traits_prefix = self.get_header_prefix();
             writeln!(
                 self.output,
-                "{} {} Provided Methods\n",
-                "#".repeat(sub_level),
-                sub_prefix
+                "\n{} {} Common Traits\n",
+                self.header_marker(common_traits_level),
+                common_traits_prefix
             )
             .unwrap();
-            self.push_level();
-            for (id, has_docs) in provided_methods {
-                if has_docs {
-                    self.print_associated_item_summary(&id);
-                }
+            writeln!(self.output, "The following traits are commonly implemented by types in this crate. Unless otherwise noted, you can assume these traits are implemented:\n").unwrap();
+
+            let sorted_common_traits: Vec<FormattedTraitImpl> = {
+                let mut traits: Vec<_> = self.crate_common_traits.iter().cloned().collect();
+                traits.sort_by(|a, b| {
+                    (&a.display_path, &a.formatted_markdown_list_entry)
+                        .cmp(&(&b.display_path, &b.formatted_markdown_list_entry))
+                });
+                traits
+            };
+
+            let formatted_list = self.format_trait_list(&sorted_common_traits);
+            if !formatted_list.is_empty() {
+                write!(self.output, "{}", formatted_list).unwrap();
             }
-            self.pop_level();
-            self.post_increment_current_level();
+            writeln!(self.output).unwrap();
+            self.post_increment_current_level(); // Increment H2 counter
         }
-    }
+        self.flush_output(w)?;
 
-    /// Generates the formatted summary string for an associated item (for use within impl blocks or trait defs).
-    /// Does NOT include the markdown header. Includes docs with adjusted headers, respecting template mode.
-    fn generate_associated_item_summary(&mut self, assoc_item_id: &Id) -> Option<String> {
-        if !self.selected_ids.contains(assoc_item_id) {
-            return None;
-        }
-        if let Some(item) = self.krate.index.get(assoc_item_id) {
-            let mut summary = String::new();
-            // Get level AFTER incrementing for the item header
-            // let assoc_item_header_level = self.get_current_header_level(); // Level not needed here
+        // --- Print Top-Level Sections (Macros first, then Modules) ---
 
-            // Add code block for associated functions if they have attrs/where clauses
-            if let ItemEnum::Function(f) = &item.inner {
-                let has_attrs = f.header.is_const
-                    || f.header.is_async
-                    || f.header.is_unsafe
-                    || !matches!(f.header.abi, Abi::Rust)
-                    || !item.attrs.is_empty(); // Check item.attrs for function attributes
-                let has_where = !f.generics.where_predicates.is_empty();
-                if has_attrs || has_where {
-                    let code = generate_function_code_block(item, f, self.krate);
-                    writeln!(summary, "```rust\n{}\n```\n", code).unwrap();
-                }
-            }
+        // --- Macros Section (Level 2) ---
+        // Find macros directly under the resolved root module
+        if let Some(resolved_root_module) = self.resolved_modules.get(&self.krate.root) {
+            let macro_ids: Vec<Id> = resolved_root_module
+                .items
+                .iter()
+                .filter(|id| self.selected_ids.contains(id))
+                .filter(|id| {
+                    matches!(
+                        self.get_item_kind(id),
+                        Some(ItemKind::Macro | ItemKind::ProcAttribute | ItemKind::ProcDerive)
+                    )
+                })
+                .cloned() // Clone the IDs
+                .collect();
 
-            // Print Documentation (using helper)
-            // Create a temporary DocPrinter to isolate output
-            let mut temp_printer = self.clone_with_new_output();
-            // Copy current doc path to temp printer for correct template marker generation
-            temp_printer.doc_path = self.doc_path.clone();
-            temp_printer.print_docs(item);
-            write!(summary, "{}", temp_printer.output).unwrap();
+            if !macro_ids.is_empty() {
+                let section_level = self.get_current_header_level(); // Should be 2
+                let header_prefix = self.get_header_prefix();
+                writeln!(
+                    self.output,
+                    "\n{} {} Macros",
+                    self.header_marker(section_level),
+                    header_prefix
+                )
+                .unwrap();
 
-            // Potentially add default values/bounds for assoc const/type here
-            match &item.inner {
-                // Use correct fields { type_, value }
-                ItemEnum::AssocConst { type_, value } => {
-                    writeln!(summary, "_Type: `{}`_", format_type(type_, self.krate)).unwrap();
-                    if let Some(val) = value {
-                        writeln!(summary, "_Default: `{}`_\n", val).unwrap(); // Add newline
+                self.push_level(); // Push H3 level for macro items
+                let mut sorted_macros = macro_ids;
+                if self.sort_order == SortOrder::AsDeclared {
+                    self.declaration_order = self.declaration_positions(&self.krate.root);
+                }
+                self.sort_items_by(&mut sorted_macros, |id| *id);
+                for id in sorted_macros {
+                    // Macro details at level 3
+                    if self.print_item_details(&id) {
+                        self.post_increment_current_level();
                     }
                 }
-                ItemEnum::AssocType { bounds, type_, .. } => {
-                    // Use renamed field type_
-                    if !bounds.is_empty() {
-                        let bounds_str = bounds
-                            .iter()
-                            .map(|b| format_generic_bound(b, self.krate))
-                            .collect::<Vec<_>>()
-                            .join(" + ");
-                        writeln!(summary, "_Bounds: `{}`_", bounds_str).unwrap();
+                self.pop_level(); // Pop H3 level
+                self.post_increment_current_level(); // Increment H2 counter
+            }
+        }
+        self.flush_output(w)?;
+
+        // --- Modules (Depth-First Traversal) ---
+
+        // 1. Print Crate Root Module explicitly (will increment H2 counter)
+        self.print_top_level_module(self.krate.root);
+        self.flush_output(w)?;
+
+        // 2. Iterate through sorted top-level modules and print recursively
+        // Clone the list to avoid borrowing issues
+        let mut top_level_ids = Vec::new();
+        if self.split_modules && self.max_module_depth.is_none_or(|max| max >= 1) {
+            // The root module did not recurse into its children
+            top_level_ids.extend(
+                self.module_tree
+                    .children
+                    .get(&self.krate.root)
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        top_level_ids.extend(self.module_tree.top_level_modules.iter().copied());
+        for module_id in top_level_ids {
+            self.print_top_level_module(module_id); // Will increment H2 counter
+            self.flush_output(w)?;
+        }
+
+        // --- Handle "Other" Items ---
+        let mut unprinted_ids = Vec::new();
+        for id in &self.selected_ids {
+            if !self.printed_ids.contains_key(id) && !self.is_beyond_module_depth(id) {
+                // Skip impl items and use items as they are handled implicitly or ignored
+                // Also skip struct fields as they are handled within their containers
+                // Also skip Modules as they are handled explicitly above
+                if let Some(item) = self.krate.index.get(id) {
+                    if !matches!(
+                        item.inner,
+                        ItemEnum::Impl(_)
+                            | ItemEnum::Use { .. }
+                            | ItemEnum::StructField(_)
+                            | ItemEnum::Module(_) // Modules explicitly skipped here
+                    ) && item.name.is_some()
+                    {
+                        unprinted_ids.push(*id);
                     }
-                    if let Some(ty) = type_ {
-                        writeln!(summary, "_Default: `{}`_\n", format_type(ty, self.krate))
-                            .unwrap(); // Add newline
+                    // If it doesn't have a name or is a StructField/Module but is selected & unprinted, mark it printed now to avoid the warning
+                    else if item.name.is_none()
+                        || matches!(item.inner, ItemEnum::StructField(_) | ItemEnum::Module(_))
+                    {
+                        self.printed_ids.insert(*id, "SKIPPED_OTHER".to_string());
                     }
+                } else {
+                    // ID selected but not in index - treat as unprinted for "Other"
+                    unprinted_ids.push(*id);
                 }
-                _ => {}
             }
-            Some(summary)
-        } else {
-            None
         }
-    }
 
-    /// Prints the header and summary for a single associated item (const, type, function).
-    fn print_associated_item_summary(&mut self, assoc_item_id: &Id) {
-        if let Some(item) = self.krate.index.get(assoc_item_id) {
-            // Generate summary first (handles template mode internally)
-            if let Some(summary) = self.generate_associated_item_summary(assoc_item_id) {
-                let declaration =
-                    generate_item_declaration(item, self.krate, &self.current_module_path);
-                let assoc_item_header_level = self.get_current_header_level();
+        if !unprinted_ids.is_empty() {
+            if self.include_other {
+                warn!(
+                    "Found {} selected items that were not printed in the main structure. Including them in the 'Other' section.",
+                    unprinted_ids.len()
+                );
+                let other_section_level = self.get_current_header_level(); // Should be 2
                 let header_prefix = self.get_header_prefix();
-                // Print Header (e.g. ##### 1.1.1.1: `declaration`)
                 writeln!(
                     self.output,
-                    "{} {} `{}`\n", // Add newline after header
-                    "#".repeat(assoc_item_header_level),
-                    header_prefix,
-                    declaration
+                    "\n{} {} Other", // Use ## level for this section
+                    self.header_marker(other_section_level),
+                    header_prefix
                 )
                 .unwrap();
-                // Print the generated summary
-                if !summary.trim().is_empty() {
-                    writeln!(self.output, "{}", summary.trim()).unwrap();
-                }
-                writeln!(self.output).unwrap(); // Ensure a blank line afterwards
-
-                self.post_increment_current_level();
-            }
-            // If generate_associated_item_summary returns None, the item wasn't selected,
-            // so we don't print anything, and the level increment effectively skips it.
-        }
-    }
-
-    /// Helper to categorize and format a list of FormattedTraitImpls for display.
-    fn format_trait_list(&mut self, traits_to_format: &[FormattedTraitImpl]) -> String {
-        if traits_to_format.is_empty() {
-            return String::new();
-        }
-
-        let mut output = String::new();
-        let mut simple_impls = Vec::new();
-        let mut generic_or_complex_impls = Vec::new();
-        let mut auto_traits = Vec::new();
-        let mut blanket_impls = Vec::new();
 
-        for norm_trait in traits_to_format {
-            match norm_trait.category {
-                TraitImplCategory::Simple => simple_impls.push(norm_trait),
-                TraitImplCategory::GenericOrComplex => generic_or_complex_impls.push(norm_trait),
-                TraitImplCategory::Auto => auto_traits.push(norm_trait),
-                TraitImplCategory::Blanket => blanket_impls.push(norm_trait),
-            }
-        }
+                // Push H3 level for items in Other
+                self.push_level();
+                // Sort unprinted items for consistent output
+                unprinted_ids.sort_by_key(|id| {
+                    (
+                        self.krate.paths.get(id).map(|p| p.path.clone()),
+                        self.krate.index.get(id).and_then(|i| i.name.clone()),
+                    )
+                });
 
-        // Sort each category by the pre-formatted list entry string
-        simple_impls.sort_by_key(|t| &t.formatted_markdown_list_entry);
-        generic_or_complex_impls.sort_by_key(|t| &t.formatted_markdown_list_entry);
-        auto_traits.sort_by_key(|t| &t.formatted_markdown_list_entry);
-        blanket_impls.sort_by_key(|t| &t.formatted_markdown_list_entry);
+                for id in &unprinted_ids {
+                    let path_str = format_id_path_canonical(id, &self);
+                    warn!("Including unprinted item in 'Other' section: {}", path_str);
 
-        self.push_level();
-        let mut preceding_section = false;
+                    // Fetch the item to print its header and span
+                    if let Some(item) = self.krate.index.get(id) {
+                        if !self.print_item_details(id) {
+                            continue;
+                        }
 
-        let mut print_section =
-            |traits: &[&FormattedTraitImpl], current_output: &mut String, _section_name: &str| {
-                if !traits.is_empty() {
-                    if preceding_section {
-                        writeln!(current_output).unwrap();
-                    }
-                    for norm_trait in traits {
+                        // Print Source Location (if available) ONLY for "Other" items
+                        if let Some(span) = &item.span {
+                            writeln!(
+                                self.output,
+                                "_Source: `{}:{}:{}`_\n", // Italic, newline after
+                                span.filename.display(),
+                                span.begin.0 + 1, // Line numbers are 0-based
+                                span.begin.1 + 1  // Column numbers are 0-based
+                            )
+                            .unwrap();
+                        }
+                        // Always print graph context afterwards for items in "Other"
+                        self.print_graph_context(id);
+                        self.post_increment_current_level();
+                    } else {
+                        // Handle case where ID is selected but not in index (rare)
+                        let other_item_level = self.get_current_header_level();
+                        let item_prefix = self.get_header_prefix();
                         writeln!(
-                            current_output,
-                            "{}",
-                            norm_trait.formatted_markdown_list_entry
+                            self.output,
+                            "\n{} {} `{}`\n",
+                            self.header_marker(other_item_level),
+                            item_prefix,
+                            path_str // Use path string as header
                         )
                         .unwrap();
-                        if let Some((trait_impl, impl_id)) = norm_trait.get_impl_data(self.krate) {
-                            self.printed_ids.insert(impl_id, self.get_header_prefix());
-                            for assoc_item_id in &trait_impl.items {
-                                if self.selected_ids.contains(assoc_item_id) {
-                                    self.printed_ids
-                                        .insert(*assoc_item_id, self.get_header_prefix());
-                                }
-                            }
-                        }
+                        writeln!(self.output, "_Error: Item details not found in index._\n")
+                            .unwrap();
+                        self.print_graph_context(id); // Still print graph context
                         self.post_increment_current_level();
                     }
-                    preceding_section = true;
                 }
-            };
-
-        print_section(&simple_impls, &mut output, "Simple");
-        print_section(&generic_or_complex_impls, &mut output, "Generic or Complex");
-        print_section(&auto_traits, &mut output, "Auto");
-        print_section(&blanket_impls, &mut output, "Blanket");
-
-        self.pop_level();
-        output
-    }
-
-    /// Prints Inherent and Trait Implementations *for* an item (Struct, Enum, Union, Primitive).
-    fn print_item_implementations(&mut self, impl_ids: &[Id], target_item: &Item) {
-        let target_item_id = target_item.id;
-        let target_name = target_item
-            .name
-            .as_deref()
-            .unwrap_or(match &target_item.inner {
-                ItemEnum::Primitive(Primitive { name, .. }) => name.as_str(),
-                _ => "{unknown_item_type}",
-            });
-
-        let mut item_specific_impl_data = Vec::new();
-        for impl_id in impl_ids {
-            if let Some(impl_item) = self.krate.index.get(impl_id) {
-                if self.selected_ids.contains(&impl_item.id) {
-                    if let ItemEnum::Impl(imp) = &impl_item.inner {
-                        // Critical: Only consider this impl if it's FOR the target_item_id
-                        if get_type_id(&imp.for_) == Some(target_item_id) {
-                            item_specific_impl_data.push((impl_item, imp.clone()));
-                        }
+                self.pop_level(); // Pop H3 level for items
+                self.write_reference_footnotes();
+            } else {
+                // Group by kind and log counts
+                let mut counts_by_kind: HashMap<ItemKind, usize> = HashMap::new(); // Use HashMap
+                for id in &unprinted_ids {
+                    if let Some(kind) = self.get_item_kind(id) {
+                        *counts_by_kind.entry(kind).or_insert(0) += 1;
+                    } else {
+                        // Count items where kind couldn't be determined (e.g., ID not in index)
+                        *counts_by_kind.entry(ItemKind::StructField).or_insert(0) += 1;
+                        // Use a placeholder kind like StructField
                     }
                 }
-            }
-        }
-
-        // --- Inherent Impls ---
-        let inherent_impl_items: Vec<_> = item_specific_impl_data
-            .iter()
-            .filter(|(_, imp)| imp.trait_.is_none())
-            .collect();
-
-        if !inherent_impl_items.is_empty() {
-            for (impl_item, imp) in inherent_impl_items {
-                if self.printed_ids.contains_key(&impl_item.id) {
-                    continue;
-                }
-                self.print_impl_block_details(impl_item, imp);
-            }
-        }
-
-        // --- Trait Impls ---
-        let trait_impl_data: Vec<FormattedTraitImpl> = item_specific_impl_data
-            .iter()
-            .filter_map(|(impl_item, imp)| {
-                if self.printed_ids.contains_key(&impl_item.id) {
-                    return None; // Skip already printed impls
-                }
-                imp.trait_.as_ref().map(|tp| {
-                    FormattedTraitImpl::from_impl(imp, Some(impl_item.id), tp, self.krate, self)
-                })
-            })
-            .collect();
-
-        if trait_impl_data.is_empty() {
-            return;
-        }
-
-        let current_module_id = self
-            .current_module_path
-            .last()
-            .and_then(|mod_name| {
-                self.resolved_modules
-                    .values()
-                    .find(|rm| {
-                        self.krate
-                            .paths
-                            .get(&rm.id)
-                            .is_some_and(|p| p.path.last() == Some(mod_name))
-                    })
-                    .map(|rm| rm.id)
-            })
-            .unwrap_or(self.krate.root);
-
-        let module_common_traits = self
-            .module_common_traits
-            .get(&current_module_id)
-            .cloned()
-            .unwrap_or_default();
-
-        let mut non_common_trait_impls = Vec::new();
-        let mut missing_module_common_trait_paths = HashSet::new(); // Store trait_id of common traits
-
-        for common_trait_format in &module_common_traits {
-            missing_module_common_trait_paths.insert(common_trait_format.trait_id);
-        }
-
-        for norm_trait in &trait_impl_data {
-            // Check if this trait's path (trait_id) is among the common trait paths
-            if module_common_traits
-                .iter()
-                .any(|ct| ct.trait_id == norm_trait.trait_id)
-            {
-                // It implements a trait that *could* be common. Remove it from missing.
-                missing_module_common_trait_paths.remove(&norm_trait.trait_id);
-
-                // Now check if the *specific format* of this impl is common
-                if !module_common_traits.contains(norm_trait) {
-                    // The specific format is not common, so list it individually
-                    non_common_trait_impls.push(norm_trait.clone());
-                } else {
-                    // The specific format *is* common, mark it printed
-                    if let Some((trait_impl, impl_id)) = norm_trait.get_impl_data(self.krate) {
-                        self.printed_ids.insert(impl_id, self.get_header_prefix());
-                        for assoc_item_id in &trait_impl.items {
-                            if self.selected_ids.contains(assoc_item_id) {
-                                self.printed_ids
-                                    .insert(*assoc_item_id, self.get_header_prefix());
-                            }
-                        }
-                    }
+                warn!(
+                    "Skipped printing {} items not fitting into standard sections (use --include-other to see them):",
+                    unprinted_ids.len()
+                );
+                // Convert HashMap to Vec for sorting before printing warnings
+                let mut sorted_counts: Vec<_> = counts_by_kind.into_iter().collect();
+                sorted_counts.sort_by_key(|(kind, _)| format!("{:?}", kind)); // Sort by debug representation for consistency
+
+                for (kind, count) in sorted_counts {
+                    warn!("  - {:?}: {}", kind, count);
                 }
-            } else {
-                // This trait path was never common, so list this specific impl
-                non_common_trait_impls.push(norm_trait.clone());
             }
         }
 
-        if !non_common_trait_impls.is_empty() || !missing_module_common_trait_paths.is_empty() {
-            let trait_impl_header_level = self.get_current_header_level();
+        self.flush_output(w)?;
+
+        // --- Examples Appendix ---
+        // Clone the necessary data from self.crate_extra before the loop
+        let examples_readme_content_clone = self
+            .crate_extra
+            .as_ref()
+            .and_then(|extra| extra.examples_readme_content.clone());
+        let examples_clone = self
+            .crate_extra
+            .as_ref()
+            .map_or_else(Vec::new, |extra| extra.examples.clone());
+
+        if !examples_clone.is_empty() || examples_readme_content_clone.is_some() {
+            let examples_section_level = self.get_current_header_level(); // Should be 2
             let header_prefix = self.get_header_prefix();
             writeln!(
                 self.output,
-                "{} {} Trait Implementations for `{}`\n",
-                "#".repeat(trait_impl_header_level),
-                header_prefix,
-                target_name
+                "\n{} {} Examples Appendix\n",
+                self.header_marker(examples_section_level),
+                header_prefix
             )
             .unwrap();
+            self.push_level(); // Push for H3 example headers
 
-            if !missing_module_common_trait_paths.is_empty() {
-                let mut sorted_missing_common_trait_names: Vec<String> =
-                    missing_module_common_trait_paths
-                        .iter()
-                        .filter_map(|trait_id| {
-                            // Find the corresponding FormattedTraitImpl from module_common_traits
-                            // to get its display name (which should be the simple form)
-                            module_common_traits
-                                .iter()
-                                .find(|ct| ct.trait_id == *trait_id)
-                                .map(|ct| {
-                                    ct.formatted_markdown_list_entry
-                                        .split_once("`")
-                                        .and_then(|(_, rest)| rest.split_once("`"))
-                                        .map(|(path, _)| path.to_string())
-                                        .unwrap_or_else(|| {
-                                            format_id_path_canonical(trait_id, self.krate)
-                                        }) // Fallback
-                                })
-                        })
-                        .collect();
-                sorted_missing_common_trait_names.sort_unstable();
-                if !sorted_missing_common_trait_names.is_empty() {
-                    writeln!(
-                        self.output,
-                        "**(Note: Does not implement common trait(s): `{}`)**\n",
-                        sorted_missing_common_trait_names.join("`, `")
-                    )
-                    .unwrap();
-                }
+            if let Some(readme) = examples_readme_content_clone {
+                let adjusted_readme = adjust_markdown_headers(
+                    &readme,
+                    self.shifted_header_level(examples_section_level),
+                );
+                writeln!(self.output, "{}\n", adjusted_readme).unwrap();
             }
 
-            let formatted_list = self.format_trait_list(&non_common_trait_impls);
-            if !formatted_list.is_empty() {
-                write!(self.output, "{}", formatted_list).unwrap();
+            for (filename, content) in &examples_clone {
+                let example_header_level = self.get_current_header_level(); // Should be 3
+                let example_prefix = self.get_header_prefix();
+                writeln!(
+                    self.output,
+                    "{} {} `{}`\n",
+                    self.header_marker(example_header_level),
+                    example_prefix,
+                    filename
+                )
+                .unwrap();
+                writeln!(
+                    self.output,
+                    "```{}\n{}\n```\n",
+                    self.code_fence_lang, content
+                )
+                .unwrap();
+                self.post_increment_current_level(); // Increment H3 counter for next example
             }
-
-            self.post_increment_current_level();
+            self.pop_level(); // Pop H3 example level
+            self.post_increment_current_level(); // Increment H2 counter for next top-level section
         }
-    }
-
-    /// Prints implementors *of* a trait. Handles template mode for the impl docs.
-    fn print_trait_implementors(&mut self, impl_ids: &[Id], _trait_item: &Item) {
-        let implementors: Vec<&Item> = impl_ids
-            .iter()
-            .filter_map(|id| self.krate.index.get(id))
-            .filter(|item| {
-                self.selected_ids.contains(&item.id) && matches!(item.inner, ItemEnum::Impl(_))
-            })
-            .collect();
 
-        if !implementors.is_empty() {
-            let implementors_section_level = self.get_current_header_level();
+        // --- License Footer ---
+        if self.license_footer {
+            let footer_level = self.get_current_header_level(); // Should be 2
             let header_prefix = self.get_header_prefix();
             writeln!(
                 self.output,
-                "{} {} Implementors\n",
-                "#".repeat(implementors_section_level),
+                "\n{} {} License\n",
+                self.header_marker(footer_level),
                 header_prefix
             )
             .unwrap();
+            let license = self
+                .manifest_data
+                .license
+                .as_deref()
+                .unwrap_or("Not specified");
+            writeln!(self.output, "- License: {}", license).unwrap();
+            if let Some(repo) = &self.manifest_data.repository {
+                writeln!(self.output, "- Repository: <{}>", repo).unwrap();
+            }
+            let crate_label = if crate_version.is_empty() {
+                format!("`{}`", crate_name)
+            } else {
+                format!("`{}` {}", crate_name, crate_version)
+            };
+            writeln!(
+                self.output,
+                "\n_This documentation was generated automatically from the source code of {}._",
+                crate_label
+            )
+            .unwrap();
+            self.post_increment_current_level();
+        }
+        self.write_reference_footnotes();
+        if self.toc {
+            let output = insert_table_of_contents(&self.output, self.shifted_header_level(2));
+            self.write_output(w, &output)?;
+        } else {
+            self.write_output(w, &self.output)?;
+        }
+        Ok((self.anchor_index, self.module_files))
+    }
+}
 
-            self.push_level();
-            for impl_item in implementors {
-                if let ItemEnum::Impl(imp) = &impl_item.inner {
-                    let impl_header_only = format_impl_decl_header_only(imp, self.krate);
-                    let impl_header_level = self.get_current_header_level();
-                    let impl_prefix = self.get_header_prefix();
+/// Runs rustdoc on a library crate named `name`, written into `dir` with `source` as
+/// its `lib.rs`, for the tests of this crate's modules.
+///
+/// Writing into a given `dir` lets tests read the sources back through
+/// [`Printer::source_root`].
+#[cfg(test)]
+pub(crate) fn document_in(
+    dir: &FilePath,
+    name: &str,
+    source: &str,
+    document_private_items: bool,
+) -> (CargoManifest, Crate) {
+    let manifest_path = dir.join("Cargo.toml");
+    fs::write(
+        &manifest_path,
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            name
+        ),
+    )
+    .unwrap();
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/lib.rs"), source).unwrap();
+    let manifest = CargoManifest::from_path(&manifest_path).unwrap();
+    let options = RustdocOptions {
+        document_private_items,
+        ..RustdocOptions::default()
+    };
+    let krate = run_rustdoc(dir, name, &options, false).unwrap();
+    (manifest, krate)
+}
 
-                    writeln!(
-                        self.output,
-                        "{} {} `{}`\n",
-                        "#".repeat(impl_header_level),
-                        impl_prefix,
-                        impl_header_only.trim()
-                    )
-                    .unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                    // Print where clause if it exists
-                    if !imp.generics.where_predicates.is_empty() {
-                        let where_clause =
-                            format_generics_where_only(&imp.generics.where_predicates, self.krate);
-                        writeln!(self.output, "```rust\n{}\n```\n", where_clause).unwrap();
-                    }
+    /// Runs rustdoc on a library crate named `fixture` with `source` as its `lib.rs`.
+    fn document(source: &str) -> (CargoManifest, Crate) {
+        document_with(source, false)
+    }
 
-                    // Print docs for the impl block itself
-                    let mut temp_printer = self.clone_with_new_output();
-                    temp_printer.doc_path = self.doc_path.clone();
-                    temp_printer.print_docs(impl_item);
-                    write!(self.output, "{}", temp_printer.output).unwrap();
+    /// Like [`document`], documenting private items if `document_private_items` is set.
+    fn document_with(source: &str, document_private_items: bool) -> (CargoManifest, Crate) {
+        document_crate("fixture", source, document_private_items)
+    }
 
-                    // Mark the impl_item ID and its associated items as printed
-                    self.printed_ids
-                        .insert(impl_item.id, self.get_header_prefix());
-                    for assoc_item_id in &imp.items {
-                        if self.selected_ids.contains(assoc_item_id) {
-                            self.printed_ids
-                                .insert(*assoc_item_id, self.get_header_prefix());
-                        }
-                    }
+    /// Like [`document_with`], for a crate named `name`, see [`document_in`].
+    fn document_crate(
+        name: &str,
+        source: &str,
+        document_private_items: bool,
+    ) -> (CargoManifest, Crate) {
+        let dir = tempfile::tempdir().unwrap();
+        document_in(dir.path(), name, source, document_private_items)
+    }
 
-                    self.post_increment_current_level();
-                }
+    /// Returns the labels of footnotes referenced in `markdown` without a definition.
+    fn undefined_footnotes(markdown: &str) -> Vec<String> {
+        let defined: HashSet<&str> = markdown
+            .lines()
+            .filter_map(|line| line.strip_prefix("[^")?.split_once("]:"))
+            .map(|(label, _)| label)
+            .collect();
+        markdown
+            .match_indices("[^")
+            .filter_map(|(start, _)| {
+                let (label, rest) = markdown[start + 2..].split_once(']')?;
+                (!rest.starts_with(':') && !defined.contains(label)).then(|| label.to_string())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reference_footnotes_are_defined_in_every_section_and_file() {
+        let (manifest, krate) = document(
+            "mod private {
+                /// Only reachable through `take`, so it is printed under \"Other\".
+                pub trait Sealed: crate::Public {}
             }
-            self.pop_level();
-            self.post_increment_current_level();
+            /// A public trait.
+            pub trait Public {
+                /// A method inherited by `Sealed`.
+                fn method(&self);
+            }
+            /// Takes a sealed value.
+            pub fn take<T: private::Sealed>(_t: T) {}
+            /// A module.
+            pub mod inner {
+                /// See [`Public`](crate::Public).
+                pub fn other() {}
+            }",
+        );
+        let printer = || {
+            Printer::new(&manifest, &krate)
+                .include_other()
+                .references_as_footnotes(true)
+                .show_inherited_trait_items(true)
+        };
+        let markdown = printer().print().unwrap();
+        assert!(markdown.contains("[^ref-1]:"), "{}", markdown);
+        assert_eq!(undefined_footnotes(&markdown), Vec::<String>::new());
+
+        let dir = tempfile::tempdir().unwrap();
+        for file in printer().print_to_dir(dir.path()).unwrap() {
+            let markdown = fs::read_to_string(&file).unwrap();
+            assert_eq!(
+                undefined_footnotes(&markdown),
+                Vec::<String>::new(),
+                "{}",
+                markdown
+            );
         }
     }
 
-    /// Prints the details of a specific impl block (header, associated items).
-    /// Handles template mode for the impl block's docs.
-    fn print_impl_block_details(&mut self, impl_item: &Item, imp: &Impl) {
-        let header_prefix = self.get_header_prefix();
-        // Mark as printed *now* before printing details
-        if self
-            .printed_ids
-            .insert(impl_item.id, header_prefix.clone())
-            .is_some()
-        {
-            // Already printed with a (potentially different) prefix, skip full details.
-            // This case should ideally be caught by the caller, but as a safeguard.
-            return;
+    #[test]
+    fn missing_crate_common_traits_are_noted() {
+        let (manifest, krate) = document(
+            "/// A.
+            #[derive(Clone, Debug)]
+            pub struct A;
+            /// B.
+            #[derive(Clone, Debug)]
+            pub struct B;
+            /// C.
+            #[derive(Clone, Debug)]
+            pub struct C;
+            /// Lacks `Clone`.
+            #[derive(Debug)]
+            pub struct NoClone;",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        let section = markdown
+            .split("Trait Implementations for `NoClone`")
+            .nth(1)
+            .expect(&markdown);
+        let note = section
+            .lines()
+            .find(|line| line.contains("Does not implement"))
+            .expect(section);
+        assert!(note.contains("`Clone`"), "{}", note);
+        assert!(!note.contains("`Debug`"), "{}", note);
+        for name in ["A", "B", "C"] {
+            assert!(
+                !markdown.contains(&format!("Trait Implementations for `{}`", name)),
+                "{}",
+                markdown
+            );
         }
+    }
 
-        // Increment level counter for this impl block
-        self.post_increment_current_level();
-        let impl_header_level = self.get_current_header_level();
-        let impl_header = format_impl_decl(imp, self.krate);
+    #[test]
+    fn section_headers_count_their_items() {
+        let (manifest, krate) = document(
+            "/// A.
+            pub fn a() {}
+            /// B.
+            pub fn b() {}
+            /// C.
+            pub fn c() {}
+            /// Re-exports `a`.
+            pub mod inner {
+                pub use super::a;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .emit_item_count_per_section(true)
+            .print()
+            .unwrap();
+        assert!(markdown.contains(": Functions (3)\n"), "{}", markdown);
+        // Cross-references are not items of the section.
+        assert!(markdown.contains(": Re-exports\n"), "{}", markdown);
+    }
 
-        // Print the impl block header (e.g. #### 1.1.1: `impl ...`)
-        writeln!(
-            self.output,
-            "{} {} `{}`\n", // Add newline after header
-            "#".repeat(impl_header_level),
-            header_prefix,      // Use the stored/current prefix
-            impl_header.trim()  // Trim potential trailing space if no where clause added
-        )
-        .unwrap();
+    #[test]
+    fn long_bound_lists_move_to_the_where_clause() {
+        let (manifest, krate) = document(
+            "/// Heavily bounded.
+            pub fn bounded<T: Clone + std::fmt::Debug + Send + Sync + 'static + Unpin>(_t: T) {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .render_bounds_on_separate_lines(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "fn bounded<T>(_t: T)\nwhere\n    T: Clone + Debug + Send + Sync + 'static + Unpin\n"
+            ),
+            "{}",
+            markdown
+        );
 
-        // Print impl block docs (using helper)
-        // Create a temporary DocPrinter to isolate output
-        let mut temp_printer = self.clone_with_new_output();
-        // Copy current doc path to temp printer for correct template marker generation
-        temp_printer.doc_path = self.doc_path.clone();
-        temp_printer.print_docs(impl_item);
-        write!(self.output, "{}", temp_printer.output).unwrap();
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(!markdown.contains("\nwhere"), "{}", markdown);
+    }
 
-        // Print associated items within this impl block
-        let mut assoc_consts = vec![];
-        let mut assoc_types = vec![];
-        let mut assoc_fns = vec![];
-        for assoc_item_id in &imp.items {
-            // Important: Only process associated items that are *selected*
-            if !self.selected_ids.contains(assoc_item_id) {
-                continue;
+    #[test]
+    fn elided_const_exprs_are_restored_from_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manifest, krate) = document_in(
+            dir.path(),
+            "fixture",
+            "/// A length.
+            pub const LEN: usize = 4;
+            /// Holds a buffer.
+            pub struct Buf<const N: usize>;
+            /// Doubles.
+            pub fn double(_b: Buf<{ LEN + 1 }>) -> [u8; LEN * 2]
+            where
+                [(); LEN / 2]: Sized,
+            {
+                [0; LEN * 2]
+            }",
+            false,
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .source_root(dir.path())
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("`fn double(_b: fixture::Buf<{ LEN + 1 }>) -> [u8; 8]`"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains(
+                "pub fn double(_b: fixture::Buf<{ LEN + 1 }>) -> [u8; 8] where [(); 2]: Sized"
+            ),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn elided_const_exprs_are_collected_by_kind() {
+        let exprs = super::elided_const_exprs(
+            "pub fn f<const N: usize>(a: Buf<{ N + 1 }, 4>, b: [u8; N]) -> [u8;  N * 2]
+            where
+                [(); N / 2]: Sized,
+                Buf<{ N - 1 }>: Send,",
+        );
+        assert_eq!(exprs.array_lengths, vec!["N * 2", "N / 2"]);
+        assert_eq!(exprs.generic_args, vec!["{ N + 1 }", "{ N - 1 }"]);
+    }
+
+    #[test]
+    fn table_of_contents_skips_headers_in_any_code_fence() {
+        let markdown = "# Crate\n\n## 1: First\n\n~~~\n## 9: Tilde\n~~~\n\n\
+            ````md\n```\n## 8: Nested\n```\n````\n\n### 1.1: Second\n";
+        let with_toc = super::insert_table_of_contents(markdown, 2);
+        let toc = with_toc.split("\n\n<a id=").next().unwrap();
+        assert_eq!(
+            toc,
+            "# Crate\n\n**Contents**\n\n- [1: First](#section-1)\n  \
+             - [1.1: Second](#section-1-1)"
+        );
+    }
+
+    #[test]
+    fn associated_functions_are_noted_only_on_dyn_compatible_traits() {
+        let (manifest, krate) = document(
+            "/// Dyn-compatible.
+            pub trait Shape {
+                /// Creates a shape.
+                fn new() -> Self
+                where
+                    Self: Sized;
+                /// Returns the area.
+                fn area(&self) -> f64;
             }
+            /// Not dyn-compatible.
+            pub trait Make {
+                /// Makes a value.
+                fn make() -> Self;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        let note = "_(not available on trait objects)_";
+        // Traits are sorted by name, so `Make` comes first
+        let (make, shape) = markdown
+            .split_once("`trait fixture::Shape`")
+            .expect(&markdown);
+        assert_eq!(shape.matches(note).count(), 1, "{}", shape);
+        assert!(!make.contains(note), "{}", make);
+    }
 
-            if let Some(assoc_item) = self.krate.index.get(assoc_item_id) {
-                match &assoc_item.inner {
-                    ItemEnum::AssocConst { .. } => assoc_consts.push(assoc_item_id),
-                    ItemEnum::AssocType { .. } => assoc_types.push(assoc_item_id),
-                    ItemEnum::Function(_) => assoc_fns.push(assoc_item_id),
-                    _ => {} // Should not happen in impl block
+    #[test]
+    fn reexport_visibility_follows_the_declaration() {
+        let (manifest, krate) = document_with(
+            "/// A.
+            pub fn a() {}
+            /// Re-exports `a`.
+            pub mod inner {
+                pub use super::a;
+            }",
+            true,
+        );
+        let printer = || Printer::new(&manifest, &krate).reexport_visibility(true);
+        let markdown = printer().print().unwrap();
+        assert!(
+            markdown.contains("- `fn a()` (`pub use`) (See section 2.1.1: for details)\n"),
+            "{}",
+            markdown
+        );
+        let markdown = printer().anchors().print().unwrap();
+        assert!(
+            markdown.contains("- [`fn a()`](#fixture-a) (`pub use`)\n"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn anchors_are_stable_when_items_are_prepended() {
+        let source = "/// B.
+            pub struct B;
+            /// Re-exports `B`.
+            pub mod inner {
+                pub use super::B;
+                /// C.
+                pub fn c() {}
+            }";
+        let anchors = |source: &str| {
+            let (manifest, krate) = document(source);
+            let markdown = Printer::new(&manifest, &krate).anchors().print().unwrap();
+            let mut anchors: Vec<String> = markdown
+                .match_indices("<a id=\"")
+                .chain(markdown.match_indices("](#"))
+                .map(|(start, prefix)| {
+                    let rest = &markdown[start + prefix.len()..];
+                    rest[..rest.find(['"', ')']).unwrap()].to_string()
+                })
+                .collect();
+            anchors.sort();
+            (markdown, anchors)
+        };
+        let (before, anchors_before) = anchors(source);
+        let (after, anchors_after) = anchors(&format!(
+            "/// A.
+            pub struct A;
+            /// Also a.
+            pub fn a() {{}}
+            {}",
+            source
+        ));
+        assert!(before.contains("`](#fixture-b)"), "{}", before);
+        assert_ne!(before, after);
+        assert_eq!(
+            anchors_after
+                .into_iter()
+                .filter(|anchor| !anchor.starts_with("fixture-a"))
+                .collect::<Vec<_>>(),
+            anchors_before
+        );
+    }
+
+    #[test]
+    fn impl_trait_return_types_keep_their_item_constraint() {
+        let (manifest, krate) = document(
+            "/// Enumerates the words of `s`.
+            pub fn f(s: &str) -> impl Iterator<Item = (usize, &str)> {
+                s.split(' ').enumerate()
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("Iterator<Item = (usize, &str)>`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn strip_hidden_doc_lines_only_touches_rust_code_blocks() {
+        let docs = "# Example\n\n```no_run\n# let x = 1;\n#\nlet y = x;\n## not hidden\n```\n\n\
+            ~~~toml\n# comment\n~~~\n\n````\n```\n# hidden\n````\n";
+        assert_eq!(
+            super::strip_hidden_doc_lines(docs),
+            "# Example\n\n```no_run\nlet y = x;\n# not hidden\n```\n\n\
+             ~~~toml\n# comment\n~~~\n\n````\n```\n````\n"
+        );
+        assert!(matches!(
+            super::strip_hidden_doc_lines("No code."),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn hidden_doc_lines_are_removed_by_default() {
+        let (manifest, krate) = document(
+            "/// Adds one.
+            ///
+            /// ```
+            /// # let x = 1;
+            /// assert_eq!(fixture::add_one(x), 2);
+            /// ```
+            pub fn add_one(x: u32) -> u32 {
+                x + 1
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(!markdown.contains("let x = 1;"), "{}", markdown);
+        assert!(markdown.contains("assert_eq!(fixture::add_one(x), 2);"));
+
+        let markdown = Printer::new(&manifest, &krate)
+            .keep_hidden_doc_lines(true)
+            .print()
+            .unwrap();
+        assert!(markdown.contains("# let x = 1;"), "{}", markdown);
+    }
+
+    #[test]
+    fn parse_rust_version_pads_missing_parts() {
+        assert_eq!(super::parse_rust_version("1.70"), Some([1, 70, 0]));
+        assert_eq!(super::parse_rust_version(" 1.70.1 "), Some([1, 70, 1]));
+        assert_eq!(super::parse_rust_version("1"), Some([1, 0, 0]));
+        assert_eq!(super::parse_rust_version("1.70.0.1"), None);
+        assert_eq!(super::parse_rust_version("1.x"), None);
+        assert!(super::parse_rust_version("1.9") < super::parse_rust_version("1.10"));
+    }
+
+    #[test]
+    fn items_requiring_a_newer_rust_are_noted() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, krate) = document_in(
+            dir.path(),
+            "fixture",
+            "/// Needs a newer Rust.
+            #[clippy::msrv = \"1.70\"]
+            pub fn newer() {}
+            /// Fine with the crate's Rust.
+            #[clippy::msrv = \"1.56\"]
+            pub fn older() {}",
+            false,
+        );
+        let manifest_path = dir.path().join("Cargo.toml");
+        let toml = fs::read_to_string(&manifest_path).unwrap();
+        fs::write(&manifest_path, toml + "rust-version = \"1.60\"\n").unwrap();
+        let manifest = CargoManifest::from_path(&manifest_path).unwrap();
+
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert_eq!(
+            markdown.matches("_Requires Rust").count(),
+            1,
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("_Requires Rust 1.70 (the crate's `rust-version` is 1.60)._"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn print_to_writer_matches_print() {
+        let (manifest, krate) = document(
+            "/// A trait.
+            pub trait Shape {
+                /// Area.
+                fn area(&self) -> f64;
+            }
+            /// A struct.
+            #[derive(Clone, Debug)]
+            pub struct Square(pub f64);
+            impl Shape for Square {
+                fn area(&self) -> f64 {
+                    self.0 * self.0
                 }
             }
-        }
+            /// A module.
+            pub mod inner {
+                /// An enum.
+                pub enum E {
+                    /// A.
+                    A,
+                }
+            }",
+        );
+        let printer = || Printer::new(&manifest, &krate).include_other();
+        let mut buffer = Vec::new();
+        printer().print_to_writer(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            printer().print().unwrap()
+        );
+
+        let mut buffer = Vec::new();
+        printer().toc(true).print_to_writer(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            printer().toc(true).print().unwrap()
+        );
+    }
+
+    #[test]
+    fn clean_trait_path_only_strips_leading_std_segments() {
+        assert_eq!(super::clean_trait_path("core::marker::Send"), "Send");
+        assert_eq!(super::clean_trait_path("std::fmt::Debug"), "Debug");
+        assert_eq!(super::clean_trait_path("alloc::Vec"), "Vec");
+        assert_eq!(
+            super::clean_trait_path("my_crate::core::Widget"),
+            "my_crate::core::Widget"
+        );
+        assert_eq!(
+            super::clean_trait_path("my_core::stdlib::Thing"),
+            "my_core::stdlib::Thing"
+        );
+        assert_eq!(super::clean_trait_path("core"), "core");
+    }
 
-        // Push level for associated items within the impl block
-        self.push_level();
+    #[test]
+    fn phase_timings_name_every_phase() {
+        // Mirrors `print --profile`: the crate is written in place of the download
+        let dir = tempfile::tempdir().unwrap();
+        let mut timings = PhaseTimings::default();
+        let manifest = timings.time("download", || {
+            let manifest_path = dir.path().join("Cargo.toml");
+            fs::write(
+                &manifest_path,
+                "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            )
+            .unwrap();
+            fs::create_dir(dir.path().join("src")).unwrap();
+            fs::write(dir.path().join("src/lib.rs"), "/// F.\npub fn f() {}").unwrap();
+            CargoManifest::from_path(&manifest_path).unwrap()
+        });
+        let krate = run_rustdoc_timed(
+            dir.path(),
+            "fixture",
+            &RustdocOptions::default(),
+            false,
+            &mut timings,
+        )
+        .unwrap();
+        Printer::new(&manifest, &krate)
+            .phase_timings(|phase, elapsed| timings.record(phase, elapsed))
+            .print()
+            .unwrap();
+        let phases: Vec<&str> = timings
+            .phases()
+            .iter()
+            .map(|(phase, _)| phase.as_str())
+            .collect();
+        assert_eq!(
+            phases,
+            [
+                "download",
+                "rustdoc build",
+                "json parse",
+                "item selection",
+                "common traits",
+                "rendering"
+            ]
+        );
+        let profile = timings.to_string();
+        assert_eq!(profile.lines().count(), phases.len(), "{}", profile);
+        // The durations line up in one column
+        let widths: HashSet<usize> = profile.lines().map(str::len).collect();
+        assert_eq!(widths.len(), 1, "{}", profile);
+        for phase in phases {
+            assert!(
+                profile
+                    .lines()
+                    .any(|line| line.starts_with(&format!("{}: ", phase))),
+                "{}",
+                profile
+            );
+        }
+    }
 
-        if !assoc_consts.is_empty() {
-            for id in assoc_consts {
-                self.print_associated_item_summary(id);
-                if !self.printed_ids.contains_key(id) {
-                    self.printed_ids.insert(*id, self.get_header_prefix());
-                }
+    #[test]
+    fn code_fence_lang_applies_to_every_fence() {
+        let (manifest, krate) = document(
+            "/// A trait.
+            pub trait Shape {
+                /// Area.
+                fn area(&self) -> f64;
             }
-        }
-        if !assoc_types.is_empty() {
-            for id in assoc_types {
-                self.print_associated_item_summary(id);
-                if !self.printed_ids.contains_key(id) {
-                    self.printed_ids.insert(*id, self.get_header_prefix());
+            /// A struct.
+            pub struct Square(pub f64);
+            impl Shape for Square {
+                fn area(&self) -> f64 {
+                    self.0 * self.0
                 }
             }
-        }
-        if !assoc_fns.is_empty() {
-            for id in assoc_fns {
-                self.print_associated_item_summary(id);
-                if !self.printed_ids.contains_key(id) {
-                    self.printed_ids.insert(*id, self.get_header_prefix());
+            /// A constant.
+            pub const ONE: Square = Square(1.0);",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .code_fence_lang("rs")
+            .print()
+            .unwrap();
+        let mut info_strings = Vec::new();
+        let mut open_fence = None;
+        for line in markdown.lines() {
+            match open_fence {
+                None => {
+                    if let Some((fence, info)) = CodeFence::open(line) {
+                        open_fence = Some(fence);
+                        info_strings.push(info);
+                    }
                 }
+                Some(fence) if fence.is_closed_by(line) => open_fence = None,
+                Some(_) => {}
             }
         }
+        assert!(info_strings.len() > 2, "{}", markdown);
+        assert!(
+            info_strings.iter().all(|&info| info == "rs"),
+            "{}",
+            markdown
+        );
+    }
 
-        self.pop_level(); // Pop associated item level
+    #[test]
+    fn short_default_bodies_are_rendered_from_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manifest, krate) = document_in(
+            dir.path(),
+            "fixture",
+            "/// A trait.
+            pub trait Shape {
+                /// Area.
+                fn area(&self) -> f64;
+                /// Twice the area.
+                fn double_area(&self) -> f64 { 2.0 * self.area() }
+            }",
+            false,
+        );
+        let printer = || Printer::new(&manifest, &krate).source_root(dir.path());
+        let body = "fn double_area(&self) -> f64 { 2.0 * self.area() }";
+        let markdown = printer().show_default_bodies(true).print().unwrap();
+        assert!(markdown.contains(body), "{}", markdown);
+        let markdown = printer().print().unwrap();
+        assert!(!markdown.contains(body), "{}", markdown);
     }
 
-    /// Prints items of a specific kind within a given list of IDs.
-    fn print_items_of_kind(&mut self, item_ids: &[Id], kind: ItemKind, header_name: &str) -> bool {
-        // Filter and sort items of the target kind
-        let mut items_to_print: Vec<&Id> = item_ids
-            .iter()
-            .filter(|id| self.selected_ids.contains(id))
-            .filter(|id| self.get_item_kind(id) == Some(kind))
-            .collect();
+    #[test]
+    fn omit_sized_bound_keeps_relaxed_bounds() {
+        let (manifest, krate) = document(
+            "/// Sized and cloneable.
+            pub fn f<T: Sized + Clone>(_t: T) {}
+            /// Maybe unsized.
+            pub fn g<T: ?Sized>(_t: &T) {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .omit_sized_bound(true)
+            .print()
+            .unwrap();
+        assert!(markdown.contains("`fn f<T: Clone>(_t: T)`"), "{}", markdown);
+        assert!(
+            markdown.contains("`fn g<T: ?Sized>(_t: &T)`"),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("`fn f<T: Sized + Clone>(_t: T)`"),
+            "{}",
+            markdown
+        );
+    }
 
-        if items_to_print.is_empty() {
-            return false; // Nothing to print for this kind
+    #[test]
+    fn categories_and_keywords_link_to_crates_io() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, krate) = document_in(dir.path(), "fixture", "//! A crate.", false);
+        let manifest_path = dir.path().join("Cargo.toml");
+        let toml = fs::read_to_string(&manifest_path).unwrap();
+        fs::write(
+            &manifest_path,
+            toml
+                + "categories = [\"parsing\", \"command-line-utilities\"]\nkeywords = [\"docs\"]\n",
+        )
+        .unwrap();
+        let manifest = CargoManifest::from_path(&manifest_path).unwrap();
+
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "- Categories: [parsing](https://crates.io/categories/parsing), \
+                 [command-line-utilities](https://crates.io/categories/command-line-utilities)\n"
+            ),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("- Keywords: [docs](https://crates.io/keywords/docs)\n"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[cfg(feature = "rustfmt")]
+    #[test]
+    fn rustfmt_signatures_wraps_wide_signatures() {
+        // Without a `rustfmt` binary there is nothing to check.
+        if super::rustfmt_code_block("fn f() {}", None).is_none() {
+            return;
         }
+        let (manifest, krate) = document(
+            "/// Wide.
+            pub trait Wide {
+                /// Takes many arguments.
+                fn wide(&self, first: u32, second: u32, third: u32, fourth: u32, fifth: u32, sixth: u32) -> u32;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .rustfmt_signatures(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("fifth: u32, sixth: u32)\n        -> u32;\n"),
+            "{}",
+            markdown
+        );
+    }
 
-        items_to_print
-            .sort_by_key(|id| self.krate.index.get(id).and_then(|item| item.name.clone()));
+    #[test]
+    fn required_associated_consts_show_their_type() {
+        let (manifest, krate) = document(
+            "/// Has a size.
+            pub trait Measured {
+                /// The size.
+                const SIZE: usize;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("pub trait Measured {\n    const SIZE: usize;\n}"),
+            "{}",
+            markdown
+        );
+        assert!(markdown.contains(": `const SIZE: usize`\n"), "{}", markdown);
+    }
 
-        let section_header_level = self.get_current_header_level();
-        let header_prefix = self.get_header_prefix();
-        writeln!(
-            self.output,
-            "\n{} {} {}",
-            "#".repeat(section_header_level),
-            header_prefix,
-            header_name
-        )
-        .unwrap();
+    #[test]
+    fn deref_target_methods_are_listed_on_the_wrapper() {
+        let (manifest, krate) = document(
+            "/// The inner type.
+            pub struct Inner;
+            impl Inner {
+                /// Says hello.
+                pub fn hello(&self) {}
+            }
+            /// Wraps [`Inner`].
+            pub struct Wrapper(Inner);
+            impl std::ops::Deref for Wrapper {
+                type Target = Inner;
+                fn deref(&self) -> &Inner {
+                    &self.0
+                }
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .show_deref_methods(true)
+            .print()
+            .unwrap();
+        let section = markdown
+            .split("Methods from `Deref<Target = fixture::Inner>`")
+            .nth(1)
+            .expect(&markdown);
+        assert!(section.contains("- `fn hello(&self)`"), "{}", markdown);
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(!markdown.contains("Methods from `Deref"), "{}", markdown);
+    }
 
-        self.push_level();
-        // Print item details
-        for id in items_to_print {
-            // print_item_details now returns true if full details were printed
-            if self.print_item_details(id) {
-                self.post_increment_current_level();
-            } else {
-                // If it was a cross-reference or skipped, we still need to increment
-                // the counter for the list item itself if we decide to print a list item.
-                // For now, print_item_details handles the cross-ref header.
-                // If we change print_module_contents to print list items for cross-refs,
-                // then this post_increment might need adjustment.
-                // For now, if print_item_details printed a cross-ref header,
-                // it means an "item" was output, so we increment.
-                self.post_increment_current_level();
+    #[test]
+    fn max_generic_depth_collapses_inner_levels() {
+        let (manifest, krate) = document(
+            "use std::collections::HashMap;
+            /// Deeply nested.
+            pub fn nested() -> Option<Vec<HashMap<u8, Option<u8>>>> {
+                None
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .max_generic_depth(2)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "`fn nested() -> option::Option<Vec<collections::hash::map::HashMap<…>>>`"
+            ),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("HashMap<u8, option::Option<u8>>>>`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn metadata_comments_describe_deprecated_feature_gated_items() {
+        let (manifest, krate) = document(
+            "#![feature(doc_cfg)]
+            /// Old and optional.
+            #[deprecated]
+            #[doc(cfg(feature = \"extra\"))]
+            pub fn legacy() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .metadata_comments(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "<!-- item: path=fixture::legacy kind=function deprecated=true feature=extra -->\n"
+            ),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn type_aliases_are_expanded_in_signatures() {
+        let (manifest, krate) = document(
+            "/// The error.
+            #[derive(Debug)]
+            pub struct MyError;
+            /// A result.
+            pub type Result<T> = std::result::Result<T, MyError>;
+            /// Parses.
+            pub fn parse() -> Result<u8> {
+                Ok(0)
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .inline_type_aliases(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "`fn parse() -> fixture::Result<u8> /* = result::Result<u8, fixture::MyError> */`"
+            ),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("`fn parse() -> fixture::Result<u8>`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn variants_get_distinct_anchors() {
+        let source = "/// A color.
+            pub enum Color {
+                /// Red.
+                Red,
+                /// Green.
+                Green,
+            }";
+        let (manifest, krate) = document(source);
+        let markdown = Printer::new(&manifest, &krate).anchors().print().unwrap();
+        let red = header_at_anchor(&markdown, "fixture-color-red").expect(&markdown);
+        assert!(red.ends_with(": `Red`"), "{}", markdown);
+        let green = header_at_anchor(&markdown, "fixture-color-green").expect(&markdown);
+        assert!(green.ends_with(": `Green`"), "{}", markdown);
+
+        let (manifest, krate) =
+            document(&source.replace("pub enum Color {", "pub enum Color {\n/// Blue.\nBlue,"));
+        let markdown = Printer::new(&manifest, &krate).anchors().print().unwrap();
+        let green = header_at_anchor(&markdown, "fixture-color-green").expect(&markdown);
+        assert!(green.ends_with(": `Green`"), "{}", markdown);
+    }
+
+    #[test]
+    fn documentation_only_items_are_noted() {
+        let (manifest, krate) = document(
+            "/// Only for docs.
+            #[cfg(doc)]
+            pub fn doc_only() {}
+            /// Always there.
+            pub fn always() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        let note = "_(documentation-only; not present in normal builds)_";
+        assert_eq!(markdown.matches(note).count(), 1, "{}", markdown);
+        assert!(
+            markdown.contains(&format!("`fn doc_only()`\n\n{}", note)),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn associated_and_impl_docs_render_in_place() {
+        let (manifest, krate) = document(
+            "/// A trait.
+            pub trait Shape {
+                /// Area.
+                ///
+                /// # Panics
+                ///
+                /// Never.
+                fn area(&self) -> f64;
             }
+            /// A square.
+            pub struct Square;
+            /// Inherent methods.
+            impl Square {
+                /// Side.
+                pub fn side(&self) -> f64 { 1.0 }
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        for expected in [
+            "`impl fixture::Square`\n\nInherent methods.\n\n",
+            "`fn side(&self) -> f64`\n\nSide.\n\n",
+            "`fn area(&self) -> f64`\n\nArea.\n\n###### Panics\n\nNever.\n\n",
+        ] {
+            assert!(markdown.contains(expected), "{}", markdown);
+        }
+
+        let markdown = Printer::new(&manifest, &krate)
+            .template_mode()
+            .print()
+            .unwrap();
+        let markers: Vec<&str> = markdown
+            .lines()
+            .filter(|line| line.starts_with("{{MISSING_DOCS_"))
+            .collect();
+        let unique: HashSet<&str> = markers.iter().copied().collect();
+        assert_eq!(markers.len(), 5, "{}", markdown);
+        assert_eq!(unique.len(), markers.len(), "{}", markdown);
+        assert!(!markdown.contains("Inherent methods."), "{}", markdown);
+    }
+
+    /// A trait with associated items and a type with inherent and trait impl docs.
+    const IMPL_DOCS: &str = "/// A trait.
+        pub trait Shape {
+            /// The unit of the area.
+            type Unit;
+            /// The number of sides.
+            const SIDES: u32;
+            /// Area.
+            ///
+            /// # Panics
+            ///
+            /// Never.
+            fn area(&self) -> f64;
+        }
+        /// A square.
+        pub struct Square;
+        /// Inherent methods.
+        impl Square {
+            /// Side.
+            pub fn side(&self) -> f64 { 1.0 }
+        }
+        /// Squares are shapes.
+        impl Shape for Square {
+            type Unit = f64;
+            const SIDES: u32 = 4;
+            fn area(&self) -> f64 { 1.0 }
+        }";
+
+    /// Compares `markdown` with the snapshot file `name`, rewriting the snapshot
+    /// instead if `UPDATE_SNAPSHOTS` is set.
+    fn assert_snapshot(name: &str, markdown: &str) {
+        let path = FilePath::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/snapshots")
+            .join(name);
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            fs::write(&path, markdown).unwrap();
+            return;
         }
-        self.pop_level(); // Pop the item level for this section
+        let expected = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        assert!(
+            markdown == expected,
+            "{} is out of date, rerun with UPDATE_SNAPSHOTS=1 to see the difference:\n{}",
+            path.display(),
+            markdown
+        );
+    }
 
-        true
+    #[test]
+    fn impl_and_associated_docs_match_the_snapshots() {
+        let (manifest, krate) = document(IMPL_DOCS);
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert_snapshot("impl_docs.md", &markdown);
+        let markdown = Printer::new(&manifest, &krate)
+            .template_mode()
+            .print()
+            .unwrap();
+        assert_snapshot("impl_docs_template.md", &markdown);
     }
 
-    /// Prints the non-module contents of a specific module (identified by its ID).
-    /// Uses the `resolved_modules` index to get the list of items.
-    fn print_module_contents(&mut self, module_id: &Id) {
-        if let Some(resolved_module) = self.resolved_modules.get(module_id) {
-            let mut items_by_kind: HashMap<ItemKind, Vec<Id>> = HashMap::new();
-            let mut cross_referenced_items: Vec<(Id, String, String)> = Vec::new(); // (Id, Declaration, Prefix)
+    #[test]
+    fn doc_footnotes_survive() {
+        let (manifest, krate) = document(
+            "/// Fast[^speed].
+            ///
+            /// [^speed]: Measured on a laptop.
+            pub fn fast() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("Fast[^speed].\n\n[^speed]: Measured on a laptop.\n"),
+            "{}",
+            markdown
+        );
+        assert_eq!(undefined_footnotes(&markdown), Vec::<String>::new());
+    }
 
-            for id in &resolved_module.items {
-                if !self.selected_ids.contains(id) {
-                    continue;
-                }
+    #[test]
+    fn see_also_links_sibling_accessors() {
+        let (manifest, krate) = document(
+            "/// A cell.
+            pub struct Cell(u8);
+            impl Cell {
+                /// Gets the value.
+                pub fn get(&self) -> &u8 { &self.0 }
+                /// Gets the value mutably.
+                pub fn get_mut(&mut self) -> &mut u8 { &mut self.0 }
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .see_also_methods(true)
+            .anchors()
+            .print()
+            .unwrap();
+        let section = |name: &str| {
+            let anchor = format!("fixture-cell-{}", name);
+            let start = markdown
+                .find(&format!("<a id=\"{}\"></a>", anchor))
+                .unwrap();
+            markdown[start..].split("\n#").nth(1).unwrap()
+        };
+        assert!(
+            section("get").contains("_See also: [`get_mut`](#fixture-cell-get_mut)_"),
+            "{}",
+            markdown
+        );
+        assert!(
+            section("get_mut").contains("_See also: [`get`](#fixture-cell-get)_"),
+            "{}",
+            markdown
+        );
+    }
 
-                if let Some(existing_prefix) = self.printed_ids.get(id) {
-                    if let Some(item) = self.krate.index.get(id) {
-                        // Only add to cross-reference list if it's a kind we'd normally list directly
-                        if !matches!(
-                            item.inner,
-                            ItemEnum::Impl(_)
-                                | ItemEnum::Use { .. }
-                                | ItemEnum::StructField(_)
-                                | ItemEnum::Variant(_) // Variants are part of enums
-                                | ItemEnum::AssocConst { .. } // Assoc items are part of traits/impls
-                                | ItemEnum::AssocType { .. }
-                                | ItemEnum::Module(_)
-                        ) {
-                            let decl = generate_item_declaration(
-                                item,
-                                self.krate,
-                                &self.current_module_path,
-                            );
-                            cross_referenced_items.push((*id, decl, existing_prefix.clone()));
-                        }
-                    }
-                    continue; // Skip adding to items_by_kind if already printed
-                }
+    #[test]
+    fn unsafe_auto_traits_order_their_keywords() {
+        let (manifest, krate) = document(
+            "#![feature(auto_traits)]
+            /// Marker.
+            pub unsafe auto trait Marker {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(": `unsafe auto trait fixture::Marker`\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("```rust\npub unsafe auto trait Marker {}\n```"),
+            "{}",
+            markdown
+        );
+    }
 
-                if let Some(kind) = self.get_item_kind(id) {
-                    match kind {
-                        ItemKind::Impl
-                        | ItemKind::Variant // Handled by Enum
-                        | ItemKind::StructField // Handled by Struct/Union/Variant
-                        | ItemKind::AssocConst // Handled by Trait/Impl
-                        | ItemKind::AssocType // Handled by Trait/Impl
-                        | ItemKind::Use // Resolved, not printed directly
-                        | ItemKind::Module => continue, // Handled by main loop
-                        _ => {}
-                    }
-                    items_by_kind.entry(kind).or_default().push(*id);
+    #[test]
+    fn allow_list_selects_exactly_the_listed_items() {
+        let (manifest, krate) = document(
+            "/// Used by `make`.
+            pub struct Made;
+            /// Makes one.
+            pub fn make() -> Made { Made }
+            /// Parses.
+            pub fn parse() {}
+            /// Not listed.
+            pub fn other() {}
+            /// Not listed either.
+            pub struct Unused;",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .allow_list(&["make".to_string(), "::parse".to_string()])
+            .print()
+            .unwrap();
+        for header in [
+            ": `struct fixture::Made`",
+            ": `fn make() -> fixture::Made`",
+            ": `fn parse()`",
+        ] {
+            assert!(markdown.contains(header), "{}", markdown);
+        }
+        assert!(!markdown.contains("`fn other()`"), "{}", markdown);
+        assert!(!markdown.contains("Unused"), "{}", markdown);
+    }
+
+    #[test]
+    fn diverging_functions_return_never() {
+        let (manifest, krate) = document(
+            "/// Never returns.
+            pub fn diverge() -> ! {
+                loop {}
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(markdown.contains(": `fn diverge() -> !`\n"), "{}", markdown);
+    }
+
+    #[test]
+    fn useful_trait_impls_sort_first() {
+        let (manifest, krate) = document(
+            "/// A value.
+            #[derive(Clone, Debug)]
+            pub struct Value(u8);
+            impl std::fmt::Display for Value {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, \"{}\", self.0)
                 }
             }
+            impl From<u8> for Value {
+                fn from(v: u8) -> Self { Value(v) }
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .sort_trait_impls_by_usefulness(true)
+            .print()
+            .unwrap();
+        let position = |entry: &str| markdown.find(&format!("- `{}`", entry)).expect(&markdown);
+        assert!(position("Display") < position("Send"), "{}", markdown);
+        assert!(
+            position("convert::From<u8>") < position("Freeze"),
+            "{}",
+            markdown
+        );
+        assert!(
+            position("convert::From<u8>") < position("impl<T> Borrow<T> for T"),
+            "{}",
+            markdown
+        );
+    }
 
-            // Sort items by name within each kind
-            for ids in items_by_kind.values_mut() {
-                ids.sort_by_key(|id| self.krate.index.get(id).and_then(|item| item.name.clone()));
-            }
-            cross_referenced_items.sort_by_key(|(_, decl, _)| decl.clone());
+    #[test]
+    fn license_footer_names_license_and_repository() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, krate) = document_in(dir.path(), "fixture", "//! A crate.", false);
+        let manifest_path = dir.path().join("Cargo.toml");
+        let toml = fs::read_to_string(&manifest_path).unwrap();
+        fs::write(
+            &manifest_path,
+            toml
+                + "license = \"MIT OR Apache-2.0\"\nrepository = \"https://example.com/fixture\"\n",
+        )
+        .unwrap();
+        let manifest = CargoManifest::from_path(&manifest_path).unwrap();
 
-            let print_order = [
-                (ItemKind::Macro, "Macros"),
-                (ItemKind::ProcAttribute, "Attribute Macros"),
-                (ItemKind::ProcDerive, "Derive Macros"),
-                (ItemKind::Struct, "Structs"),
-                (ItemKind::Enum, "Enums"),
-                (ItemKind::Union, "Unions"),
-                (ItemKind::Trait, "Traits"),
-                (ItemKind::Function, "Functions"),
-                (ItemKind::TypeAlias, "Type Aliases"),
-                (ItemKind::TraitAlias, "Trait Aliases"),
-                (ItemKind::Static, "Statics"),
-                (ItemKind::Constant, "Constants"),
-                (ItemKind::ExternCrate, "External Crates"),
-                (ItemKind::ExternType, "External Types"),
-                (ItemKind::Primitive, "Primitives"),
-            ];
+        let markdown = Printer::new(&manifest, &krate)
+            .license_footer(true)
+            .print()
+            .unwrap();
+        let footer = markdown.split(": License\n").nth(1).expect(&markdown);
+        assert!(
+            footer.contains("- License: MIT OR Apache-2.0\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            footer.contains("- Repository: <https://example.com/fixture>\n"),
+            "{}",
+            markdown
+        );
+    }
 
-            for (kind, header_name) in print_order {
-                if let Some(ids) = items_by_kind.get(&kind) {
-                    if ids.is_empty() {
-                        continue;
-                    }
-                    if self.print_items_of_kind(ids, kind, header_name) {
-                        self.post_increment_current_level();
-                    }
-                }
+    #[test]
+    fn inherited_supertrait_methods_are_noted() {
+        let (manifest, krate) = document(
+            "/// The base.
+            pub trait A {
+                /// Does a.
+                fn a(&self);
             }
+            /// Extends [`A`].
+            pub trait B: A {
+                /// Does b.
+                fn b(&self);
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .show_inherited_trait_items(true)
+            .print()
+            .unwrap();
+        let section = markdown
+            .split("`trait fixture::B`")
+            .nth(1)
+            .expect(&markdown);
+        assert!(
+            section.contains("- `fn a(&self)` _(from supertrait `fixture::A`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn unavailable_toolchains_are_reported_as_errors() {
+        let error = install_named_toolchain("not a toolchain").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("install it manually with `rustup toolchain install not a toolchain`"),
+            "{:#}",
+            error
+        );
+    }
+
+    #[test]
+    fn where_clause_bounds_are_consolidated() {
+        let (manifest, krate) = document(
+            "/// Clones.
+            pub fn f<T>(_t: T) where T: Clone {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .consolidate_bounds(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(": `fn f<T: Clone>(_t: T)`\n"),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            !markdown.contains(": `fn f<T: Clone>(_t: T)`\n"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn changed_files_select_only_their_items() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "pub mod changed;\npub mod unchanged;\n";
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), source).unwrap();
+        fs::write(
+            dir.path().join("src/changed.rs"),
+            "/// Old.\npub fn old() {}\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("src/unchanged.rs"),
+            "/// Stable.\npub fn stable() {}\n",
+        )
+        .unwrap();
+        let repo = git2::Repository::init(dir.path()).unwrap();
+        let mut index = repo.index().unwrap();
+        index
+            .add_all(["src"], git2::IndexAddOption::DEFAULT, None)
+            .unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+        fs::write(
+            dir.path().join("src/changed.rs"),
+            "/// Old.\npub fn old() {}\n/// New.\npub fn new() {}\n",
+        )
+        .unwrap();
+
+        let (manifest, krate) = document_in(dir.path(), "fixture", source, false);
+        let files = changed_files_since(dir.path(), "HEAD").unwrap();
+        let markdown = Printer::new(&manifest, &krate)
+            .source_root(dir.path())
+            .changed_files(&files)
+            .print()
+            .unwrap();
+        assert!(markdown.contains(": `fn new()`"), "{}", markdown);
+        assert!(markdown.contains(": `fn old()`"), "{}", markdown);
+        assert!(!markdown.contains("stable"), "{}", markdown);
+        assert!(!markdown.contains("fixture::unchanged"), "{}", markdown);
+    }
+
+    #[test]
+    fn plain_enums_render_as_a_table() {
+        let (manifest, krate) = document(
+            "/// A level.
+            pub enum Level {
+                /// Quiet.
+                Low = 1,
+                /// Loud.
+                High = 10,
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .field_layout(FieldLayout::Table)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "| Variant | Discriminant | Description |\n\
+                 |---|---|---|\n\
+                 | `Low` | `1` | Quiet. |\n\
+                 | `High` | `10` | Loud. |\n"
+            ),
+            "{}",
+            markdown
+        );
+    }
 
-            // Print cross-referenced items at the end of the module's direct items
-            if !cross_referenced_items.is_empty() {
-                let re_exports_header_level = self.get_current_header_level();
-                let re_exports_prefix = self.get_header_prefix();
-                writeln!(
-                    self.output,
-                    "\n{} {} Re-exports\n",
-                    "#".repeat(re_exports_header_level),
-                    re_exports_prefix
-                )
-                .unwrap();
-                for (_id, declaration, original_prefix) in cross_referenced_items {
-                    writeln!(
-                        self.output,
-                        "- `{}` (See section {} for details)",
-                        declaration, original_prefix
-                    )
-                    .unwrap();
-                }
-                writeln!(self.output).unwrap(); // Add a blank line after the list
-                self.post_increment_current_level();
-            }
-        } else {
-            warn!(
-                "Could not find resolved module data for ID: {:?}",
-                module_id
-            );
+    #[test]
+    fn impl_trait_arguments_become_generics() {
+        let (manifest, krate) = document(
+            "/// Prints.
+            pub fn show(_value: impl std::fmt::Display) {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .render_impl_trait_as_generic(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(": `fn show<T: Display>(_value: T)`\n"),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(": `fn show(_value: impl Display)`\n"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn readme_fallback_names_are_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manifest, krate) = document_in(dir.path(), "fixture", "//! A crate.", false);
+        fs::write(dir.path().join("Readme.markdown"), "Read me, please.\n").unwrap();
+        let extra = CrateExtraReader::new()
+            .no_examples()
+            .read(&manifest, dir.path())
+            .unwrap();
+        assert_eq!(extra.readme_content.as_deref(), Some("Read me, please.\n"));
+        let markdown = Printer::new(&manifest, &krate)
+            .crate_extra(extra)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(": README\n\nRead me, please.\n"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn abis_are_spelled_as_in_source() {
+        assert_eq!(format_abi(&Abi::Rust), "Rust");
+        assert_eq!(format_abi(&Abi::C { unwind: false }), "C");
+        assert_eq!(format_abi(&Abi::C { unwind: true }), "C-unwind");
+        assert_eq!(format_abi(&Abi::System { unwind: false }), "system");
+        assert_eq!(format_abi(&Abi::System { unwind: true }), "system-unwind");
+        assert_eq!(format_abi(&Abi::Other("\"efiapi\"".to_string())), "efiapi");
+
+        let (manifest, krate) = document(
+            "/// C.
+            pub extern \"C\" fn c() {}
+            /// C, unwinding.
+            pub extern \"C-unwind\" fn c_unwind() {}
+            /// System.
+            pub extern \"system\" fn system() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        for declaration in [
+            "pub extern \"C\" fn c() { ... }",
+            "pub extern \"C-unwind\" fn c_unwind() { ... }",
+            "pub extern \"system\" fn system() { ... }",
+        ] {
+            assert!(markdown.contains(declaration), "{}", markdown);
         }
     }
 
-    /// Prints graph context for an unprinted item.
-    fn print_graph_context(&mut self, id: &Id) {
-        // Collect incoming edges first to release immutable borrow on self.graph
-        let incoming_edges_data: Vec<Edge> = self
-            .graph
-            .find_incoming_edges(id)
-            .into_iter()
-            .cloned()
+    #[test]
+    fn fundamental_types_are_noted() {
+        let (manifest, krate) = document(
+            "#![feature(fundamental)]
+            /// A box.
+            #[fundamental]
+            pub struct MyBox<T>(pub T);",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "`struct fixture::MyBox<T>`\n\n_This type is `#[fundamental]` (affects coherence)._\n"
+            ),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn single_variant_enums_collapse() {
+        let (manifest, krate) = document(
+            "/// Only one.
+            pub enum Only {
+                /// The one.
+                One(u8),
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .collapse_single_variant_enums(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("Only one.\n\n_Single variant: `One(u8)`_\n\nThe one.\n"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains(": Variants\n"), "{}", markdown);
+    }
+
+    #[test]
+    fn table_of_contents_links_match_the_headers() {
+        let (manifest, krate) = document(
+            "/// A struct.
+            pub struct Square;
+            /// A module.
+            pub mod inner {
+                /// A function.
+                pub fn f() {}
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).toc(true).print().unwrap();
+        let toc = markdown
+            .split("**Contents**\n\n")
+            .nth(1)
+            .and_then(|rest| rest.split("\n\n").next())
+            .expect(&markdown);
+        let entries: Vec<(&str, &str)> = toc
+            .lines()
+            .filter_map(|line| {
+                let (title, anchor) = line.trim_start().strip_prefix("- [")?.split_once("](#")?;
+                Some((title, anchor.strip_suffix(')')?))
+            })
             .collect();
+        assert_eq!(entries.len(), toc.lines().count(), "{}", toc);
+        assert!(entries.len() > 4, "{}", toc);
+        for (title, anchor) in entries {
+            let header = header_at_anchor(&markdown, anchor).expect(anchor);
+            assert_eq!(header.trim_start_matches('#').trim(), title, "{}", markdown);
+        }
+    }
 
-        if !incoming_edges_data.is_empty() {
-            writeln!(self.output, "_Referenced by:_").unwrap();
-            // Sort edges for consistent output
-            let mut sorted_edges = incoming_edges_data;
-            sorted_edges.sort_by_key(|edge| {
-                (
-                    format_id_path_canonical(&edge.source, self.krate),
-                    format!("{:?}", edge.label),
-                )
-            });
+    #[test]
+    fn const_generic_functions_render_their_parameters() {
+        let (manifest, krate) = document(
+            "/// Makes an array.
+            pub fn make<const N: usize>() -> [u8; N] {
+                [0; N]
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(": `fn make<const N: usize>() -> [u8; N]`\n"),
+            "{}",
+            markdown
+        );
+    }
 
-            // Push level for this list (for template markers)
-            self.push_level();
-            for edge in sorted_edges {
-                self.post_increment_current_level(); // Increment for this list item
-                let source_path = format_id_path_canonical(&edge.source, self.krate);
-                let template_marker = if self.template_mode
-                    && self
-                        .krate
-                        .index
-                        .get(&edge.source)
-                        .is_some_and(|i| i.docs.is_some())
-                {
-                    format!("\n  {}", self.get_template_marker())
-                } else {
-                    "".to_string()
-                };
-                writeln!(
-                    self.output,
-                    "- `{}` ({}){}",
-                    source_path,
-                    edge.label, // Use Display impl for EdgeLabel
-                    template_marker
-                )
-                .unwrap();
+    #[test]
+    fn intra_doc_links_resolve_or_unlink() {
+        let (manifest, krate) = document(
+            "#![allow(rustdoc::broken_intra_doc_links)]
+            /// The target.
+            pub struct Target;
+            /// Uses [`Target`] but not [`Missing`].
+            pub fn link() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).anchors().print().unwrap();
+        assert!(
+            markdown.contains("Uses [`Target`](#fixture-target) but not \\[`Missing`\\]."),
+            "{}",
+            markdown
+        );
+        assert!(
+            header_at_anchor(&markdown, "fixture-target").is_some_and(|h| h.contains("Target")),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn deprecated_items_get_a_callout() {
+        let (manifest, krate) = document(
+            "/// Old.
+            #[deprecated(since = \"0.1.0\", note = \"use `New` instead\")]
+            pub struct Old;
+            impl Old {
+                /// Older.
+                #[deprecated(note = \"do not call\")]
+                pub fn older(&self) {}
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "`struct fixture::Old`\n\n> **Deprecated** since 0.1.0: use `New` instead\n"
+            ),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("`fn older(&self)`\n\n> **Deprecated**: do not call\n"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn reexported_items_are_shown_as_public() {
+        let (manifest, krate) = document_with(
+            "mod private {
+                /// Reachable only through the re-export.
+                pub struct Foo;
             }
-            self.pop_level(); // Pop list level
-            writeln!(self.output).unwrap(); // Add trailing newline
-        } else {
-            writeln!(
-                self.output,
-                "_Item has no known incoming references in the graph._\n"
-            )
+            pub use private::Foo;",
+            true,
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(": `struct fixture::Foo`\n\n```rust\npub struct Foo;\n```"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn free_functions_are_grouped_by_first_argument() {
+        let (manifest, krate) = document(
+            "/// A foo.
+            pub struct Foo;
+            /// Reads.
+            pub fn read(_foo: &Foo) {}
+            /// Writes.
+            pub fn write(_foo: &Foo, _value: u8) {}
+            /// Unrelated.
+            pub fn alone() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .group_free_functions_by_first_arg(true)
+            .print()
             .unwrap();
-        }
+        let group = markdown
+            .split(": Functions operating on `fixture::Foo`\n")
+            .nth(1)
+            .expect(&markdown);
+        assert!(
+            group.contains(": `fn read(_foo: &fixture::Foo)`"),
+            "{}",
+            markdown
+        );
+        assert!(
+            group.contains(": `fn write(_foo: &fixture::Foo, _value: u8)`"),
+            "{}",
+            markdown
+        );
+        assert!(!group.contains("`fn alone()`"), "{}", markdown);
     }
 
-    /// Creates a clone of the printer with an empty output buffer.
-    fn clone_with_new_output(&self) -> Self {
-        Printer {
-            krate: self.krate,
-            manifest_data: self.manifest_data.clone(),
-            paths: self.paths.clone(),
-            crate_extra: self.crate_extra.clone(),
-            include_other: self.include_other,
-            template_mode: self.template_mode,
-            no_common_traits: self.no_common_traits,
-            selected_ids: self.selected_ids.clone(), // Clone relevant fields
-            resolved_modules: self.resolved_modules.clone(),
-            graph: self.graph.clone(),
-            printed_ids: self.printed_ids.clone(),
-            output: String::new(), // New output buffer
-            module_tree: self.module_tree.clone(),
-            doc_path: self.doc_path.clone(),
-            current_module_path: self.current_module_path.clone(),
-            crate_common_traits: self.crate_common_traits.clone(),
-            all_type_ids_with_impls: self.all_type_ids_with_impls.clone(),
-            module_common_traits: self.module_common_traits.clone(),
-        }
+    #[test]
+    fn common_traits_threshold_is_configurable() {
+        let (manifest, krate) = document(
+            "/// A.
+            #[derive(Clone)]
+            pub struct A;
+            /// B.
+            #[derive(Clone)]
+            pub struct B;
+            /// C.
+            #[derive(Clone)]
+            pub struct C;
+            /// D.
+            pub struct D;",
+        );
+        let printer = |ratio| Printer::new(&manifest, &krate).common_traits_threshold(ratio);
+        let common_traits = |markdown: &str| {
+            let section = markdown.split(": Common Traits\n").nth(1).unwrap();
+            section.split(": Module:").next().unwrap().to_string()
+        };
+        let markdown = printer(0.5).print().unwrap();
+        assert!(
+            common_traits(&markdown).contains("- `Clone`\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            !markdown.contains("Trait Implementations for `A`"),
+            "{}",
+            markdown
+        );
+        let markdown = printer(0.9).print().unwrap();
+        assert!(
+            !common_traits(&markdown).contains("- `Clone`\n"),
+            "{}",
+            markdown
+        );
+        let section = markdown
+            .split("Trait Implementations for `A`\n")
+            .nth(1)
+            .expect(&markdown);
+        assert!(section.starts_with("\n- `Clone`\n"), "{}", markdown);
     }
 
-    /// Recursive function to print modules and their contents depth-first.
-    fn print_module_recursive(&mut self, module_id: Id) {
-        // Skip if not selected. If already printed, we still need to list its re-exports.
-        if module_id != self.krate.root && !self.selected_ids.contains(&module_id) {
-            return;
-        }
+    #[test]
+    fn heading_attributes_survive() {
+        let (manifest, krate) = document(
+            "/// Parses.
+            ///
+            /// # Title {#custom .important}
+            ///
+            /// Body.
+            pub fn parse() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("# Title {#custom .important}\n\nBody.\n"),
+            "{}",
+            markdown
+        );
+    }
 
-        if let Some(item) = self.krate.index.get(&module_id) {
-            // Update current_module_path
-            let module_segment = item.name.as_deref().unwrap_or("").to_string();
-            if module_id == self.krate.root {
-                // For root module, use the crate name
-                self.current_module_path = vec![self
-                    .krate
-                    .index
-                    .get(&self.krate.root)
-                    .unwrap()
-                    .name
-                    .as_ref()
-                    .unwrap()
-                    .replace('-', "_")];
-            } else {
-                self.current_module_path.push(module_segment);
+    #[test]
+    fn pattern_types_render_their_pattern() {
+        let (manifest, krate) = document(
+            "#![feature(pattern_types, pattern_type_macro)]
+            /// Non-zero.
+            pub fn positive(_value: std::pat::pattern_type!(u32 is 1..)) {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        // The nightly rustdoc dumps compiler internals instead of the pattern, so only
+        // the type is left.
+        assert!(
+            markdown.contains(": `fn positive(_value: u32)`\n"),
+            "{}",
+            markdown
+        );
+
+        let printer = Printer::new(&manifest, &krate);
+        let pattern_type = |pat: &str| Type::Pat {
+            type_: Box::new(Type::Primitive("u32".to_string())),
+            __pat_unstable_do_not_use: pat.to_string(),
+        };
+        assert_eq!(format_type(&pattern_type("1.."), &printer), "u32 is 1..");
+        assert_eq!(
+            format_type(&pattern_type("TyPat { hir_id: HirId(0) }"), &printer),
+            "u32"
+        );
+    }
+
+    #[test]
+    fn print_item_renders_only_the_item() {
+        let (manifest, krate) = document(
+            "/// A point.
+            pub struct Point {
+                /// X.
+                pub x: i32,
             }
+            /// Unrelated.
+            pub fn other() {}",
+        );
+        let id = krate
+            .index
+            .values()
+            .find(|item| item.name.as_deref() == Some("Point"))
+            .unwrap()
+            .id;
+        let markdown = Printer::new(&manifest, &krate).print_item(id).unwrap();
+        assert!(
+            markdown.contains("```rust\npub struct Point {\n    pub x: i32,\n}\n```"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains("Manifest"), "{}", markdown);
+        assert!(!markdown.contains("fn other()"), "{}", markdown);
+    }
 
-            let module_header_level = self.get_current_header_level(); // Should be 2
-            let header_prefix = self.get_header_prefix();
-            let module_path_str = self.current_module_path.join("::");
-            let display_path = if module_path_str.is_empty() {
-                item.name.as_deref().unwrap_or("::")
-            } else {
-                &module_path_str
-            };
+    #[test]
+    fn impl_associated_types_keep_generics_and_where_clauses() {
+        let (manifest, krate) = document(
+            "/// Lends items.
+            pub trait Lend {
+                /// The item.
+                type Item<'a>
+                where
+                    Self: 'a;
+            }
+            /// A buffer.
+            pub struct Buffer(Vec<u8>);
+            impl Lend for Buffer {
+                type Item<'a> = &'a [u8] where Self: 'a;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "impl fixture::Lend for fixture::Buffer {\n        \
+                 type Item<'a> = &'a [u8] where Self: 'a;\n    }"
+            ),
+            "{}",
+            markdown
+        );
+    }
 
-            // Print module header (always H2)
-            writeln!(
-                self.output,
-                "\n{} {} Module: `{}`\n", // Module header uses level 2
-                "#".repeat(module_header_level),
-                header_prefix,
-                display_path
-            )
+    #[test]
+    fn heading_offset_shifts_every_header() {
+        let (manifest, krate) = document(
+            "//! Crate docs.
+            /// A module.
+            pub mod shapes {
+                /// A square.
+                pub struct Square;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .heading_offset(1)
+            .print()
             .unwrap();
+        assert!(
+            markdown.starts_with("## fixture API (0.1.0)\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("\n### 4: Module: `fixture::shapes`\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            !markdown.lines().any(|line| line.starts_with("# ")),
+            "{}",
+            markdown
+        );
+    }
 
-            // Mark module as printed only AFTER printing its header, if not already printed
-            // This ensures the first time a module is encountered, its prefix is stored.
-            self.printed_ids
-                .entry(module_id)
-                .or_insert_with(|| header_prefix.clone());
+    #[test]
+    fn constants_and_statics_show_type_and_value() {
+        let (manifest, krate) = document(
+            "/// The limit.
+            pub const LIMIT: u32 = 64;
+            /// A counter.
+            pub static mut COUNTER: usize = 0;",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("```rust\npub const LIMIT: u32 = 64 /* = 64u32 */;\n```"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("```rust\npub static mut COUNTER: usize = 0;\n```"),
+            "{}",
+            markdown
+        );
+    }
 
-            self.push_level();
+    #[test]
+    fn sized_requirement_breakdown_lists_each_method() {
+        let (manifest, krate) = document(
+            "/// A shape.
+            pub trait Shape {
+                /// Returns the area.
+                fn area(&self) -> f64;
+                /// Returns the name.
+                fn name(&self) -> String;
+                /// Clones into a box.
+                fn boxed(&self) -> Box<Self>
+                where
+                    Self: Sized + Clone;
+                /// Creates a shape.
+                fn new() -> Self
+                where
+                    Self: Sized;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "_Callable on `dyn Shape`: `area`, `name`. \
+                 Requires `Self: Sized` (not callable on `dyn Shape`): `boxed`, `new`._"
+            ),
+            "{}",
+            markdown
+        );
+    }
 
-            // Print module docs (using helper)
-            self.print_docs(item);
+    #[test]
+    fn max_module_depth_stubs_deeper_modules() {
+        let (manifest, krate) = document(
+            "/// Outer.
+            pub mod outer {
+                /// In outer.
+                pub fn shallow() {}
+                /// Inner.
+                pub mod inner {
+                    /// In inner.
+                    pub fn deep() {}
+                }
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .max_module_depth(Some(1))
+            .print()
+            .unwrap();
+        assert!(markdown.contains("fn shallow()"), "{}", markdown);
+        assert!(
+            markdown.contains("- `mod inner` (depth limit reached)"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains("fn deep()"), "{}", markdown);
+        assert!(
+            !markdown.contains("Module: `fixture::outer::inner`"),
+            "{}",
+            markdown
+        );
+    }
 
-            // --- Module Common Traits ---
-            if !self.no_common_traits {
-                let mod_common = self.calculate_module_common_traits(&module_id);
-                self.module_common_traits
-                    .insert(module_id, mod_common.clone()); // Store for later use
+    #[test]
+    fn crate_visible_reexports_are_annotated_differently() {
+        let (manifest, krate) = document_with(
+            "/// A.
+            pub fn a() {}
+            /// Re-exports `a`.
+            pub mod inner {
+                pub use super::a;
+            }",
+            true,
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .reexport_visibility(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("- `fn a()` (`pub use`) (See section"),
+            "{}",
+            markdown
+        );
+        // rustdoc leaves restricted `use` items out of its JSON, so their annotation is
+        // checked directly
+        let mut printer = Printer::new(&manifest, &krate);
+        printer.current_module_path = vec!["fixture".to_string(), "inner".to_string()];
+        assert_eq!(
+            format_use_visibility(&rustdoc_types::Visibility::Crate, &printer),
+            "pub(crate) use"
+        );
+        assert_eq!(
+            format_use_visibility(&rustdoc_types::Visibility::Public, &printer),
+            "pub use"
+        );
+    }
 
-                let displayable_module_common: Vec<FormattedTraitImpl> = mod_common
-                    .iter()
-                    .filter(|nt| !self.crate_common_traits.contains(nt)) // Only those not in crate common
-                    .cloned()
-                    .collect();
+    #[test]
+    fn all_features_reach_rustdoc() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "/// Always there.
+            pub fn base() {}
+            /// Behind a feature.
+            #[cfg(feature = \"extra\")]
+            pub fn extra() {}";
+        let (_, default_krate) = document_in(dir.path(), "fixture", source, false);
+        let manifest_path = dir.path().join("Cargo.toml");
+        let toml = fs::read_to_string(&manifest_path).unwrap();
+        fs::write(&manifest_path, toml + "\n[features]\nextra = []\n").unwrap();
+        let manifest = CargoManifest::from_path(&manifest_path).unwrap();
+        let options = RustdocOptions {
+            all_features: true,
+            ..RustdocOptions::default()
+        };
+        let krate = run_rustdoc(dir.path(), "fixture", &options, false).unwrap();
 
-                if !displayable_module_common.is_empty() {
-                    let common_traits_header_level = self.get_current_header_level(); // Should be H3
-                    let common_traits_prefix = self.get_header_prefix();
-                    writeln!(
-                        self.output,
-                        "{} {} Common Traits\n",
-                        "#".repeat(common_traits_header_level),
-                        common_traits_prefix
-                    )
-                    .unwrap();
-                    writeln!(self.output, "In addition to the crate's 'Common Traits', the following traits are commonly implemented by types in this module. Unless otherwise noted, you can assume these traits are implemented:\n").unwrap();
-                    let formatted_list = self.format_trait_list(&displayable_module_common);
-                    if !formatted_list.is_empty() {
-                        write!(self.output, "{}", formatted_list).unwrap();
-                    }
-                    self.post_increment_current_level(); // Increment for this section
+        let has_extra = |krate: &Crate| {
+            krate
+                .index
+                .values()
+                .any(|item| item.name.as_deref() == Some("extra"))
+        };
+        assert!(!has_extra(&default_krate));
+        assert!(has_extra(&krate));
+        let markdown = Printer::new(&manifest, &krate)
+            .all_features(true)
+            .print()
+            .unwrap();
+        assert!(markdown.contains("- `extra` (enabled)\n"), "{}", markdown);
+        assert!(markdown.contains("fn extra()"), "{}", markdown);
+    }
+
+    #[test]
+    fn exclusion_wins_over_inclusion() {
+        let (manifest, krate) = document(
+            "/// Module a.
+            pub mod a {
+                /// Kept.
+                pub fn kept() {}
+                /// Module b.
+                pub mod b {
+                    /// Dropped.
+                    pub fn dropped() {}
                 }
             }
+            /// Outside.
+            pub fn outside() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .paths(&["::a".to_string()])
+            .exclude_paths(&["::a::b".to_string()])
+            .print()
+            .unwrap();
+        assert!(markdown.contains("`fn kept()`"), "{}", markdown);
+        for excluded in ["fixture::a::b", "dropped", "outside"] {
+            assert!(!markdown.contains(excluded), "{}: {}", excluded, markdown);
+        }
+    }
+
+    #[test]
+    fn multi_input_fn_bounds_keep_their_inputs() {
+        let (manifest, krate) = document(
+            "/// State.
+            pub struct State;
+            /// An event.
+            pub struct Event;
+            /// Flow.
+            pub enum ControlFlow { Continue, Break }
+            /// Runs a handler.
+            pub fn run<F, G>(_f: F, _g: G)
+            where
+                F: FnMut(&mut State, Event) -> ControlFlow,
+                G: Fn(u8, &str),
+            {
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown
+                .contains("FnMut(&mut fixture::State, fixture::Event) -> fixture::ControlFlow,\n"),
+            "{}",
+            markdown
+        );
+        // A unit output is left out, as in source
+        assert!(markdown.contains("Fn(u8, &str)\n{ ... }"), "{}", markdown);
+    }
+
+    #[test]
+    fn hidden_items_need_include_hidden() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manifest, _) = document_in(
+            dir.path(),
+            "fixture",
+            "/// Visible.
+            pub fn visible() {}
+            /// Hidden.
+            #[doc(hidden)]
+            pub fn secret() {}",
+            false,
+        );
+        let options = RustdocOptions {
+            document_hidden_items: true,
+            ..RustdocOptions::default()
+        };
+        let krate = run_rustdoc(dir.path(), "fixture", &options, false).unwrap();
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(markdown.contains("`fn visible()`"), "{}", markdown);
+        assert!(!markdown.contains("secret"), "{}", markdown);
+        let markdown = Printer::new(&manifest, &krate)
+            .include_hidden(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("`#[doc(hidden)] fn secret()`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn links_to_std_items_point_to_the_std_docs() {
+        let (manifest, krate) = document(
+            "/// Collects into a [`Vec`] or a [String].
+            pub fn collect() {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "Collects into a [`Vec`](https://doc.rust-lang.org/nightly/alloc/vec/struct.Vec.html) \
+                 or a [String](https://doc.rust-lang.org/nightly/alloc/string/struct.String.html)."
+            ),
+            "{}",
+            markdown
+        );
+    }
 
-            // Print module contents (non-module items only)
-            self.print_module_contents(&module_id);
+    #[test]
+    fn custom_readme_file_replaces_the_discovered_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manifest, krate) = document_in(dir.path(), "fixture", "//! A crate.", false);
+        fs::write(dir.path().join("README.md"), "Discovered.\n").unwrap();
+        let custom = dir.path().join("curated.md");
+        fs::write(&custom, "Curated for readers.\n").unwrap();
+        let extra = CrateExtraReader::new()
+            .no_readme()
+            .no_examples()
+            .readme_file(&custom)
+            .read(&manifest, dir.path())
+            .unwrap();
+        let markdown = Printer::new(&manifest, &krate)
+            .crate_extra(extra)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(": README\n\nCurated for readers.\n"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains("Discovered."), "{}", markdown);
+
+        let missing = CrateExtraReader::new()
+            .readme_file(dir.path().join("missing.md"))
+            .read(&manifest, dir.path())
+            .unwrap_err();
+        assert!(
+            missing.to_string().starts_with("README file not found: "),
+            "{}",
+            missing
+        );
+    }
 
-            self.pop_level();
-            self.post_increment_current_level();
+    #[test]
+    fn redacted_signatures_use_the_public_path() {
+        let (manifest, krate) = document(
+            "mod internal {
+                /// A thing.
+                pub struct Thing;
+            }
+            /// The public API.
+            pub mod api {
+                pub use crate::internal::Thing;
+            }
+            /// Makes a thing.
+            pub fn make() -> internal::Thing {
+                internal::Thing
+            }",
+        );
+        // Off by default, keeping the canonical path
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("`fn make() -> fixture::internal::Thing`"),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .redact_private_paths(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("`fn make() -> fixture::api::Thing`"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains("internal"), "{}", markdown);
+    }
 
-            // Recursively print child modules
-            if let Some(children) = self.module_tree.children.get(&module_id).cloned() {
-                for child_id in children {
-                    self.print_module_recursive(child_id);
+    #[test]
+    fn receivers_use_their_shorthand_forms() {
+        let (manifest, krate) = document(
+            "use std::rc::Rc;
+            /// A counter.
+            pub struct Counter;
+            impl Counter {
+                /// Reads.
+                pub fn get(&self) -> u32 { 0 }
+                /// Writes.
+                pub fn bump(&mut self) {}
+                /// Consumes.
+                pub fn finish(self) {}
+                /// Shares.
+                pub fn share(self: Rc<Self>) {}
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        for signature in [
+            "`fn get(&self) -> u32`",
+            "`fn bump(&mut self)`",
+            "`fn finish(self)`",
+            "`fn share(self: rc::Rc<Self>)`",
+        ] {
+            assert!(markdown.contains(signature), "{}: {}", signature, markdown);
+        }
+    }
+
+    #[test]
+    fn reexport_modes_choose_where_details_go() {
+        let (manifest, krate) = document_with(
+            "mod internal {
+                /// A thing.
+                pub struct Thing;
+            }
+            /// Printed first.
+            pub mod a {
+                /// Nested.
+                pub mod b {
+                    pub use crate::internal::Thing;
                 }
             }
-
-            // Restore current_module_path
-            if module_id != self.krate.root {
-                self.current_module_path.pop();
+            /// Printed last.
+            pub mod z {
+                pub use crate::internal::Thing;
+            }",
+            true,
+        );
+        // Where the details go, and how each other module lists the item
+        for (mode, detailed, listed) in [
+            (
+                ReexportMode::Definition,
+                "#### 5.1.1: `struct fixture::internal::Thing`",
+                [
+                    "- `struct fixture::a::b::Thing` (See module `fixture::internal` for details)",
+                    "- `struct fixture::z::Thing` (See section 5.1.1: for details)",
+                ],
+            ),
+            (
+                ReexportMode::ReexportSite,
+                "#### 6.1.1: `struct fixture::z::Thing`",
+                [
+                    "- `struct fixture::a::b::Thing` (See module `fixture::z` for details)",
+                    "- `struct fixture::internal::Thing` (See module `fixture::z` for details)",
+                ],
+            ),
+            (
+                ReexportMode::Both,
+                "#### 4.1.1: `struct fixture::a::b::Thing`",
+                [
+                    "- `struct fixture::internal::Thing` (See section 4.1.1: for details)",
+                    "- `struct fixture::z::Thing` (See section 4.1.1: for details)",
+                ],
+            ),
+        ] {
+            let markdown = Printer::new(&manifest, &krate)
+                .reexport_mode(mode)
+                .print()
+                .unwrap();
+            assert_eq!(
+                markdown.matches("A thing.").count(),
+                1,
+                "{:?}: {}",
+                mode,
+                markdown
+            );
+            assert!(markdown.contains(detailed), "{:?}: {}", mode, markdown);
+            for entry in listed {
+                assert!(markdown.contains(entry), "{:?}: {}", mode, markdown);
             }
         }
     }
 
-    /// Finalizes the documentation string, printing the crate header and contents.
-    fn finalize(mut self) -> String {
-        let root_item = self.krate.index.get(&self.krate.root).unwrap(); // Assume root exists
-        let crate_name = root_item.name.as_deref().unwrap_or("Unknown Crate");
-        let crate_version = self.krate.crate_version.as_deref().unwrap_or("");
-        let crate_header_level = 1; // Level 1 for crate header
-
-        // Clear doc path before starting
-        self.doc_path.clear();
+    #[test]
+    fn anchor_index_maps_paths_to_emitted_anchors() {
+        let (manifest, krate) = document(
+            "/// A message.
+            pub enum Msg {
+                /// Moves.
+                Move { x: i32 },
+                /// Quits.
+                Quit,
+            }",
+        );
+        let (markdown, index) = Printer::new(&manifest, &krate)
+            .anchors()
+            .print_with_anchor_index()
+            .unwrap();
+        assert_eq!(
+            index.get("fixture::Msg::Move").map(String::as_str),
+            Some("fixture-msg-move")
+        );
+        assert_eq!(index.len(), 3, "{:?}", index);
+        for anchor in index.values() {
+            let tag = format!("<a id=\"{}\"></a>", anchor);
+            assert!(markdown.contains(&tag), "{}: {}", tag, markdown);
+        }
+        let (_, index) = Printer::new(&manifest, &krate)
+            .print_with_anchor_index()
+            .unwrap();
+        assert!(index.is_empty(), "{:?}", index);
+    }
 
-        // Print Crate Header (# Crate Name (Version)) - No prefix
-        writeln!(
-            self.output,
-            "{} {} API ({})\n", // Add newline after header
-            "#".repeat(crate_header_level),
-            crate_name,
-            crate_version
-        )
-        .unwrap();
-        // Push H2 level before starting sections/modules
-        self.push_level();
+    #[test]
+    fn render_crate_applies_its_options() {
+        let (manifest, krate) = document(
+            "/// Public API.
+            pub mod api {
+                /// Kept.
+                pub fn kept() {}
+            }
+            /// Outside.
+            pub fn outside() {}",
+        );
+        let options = RenderOptions {
+            paths: vec!["::api".to_string()],
+            no_common_traits: true,
+            anchors: true,
+            toc: true,
+            ..RenderOptions::default()
+        };
+        let markdown = render_crate(&manifest, &krate, options).unwrap();
+        let expected = Printer::new(&manifest, &krate)
+            .paths(&["::api".to_string()])
+            .no_common_traits()
+            .anchors()
+            .toc(true)
+            .print()
+            .unwrap();
+        assert_eq!(markdown, expected);
+        assert!(
+            markdown.contains("<a id=\"fixture-api-kept\"></a>"),
+            "{}",
+            markdown
+        );
+        assert!(!markdown.contains("outside"), "{}", markdown);
+    }
 
-        // Print Crate Description (if available) - NEW
-        if let Some(desc) = &self.manifest_data.description {
-            writeln!(self.output, "{}\n", desc).unwrap();
-        }
+    #[test]
+    fn impl_generics_and_trait_generics_stay_apart() {
+        let (manifest, krate) = document(
+            "/// Wraps a value.
+            pub struct Wrapper<T>(pub T);
+            impl<'a, T: Clone> From<&'a T> for Wrapper<T> {
+                fn from(value: &'a T) -> Self {
+                    Wrapper(value.clone())
+                }
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown
+                .contains("- `impl<'a, T: Clone> convert::From<&'a T> for fixture::Wrapper<T>`\n"),
+            "{}",
+            markdown
+        );
+        // Shared names in the blanket impl are not conflated either
+        assert!(
+            markdown.contains("- `impl<T> convert::From<T> for T`\n"),
+            "{}",
+            markdown
+        );
+    }
 
-        // Print Manifest Section (H2) - NEW
-        let manifest_section_level = self.get_current_header_level(); // Should be 2
-        let manifest_header_prefix = self.get_header_prefix();
-        writeln!(
-            self.output,
-            "{} {} Manifest\n",
-            "#".repeat(manifest_section_level),
-            manifest_header_prefix
-        )
-        .unwrap();
+    #[test]
+    fn blanket_impls_keep_generics_and_where_clauses() {
+        let (manifest, krate) = document(
+            "/// Describes.
+            pub trait Describe {}
+            impl<T: Clone> Describe for T where T: std::fmt::Debug {}
+            /// A point.
+            #[derive(Clone, Debug)]
+            pub struct Point;",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("- `impl<T> fixture::Describe for T` (`where T: Clone + Debug`)\n"),
+            "{}",
+            markdown
+        );
+        let (_, section) = markdown
+            .split_once(": `impl<T> fixture::Describe for T`\n")
+            .expect(&markdown);
+        let code = section.split("```").nth(1).expect(section);
+        assert!(code.contains("Clone") && code.contains("Debug"), "{}", code);
+    }
 
-        // Use simple list format for manifest details
-        if let Some(hp) = &self.manifest_data.homepage {
-            writeln!(self.output, "- Homepage: <{}>", hp).unwrap();
-        }
-        if let Some(repo) = &self.manifest_data.repository {
-            writeln!(self.output, "- Repository: <{}>", repo).unwrap();
-        }
-        if !self.manifest_data.categories.is_empty() {
-            writeln!(
-                self.output,
-                "- Categories: {}",
-                self.manifest_data.categories.join(", ")
-            )
-            .unwrap();
-        }
-        if let Some(lic) = &self.manifest_data.license {
-            writeln!(self.output, "- License: {}", lic).unwrap();
-        }
-        if let Some(rv) = &self.manifest_data.rust_version {
-            writeln!(self.output, "- rust-version: `{}`", rv).unwrap();
-        }
-        if let Some(ed) = &self.manifest_data.edition {
-            writeln!(self.output, "- edition: `{}`", ed).unwrap();
+    #[test]
+    fn source_span_order_follows_the_source() {
+        let (manifest, krate) = document(
+            "/// Beta.
+            pub fn beta() {}
+            /// Alpha.
+            pub fn alpha() {}
+            /// Zeta.
+            pub struct Zeta;
+            /// Eta.
+            pub struct Eta;
+            /// A trait.
+            pub trait Tr {
+                /// Second.
+                fn second(&self);
+                /// First.
+                fn first(&self);
+            }",
+        );
+        let print = |order| {
+            Printer::new(&manifest, &krate)
+                .sort_order(order)
+                .print()
+                .unwrap()
+        };
+        let in_order = |markdown: &str, needles: [&str; 2]| {
+            let position = |needle: &str| {
+                markdown
+                    .find(needle)
+                    .unwrap_or_else(|| panic!("{} not found in {}", needle, markdown))
+            };
+            position(needles[0]) < position(needles[1])
+        };
+        let pairs = [
+            ["`fn beta()`", "`fn alpha()`"],
+            ["`struct fixture::Zeta`", "`struct fixture::Eta`"],
+            ["`fn second(&self)`", "`fn first(&self)`"],
+        ];
+        let alphabetical = print(SortOrder::Alphabetical);
+        let source_span = print(SortOrder::SourceSpan);
+        for pair in pairs {
+            assert!(
+                !in_order(&alphabetical, pair),
+                "{:?}: {}",
+                pair,
+                alphabetical
+            );
+            assert!(in_order(&source_span, pair), "{:?}: {}", pair, source_span);
         }
-        writeln!(self.output).unwrap(); // Add a newline after the list
+    }
 
-        // Print Features Sub-section (H3) - NEW
-        let features_section_level = self.get_current_header_level() + 1; // H3
-        self.push_level(); // Push for the H3 features section
-        let features_header_prefix = self.get_header_prefix();
-        writeln!(
-            self.output,
-            "{} {} Features\n",
-            "#".repeat(features_section_level),
-            features_header_prefix
+    #[test]
+    fn track_caller_is_a_note_not_part_of_the_signature() {
+        let (manifest, krate) = document(
+            "/// Panics with the caller's location.
+            #[track_caller]
+            pub fn check(ok: bool) {
+                assert!(ok);
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "#### 2.1.1: `fn check(ok: bool)`\n\n_Attributes: `#[track_caller]`_\n\n\
+                 Panics with the caller's location.\n"
+            ),
+            "{}",
+            markdown
+        );
+        // The signature alone adds nothing to the header, so no code block is printed
+        assert!(!markdown.contains("pub fn check"), "{}", markdown);
+    }
+
+    #[test]
+    fn package_targets_select_the_documented_crate() {
+        let dir = tempfile::tempdir().unwrap();
+        document_in(
+            dir.path(),
+            "fixture",
+            "/// In the library.
+            pub fn from_lib() {}",
+            false,
+        );
+        fs::write(
+            dir.path().join("src/main.rs"),
+            "/// In the binary.
+            pub fn from_bin() {}
+            fn main() {}",
         )
         .unwrap();
+        let document_target = |package_target: PackageTarget| {
+            let options = RustdocOptions {
+                package_target,
+                ..RustdocOptions::default()
+            };
+            run_rustdoc(dir.path(), "fixture", &options, false).unwrap()
+        };
+        let lib = document_target(PackageTarget::Lib);
+        let bin = document_target(PackageTarget::Bin("fixture".to_string()));
+        let names = |krate: &Crate| -> HashSet<String> {
+            krate
+                .index
+                .values()
+                .filter(|item| item.crate_id == 0)
+                .filter_map(|item| item.name.clone())
+                .collect()
+        };
+        assert!(names(&lib).contains("from_lib"), "{:?}", names(&lib));
+        assert!(!names(&lib).contains("from_bin"), "{:?}", names(&lib));
+        assert!(names(&bin).contains("from_bin"), "{:?}", names(&bin));
+        assert!(!names(&bin).contains("from_lib"), "{:?}", names(&bin));
+    }
 
-        // List features or state None
-        if self.manifest_data.features.is_empty() {
-            writeln!(self.output, "- None").unwrap();
-        } else {
-            // Sort features for consistent output
-            let mut sorted_features: Vec<_> = self.manifest_data.features.keys().collect();
-            sorted_features.sort_unstable();
-            for feature_name in sorted_features {
-                // TODO: Maybe show what features a feature enables? Requires more parsing.
-                writeln!(self.output, "- `{}`", feature_name).unwrap();
+    #[test]
+    fn multi_line_where_clauses_put_the_brace_on_its_own_line() {
+        let (manifest, krate) = document(
+            "/// A pair.
+            pub struct Pair<A, B>
+            where
+                A: Clone + Default,
+                B: Clone + Default,
+            {
+                pub a: A,
+                pub b: B,
             }
+            /// Combines.
+            pub trait Combine<A, B>
+            where
+                A: Clone + Default,
+                B: Clone + Default,
+            {
+                /// Combines.
+                fn combine(&self);
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        for header in ["pub struct Pair<A, B>", "pub trait Combine<A, B>"] {
+            let expected = format!(
+                "```rust\n{}\n  where\n    A: Clone + default::Default,\n    \
+                 B: Clone + default::Default\n{{\n",
+                header
+            );
+            assert!(markdown.contains(&expected), "{}: {}", header, markdown);
         }
-        writeln!(self.output).unwrap(); // Add newline after features list
-        self.pop_level(); // Pop H3 features level
-
-        // Increment H2 counter for the next section (README or Common Traits)
-        self.post_increment_current_level();
+    }
 
-        // Print README content if available from CrateExtra
-        if let Some(extra) = &self.crate_extra {
-            if let Some(readme) = &extra.readme_content {
-                info!("Injecting README content.");
-                let section_level = self.get_current_header_level(); // Should be 2
-                let header_prefix = self.get_header_prefix();
-                writeln!(
-                    self.output,
-                    "\n{} {} README\n",
-                    "#".repeat(section_level),
-                    header_prefix
-                )
-                .unwrap();
-                let adjusted_readme = adjust_markdown_headers(readme, section_level);
-                writeln!(self.output, "{}\n", adjusted_readme).unwrap();
-                self.post_increment_current_level(); // Increment H2 counter
+    #[test]
+    fn cross_references_leave_no_numbering_gaps() {
+        let (manifest, krate) = document(
+            "/// Inner.
+            pub mod inner {
+                /// A.
+                pub struct A;
+                /// B.
+                pub struct B;
+                /// C.
+                pub struct C;
             }
+            /// Outer.
+            pub mod outer {
+                pub use crate::inner::B;
+                /// D.
+                pub struct D;
+                /// E.
+                pub struct E;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains("- `struct fixture::outer::B` (See section 4.1.2: for details)"),
+            "{}",
+            markdown
+        );
+        // Every numbered header follows its previous sibling, or starts at 1
+        let mut last_child: HashMap<Vec<u32>, u32> = HashMap::new();
+        for line in markdown.lines().filter(|line| line.starts_with('#')) {
+            let Some((number, _)) = line.trim_start_matches('#').trim().split_once(": ") else {
+                continue;
+            };
+            let Ok(mut parts) = number
+                .split('.')
+                .map(str::parse)
+                .collect::<Result<Vec<u32>, _>>()
+            else {
+                continue;
+            };
+            let index = parts.pop().unwrap();
+            let previous = last_child.insert(parts, index).unwrap_or(0);
+            assert_eq!(index, previous + 1, "{}: {}", line, markdown);
         }
+    }
 
-        // Print Crate Common Traits Section (H2)
-        if !self.no_common_traits && !self.crate_common_traits.is_empty() {
-            let common_traits_level = self.get_current_header_level(); // Should be 2
-            let common_traits_prefix = self.get_header_prefix();
-            writeln!(
-                self.output,
-                "\n{} {} Common Traits\n",
-                "#".repeat(common_traits_level),
-                common_traits_prefix
-            )
-            .unwrap();
-            writeln!(self.output, "The following traits are commonly implemented by types in this crate. Unless otherwise noted, you can assume these traits are implemented:\n").unwrap();
-
-            let sorted_common_traits: Vec<FormattedTraitImpl> = {
-                let mut traits: Vec<_> = self.crate_common_traits.iter().cloned().collect();
-                traits.sort_by_key(|t| t.formatted_markdown_list_entry.clone());
-                traits
-            };
+    #[test]
+    fn dyn_auto_traits_follow_the_principal_trait() {
+        let (manifest, krate) = document(
+            "/// A trait.
+            pub trait MyTrait {}
+            /// Takes a trait object.
+            pub fn take(_value: Box<dyn Sync + MyTrait + Send + 'static>) {}",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown
+                .contains("`fn take(_value: Box<dyn fixture::MyTrait + Send + Sync + 'static>)`"),
+            "{}",
+            markdown
+        );
+    }
 
-            let formatted_list = self.format_trait_list(&sorted_common_traits);
-            if !formatted_list.is_empty() {
-                write!(self.output, "{}", formatted_list).unwrap();
+    #[test]
+    fn sealed_details_name_the_sealing_trait_and_its_implementors() {
+        let source = "mod private {
+                pub trait Sealed {}
             }
-            writeln!(self.output).unwrap();
-            self.post_increment_current_level(); // Increment H2 counter
-        }
-
-        // --- Print Top-Level Sections (Macros first, then Modules) ---
+            /// A sealed trait.
+            pub trait Shape: private::Sealed {}
+            /// A square.
+            pub struct Square;
+            /// A circle.
+            pub struct Circle;
+            impl private::Sealed for Square {}
+            impl private::Sealed for Circle {}
+            impl Shape for Square {}
+            impl Shape for Circle {}";
+        let (manifest, krate) = document_with(source, true);
+        let markdown = Printer::new(&manifest, &krate)
+            .include_sealed_details(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "_Sealed: requires the private supertrait `fixture::private::Sealed`, \
+                 implemented for `fixture::Circle`, `fixture::Square`._"
+            ),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(!markdown.contains("_Sealed:"), "{}", markdown);
+
+        // rustdoc strips the impls of private traits from the public JSON
+        let (manifest, krate) = document(source);
+        let markdown = Printer::new(&manifest, &krate)
+            .include_sealed_details(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains(
+                "_Sealed: requires the private supertrait `fixture::private::Sealed`, \
+                 so it cannot be implemented outside this crate._"
+            ),
+            "{}",
+            markdown
+        );
+    }
 
-        // --- Macros Section (Level 2) ---
-        // Find macros directly under the resolved root module
-        if let Some(resolved_root_module) = self.resolved_modules.get(&self.krate.root) {
-            let macro_ids: Vec<Id> = resolved_root_module
-                .items
-                .iter()
-                .filter(|id| self.selected_ids.contains(id))
-                .filter(|id| {
-                    matches!(
-                        self.get_item_kind(id),
-                        Some(ItemKind::Macro | ItemKind::ProcAttribute | ItemKind::ProcDerive)
-                    )
-                })
-                .cloned() // Clone the IDs
-                .collect();
+    #[test]
+    fn crate_docs_come_before_the_manifest() {
+        let (manifest, krate) = document(
+            "//! The crate-level overview.
+            /// A module.
+            pub mod shapes {
+                /// A square.
+                pub struct Square;
+            }",
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.starts_with(
+                "# fixture API (0.1.0)\n\n## 1: Crate Documentation\n\n\
+                 The crate-level overview.\n\n## 2: Manifest\n"
+            ),
+            "{}",
+            markdown
+        );
+        assert_eq!(
+            markdown.matches("The crate-level overview.").count(),
+            1,
+            "{}",
+            markdown
+        );
+    }
 
-            if !macro_ids.is_empty() {
-                let section_level = self.get_current_header_level(); // Should be 2
-                let header_prefix = self.get_header_prefix();
-                writeln!(
-                    self.output,
-                    "\n{} {} Macros",
-                    "#".repeat(section_level),
-                    header_prefix
-                )
-                .unwrap();
+    #[test]
+    fn wide_supertrait_lists_wrap_one_bound_per_line() {
+        let (manifest, krate) = document(
+            "/// A trait.
+            pub trait Component: Clone + std::fmt::Debug + Send + Sync {}",
+        );
+        let print = |width| {
+            Printer::new(&manifest, &krate)
+                .max_line_width(width)
+                .print()
+                .unwrap()
+        };
+        let markdown = print(Some(40));
+        assert!(
+            markdown.contains(
+                "```rust\npub trait Component:\n    Clone +\n    Debug +\n    Send +\n    Sync\n{}\n```"
+            ),
+            "{}",
+            markdown
+        );
+        let markdown = print(Some(100));
+        assert!(
+            markdown.contains("```rust\npub trait Component: Clone + Debug + Send + Sync {}\n```"),
+            "{}",
+            markdown
+        );
+    }
 
-                self.push_level(); // Push H3 level for macro items
-                let mut sorted_macros = macro_ids;
-                sorted_macros
-                    .sort_by_key(|id| self.krate.index.get(id).and_then(|item| item.name.clone()));
-                for id in sorted_macros {
-                    self.print_item_details(&id); // Macro details at level 3
-                }
-                self.pop_level(); // Pop H3 level
-                self.post_increment_current_level(); // Increment H2 counter
+    #[test]
+    fn dependency_order_documents_building_blocks_first() {
+        let (manifest, krate) = document(
+            "/// Uses a wheel.
+            pub struct Car {
+                /// The wheel.
+                pub wheel: Wheel,
             }
+            /// Uses a rim.
+            pub struct Wheel {
+                /// The rim.
+                pub rim: Rim,
+            }
+            /// A building block.
+            pub struct Rim;",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .dependency_order(true)
+            .print()
+            .unwrap();
+        let position = |needle: &str| {
+            markdown
+                .find(needle)
+                .unwrap_or_else(|| panic!("{} not found in {}", needle, markdown))
+        };
+        assert!(position("`struct fixture::Rim`") < position("`struct fixture::Wheel`"));
+        assert!(position("`struct fixture::Wheel`") < position("`struct fixture::Car`"));
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.find("`struct fixture::Car`") < markdown.find("`struct fixture::Rim`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[cfg(feature = "rustfmt")]
+    #[test]
+    fn rustfmt_code_block_keeps_body_placeholders() {
+        let code = "pub fn f<T>(x: T) -> T where T: Clone { ... }\npub fn g() { ... }";
+        // Without a `rustfmt` binary there is nothing to check.
+        let Some(formatted) = super::rustfmt_code_block(code, Some("2021")) else {
+            return;
+        };
+        assert_eq!(
+            formatted,
+            "pub fn f<T>(x: T) -> T\nwhere\n    T: Clone,\n{ ... }\npub fn g() { ... }"
+        );
+    }
+
+    #[test]
+    fn declarations_use_the_item_visibility() {
+        let (manifest, krate) = document_with(
+            "pub struct Root { pub a: u8, b: u8, pub(crate) c: u8 }
+            pub mod inner {
+                pub struct S { pub a: u8, b: u8, pub(crate) c: u8, pub(in crate::inner) d: u8 }
+                pub(crate) trait Tr { fn method(&self) where Self: Sized; }
+            }",
+            true,
+        );
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        for line in [
+            "pub struct Root {\n    pub a: u8,\n    b: u8,\n    c: u8,\n}",
+            "pub struct S {\n    pub a: u8,\n    b: u8,\n    pub(crate) c: u8,\n    d: u8,\n}",
+            "pub(crate) trait Tr {\n    fn method(&self) where Self: Sized;\n}",
+        ] {
+            assert!(
+                markdown.contains(line),
+                "missing {:?} in:\n{}",
+                line,
+                markdown
+            );
         }
+    }
 
-        // --- Modules (Depth-First Traversal) ---
+    #[test]
+    fn rustdoc_json_is_cached_when_building_for_a_target() {
+        let host = std::process::Command::new("rustc")
+            .arg("-vV")
+            .output()
+            .ok()
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .and_then(|info| {
+                info.lines()
+                    .find_map(|line| line.strip_prefix("host: ").map(str::to_string))
+            })
+            .expect("rustc -vV should print the host triple");
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[package]\nname = \"fixture\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "pub fn f() {}").unwrap();
+        let options = RustdocOptions {
+            target: Some(host),
+            ..RustdocOptions::default()
+        };
+        let json_path = build_rustdoc_json(dir.path(), "fixture", &options, false).unwrap();
+        assert!(json_path.starts_with(dir.path().join("target/doc")));
+        assert!(read_rustdoc_json(&json_path, "fixture").is_ok());
+    }
 
-        // 1. Print Crate Root Module explicitly (will increment H2 counter)
-        self.print_module_recursive(self.krate.root);
+    #[test]
+    fn rustdoc_json_cache_path_is_keyed_by_the_build_flags() {
+        let dir = FilePath::new("/crate");
+        let cache_path =
+            |options: RustdocOptions| rustdoc_json_cache_path(dir, "my-crate", &options);
+        let with_features = |features: &str| {
+            cache_path(RustdocOptions {
+                features: Some(features.to_string()),
+                ..RustdocOptions::default()
+            })
+        };
+        let default_path = cache_path(RustdocOptions::default());
+        assert!(default_path.starts_with("/crate/target/doc"));
+        assert!(default_path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("my_crate-"));
+
+        assert_ne!(with_features("a"), with_features("b"));
+        assert_eq!(with_features("a b"), with_features("b a"));
+        assert_eq!(with_features(""), default_path);
+        assert_ne!(
+            cache_path(RustdocOptions {
+                no_default_features: true,
+                ..RustdocOptions::default()
+            }),
+            default_path
+        );
+        assert_ne!(
+            cache_path(RustdocOptions {
+                target: Some("wasm32-unknown-unknown".to_string()),
+                ..RustdocOptions::default()
+            }),
+            default_path
+        );
+    }
 
-        // 2. Iterate through sorted top-level modules and print recursively
-        // Clone the list to avoid borrowing issues
-        let top_level_ids = self.module_tree.top_level_modules.clone();
-        for module_id in top_level_ids {
-            self.print_module_recursive(module_id); // Will increment H2 counter
+    #[test]
+    fn doc_overrides_apply_to_undocumented_members() {
+        let (manifest, krate) = document(
+            "/// A struct.
+            pub struct S { pub field: u8 }
+            /// An enum.
+            pub enum E { Variant(u8), Other }
+            /// A trait.
+            pub trait Tr { fn method(&self); }",
+        );
+        let overrides: HashMap<String, DocOverride> = [
+            "fixture::S::field",
+            "fixture::E::Variant",
+            "fixture::Tr::method",
+        ]
+        .into_iter()
+        .map(|path| {
+            (
+                path.to_string(),
+                DocOverride::Append(format!("Docs of `{}`.", path)),
+            )
+        })
+        .collect();
+        let markdown = Printer::new(&manifest, &krate)
+            .doc_overrides(overrides.clone())
+            .print()
+            .unwrap();
+        for path in overrides.keys() {
+            let docs = format!("Docs of `{}`.", path);
+            assert!(
+                markdown.contains(&docs),
+                "missing {:?} in:\n{}",
+                docs,
+                markdown
+            );
         }
+    }
 
-        // --- Handle "Other" Items ---
-        let mut unprinted_ids = Vec::new();
-        for id in &self.selected_ids {
-            if !self.printed_ids.contains_key(id) {
-                // Skip impl items and use items as they are handled implicitly or ignored
-                // Also skip struct fields as they are handled within their containers
-                // Also skip Modules as they are handled explicitly above
-                if let Some(item) = self.krate.index.get(id) {
-                    if !matches!(
-                        item.inner,
-                        ItemEnum::Impl(_)
-                            | ItemEnum::Use { .. }
-                            | ItemEnum::StructField(_)
-                            | ItemEnum::Module(_) // Modules explicitly skipped here
-                    ) && item.name.is_some()
-                    {
-                        unprinted_ids.push(*id);
-                    }
-                    // If it doesn't have a name or is a StructField/Module but is selected & unprinted, mark it printed now to avoid the warning
-                    else if item.name.is_none()
-                        || matches!(item.inner, ItemEnum::StructField(_) | ItemEnum::Module(_))
-                    {
-                        self.printed_ids.insert(*id, "SKIPPED_OTHER".to_string());
-                    }
-                } else {
-                    // ID selected but not in index - treat as unprinted for "Other"
-                    unprinted_ids.push(*id);
-                }
+    #[test]
+    fn renamed_reexports_are_named_by_their_use() {
+        let (manifest, krate) = document(
+            "mod private {
+                /// The item.
+                pub struct Foo;
             }
-        }
+            pub use private::Foo as Bar;
+            /// Takes the item.
+            pub fn take(_bar: Bar) {}",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .redact_private_paths(true)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("fn take(_bar: fixture::Bar)"),
+            "{}",
+            markdown
+        );
+    }
 
-        if !unprinted_ids.is_empty() {
-            if self.include_other {
-                warn!(
-                    "Found {} selected items that were not printed in the main structure. Including them in the 'Other' section.",
-                    unprinted_ids.len()
-                );
-                let other_section_level = self.get_current_header_level(); // Should be 2
-                let header_prefix = self.get_header_prefix();
-                writeln!(
-                    self.output,
-                    "\n{} {} Other", // Use ## level for this section
-                    "#".repeat(other_section_level),
-                    header_prefix
-                )
-                .unwrap();
+    /// Returns the header line following the `<a id="anchor">` anchor in `markdown`.
+    fn header_at_anchor<'m>(markdown: &'m str, anchor: &str) -> Option<&'m str> {
+        let start = markdown.find(&format!("<a id=\"{}\"></a>", anchor))?;
+        markdown[start..].lines().nth(1)
+    }
 
-                // Push H3 level for items in Other
-                self.push_level();
-                // Sort unprinted items for consistent output
-                unprinted_ids.sort_by_key(|id| {
-                    (
-                        self.krate.paths.get(id).map(|p| p.path.clone()),
-                        self.krate.index.get(id).and_then(|i| i.name.clone()),
-                    )
-                });
+    #[test]
+    fn anchor_links_point_at_their_item_when_slugs_collide() {
+        let (manifest, krate) = document(
+            "/// The config. See [`config`] and [`Config::new`].
+            pub struct Config;
+            impl Config {
+                /// Creates the config.
+                pub fn new() -> Self { Config }
+            }
+            /// Makes a [`Config`].
+            pub fn config() -> Config { Config }",
+        );
+        let markdown = Printer::new(&manifest, &krate).anchors().print().unwrap();
+        let link_target = |text: &str| {
+            let start = markdown.find(&format!("[`{}`](#", text)).unwrap() + text.len() + 6;
+            markdown[start..].split(')').next().unwrap().to_string()
+        };
+        let function = header_at_anchor(&markdown, &link_target("config")).unwrap();
+        assert!(function.contains("`fn config() -> "), "{}", markdown);
+        let structure = header_at_anchor(&markdown, &link_target("Config")).unwrap();
+        assert!(
+            structure.contains("`struct fixture::Config`"),
+            "{}",
+            markdown
+        );
+        let method = header_at_anchor(&markdown, &link_target("Config::new")).unwrap();
+        assert!(method.contains("`fn new() -> Self`"), "{}", markdown);
+    }
 
-                for id in &unprinted_ids {
-                    let path_str = format_id_path_canonical(id, self.krate);
-                    warn!("Including unprinted item in 'Other' section: {}", path_str);
+    #[test]
+    fn strip_control_chars_removes_escape_sequences() {
+        assert_eq!(strip_control_chars("plain\ttext\n"), "plain\ttext\n");
+        assert_eq!(strip_control_chars("\x1b[31mred\x1b[0m"), "red");
+        assert_eq!(
+            strip_control_chars("\x1b]0;title\x07a\x1b]8;;url\x1b\\b"),
+            "ab"
+        );
+        assert_eq!(strip_control_chars("\x1b\\[1mbold\x1b\\[0m"), "bold");
+        assert_eq!(strip_control_chars("bell\x07 and \r\n"), "bell and \n");
+    }
 
-                    // Fetch the item to print its header and span
-                    if let Some(item) = self.krate.index.get(id) {
-                        // Print details (handles level incrementing internally)
-                        self.print_item_details(id);
+    #[test]
+    fn control_chars_are_stripped_from_every_text_source() {
+        let (manifest, krate) = document(
+            "/// Docs \u{1b}cwith a reset.
+            #[deprecated(note = \"use \u{1b}[31mnothing\u{1b}[0m\")]
+            pub fn f() {}",
+        );
+        let extra = CrateExtra {
+            readme_content: Some("# Readme\n\nRings\x07 the \x1b[1mbell\x1b[0m.".to_string()),
+            examples_readme_content: Some("Examples \x1b]0;title\x07here.".to_string()),
+            examples: vec![(
+                "demo.rs".to_string(),
+                "// \x1b[1mbold\nfn main() {}".to_string(),
+            )],
+        };
+        let printer = || Printer::new(&manifest, &krate).crate_extra(extra.clone());
+        let markdown = printer().print().unwrap();
+        assert!(markdown.contains("Rings the bell."), "{}", markdown);
+        assert!(markdown.contains("Docs with a reset."), "{}", markdown);
+        assert!(markdown.contains("use nothing"), "{}", markdown);
+        assert!(markdown.contains("// bold"), "{}", markdown);
+        assert!(!markdown.contains(['\x1b', '\x07']), "{:?}", markdown);
+        assert!(printer()
+            .sanitize_docs(false)
+            .print()
+            .unwrap()
+            .contains('\x1b'));
+    }
 
-                        // Get level AFTER printing details for graph context
-                        // let item_level = self.get_current_header_level(); // Should be H3+1 = H4 // Level not needed here
+    #[test]
+    fn split_output_keeps_the_index_and_links_across_files() {
+        let (manifest, krate) = document_crate(
+            "index",
+            "pub mod shapes {
+                /// A shape.
+                pub struct Shape;
+            }
+            /// Draws a [`shapes::Shape`].
+            pub fn draw() {}",
+            false,
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let paths = Printer::new(&manifest, &krate)
+            .print_to_dir(dir.path())
+            .unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|path| path.file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["index.md", "index-crate.md", "index-shapes.md"]);
+        let index = fs::read_to_string(dir.path().join("index.md")).unwrap();
+        assert!(
+            index.contains("- [Module `index`](index-crate.md)"),
+            "{}",
+            index
+        );
+        let root = fs::read_to_string(dir.path().join("index-crate.md")).unwrap();
+        assert!(
+            root.contains("[`shapes::Shape`](index-shapes.md#index-shapes-shape)"),
+            "{}",
+            root
+        );
+        let shapes = fs::read_to_string(dir.path().join("index-shapes.md")).unwrap();
+        assert!(
+            shapes.contains("<a id=\"index-shapes-shape\"></a>"),
+            "{}",
+            shapes
+        );
+    }
 
-                        // Print Source Location (if available) ONLY for "Other" items
-                        if let Some(span) = &item.span {
-                            writeln!(
-                                self.output,
-                                "_Source: `{}:{}:{}`_\n", // Italic, newline after
-                                span.filename.display(),
-                                span.begin.0 + 1, // Line numbers are 0-based
-                                span.begin.1 + 1  // Column numbers are 0-based
-                            )
-                            .unwrap();
-                        }
-                        // Always print graph context afterwards for items in "Other"
-                        self.print_graph_context(id);
-                    } else {
-                        // Handle case where ID is selected but not in index (rare)
-                        self.post_increment_current_level(); // Increment level for this item
-                        let other_item_level = self.get_current_header_level();
-                        let item_prefix = self.get_header_prefix();
-                        writeln!(
-                            self.output,
-                            "\n{} {} `{}`\n",
-                            "#".repeat(other_item_level),
-                            item_prefix,
-                            path_str // Use path string as header
-                        )
-                        .unwrap();
-                        writeln!(self.output, "_Error: Item details not found in index._\n")
-                            .unwrap();
-                        self.print_graph_context(id); // Still print graph context
-                    }
-                }
-                self.pop_level(); // Pop H3 level for items
-            } else {
-                // Group by kind and log counts
-                let mut counts_by_kind: HashMap<ItemKind, usize> = HashMap::new(); // Use HashMap
-                for id in &unprinted_ids {
-                    if let Some(kind) = self.get_item_kind(id) {
-                        *counts_by_kind.entry(kind).or_insert(0) += 1;
-                    } else {
-                        // Count items where kind couldn't be determined (e.g., ID not in index)
-                        *counts_by_kind.entry(ItemKind::StructField).or_insert(0) += 1;
-                        // Use a placeholder kind like StructField
-                    }
-                }
-                warn!(
-                    "Skipped printing {} items not fitting into standard sections (use --include-other to see them):",
-                    unprinted_ids.len()
-                );
-                // Convert HashMap to Vec for sorting before printing warnings
-                let mut sorted_counts: Vec<_> = counts_by_kind.into_iter().collect();
-                sorted_counts.sort_by_key(|(kind, _)| format!("{:?}", kind)); // Sort by debug representation for consistency
+    #[test]
+    fn link_anchors_across_files_only_rewrites_links_into_other_files() {
+        let file = |name: &str, content: &str| ModuleFile {
+            name: name.to_string(),
+            content: content.to_string(),
+        };
+        let mut files = [
+            file("index.md", "[a](#a) [b](#b) [unknown](#c)"),
+            file("b.md", "<a id=\"b\"></a>\n[b](#b) [a](#a)"),
+            file("a.md", "<a id=\"a\"></a>"),
+        ];
+        link_anchors_across_files(&mut files);
+        assert_eq!(files[0].content, "[a](a.md#a) [b](b.md#b) [unknown](#c)");
+        assert_eq!(files[1].content, "<a id=\"b\"></a>\n[b](#b) [a](a.md#a)");
+    }
 
-                for (kind, count) in sorted_counts {
-                    warn!("  - {:?}: {}", kind, count);
-                }
+    #[test]
+    fn as_declared_order_follows_the_module_items() {
+        let (manifest, krate) = document(
+            "mod inner {
+                pub fn zeta() {}
             }
-        }
-
-        // --- Examples Appendix ---
-        // Clone the necessary data from self.crate_extra before the loop
-        let examples_readme_content_clone = self
-            .crate_extra
-            .as_ref()
-            .and_then(|extra| extra.examples_readme_content.clone());
-        let examples_clone = self
-            .crate_extra
-            .as_ref()
-            .map_or_else(Vec::new, |extra| extra.examples.clone());
+            pub fn beta() {}
+            pub trait Tr {
+                /// Second.
+                fn second();
+                /// First.
+                fn first();
+            }
+            pub fn alpha() {}
+            pub use inner::zeta;",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .sort_order(SortOrder::AsDeclared)
+            .print()
+            .unwrap();
+        let position = |needle: &str| {
+            markdown
+                .find(needle)
+                .unwrap_or_else(|| panic!("{} not found in {}", needle, markdown))
+        };
+        assert!(position("`fn beta()`") < position("`fn alpha()`"));
+        assert!(position("`fn alpha()`") < position("`fn zeta()`"));
+        assert!(position("`fn second()`") < position("`fn first()`"));
+    }
 
-        if !examples_clone.is_empty() || examples_readme_content_clone.is_some() {
-            let examples_section_level = self.get_current_header_level(); // Should be 2
-            let header_prefix = self.get_header_prefix();
-            writeln!(
-                self.output,
-                "\n{} {} Examples Appendix\n",
-                "#".repeat(examples_section_level),
-                header_prefix
-            )
+    #[test]
+    fn reexports_are_detailed_where_printed_past_the_depth_limit() {
+        let (manifest, krate) = document(
+            "pub mod inner {
+                /// A thing.
+                pub struct Thing;
+            }
+            pub use inner::Thing;",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .reexport_mode(ReexportMode::Definition)
+            .max_module_depth(Some(0))
+            .print()
             .unwrap();
-            self.push_level(); // Push for H3 example headers
+        assert!(markdown.contains("`struct fixture::Thing`"), "{}", markdown);
+        assert!(markdown.contains("A thing."), "{}", markdown);
+    }
 
-            if let Some(readme) = examples_readme_content_clone {
-                let adjusted_readme = adjust_markdown_headers(&readme, examples_section_level);
-                writeln!(self.output, "{}\n", adjusted_readme).unwrap();
+    #[test]
+    fn variants_table_falls_back_to_headers_for_multi_paragraph_docs() {
+        let print = |source: &str| {
+            let (manifest, krate) = document(source);
+            Printer::new(&manifest, &krate)
+                .field_layout(FieldLayout::Table)
+                .print()
+                .unwrap()
+        };
+        let markdown = print(
+            "pub enum Mode {
+                /// Fast.
+                Fast,
+                /// Slow.
+                Slow,
+            }",
+        );
+        assert!(
+            markdown.contains("| `Fast` | `0` | Fast. |"),
+            "{}",
+            markdown
+        );
+        let markdown = print(
+            "pub enum Mode {
+                /// Fast.
+                ///
+                /// Skips all checks.
+                Fast,
+                /// Slow.
+                Slow,
+            }",
+        );
+        assert!(!markdown.contains("| Variant |"), "{}", markdown);
+        assert!(markdown.contains("Skips all checks."), "{}", markdown);
+        let markdown = print(
+            "pub enum Shape {
+                Circle {
+                    /// Radius.
+                    ///
+                    /// In meters.
+                    radius: f64,
+                },
+            }",
+        );
+        assert!(!markdown.contains("| Variant |"), "{}", markdown);
+        assert!(markdown.contains("In meters."), "{}", markdown);
+    }
+
+    #[test]
+    fn cfg_features_only_lists_required_features() {
+        let features = |attr: &str| cfg_features(&[attr.to_string()]);
+        assert_eq!(features("#[cfg(feature = \"serde\")]"), ["serde"]);
+        assert_eq!(
+            features("#[doc(cfg(all(unix, feature = \"a\", feature = \"b\")))]"),
+            ["a", "b"]
+        );
+        assert!(features("#[cfg(not(feature = \"std\"))]").is_empty());
+        assert!(features("#[cfg(any(feature = \"a\", feature = \"b\"))]").is_empty());
+        assert!(features("#[cfg(target_feature = \"avx2\")]").is_empty());
+        assert!(features("#[doc(hidden)]").is_empty());
+    }
+
+    #[test]
+    fn cfg_predicates_are_parsed() {
+        assert_eq!(
+            CfgPredicate::parse("not(any(unix, target_os = \"a\\\"b\"),)"),
+            Some(CfgPredicate::Not(Box::new(CfgPredicate::Any(vec![
+                CfgPredicate::Name("unix".to_string()),
+                CfgPredicate::KeyValue("target_os".to_string(), "a\\\"b".to_string()),
+            ]))))
+        );
+        assert_eq!(CfgPredicate::parse("all(unix"), None);
+        assert_eq!(CfgPredicate::parse("unix windows"), None);
+        assert_eq!(CfgPredicate::parse("not(unix, windows)"), None);
+    }
+
+    #[test]
+    fn format_cfg_note_lists_features_and_other_predicates() {
+        let note = |attrs: &[&str]| {
+            format_cfg_note(&attrs.iter().map(|a| a.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(
+            note(&["#[cfg(feature = \"serde\")]"]).as_deref(),
+            Some("_Requires feature: `serde`_")
+        );
+        assert_eq!(
+            note(&[
+                "#[cfg(feature = \"a\")]",
+                "#[cfg(feature = \"b\")]",
+                "#[cfg(unix)]"
+            ])
+            .as_deref(),
+            Some("_Requires features: `a`, `b`; `cfg(unix)`_")
+        );
+        assert_eq!(
+            note(&["#[cfg(not(feature = \"std\"))]"]).as_deref(),
+            Some("_Requires `cfg(not(feature = \"std\"))`_")
+        );
+        assert_eq!(note(&["#[cfg(doc)]", "#[doc(cfg(unix))]"]), None);
+    }
+
+    #[test]
+    fn cfg_notes_are_printed_for_fields_and_variants() {
+        let source = "pub struct Config {
+                /// Serializer.
+                #[cfg(target_pointer_width = \"64\")]
+                pub serializer: u8,
             }
+            pub enum Backend {
+                /// Native.
+                #[cfg(unix)]
+                Native,
+                /// Fallback.
+                Fallback,
+            }";
+        let (manifest, krate) = document(source);
+        let markdown = Printer::new(&manifest, &krate).print().unwrap();
+        assert!(
+            markdown.contains(
+                "`serializer`\n\n_Requires `cfg(target_pointer_width = \"64\")`_\n\nSerializer."
+            ),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("`Native`\n\n_Requires `cfg(unix)`_\n\nNative."),
+            "{}",
+            markdown
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .field_layout(FieldLayout::Table)
+            .print()
+            .unwrap();
+        assert!(
+            markdown.contains("| `Native` | `0` | Native.<br>_Requires `cfg(unix)`_ |"),
+            "{}",
+            markdown
+        );
+    }
 
-            for (filename, content) in &examples_clone {
-                let example_header_level = self.get_current_header_level(); // Should be 3
-                let example_prefix = self.get_header_prefix();
-                writeln!(
-                    self.output,
-                    "{} {} `{}`\n",
-                    "#".repeat(example_header_level),
-                    example_prefix,
-                    filename
-                )
-                .unwrap();
-                writeln!(self.output, "```rust\n{}\n```\n", content).unwrap();
-                self.post_increment_current_level(); // Increment H3 counter for next example
+    #[test]
+    fn excluded_items_take_their_members_along() {
+        let (manifest, krate) = document(
+            "/// A secret.
+            pub struct Secret {
+                /// The value.
+                pub value: u8,
             }
-            self.pop_level(); // Pop H3 example level
-            self.post_increment_current_level(); // Increment H2 counter for next top-level section
+            impl Secret {
+                /// Reveals the secret.
+                pub fn reveal(&self) {}
+            }
+            /// A key.
+            pub trait Key {
+                /// Turns the key.
+                fn turn(&self);
+            }
+            /// Public.
+            pub struct Public;",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .exclude_paths(&["Secret".to_string(), "Key".to_string()])
+            .include_other()
+            .print()
+            .unwrap();
+        for excluded in ["Secret", "value", "reveal", "Key", "turn"] {
+            assert!(!markdown.contains(excluded), "{}: {}", excluded, markdown);
         }
-        self.output
+        assert!(
+            markdown.contains("`struct fixture::Public`"),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn signature_index_elides_bodies() {
+        let (manifest, krate) = document(
+            "pub struct Unit;
+            pub struct Meters(pub f64);
+            pub struct Point { pub x: i32 }
+            pub enum Mode { Fast }
+            pub union Bits { pub int: u32 }
+            pub trait Shape {}
+            pub const MAX: u8 = 1;",
+        );
+        let markdown = Printer::new(&manifest, &krate)
+            .signature_index(true)
+            .print()
+            .unwrap();
+        let index = "```rust\npub struct Meters(..);\npub struct Point { .. }\npub struct Unit;\n\
+                     pub enum Mode { .. }\npub union Bits { .. }\npub trait Shape { .. }\n\
+                     pub const MAX: u8;\n```";
+        assert!(markdown.contains(index), "{}", markdown);
+    }
+
+    #[test]
+    fn blanket_impls_refer_to_their_trait_like_other_references() {
+        let (manifest, krate) = document(
+            "pub mod a {
+                /// Describes.
+                pub trait Describe {}
+                impl<T: core::fmt::Debug> Describe for T {}
+            }
+            pub mod b {
+                /// A foo.
+                #[derive(Debug)]
+                pub struct Foo;
+            }",
+        );
+        let printer = || Printer::new(&manifest, &krate).no_common_traits();
+        let markdown = printer().print().unwrap();
+        let section = markdown
+            .lines()
+            .find(|line| line.ends_with(" `trait fixture::a::Describe`"))
+            .and_then(|line| line.split(' ').nth(1))
+            .unwrap();
+        assert!(
+            markdown.contains(&format!(
+                "- `impl<T> fixture::a::Describe for T` (See section {} for details) \
+                 (`where T: Debug`)",
+                section
+            )),
+            "{}",
+            markdown
+        );
+        let markdown = printer().references_as_footnotes(true).print().unwrap();
+        assert!(
+            markdown.contains("- `impl<T> fixture::a::Describe for T` [^ref-1] (`where T: Debug`)"),
+            "{}",
+            markdown
+        );
+        assert!(undefined_footnotes(&markdown).is_empty(), "{}", markdown);
+        let markdown = printer().anchors().print().unwrap();
+        assert!(
+            markdown.contains(
+                "- [`impl<T> fixture::a::Describe for T`](#fixture-a-describe) (`where T: Debug`)"
+            ),
+            "{}",
+            markdown
+        );
+    }
+
+    #[test]
+    fn short_fn_bodies_skip_braces_in_literals_and_comments() {
+        let body = |source: &str| extract_short_fn_body(source);
+        assert_eq!(body("fn f() -> u8 { 1 }").as_deref(), Some("1"));
+        assert_eq!(
+            body("fn f() -> &'static str { \"}\" }").as_deref(),
+            Some("\"}\"")
+        );
+        assert_eq!(body("fn f() -> char { '}' }").as_deref(), Some("'}'"));
+        assert_eq!(body("fn f() -> char { '\\'' }").as_deref(), Some("'\\''"));
+        assert_eq!(
+            body("fn f<'a>(s: &'a str) -> &'a str { s }").as_deref(),
+            Some("s")
+        );
+        assert_eq!(
+            body("fn f() -> &'static str { r#\"{\"# }").as_deref(),
+            Some("r#\"{\"#")
+        );
+        assert_eq!(body("fn f() -> u8 /* { */ { 2 }").as_deref(), Some("2"));
+        assert_eq!(body("fn f() -> u8 { 2 /* } */ }"), None);
+        assert_eq!(body("fn f() { // }\n}"), None);
+        assert_eq!(
+            strip_fn_body("fn f(x: [u8; { 2 }]) { let _ = '{'; }"),
+            "fn f(x: [u8; { 2 }]) "
+        );
+    }
+
+    #[test]
+    fn span_text_uses_character_columns() {
+        let span = |begin: (usize, usize), end: (usize, usize)| Span {
+            filename: PathBuf::from("lib.rs"),
+            begin,
+            end,
+        };
+        let content = "/// Größe\nfn größe() {}\nfn x() {}";
+        assert_eq!(
+            span_text(content, &span((2, 3), (2, 8))).as_deref(),
+            Some("größe")
+        );
+        assert_eq!(
+            span_text(content, &span((2, 0), (3, 9))).as_deref(),
+            Some("fn größe() {}\nfn x() {}")
+        );
     }
 }
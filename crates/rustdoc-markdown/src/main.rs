@@ -1,14 +1,18 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use cargo_manifest::Manifest;
 use clap::Parser;
-use rustdoc_markdown::{cratesio, graph, run_rustdoc, CrateExtraReader, Printer}; // Added CrateExtraReader
+use rustdoc_markdown::{
+    changed_files_since, cratesio, graph, html, install_toolchain, run_rustdoc, run_rustdoc_timed,
+    CrateExtraReader, DocOverride, FieldLayout, PackageTarget, PhaseTimings, Printer, ReexportMode,
+    RustdocOptions, SortOrder,
+};
 use rustdoc_types::{Crate, Id, ItemEnum};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use tracing_subscriber::EnvFilter;
 // Keep this for parse_id
 use std::fs::File;
-use std::io::{BufWriter, Write as IoWrite}; // Use IoWrite alias
-use std::path::PathBuf;
+use std::io::{BufRead, BufWriter, IsTerminal, Write as IoWrite}; // Use IoWrite alias
+use std::path::{Path, PathBuf};
 use tracing::{info, warn};
 
 /// Parses a string into an `Id`.
@@ -18,6 +22,123 @@ fn parse_id(s: &str) -> Result<Id, String> {
         .map_err(|_| format!("Invalid ID: '{}'. Must be a non-negative integer.", s))
 }
 
+/// Parses a `--field-layout` value into a [`FieldLayout`].
+fn parse_field_layout(s: &str) -> Result<FieldLayout, String> {
+    match s {
+        "headers" => Ok(FieldLayout::Headers),
+        "table" => Ok(FieldLayout::Table),
+        _ => Err(format!(
+            "Invalid field layout: '{}'. Must be 'headers' or 'table'.",
+            s
+        )),
+    }
+}
+
+/// Parses a `--reexport-mode` value into a [`ReexportMode`].
+fn parse_reexport_mode(s: &str) -> Result<ReexportMode, String> {
+    match s {
+        "definition" => Ok(ReexportMode::Definition),
+        "reexport-site" => Ok(ReexportMode::ReexportSite),
+        "both" => Ok(ReexportMode::Both),
+        _ => Err(format!(
+            "Invalid re-export mode: '{}'. Must be 'definition', 'reexport-site' or 'both'.",
+            s
+        )),
+    }
+}
+
+/// Parses a `--sort` value into a [`SortOrder`].
+fn parse_sort_order(s: &str) -> Result<SortOrder, String> {
+    match s {
+        "alphabetical" => Ok(SortOrder::Alphabetical),
+        "source" => Ok(SortOrder::SourceSpan),
+        "declared" => Ok(SortOrder::AsDeclared),
+        _ => Err(format!(
+            "Invalid sort order: '{}'. Must be 'alphabetical', 'source' or 'declared'.",
+            s
+        )),
+    }
+}
+
+/// Format of the graph written by the `dump-graph` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GraphFormat {
+    Text,
+    Json,
+    Dot,
+    Mermaid,
+}
+
+fn parse_graph_format(s: &str) -> Result<GraphFormat, String> {
+    match s {
+        "text" => Ok(GraphFormat::Text),
+        "json" => Ok(GraphFormat::Json),
+        "dot" => Ok(GraphFormat::Dot),
+        "mermaid" => Ok(GraphFormat::Mermaid),
+        _ => Err(format!(
+            "Invalid graph format: '{}'. Must be 'text', 'json', 'dot' or 'mermaid'.",
+            s
+        )),
+    }
+}
+
+/// Format of the document written by the `print` command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Markdown,
+    HtmlStandalone,
+}
+
+fn parse_output_format(s: &str) -> Result<OutputFormat, String> {
+    match s {
+        "markdown" => Ok(OutputFormat::Markdown),
+        "html-standalone" => Ok(OutputFormat::HtmlStandalone),
+        _ => Err(format!(
+            "Invalid format: '{}'. Must be 'markdown' or 'html-standalone'.",
+            s
+        )),
+    }
+}
+
+fn parse_common_traits_ratio(s: &str) -> Result<f32, String> {
+    match s.parse::<f32>() {
+        Ok(ratio) if (0.0..=1.0).contains(&ratio) => Ok(ratio),
+        _ => Err(format!(
+            "Invalid common traits ratio: '{}'. Must be a number between 0 and 1.",
+            s
+        )),
+    }
+}
+
+/// Number of selected items above which printing to an interactive terminal asks
+/// for confirmation first.
+const LARGE_OUTPUT_ITEM_THRESHOLD: usize = 2000;
+
+/// Asks whether documentation for `item_count` items should really be printed to
+/// the terminal. Returns `true` without prompting for non-interactive runs or small
+/// selections.
+fn confirm_large_output(
+    item_count: usize,
+    interactive: bool,
+    input: &mut impl BufRead,
+    prompt: &mut impl IoWrite,
+) -> Result<bool> {
+    if !interactive || item_count <= LARGE_OUTPUT_ITEM_THRESHOLD {
+        return Ok(true);
+    }
+    write!(
+        prompt,
+        "About to print documentation for {} items to the terminal.\n\
+         Use --output to write to a file or --path to narrow the selection.\n\
+         Continue anyway? [y/N] ",
+        item_count
+    )?;
+    prompt.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -26,6 +147,7 @@ struct Args {
 }
 
 #[derive(Parser, Debug)]
+#[allow(clippy::large_enum_variant)] // Parsed once, the size doesn't matter
 enum Command {
     /// Print crate documentation to Markdown
     Print(PrintCommand),
@@ -49,6 +171,10 @@ struct PrintCommand {
     #[arg(long)]
     include_prerelease: bool,
 
+    /// How often to retry crates.io requests that are rate limited or fail transiently.
+    #[arg(long, default_value_t = 3)]
+    max_retries: u32,
+
     /// Build directory for crate documentation artifacts (e.g., downloaded crate source, rustdoc JSON, cloned git repos).
     #[arg(long, default_value = ".ai/docs/rust/build")]
     build_dir: String,
@@ -57,6 +183,20 @@ struct PrintCommand {
     #[arg(long)]
     output: Option<PathBuf>,
 
+    /// Directory to write the documentation into as one Markdown file per top-level
+    /// module, plus an `index.md` linking to them (implies --anchors).
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "anchor_index"])]
+    output_dir: Option<PathBuf>,
+
+    /// Output format: `markdown`, or `html-standalone` for a single HTML file with
+    /// embedded CSS and a navigation sidebar.
+    #[arg(long, value_parser = parse_output_format, default_value = "markdown")]
+    format: OutputFormat,
+
+    /// Stylesheet to embed instead of the default one with `--format html-standalone`.
+    #[arg(long, value_name = "FILE")]
+    css: Option<PathBuf>,
+
     /// Filter documented items by module path (e.g., "::style", "widgets::Button").
     /// Can be specified multiple times.
     /// - Paths starting with `::` are absolute within the current crate.
@@ -65,6 +205,21 @@ struct PrintCommand {
     #[arg(long = "path")]
     paths: Vec<String>,
 
+    /// Exclude items whose path matches this prefix, even if a `--path` filter
+    /// includes them (e.g., "::api::internal"). Can be specified multiple times.
+    #[arg(long = "exclude-path")]
+    exclude_paths: Vec<String>,
+
+    /// File listing canonical item paths (one per line) to document exactly, plus their
+    /// dependencies. Empty lines and lines starting with `#` are ignored.
+    #[arg(long)]
+    allow_list: Option<PathBuf>,
+
+    /// Only document items defined in files changed since this git ref (plus their
+    /// dependencies). Requires --manifest or --git.
+    #[arg(long, value_name = "GIT_REF")]
+    changed_since: Option<String>,
+
     /// Include items that don't fit standard categories (e.g., unprinted selected items)
     /// in a final 'Other' section. By default, these are logged as warnings and omitted.
     #[arg(long)]
@@ -74,6 +229,10 @@ struct PrintCommand {
     #[arg(long)]
     features: Option<String>,
 
+    /// Activate all available features when running rustdoc.
+    #[arg(long)]
+    all_features: bool,
+
     /// Do not activate the `default` feature when running rustdoc.
     #[arg(long)]
     no_default_features: bool,
@@ -82,6 +241,20 @@ struct PrintCommand {
     #[arg(long)]
     target: Option<String>,
 
+    /// Document the public API of the binary target with this name instead of the
+    /// library.
+    #[arg(long, value_name = "NAME", conflicts_with_all = ["lib", "example"])]
+    bin: Option<String>,
+
+    /// Document the public API of the example target with this name instead of the
+    /// library.
+    #[arg(long, value_name = "NAME", conflicts_with = "lib")]
+    example: Option<String>,
+
+    /// Document the library target (the default).
+    #[arg(long)]
+    lib: bool,
+
     /// Output Mustache-like template markers (e.g., `{{MISSING_DOCS_1_2_1}}`)
     /// instead of the actual documentation content for items that have docstrings.
     /// Useful for identifying missing documentation in the source crate.
@@ -92,6 +265,11 @@ struct PrintCommand {
     #[arg(long)]
     no_readme: bool,
 
+    /// Embed this file as the crate's README instead of the one found in the crate.
+    /// Takes precedence over `--no-readme`.
+    #[arg(long)]
+    readme_file: Option<PathBuf>,
+
     /// Disable the "Common Traits" summarization. If set, all implemented traits
     /// for each item will be listed directly with that item, instead of being
     /// summarized at the crate or module level.
@@ -102,9 +280,175 @@ struct PrintCommand {
     #[arg(long)]
     no_examples: bool,
 
+    /// Render the bodies of short (single-expression) provided trait methods,
+    /// read from the crate's source files.
+    #[arg(long)]
+    show_default_bodies: bool,
+
+    /// Hide explicit `Sized` bounds on generic parameters and where clauses
+    /// (`?Sized` is always shown).
+    #[arg(long)]
+    omit_sized_bound: bool,
+
+    /// List the methods available through `Deref` on types that implement it.
+    #[arg(long)]
+    show_deref_methods: bool,
+
+    /// Collapse generic arguments nested deeper than this many levels to `…`.
+    #[arg(long)]
+    max_generic_depth: Option<usize>,
+
+    /// Precede each item section with an `<!-- item: ... -->` metadata comment
+    /// (path, kind, deprecation and feature gates) for downstream tooling.
+    #[arg(long)]
+    metadata_comments: bool,
+
+    /// Annotate uses of the crate's type aliases with the type they expand to.
+    #[arg(long)]
+    inline_type_aliases: bool,
+
+    /// Emit `<a id="...">` anchors before item, variant and field headers for deep-linking.
+    #[arg(long)]
+    anchors: bool,
+
+    /// Write a JSON object mapping the canonical path of each anchored item to its
+    /// anchor id to this file (implies --anchors).
+    #[arg(long, value_name = "FILE")]
+    anchor_index: Option<PathBuf>,
+
+    /// Add "See also" links between related methods (e.g. `get` and `get_mut`).
+    #[arg(long)]
+    see_also_methods: bool,
+
+    /// Move long bound lists on generic parameters into a multi-line where-clause.
+    #[arg(long)]
+    render_bounds_on_separate_lines: bool,
+
+    /// Move simple where-clause bounds inline (`<T: Clone>` instead of `where T: Clone`).
+    #[arg(long)]
+    consolidate_bounds: bool,
+
+    /// Layout for fields and variants: `headers` (default) or `table` (compact tables
+    /// for enum variants).
+    #[arg(long, value_parser = parse_field_layout, default_value = "headers")]
+    field_layout: FieldLayout,
+
+    /// Where items reachable from several modules get their full details:
+    /// `definition` (their defining module), `reexport-site` (the module re-exporting
+    /// them) or `both` (default; the first module, other modules list a re-export).
+    #[arg(long, value_parser = parse_reexport_mode, default_value = "both")]
+    reexport_mode: ReexportMode,
+
+    /// Order of items within sections: `alphabetical` (default), `source` (by source
+    /// position) or `declared` (as declared in the rustdoc JSON).
+    #[arg(long, value_parser = parse_sort_order, default_value = "alphabetical")]
+    sort: SortOrder,
+
+    /// Start each module with a code block listing the one-line signature of every item.
+    #[arg(long)]
+    signature_index: bool,
+
+    /// Name the private supertrait sealing each sealed trait, and the types implementing it.
+    #[arg(long)]
+    include_sealed_details: bool,
+
+    /// Keep the lines of doc examples hidden by rustdoc (those starting with `# `).
+    #[arg(long)]
+    keep_hidden_doc_lines: bool,
+
+    /// Wrap declarations in code blocks that are wider than this many characters,
+    /// e.g. by putting each supertrait of a trait on its own line.
+    #[arg(long, value_name = "WIDTH")]
+    max_line_width: Option<usize>,
+
+    /// Order items so that they come after the items they use (leaves first),
+    /// instead of by `--sort`.
+    #[arg(long)]
+    dependency_order: bool,
+
+    /// Render argument-position `impl Trait` as explicit generic parameters.
+    #[arg(long)]
+    render_impl_trait_as_generic: bool,
+
+    /// Render the variant of single-variant enums as a compact note.
+    #[arg(long)]
+    collapse_single_variant_enums: bool,
+
+    /// Order trait implementations by likely interest (conversions first, marker traits last).
+    #[arg(long)]
+    sort_trait_impls_by_usefulness: bool,
+
+    /// Append a footer with the crate's license and repository for attribution.
+    #[arg(long)]
+    license_footer: bool,
+
+    /// List methods traits inherit from their supertraits.
+    #[arg(long)]
+    show_inherited_trait_items: bool,
+
+    /// Emit a linked table of contents after the crate header.
+    #[arg(long)]
+    toc: bool,
+
+    /// Render cross-references to other sections as footnotes.
+    #[arg(long)]
+    references_as_footnotes: bool,
+
+    /// Group free functions by the local type of their first parameter.
+    #[arg(long)]
+    group_free_functions_by_first_arg: bool,
+
+    /// Fraction of types that must implement a trait for it to be listed as common
+    /// (1.0 means every type).
+    #[arg(long, value_parser = parse_common_traits_ratio, default_value = "0.5")]
+    common_traits_ratio: f32,
+
+    /// Shift every header down by this many levels (capped at H6), e.g. to embed the
+    /// output in a document that is already at H2.
+    #[arg(long, default_value_t = 0)]
+    heading_offset: usize,
+
+    /// JSON file mapping canonical item paths to extra Markdown appended to their
+    /// docs, or `{"replace": "..."}` objects replacing them.
+    #[arg(long)]
+    doc_overrides: Option<PathBuf>,
+
+    /// Only document modules up to this depth (0 = crate root items only); deeper
+    /// modules are listed as stubs.
+    #[arg(long)]
+    max_depth: Option<usize>,
+
+    /// Document private items too, annotating re-exports with their visibility.
+    #[arg(long)]
+    include_private: bool,
+
+    /// Append the number of items in each section to its header, e.g. "Functions (12)".
+    #[arg(long)]
+    emit_item_count_per_section: bool,
+
+    /// Print the wall-clock time spent in each phase (download, rustdoc build, json
+    /// parse, item selection, common traits, rendering) to stderr.
+    #[arg(long)]
+    profile: bool,
+
+    /// Document items marked `#[doc(hidden)]`, which are skipped by default.
+    #[arg(long)]
+    include_hidden: bool,
+
+    /// Name types defined in private modules by their public re-export path instead
+    /// of their internal canonical path.
+    #[arg(long)]
+    redact_private_paths: bool,
+
+    /// Reformat generated declaration code blocks with `rustfmt` (if found on PATH).
+    #[cfg(feature = "rustfmt")]
+    #[arg(long)]
+    rustfmt_signatures: bool,
+
     /// Path to the Cargo.toml manifest file of a local crate.
     /// If provided, crates.io will not be queried, and the specified crate will be documented.
-    /// The `crate_name` argument must match the `[package].name` in this manifest.
+    /// The `crate_name` argument (or --package) must match the `[package].name` in this
+    /// manifest, or name a member if it is a workspace root.
     /// Mutually exclusive with --git.
     #[arg(long, conflicts_with = "git_url")]
     manifest: Option<PathBuf>,
@@ -115,6 +459,11 @@ struct PrintCommand {
     /// Mutually exclusive with --manifest.
     #[arg(long, conflicts_with = "manifest")]
     git_url: Option<String>,
+
+    /// Workspace member to document when --manifest or --git points at a workspace
+    /// root. Defaults to `crate_name`.
+    #[arg(long)]
+    package: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -130,6 +479,10 @@ struct DumpGraphCommand {
     #[arg(long)]
     include_prerelease: bool,
 
+    /// How often to retry crates.io requests that are rate limited or fail transiently.
+    #[arg(long, default_value_t = 3)]
+    max_retries: u32,
+
     /// Build directory for crate documentation artifacts
     #[arg(long, default_value = ".ai/docs/rust/build")]
     build_dir: String,
@@ -155,10 +508,20 @@ struct DumpGraphCommand {
     #[arg(long)]
     max_depth: Option<usize>,
 
+    /// Output format: an indented `text` tree, `json` with an edge list and a
+    /// table of node paths and kinds, a Graphviz `dot` digraph, or a `mermaid`
+    /// flowchart.
+    #[arg(long, value_parser = parse_graph_format, default_value = "text")]
+    format: GraphFormat,
+
     /// Space-separated list of features to activate
     #[arg(long)]
     features: Option<String>,
 
+    /// Activate all available features
+    #[arg(long)]
+    all_features: bool,
+
     /// Do not activate the `default` feature
     #[arg(long)]
     no_default_features: bool,
@@ -186,11 +549,102 @@ struct DumpGraphCommand {
 fn repo_name_from_url(url: &str) -> Result<String> {
     let path = url
         .split('/')
-        .last()
+        .next_back()
         .ok_or_else(|| anyhow!("Could not extract repository name from URL: {}", url))?;
     Ok(path.trim_end_matches(".git").to_string())
 }
 
+/// Searches the members of the workspace defined by `manifest` (located in `root_dir`)
+/// for the package called `name`, returning its directory and parsed manifest.
+///
+/// Returns `Ok(None)` if `manifest` has no `[workspace]` table or no member matches.
+fn find_workspace_member(
+    manifest: &Manifest,
+    root_dir: &Path,
+    name: &str,
+) -> Result<Option<(PathBuf, Manifest)>> {
+    let Some(workspace) = &manifest.workspace else {
+        return Ok(None);
+    };
+    info!("Searching workspace members for package '{}'...", name);
+    for member_glob_pattern_str in &workspace.members {
+        let full_glob_pattern = root_dir
+            .join(member_glob_pattern_str)
+            .to_string_lossy()
+            .into_owned();
+        info!("Searching glob pattern: {}", full_glob_pattern);
+
+        for entry in glob::glob(&full_glob_pattern)
+            .with_context(|| format!("Failed to read glob pattern: {}", full_glob_pattern))?
+        {
+            match entry {
+                Ok(member_path) => {
+                    let member_manifest_path = member_path.join("Cargo.toml");
+                    if !member_path.is_dir() || !member_manifest_path.exists() {
+                        continue;
+                    }
+                    let member_manifest =
+                        Manifest::from_path(&member_manifest_path).with_context(|| {
+                            format!(
+                                "Failed to parse member manifest: {}",
+                                member_manifest_path.display()
+                            )
+                        })?;
+                    if member_manifest
+                        .package
+                        .as_ref()
+                        .is_some_and(|pkg| pkg.name == name)
+                    {
+                        info!(
+                            "Found package '{}' in workspace at: {}",
+                            name,
+                            member_path.display()
+                        );
+                        return Ok(Some((member_path, member_manifest)));
+                    }
+                }
+                Err(e) => warn!("Error matching glob entry: {:?}", e),
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Writes the graph reachable from `root_ids` in the given format, following only
+/// edges between the nodes of `node_filter` if given.
+#[allow(clippy::too_many_arguments)]
+fn dump_graph(
+    format: GraphFormat,
+    graph: &graph::IdGraph,
+    krate: &Crate,
+    root_ids: &HashSet<Id>,
+    writer: &mut dyn IoWrite,
+    dump_description: &str,
+    max_depth: Option<usize>,
+    node_filter: Option<&HashSet<Id>>,
+) -> Result<()> {
+    match format {
+        GraphFormat::Text => graph::dump_graph_subset(
+            graph,
+            krate,
+            root_ids,
+            writer,
+            dump_description,
+            max_depth,
+            node_filter,
+        ),
+        GraphFormat::Json => {
+            graph::dump_graph_json(graph, krate, root_ids, writer, max_depth, node_filter)
+        }
+        GraphFormat::Dot => {
+            graph::dump_graph_dot(graph, krate, root_ids, writer, max_depth, node_filter)
+        }
+        GraphFormat::Mermaid => {
+            graph::dump_graph_mermaid(graph, krate, root_ids, writer, max_depth, node_filter)
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize logging: default to 'info' if RUST_LOG is not set, write to stderr
@@ -211,6 +665,9 @@ async fn main() -> Result<()> {
 
     match args.command {
         Command::Print(print_args) => {
+            // Validate the toolchain before downloading anything
+            install_toolchain()?;
+
             let build_dir_path = PathBuf::from(&print_args.build_dir);
             std::fs::create_dir_all(&build_dir_path).with_context(|| {
                 format!(
@@ -219,6 +676,8 @@ async fn main() -> Result<()> {
                 )
             })?;
 
+            let mut timings = PhaseTimings::default();
+            let download_start = std::time::Instant::now();
             let (package_dir, manifest, actual_crate_name_from_manifest, _target_version_num) = {
                 if let Some(manifest_path) = &print_args.manifest {
                     info!(
@@ -238,16 +697,34 @@ async fn main() -> Result<()> {
                     let m = Manifest::from_path(&m_path).with_context(|| {
                         format!("Failed to read or parse Cargo.toml: {}", m_path.display())
                     })?;
+                    let package_name = print_args
+                        .package
+                        .as_deref()
+                        .unwrap_or(&print_args.crate_name);
+                    let (dir, m) = match &m.package {
+                        Some(pkg) if pkg.name == package_name => (dir, m),
+                        _ => match find_workspace_member(&m, &dir, package_name)? {
+                            Some(member) => member,
+                            None if m.workspace.is_some() => {
+                                return Err(anyhow!(
+                                    "Package '{}' not found in workspace members of manifest: {}",
+                                    package_name,
+                                    m_path.display()
+                                ));
+                            }
+                            None => (dir, m),
+                        },
+                    };
                     let name_from_manifest = m
                         .package
                         .as_ref()
                         .ok_or_else(|| anyhow!("Manifest is missing [package] table"))?
                         .name
                         .clone();
-                    if name_from_manifest != print_args.crate_name {
+                    if name_from_manifest != package_name {
                         return Err(anyhow!(
                             "Crate name mismatch: command line '{}' vs manifest '{}'",
-                            print_args.crate_name,
+                            package_name,
                             name_from_manifest
                         ));
                     }
@@ -294,84 +771,33 @@ async fn main() -> Result<()> {
                             )
                         })?;
 
-                    if let Some(workspace) = &root_manifest.workspace {
-                        info!(
-                            "Repository is a workspace. Searching for package '{}'...",
-                            print_args.crate_name
-                        );
-                        let mut found_member_manifest_path = None;
-                        let mut found_member_dir = None;
-
-                        for member_glob_pattern_str in &workspace.members {
-                            let full_glob_pattern = repo_clone_target_dir
-                                .join(member_glob_pattern_str)
-                                .to_string_lossy()
-                                .into_owned();
-                            info!("Searching glob pattern: {}", full_glob_pattern);
-
-                            for entry in glob::glob(&full_glob_pattern).with_context(|| {
-                                format!("Failed to read glob pattern: {}", full_glob_pattern)
-                            })? {
-                                match entry {
-                                    Ok(member_path) => {
-                                        if member_path.is_dir() {
-                                            let member_manifest_path =
-                                                member_path.join("Cargo.toml");
-                                            if member_manifest_path.exists() {
-                                                let member_manifest =
-                                                    Manifest::from_path(&member_manifest_path)
-                                                        .with_context(|| {
-                                                            format!(
-                                                        "Failed to parse member manifest: {}",
-                                                        member_manifest_path.display()
-                                                    )
-                                                        })?;
-                                                if let Some(pkg) = &member_manifest.package {
-                                                    if pkg.name == print_args.crate_name {
-                                                        found_member_manifest_path =
-                                                            Some(member_manifest_path);
-                                                        found_member_dir = Some(member_path);
-                                                        break; // Found the target package
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                    Err(e) => warn!("Error matching glob entry: {:?}", e),
-                                }
-                            }
-                            if found_member_manifest_path.is_some() {
-                                break; // Found in this glob pattern
-                            }
-                        }
-
-                        if let (Some(m_path), Some(dir)) =
-                            (found_member_manifest_path, found_member_dir)
-                        {
-                            info!(
-                                "Found package '{}' in workspace at: {}",
-                                print_args.crate_name,
-                                dir.display()
-                            );
-                            let m = Manifest::from_path(&m_path).with_context(|| {
-                                format!("Failed to parse member manifest: {}", m_path.display())
-                            })?;
-                            let version_from_manifest = m
-                                .package
-                                .as_ref()
-                                .and_then(|p| p.version.as_ref())
-                                .and_then(|v| v.as_ref().as_local().cloned());
-                            (dir, m, print_args.crate_name.clone(), version_from_manifest)
-                        } else {
+                    let package_name = print_args
+                        .package
+                        .as_deref()
+                        .unwrap_or(&print_args.crate_name);
+                    if root_manifest.workspace.is_some() {
+                        info!("Repository is a workspace.");
+                        let Some((dir, m)) = find_workspace_member(
+                            &root_manifest,
+                            &repo_clone_target_dir,
+                            package_name,
+                        )?
+                        else {
                             return Err(anyhow!(
                                 "Package '{}' not found in workspace members of repository '{}'",
-                                print_args.crate_name,
+                                package_name,
                                 git_url
                             ));
-                        }
+                        };
+                        let version_from_manifest = m
+                            .package
+                            .as_ref()
+                            .and_then(|p| p.version.as_ref())
+                            .and_then(|v| v.as_ref().as_local().cloned());
+                        (dir, m, package_name.to_string(), version_from_manifest)
                     } else if let Some(pkg) = &root_manifest.package {
                         // Root is a single package
-                        if pkg.name == print_args.crate_name {
+                        if pkg.name == package_name {
                             info!("Using root package '{}' from repository.", pkg.name);
                             let version_from_manifest = pkg
                                 .version
@@ -386,7 +812,7 @@ async fn main() -> Result<()> {
                         } else {
                             return Err(anyhow!(
                                 "Crate name mismatch: command line '{}' vs repository root package name '{}'",
-                                print_args.crate_name,
+                                package_name,
                                 pkg.name
                             ));
                         }
@@ -403,6 +829,7 @@ async fn main() -> Result<()> {
                         &print_args.crate_name,
                         &print_args.crate_version,
                         print_args.include_prerelease,
+                        print_args.max_retries,
                     )
                     .await?;
                     info!(
@@ -414,6 +841,7 @@ async fn main() -> Result<()> {
                         &client,
                         &target_version,
                         &build_dir_path,
+                        print_args.max_retries,
                     )
                     .await?;
                     let m_path = dir.join("Cargo.toml");
@@ -429,13 +857,28 @@ async fn main() -> Result<()> {
                 }
             };
 
-            let krate: Crate = run_rustdoc(
+            timings.record("download", download_start.elapsed());
+
+            let package_target = match (&print_args.bin, &print_args.example) {
+                (Some(bin), _) => PackageTarget::Bin(bin.clone()),
+                (None, Some(example)) => PackageTarget::Example(example.clone()),
+                (None, None) => PackageTarget::Lib,
+            };
+            let rustdoc_options = RustdocOptions {
+                features: print_args.features.clone(),
+                all_features: print_args.all_features,
+                no_default_features: print_args.no_default_features,
+                target: print_args.target.clone(),
+                package_target,
+                document_private_items: print_args.include_private,
+                document_hidden_items: print_args.include_hidden,
+            };
+            let krate: Crate = run_rustdoc_timed(
                 &package_dir, // Use package_dir for rustdoc
                 &actual_crate_name_from_manifest,
-                print_args.features.as_deref(),
-                print_args.no_default_features,
-                print_args.target.as_deref(),
-                true,
+                &rustdoc_options,
+                false, // Toolchain already installed above
+                &mut timings,
             )?;
 
             let mut printer = Printer::new(&manifest, &krate);
@@ -443,11 +886,46 @@ async fn main() -> Result<()> {
             if !print_args.paths.is_empty() {
                 printer = printer.paths(&print_args.paths);
             }
+            if !print_args.exclude_paths.is_empty() {
+                printer = printer.exclude_paths(&print_args.exclude_paths);
+            }
+            if let Some(allow_list_path) = &print_args.allow_list {
+                let contents = std::fs::read_to_string(allow_list_path).with_context(|| {
+                    format!("Failed to read allow-list {}", allow_list_path.display())
+                })?;
+                let allowed: Vec<String> = contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string)
+                    .collect();
+                printer = printer.allow_list(&allowed);
+            }
+            if let Some(overrides_path) = &print_args.doc_overrides {
+                let contents = std::fs::read_to_string(overrides_path).with_context(|| {
+                    format!("Failed to read doc overrides {}", overrides_path.display())
+                })?;
+                let overrides: HashMap<String, DocOverride> = serde_json::from_str(&contents)
+                    .with_context(|| {
+                        format!("Failed to parse doc overrides {}", overrides_path.display())
+                    })?;
+                printer = printer.doc_overrides(overrides);
+            }
+            if let Some(git_ref) = &print_args.changed_since {
+                if print_args.manifest.is_none() && print_args.git_url.is_none() {
+                    bail!("--changed-since requires --manifest or --git");
+                }
+                let changed = changed_files_since(&package_dir, git_ref)?;
+                printer = printer.changed_files(&changed);
+            }
 
             let mut extra_reader = CrateExtraReader::new();
             if print_args.no_readme {
                 extra_reader = extra_reader.no_readme();
             }
+            if let Some(readme_file) = &print_args.readme_file {
+                extra_reader = extra_reader.readme_file(readme_file);
+            }
             if print_args.no_examples {
                 extra_reader = extra_reader.no_examples();
             }
@@ -463,36 +941,164 @@ async fn main() -> Result<()> {
             if print_args.no_common_traits {
                 printer = printer.no_common_traits();
             }
+            printer = printer
+                .source_root(&package_dir)
+                .show_default_bodies(print_args.show_default_bodies)
+                .omit_sized_bound(print_args.omit_sized_bound)
+                .show_deref_methods(print_args.show_deref_methods)
+                .metadata_comments(print_args.metadata_comments)
+                .inline_type_aliases(print_args.inline_type_aliases)
+                .see_also_methods(print_args.see_also_methods)
+                .render_bounds_on_separate_lines(print_args.render_bounds_on_separate_lines)
+                .consolidate_bounds(print_args.consolidate_bounds)
+                .field_layout(print_args.field_layout)
+                .reexport_mode(print_args.reexport_mode)
+                .sort_order(print_args.sort)
+                .signature_index(print_args.signature_index)
+                .include_sealed_details(print_args.include_sealed_details)
+                .keep_hidden_doc_lines(print_args.keep_hidden_doc_lines)
+                .max_line_width(print_args.max_line_width)
+                .dependency_order(print_args.dependency_order)
+                .render_impl_trait_as_generic(print_args.render_impl_trait_as_generic)
+                .collapse_single_variant_enums(print_args.collapse_single_variant_enums)
+                .sort_trait_impls_by_usefulness(print_args.sort_trait_impls_by_usefulness)
+                .license_footer(print_args.license_footer)
+                .show_inherited_trait_items(print_args.show_inherited_trait_items)
+                .toc(print_args.toc)
+                .references_as_footnotes(print_args.references_as_footnotes)
+                .group_free_functions_by_first_arg(print_args.group_free_functions_by_first_arg)
+                .common_traits_threshold(print_args.common_traits_ratio)
+                .heading_offset(print_args.heading_offset)
+                .max_module_depth(print_args.max_depth)
+                .reexport_visibility(print_args.include_private)
+                .all_features(print_args.all_features)
+                .emit_item_count_per_section(print_args.emit_item_count_per_section)
+                .redact_private_paths(print_args.redact_private_paths);
+            if print_args.include_hidden {
+                printer = printer.include_hidden(true);
+            }
+            if print_args.profile {
+                printer = printer.phase_timings(|phase, elapsed| timings.record(phase, elapsed));
+            }
+            if print_args.anchors || print_args.anchor_index.is_some() {
+                printer = printer.anchors();
+            }
+            if let Some(depth) = print_args.max_generic_depth {
+                printer = printer.max_generic_depth(depth);
+            }
+            #[cfg(feature = "rustfmt")]
+            {
+                printer = printer.rustfmt_signatures(print_args.rustfmt_signatures);
+            }
 
-            let documentation = printer.print()?;
+            // Guard against flooding the terminal with the docs of a huge crate
+            if print_args.output.is_none()
+                && print_args.output_dir.is_none()
+                && print_args.allow_list.is_none()
+                && print_args.changed_since.is_none()
+            {
+                let interactive = std::io::stdout().is_terminal() && std::io::stdin().is_terminal();
+                let item_count = if interactive {
+                    let resolved_modules = graph::build_resolved_module_index(&krate);
+                    let (mut selected_ids, _) =
+                        graph::select_items(&krate, &print_args.paths, &[], &resolved_modules)?;
+                    graph::exclude_items(&krate, &mut selected_ids, &print_args.exclude_paths);
+                    selected_ids.len()
+                } else {
+                    0
+                };
+                if !confirm_large_output(
+                    item_count,
+                    interactive,
+                    &mut std::io::stdin().lock(),
+                    &mut std::io::stderr(),
+                )? {
+                    info!("Aborted; nothing was printed.");
+                    return Ok(());
+                }
+            }
 
-            if let Some(output_file_path) = print_args.output {
+            if let Some(output_dir) = &print_args.output_dir {
+                if print_args.format == OutputFormat::HtmlStandalone {
+                    bail!("--output-dir only supports the markdown format");
+                }
+                info!(
+                    "Writing documentation to directory: {}",
+                    output_dir.display()
+                );
+                let paths = printer.print_to_dir(output_dir)?;
+                if print_args.profile {
+                    eprint!("{}", timings);
+                }
+                info!(
+                    "Successfully wrote {} files to {}",
+                    paths.len(),
+                    output_dir.display()
+                );
+                return Ok(());
+            }
+
+            let mut out: Box<dyn IoWrite> = if let Some(output_file_path) = &print_args.output {
                 info!(
                     "Writing documentation to file: {}",
                     output_file_path.display()
                 );
-                let mut file = File::create(&output_file_path).with_context(|| {
+                let file = File::create(output_file_path).with_context(|| {
                     format!(
                         "Failed to create output file: {}",
                         output_file_path.display()
                     )
                 })?;
-                file.write_all(documentation.as_bytes()).with_context(|| {
+                Box::new(BufWriter::new(file))
+            } else {
+                info!("Printing documentation to stdout.");
+                Box::new(std::io::stdout().lock())
+            };
+            let anchor_index = if print_args.format == OutputFormat::HtmlStandalone {
+                let (documentation, anchor_index) = printer.print_with_anchor_index()?;
+                let css = print_args
+                    .css
+                    .as_ref()
+                    .map(|path| {
+                        std::fs::read_to_string(path)
+                            .with_context(|| format!("Failed to read CSS file: {}", path.display()))
+                    })
+                    .transpose()?;
+                let title = format!("{} API", print_args.crate_name);
+                let html = html::render_standalone_html(&documentation, &title, css.as_deref());
+                out.write_all(html.as_bytes())
+                    .context("Failed to write documentation")?;
+                anchor_index
+            } else {
+                printer.print_to_writer_with_anchor_index(&mut out)?
+            };
+            out.flush().context("Failed to write documentation")?;
+            drop(out);
+            if print_args.profile {
+                eprint!("{}", timings);
+            }
+
+            if let Some(index_path) = &print_args.anchor_index {
+                let sorted_index: BTreeMap<_, _> = anchor_index.into_iter().collect();
+                let json = serde_json::to_string_pretty(&sorted_index)?;
+                std::fs::write(index_path, json).with_context(|| {
                     format!(
-                        "Failed to write to output file: {}",
-                        output_file_path.display()
+                        "Failed to write anchor index file: {}",
+                        index_path.display()
                     )
                 })?;
+            }
+
+            if let Some(output_file_path) = &print_args.output {
                 info!(
                     "Successfully wrote documentation to {}",
                     output_file_path.display()
                 );
-            } else {
-                info!("Printing documentation to stdout.");
-                print!("{}", documentation);
             }
         }
         Command::DumpGraph(dump_args) => {
+            install_toolchain()?;
+
             let build_dir_path = PathBuf::from(&dump_args.build_dir);
             std::fs::create_dir_all(&build_dir_path).with_context(|| {
                 format!(
@@ -545,6 +1151,7 @@ async fn main() -> Result<()> {
                         &dump_args.crate_name,
                         &dump_args.crate_version,
                         dump_args.include_prerelease,
+                        dump_args.max_retries,
                     )
                     .await?;
                     info!(
@@ -556,6 +1163,7 @@ async fn main() -> Result<()> {
                         &client,
                         &target_version,
                         &build_dir_path,
+                        dump_args.max_retries,
                     )
                     .await?;
                     let m_path = dir.join("Cargo.toml");
@@ -570,41 +1178,53 @@ async fn main() -> Result<()> {
                     )
                 };
 
+            let rustdoc_options = RustdocOptions {
+                features: dump_args.features.clone(),
+                all_features: dump_args.all_features,
+                no_default_features: dump_args.no_default_features,
+                target: dump_args.target.clone(),
+                ..RustdocOptions::default()
+            };
             let krate: Crate = run_rustdoc(
                 &crate_dir,
                 &actual_crate_name_from_manifest,
-                dump_args.features.as_deref(),
-                dump_args.no_default_features,
-                dump_args.target.as_deref(),
-                true,
+                &rustdoc_options,
+                false, // Toolchain already installed above
             )?;
 
             let resolved_modules = graph::build_resolved_module_index(&krate);
-            let (_, full_graph) = graph::select_items(&krate, &dump_args.paths, &resolved_modules)?;
+            let (_, full_graph) =
+                graph::select_items(&krate, &dump_args.paths, &[], &resolved_modules)?;
 
-            let graph_to_dump = if let Some(target_leaf_id) = dump_args.to_id {
+            // Rather than building a filtered copy of the graph for --to-id, the dump only
+            // follows edges between the nodes leading to the leaf
+            let reachable_nodes = dump_args.to_id.map(|target_leaf_id| {
                 info!(
                     "Filtering graph to include only paths leading to leaf ID: {}",
                     target_leaf_id.0
                 );
-                let filtered_graph = full_graph.filter_to_leaf(target_leaf_id);
-                if filtered_graph.edges.is_empty() && !full_graph.edges.is_empty() {
+                let nodes = full_graph.nodes_reaching(target_leaf_id);
+                if nodes.is_empty() && !full_graph.edges.is_empty() {
                     warn!(
                         "Target leaf ID {} for --to-id not found or no paths lead to it in the graph. Dump will be empty.",
                         target_leaf_id.0
                     );
                 }
-                filtered_graph
-            } else {
-                full_graph.clone()
+                nodes
+            });
+            let graph_to_dump = full_graph;
+            let in_dumped_graph = |id: &Id| match &reachable_nodes {
+                Some(nodes) => nodes.contains(id),
+                None => {
+                    graph_to_dump.adjacency.contains_key(id)
+                        || graph_to_dump.reverse_adjacency.contains_key(id)
+                }
             };
 
             let (root_ids, dump_description) = if let Some(root_id) = dump_args.from_id {
                 let roots: HashSet<Id> = [root_id].into_iter().collect();
                 let description = format!("ID {}", root_id.0);
-                let root_exists_in_graph = graph_to_dump.adjacency.contains_key(&root_id)
-                    || graph_to_dump.reverse_adjacency.contains_key(&root_id);
-                if !root_exists_in_graph {
+                if !in_dumped_graph(&root_id) {
                     warn!(
                         "Root ID {} provided via --from-id not found in the {}graph. Dump will be empty.",
                         root_id.0,
@@ -620,14 +1240,15 @@ async fn main() -> Result<()> {
                     .iter()
                     .filter(|(_, item)| matches!(item.inner, ItemEnum::Module(_)))
                     .map(|(id, _)| *id)
-                    .filter(|id| {
-                        graph_to_dump.adjacency.contains_key(id)
-                            || graph_to_dump.reverse_adjacency.contains_key(id)
-                    })
+                    .filter(|id| in_dumped_graph(id))
                     .collect();
                 (module_roots, "modules".to_string())
             } else {
-                (graph_to_dump.find_roots(), "full".to_string())
+                let roots = match &reachable_nodes {
+                    Some(nodes) => graph_to_dump.find_roots_within(nodes),
+                    None => graph_to_dump.find_roots(),
+                };
+                (roots, "full".to_string())
             };
 
             if !root_ids.is_empty() {
@@ -644,13 +1265,15 @@ async fn main() -> Result<()> {
                         )
                     })?;
                     let mut writer = BufWriter::new(file);
-                    graph::dump_graph_subset(
+                    dump_graph(
+                        dump_args.format,
                         &graph_to_dump,
                         &krate,
                         &root_ids,
                         &mut writer,
                         &dump_description,
                         dump_args.max_depth,
+                        reachable_nodes.as_ref(),
                     )?;
                     writer.flush().with_context(|| {
                         format!("Failed to write graph to file: {}", output_path.display())
@@ -659,13 +1282,15 @@ async fn main() -> Result<()> {
                 } else {
                     info!("Dumping {} graph to stdout.", dump_description);
                     let mut stdout_writer = BufWriter::new(std::io::stdout());
-                    graph::dump_graph_subset(
+                    dump_graph(
+                        dump_args.format,
                         &graph_to_dump,
                         &krate,
                         &root_ids,
                         &mut stdout_writer,
                         &dump_description,
                         dump_args.max_depth,
+                        reachable_nodes.as_ref(),
                     )?;
                     stdout_writer.flush()?;
                 }
@@ -681,3 +1306,55 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn large_output_prompts_only_when_interactive() {
+        let count = LARGE_OUTPUT_ITEM_THRESHOLD + 1;
+        let mut prompt = Vec::new();
+        let mut input = "n\n".as_bytes();
+        assert!(confirm_large_output(count, false, &mut input, &mut prompt).unwrap());
+        assert!(prompt.is_empty());
+        assert_eq!(input, b"n\n");
+
+        assert!(!confirm_large_output(count, true, &mut input, &mut prompt).unwrap());
+        assert!(String::from_utf8(prompt)
+            .unwrap()
+            .ends_with("Continue anyway? [y/N] "));
+        assert!(confirm_large_output(count, true, &mut "y\n".as_bytes(), &mut Vec::new()).unwrap());
+        assert!(
+            confirm_large_output(count - 1, true, &mut "n\n".as_bytes(), &mut Vec::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn workspace_members_are_found_by_package_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let root_manifest = dir.path().join("Cargo.toml");
+        fs::write(&root_manifest, "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        for name in ["first-lib", "second-lib"] {
+            let member = dir.path().join("crates").join(name);
+            fs::create_dir_all(member.join("src")).unwrap();
+            fs::write(
+                member.join("Cargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name),
+            )
+            .unwrap();
+            fs::write(member.join("src/lib.rs"), "").unwrap();
+        }
+        let manifest = Manifest::from_path(&root_manifest).unwrap();
+
+        let (path, member) = find_workspace_member(&manifest, dir.path(), "second-lib")
+            .unwrap()
+            .unwrap();
+        assert_eq!(path, dir.path().join("crates/second-lib"));
+        assert_eq!(member.package.unwrap().name, "second-lib");
+        assert!(find_workspace_member(&manifest, dir.path(), "missing")
+            .unwrap()
+            .is_none());
+    }
+}